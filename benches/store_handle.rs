@@ -0,0 +1,97 @@
+//! Compares validating against a [StoreHandle] with a `RwLock`, with many threads validating at once while another keeps reloading
+
+use axum_auth::{AuthBasic, AuthValidator, BasicUser, BasicUsers, StoreHandle};
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::{
+    future::Future,
+    hint::black_box,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+    task::{Context, Waker},
+    thread,
+};
+
+/// Threads validating at once
+const READERS: usize = 8;
+
+/// Validations made by each thread in an iteration
+const READS: usize = 10_000;
+
+/// Makes the users to validate against
+fn users() -> BasicUsers {
+    (0..100).fold(BasicUsers::new(), |users, i| {
+        users.with_user(format!("user{}", i), BasicUser::new("hunter2"))
+    })
+}
+
+/// Polls the future of a validation once, which is all it needs as neither store waits
+fn ready<F: Future>(future: F) -> F::Output {
+    let mut cx = Context::from_waker(Waker::noop());
+    match std::pin::pin!(future).poll(&mut cx) {
+        std::task::Poll::Ready(output) => output,
+        std::task::Poll::Pending => unreachable!("validating never waits"),
+    }
+}
+
+/// Runs the readers while another thread keeps swapping in new users
+fn under_load(read: impl Fn() + Sync, reload: impl Fn() + Sync) {
+    let done = AtomicBool::new(false);
+    thread::scope(|scope| {
+        scope.spawn(|| {
+            while !done.load(Ordering::Relaxed) {
+                reload();
+            }
+        });
+        let readers: Vec<_> = (0..READERS)
+            .map(|_| {
+                scope.spawn(|| {
+                    for _ in 0..READS {
+                        read();
+                    }
+                })
+            })
+            .collect();
+        for reader in readers {
+            reader.join().unwrap();
+        }
+        done.store(true, Ordering::Relaxed);
+    });
+}
+
+fn bench(c: &mut Criterion) {
+    let credentials = AuthBasic((String::from("user42"), Some(String::from("hunter2"))));
+    let reloaded = users();
+    let mut group = c.benchmark_group("validating while reloading");
+
+    let locked = RwLock::new(Arc::new(users()));
+    group.bench_function("RwLock", |b| {
+        b.iter(|| {
+            under_load(
+                || {
+                    let users = locked.read().unwrap();
+                    black_box(ready(users.validate(black_box(&credentials)))).unwrap();
+                },
+                || *locked.write().unwrap() = Arc::new(BasicUsers::clone(&reloaded)),
+            )
+        })
+    });
+
+    let handle = StoreHandle::new(users());
+    group.bench_function("StoreHandle", |b| {
+        b.iter(|| {
+            under_load(
+                || {
+                    black_box(ready(handle.validate(black_box(&credentials)))).unwrap();
+                },
+                || handle.replace(BasicUsers::clone(&reloaded)),
+            )
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
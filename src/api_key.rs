@@ -0,0 +1,408 @@
+//! Implementation of accepting API keys from their own header, alone or alongside bearer tokens
+//!
+//! See [AuthApiKey] and [AuthBearerOrApiKey] for the most commonly-used data structures
+
+use crate::{
+    credential_header::credential_header, error::overwrite, AuthBearer, AuthBearerCustom,
+    AuthErrorKind, AuthScheme, Challenge, Rejection, ERR_API_KEY_CHARS, ERR_API_KEY_MISSING,
+    ERR_DEFAULT, ERR_NO_BEARER_OR_KEY,
+};
+use axum_core::{
+    extract::{FromRequestParts, OptionalFromRequestParts},
+    response::IntoResponse,
+};
+use http::{request::Parts, StatusCode};
+use std::{
+    fmt,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+};
+
+/// Header which API keys are sent in, used by [AuthApiKey] and [AuthBearerOrApiKey]
+///
+/// The ready-made [XApiKey] uses `X-Api-Key`, but other headers can be used by implementing this yourself:
+///
+/// ```rust
+/// use axum_auth::{ApiKeyHeader, AuthApiKey, AuthBearerOrApiKey};
+///
+/// /// Header our old clients send their keys in
+/// struct LegacyKey;
+///
+/// impl ApiKeyHeader for LegacyKey {
+///     const NAME: &'static str = "x-legacy-key";
+/// }
+///
+/// async fn handler(auth: AuthBearerOrApiKey<LegacyKey>) -> String {
+///     format!("Found a {:?} token", auth.mechanism)
+/// }
+///
+/// async fn legacy_only(auth: AuthApiKey<LegacyKey>) -> String {
+///     format!("Found a legacy key: {}", auth.key)
+/// }
+/// ```
+pub trait ApiKeyHeader {
+    /// Name of the header in lowercase, like `x-api-key`; you need to implement this
+    const NAME: &'static str;
+}
+
+/// The `X-Api-Key` header, which is the most common for API keys
+#[derive(Debug, Clone, Copy)]
+pub struct XApiKey;
+
+impl ApiKeyHeader for XApiKey {
+    const NAME: &'static str = "x-api-key";
+}
+
+/// API key extractor which contains the key sent in its own header, which is `X-Api-Key` by default
+///
+/// Many APIs take keys in a header like `X-Api-Key` instead of `Authorization`. The header is chosen using an [ApiKeyHeader], and surrounding whitespace is trimmed from the key. Use [AuthApiKeyCustom] instead for a different status code or message.
+///
+/// # Example
+///
+/// ```no_run
+/// use axum_auth::AuthApiKey;
+///
+/// /// Handler for a typical [axum] route, takes a `key` and returns it
+/// async fn handler(auth: AuthApiKey) -> String {
+///     format!("Found an API key: {}", auth.key)
+/// }
+/// ```
+///
+/// # Errors
+///
+/// There are a few errors which this extractor can make. By default, all invalid responses are `400 BAD REQUEST` with one of these messages:
+///
+/// - API key header is missing – The header was required but it wasn't found
+/// - API key header contains invalid characters – The API key was empty or had characters which aren't visible ASCII
+///
+/// Taking an `Option<AuthApiKey>` gives [None] if the header wasn't sent.
+///
+/// # Debugging
+///
+/// The [Debug](fmt::Debug) output of this extractor never contains the key itself, just like [AuthBearer].
+pub struct AuthApiKey<H = XApiKey> {
+    /// Key which was sent
+    pub key: String,
+    header: PhantomData<fn() -> H>,
+}
+
+impl<H> AuthApiKey<H> {
+    /// Creates the extractor from a key
+    pub fn new(key: impl Into<String>) -> Self {
+        Self {
+            key: key.into(),
+            header: PhantomData,
+        }
+    }
+}
+
+impl<H> fmt::Debug for AuthApiKey<H> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Keys are redacted just like bearer tokens are
+        f.debug_struct("AuthApiKey")
+            .field("key", &AuthBearer(self.key.clone()))
+            .finish()
+    }
+}
+
+impl<H> Clone for AuthApiKey<H> {
+    fn clone(&self) -> Self {
+        Self::new(self.key.clone())
+    }
+}
+
+impl<H> PartialEq for AuthApiKey<H> {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl<H> Eq for AuthApiKey<H> {}
+
+impl<H> Hash for AuthApiKey<H> {
+    fn hash<S: Hasher>(&self, state: &mut S) {
+        self.key.hash(state);
+    }
+}
+
+impl<H: ApiKeyHeader> AuthApiKeyCustom for AuthApiKey<H> {
+    type Rejection = Rejection;
+    const HEADER: &'static str = H::NAME;
+    const ERROR_CODE: StatusCode = ERR_DEFAULT;
+    const ERROR_OVERWRITE: Option<&'static str> = None;
+
+    fn from_header(contents: &str) -> Self {
+        Self::new(contents)
+    }
+}
+
+impl<H: ApiKeyHeader, B> FromRequestParts<B> for AuthApiKey<H>
+where
+    B: Send + Sync,
+{
+    type Rejection = Rejection;
+
+    async fn from_request_parts(parts: &mut Parts, _: &B) -> Result<Self, Self::Rejection> {
+        Self::decode_request_parts(parts)
+    }
+}
+
+impl<H: ApiKeyHeader, B> OptionalFromRequestParts<B> for AuthApiKey<H>
+where
+    B: Send + Sync,
+{
+    type Rejection = Rejection;
+
+    async fn from_request_parts(parts: &mut Parts, _: &B) -> Result<Option<Self>, Self::Rejection> {
+        Self::decode_optional_request_parts(parts)
+    }
+}
+
+impl<H: ApiKeyHeader> AuthScheme for AuthApiKey<H> {
+    fn challenges() -> Vec<Challenge> {
+        // There's no standard scheme for API keys to advertise
+        vec![]
+    }
+
+    fn extract(parts: &mut Parts) -> Result<Self, Rejection> {
+        Self::decode_request_parts(parts)
+    }
+}
+
+/// Custom extractor trait for API keys in their own header, allowing you to implement custom responses
+///
+/// This works just like [AuthBearerCustom], but reads the key from the header given by [AuthApiKeyCustom::HEADER] instead of the `Authorization` header:
+///
+/// 1. Make your own tuple struct for the data extractor
+/// 2. Implement [FromRequestParts] for your struct, calling [AuthApiKeyCustom::decode_request_parts]
+/// 3. Implement [AuthApiKeyCustom] to generate your extractor with your custom options, see the example below
+///
+/// # Example
+///
+/// This is what a typical custom extractor should look like in full, copy-paste this and edit it:
+///
+/// ```rust
+/// use axum::extract::FromRequestParts;
+/// use axum_auth::{AuthApiKeyCustom, Rejection};
+/// use http::{request::Parts, StatusCode};
+///
+/// /// Your custom API key auth returning `401 UNAUTHORIZED` without saying why
+/// struct MyApiKey(String);
+///
+/// // This is where you define your custom options:
+/// impl AuthApiKeyCustom for MyApiKey {
+///     type Rejection = Rejection; // <-- or your own error type, see the docs for this
+///     const HEADER: &'static str = "x-my-api-key"; // <-- define the header name here
+///     const ERROR_CODE: StatusCode = StatusCode::UNAUTHORIZED; // <-- define custom status code here
+///     const ERROR_OVERWRITE: Option<&'static str> = Some("Invalid API key"); // <-- define overwriting message here
+///
+///     fn from_header(contents: &str) -> Self {
+///         Self(contents.to_string())
+///     }
+/// }
+///
+/// // This is boilerplate for now, copy and paste this:
+/// impl<B> FromRequestParts<B> for MyApiKey
+/// where
+///     B: Send + Sync,
+/// {
+///     type Rejection = Rejection; // <-- or your own error type, see the docs for this
+///
+///     async fn from_request_parts(parts: &mut Parts, _: &B) -> Result<Self, Self::Rejection> {
+///         Self::decode_request_parts(parts)
+///     }
+/// }
+/// ```
+///
+/// Optional extractors work the same as in [AuthBearerCustom], using [AuthApiKeyCustom::decode_optional_request_parts] instead.
+pub trait AuthApiKeyCustom: Sized {
+    /// Rejection given back when decoding fails, which is usually [Rejection](crate::Rejection) but can be an error type of your own which can be made from one, see [AuthBasicCustom::Rejection](crate::AuthBasicCustom::Rejection) for an example
+    type Rejection: IntoResponse + From<Rejection>;
+
+    /// Name of the header the key is sent in, in lowercase like `x-api-key`; you need to implement this
+    const HEADER: &'static str;
+
+    /// Error code to use instead of the typical `400 BAD REQUEST` error
+    const ERROR_CODE: StatusCode;
+
+    /// Message to overwrite all default ones with if required, leave as [None] ideally
+    ///
+    /// This hides whether the header was missing or malformed, see [AuthBasicCustom::ERROR_OVERWRITE](crate::AuthBasicCustom::ERROR_OVERWRITE) for more information.
+    const ERROR_OVERWRITE: Option<&'static str>;
+
+    /// Converts provided header contents to new instance of self; you need to implement this
+    fn from_header(contents: &str) -> Self;
+
+    /// Decodes the API key into new instance of self from axum body parts; this is automatically implemented
+    fn decode_request_parts(req: &mut Parts) -> Result<Self, Self::Rejection> {
+        api_key(req, Self::HEADER, Self::ERROR_CODE, ERR_API_KEY_MISSING)
+            .map(Self::from_header)
+            .map_err(|rejection| overwrite(rejection, Self::ERROR_OVERWRITE).into())
+    }
+
+    /// Decodes the API key into new instance of self like [decode_request_parts](Self::decode_request_parts), giving back [None] if there's no header at all; this is automatically implemented
+    fn decode_optional_request_parts(req: &mut Parts) -> Result<Option<Self>, Self::Rejection> {
+        if !req.headers.contains_key(Self::HEADER) {
+            req.extensions.insert(AuthErrorKind::MissingHeader);
+            return Ok(None);
+        }
+        match api_key(req, Self::HEADER, Self::ERROR_CODE, ERR_API_KEY_MISSING) {
+            Ok(key) => Ok(Some(Self::from_header(key))),
+            Err(rejection) => {
+                // Keep the kind from before any overwrite, as the overwritten message hides it
+                req.extensions.insert(AuthErrorKind::of(&rejection));
+                Err(overwrite(rejection, Self::ERROR_OVERWRITE).into())
+            }
+        }
+    }
+}
+
+/// Mechanism a token was sent with, found by [AuthBearerOrApiKey]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum TokenMechanism {
+    /// Sent as a bearer token in the `Authorization` header
+    Bearer,
+    /// Sent as an API key in its own header
+    ApiKey,
+}
+
+/// Extractor accepting either a bearer token or an API key from its own header, tagging which was used
+///
+/// Public APIs often accept both while clients migrate from one to the other. The `Authorization` header is used if it's there, parsed exactly like [AuthBearer] does, and otherwise the API key header given by [ApiKeyHeader] is used, which is `X-Api-Key` by default.
+///
+/// # Example
+///
+/// ```no_run
+/// use axum_auth::{AuthBearerOrApiKey, TokenMechanism};
+///
+/// async fn handler(auth: AuthBearerOrApiKey) -> String {
+///     match auth.mechanism {
+///         TokenMechanism::Bearer => format!("Found a bearer token: {}", auth.token),
+///         TokenMechanism::ApiKey => format!("Found an API key, please move to bearer tokens: {}", auth.token),
+///     }
+/// }
+/// ```
+///
+/// # Errors
+///
+/// Bearer tokens are rejected in the same way as [AuthBearer], and otherwise all invalid responses are `400 BAD REQUEST` with one of these messages:
+///
+/// - Neither a bearer token nor an API key was found – Neither header was sent
+/// - API key header contains invalid characters – The API key was empty or had characters which aren't visible ASCII
+///
+/// Taking an `Option<AuthBearerOrApiKey>` gives [None] if neither header was sent.
+pub struct AuthBearerOrApiKey<H = XApiKey> {
+    /// Token which was sent
+    pub token: String,
+    /// Mechanism the token was sent with
+    pub mechanism: TokenMechanism,
+    header: PhantomData<fn() -> H>,
+}
+
+impl<H> AuthBearerOrApiKey<H> {
+    /// Creates the extractor from a token and the mechanism it was sent with
+    pub fn new(token: impl Into<String>, mechanism: TokenMechanism) -> Self {
+        Self {
+            token: token.into(),
+            mechanism,
+            header: PhantomData,
+        }
+    }
+}
+
+impl<H> fmt::Debug for AuthBearerOrApiKey<H> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Tokens are redacted just like bearer tokens are
+        f.debug_struct("AuthBearerOrApiKey")
+            .field("token", &AuthBearer(self.token.clone()))
+            .field("mechanism", &self.mechanism)
+            .finish()
+    }
+}
+
+impl<H> Clone for AuthBearerOrApiKey<H> {
+    fn clone(&self) -> Self {
+        Self::new(self.token.clone(), self.mechanism)
+    }
+}
+
+impl<H> PartialEq for AuthBearerOrApiKey<H> {
+    fn eq(&self, other: &Self) -> bool {
+        self.token == other.token && self.mechanism == other.mechanism
+    }
+}
+
+impl<H> Eq for AuthBearerOrApiKey<H> {}
+
+impl<H> Hash for AuthBearerOrApiKey<H> {
+    fn hash<S: Hasher>(&self, state: &mut S) {
+        self.token.hash(state);
+        self.mechanism.hash(state);
+    }
+}
+
+impl<H: ApiKeyHeader, B> FromRequestParts<B> for AuthBearerOrApiKey<H>
+where
+    B: Send + Sync,
+{
+    type Rejection = Rejection;
+
+    async fn from_request_parts(parts: &mut Parts, _: &B) -> Result<Self, Self::Rejection> {
+        Self::extract(parts)
+    }
+}
+
+impl<H: ApiKeyHeader, B> OptionalFromRequestParts<B> for AuthBearerOrApiKey<H>
+where
+    B: Send + Sync,
+{
+    type Rejection = Rejection;
+
+    async fn from_request_parts(parts: &mut Parts, _: &B) -> Result<Option<Self>, Self::Rejection> {
+        if !parts.headers.contains_key(credential_header(parts))
+            && !parts.headers.contains_key(H::NAME)
+        {
+            parts.extensions.insert(AuthErrorKind::MissingHeader);
+            return Ok(None);
+        }
+        Self::extract(parts).map(Some).inspect_err(|rejection| {
+            parts.extensions.insert(AuthErrorKind::of(rejection));
+        })
+    }
+}
+
+impl<H: ApiKeyHeader> AuthScheme for AuthBearerOrApiKey<H> {
+    fn challenges() -> Vec<Challenge> {
+        AuthBearer::challenges()
+    }
+
+    fn extract(parts: &mut Parts) -> Result<Self, Rejection> {
+        // Prefer bearer tokens, as that's what clients are migrating to
+        if parts.headers.contains_key(credential_header(parts)) {
+            let AuthBearer(token) = AuthBearer::decode_request_parts(parts)?;
+            return Ok(Self::new(token, TokenMechanism::Bearer));
+        }
+        let key = api_key(parts, H::NAME, ERR_DEFAULT, ERR_NO_BEARER_OR_KEY)?;
+        Ok(Self::new(key, TokenMechanism::ApiKey))
+    }
+}
+
+/// Gets the trimmed API key from the header, erroring with the status code and the message for when it's missing
+fn api_key<'a>(
+    parts: &'a Parts,
+    header: &str,
+    code: StatusCode,
+    missing: &'static str,
+) -> Result<&'a str, Rejection> {
+    let key = parts
+        .headers
+        .get(header)
+        .ok_or((code, missing))?
+        .to_str()
+        .map_err(|_| (code, ERR_API_KEY_CHARS))?
+        .trim();
+    if key.is_empty() {
+        return Err((code, ERR_API_KEY_CHARS));
+    }
+    Ok(key)
+}
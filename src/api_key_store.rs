@@ -0,0 +1,516 @@
+//! Implementation of issuing, checking, and revoking API keys kept in a store
+//!
+//! See [ApiKeys] for the most commonly-used data structure
+
+use crate::{
+    constant_eq, crypto, ApiKeyHeader, AuthApiKey, AuthBearer, AuthBearerOrApiKey, AuthUser,
+    AuthValidator, CredentialExpiry, CredentialId, Quota, Rejection, RequireRole, Role,
+    ERR_API_KEY_EXPIRED, ERR_API_KEY_REQUEST, ERR_API_KEY_REVOKED, ERR_INVALID_CREDENTIALS,
+};
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+    routing::{delete, get},
+    Router,
+};
+use axum_core::response::{IntoResponse, Response};
+use base64::{engine::general_purpose, Engine};
+use http::{header::CONTENT_TYPE, Extensions, StatusCode};
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    fmt,
+    future::Future,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// API key kept in an [ApiKeyStore], along with who it authenticates as
+///
+/// This is enabled via the `api-key-store` feature alongside a crypto backend
+///
+/// Keys are made of a random identifier, used to find the key in the store, and a random secret, whose digest is checked against the stored one. Only the digest is kept, so a leaked store can't be used to authenticate. Revoked keys are kept with the time they were revoked, so they still show up when auditing which keys existed.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ApiKeyRecord {
+    /// Identifier of the key, which is the public part before the `.`
+    pub id: String,
+    /// Name given to the key when it was made, like the service using it
+    pub name: String,
+    /// User the key authenticates as
+    pub user: AuthUser,
+    /// SHA-256 digest of the secret part of the key
+    pub secret_hash: Vec<u8>,
+    /// When the key was made
+    pub created_at: SystemTime,
+    /// When the key stops being accepted, if ever
+    pub expires_at: Option<SystemTime>,
+    /// When the key was revoked, if it has been
+    pub revoked_at: Option<SystemTime>,
+    /// When the key was last accepted, if it has been
+    pub last_used_at: Option<SystemTime>,
+    /// How many requests the key can make, enforced by a [QuotaLayer](crate::QuotaLayer), if it's limited
+    pub quota: Option<Quota>,
+}
+
+/// Store keeping the API keys of [ApiKeys] by their identifier
+///
+/// This is enabled via the `api-key-store` feature alongside a crypto backend
+///
+/// The in-memory [MemoryApiKeyStore] forgets every key when the server restarts, so implement this yourself for a database in production.
+pub trait ApiKeyStore: Send + Sync + 'static {
+    /// Stores the key under its identifier; you need to implement this
+    fn insert(&self, key: ApiKeyRecord) -> impl Future<Output = ()> + Send;
+
+    /// Gets the key with the identifier, if there is one; you need to implement this
+    fn get(&self, id: &str) -> impl Future<Output = Option<ApiKeyRecord>> + Send;
+
+    /// Gets every key; you need to implement this
+    fn list(&self) -> impl Future<Output = Vec<ApiKeyRecord>> + Send;
+
+    /// Removes the key with the identifier, giving back whether there was one; you need to implement this
+    fn remove(&self, id: &str) -> impl Future<Output = bool> + Send;
+
+    /// Sets when the key with the identifier was revoked unless it already was, giving back whether there was one; you need to implement this
+    fn revoke(&self, id: &str, at: SystemTime) -> impl Future<Output = bool> + Send;
+
+    /// Sets when the key with the identifier was last used; you need to implement this
+    fn touch(&self, id: &str, at: SystemTime) -> impl Future<Output = ()> + Send;
+}
+
+/// In-memory [ApiKeyStore]
+///
+/// This is enabled via the `api-key-store` feature alongside a crypto backend
+#[derive(Debug, Default)]
+pub struct MemoryApiKeyStore {
+    keys: Mutex<HashMap<String, ApiKeyRecord>>,
+}
+
+impl MemoryApiKeyStore {
+    /// Creates a new empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ApiKeyStore for MemoryApiKeyStore {
+    async fn insert(&self, key: ApiKeyRecord) {
+        self.keys.lock().unwrap().insert(key.id.clone(), key);
+    }
+
+    async fn get(&self, id: &str) -> Option<ApiKeyRecord> {
+        self.keys.lock().unwrap().get(id).cloned()
+    }
+
+    async fn list(&self) -> Vec<ApiKeyRecord> {
+        let mut keys: Vec<_> = self.keys.lock().unwrap().values().cloned().collect();
+        keys.sort_by(|a, b| (a.created_at, &a.id).cmp(&(b.created_at, &b.id)));
+        keys
+    }
+
+    async fn remove(&self, id: &str) -> bool {
+        self.keys.lock().unwrap().remove(id).is_some()
+    }
+
+    async fn revoke(&self, id: &str, at: SystemTime) -> bool {
+        match self.keys.lock().unwrap().get_mut(id) {
+            Some(key) => {
+                key.revoked_at.get_or_insert(at);
+                true
+            }
+            None => false,
+        }
+    }
+
+    async fn touch(&self, id: &str, at: SystemTime) {
+        if let Some(key) = self.keys.lock().unwrap().get_mut(id) {
+            key.last_used_at = Some(at);
+        }
+    }
+}
+
+/// Issuer and validator of API keys kept in an [ApiKeyStore], which can also make an admin router to manage them
+///
+/// This is enabled via the `api-key-store` feature alongside a crypto backend
+///
+/// Keys look like `<id>.<secret>` and are checked when sent as a bearer token or in an API key header using [AuthBearerOrApiKey], or only in one of them using [AuthBearer] or [AuthApiKey]. Once a key is accepted, the [AuthUser] it was made for is used, and its [ApiKeyRecord] and [CredentialId] are put into the request extensions, along with a [CredentialExpiry] if it expires. The time it was last used is updated in the store in the background, so a slow store doesn't hold up the request.
+///
+/// Keys never expire unless a TTL is set using [with_ttl](Self::with_ttl) or given when minting them. Likewise, keys can make as many requests as they like unless a [Quota] is set using [with_quota](Self::with_quota) or given to the admin router, which is put into the request extensions for a [QuotaLayer](crate::QuotaLayer) to enforce.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{routing::get, Router};
+/// use axum_auth::{
+///     ApiKeys, AuthBearerOrApiKey, AuthUser, BasicUser, BasicUsers, MemoryApiKeyStore,
+///     RequireAuthLayer, RequireBasicAuthLayer, Role,
+/// };
+/// use std::sync::Arc;
+///
+/// struct Admin;
+///
+/// impl Role for Admin {
+///     const NAME: &'static str = "admin";
+/// }
+///
+/// let keys = ApiKeys::new(Arc::new(MemoryApiKeyStore::new()));
+/// let admins = BasicUsers::new().with_user("alice", BasicUser::new("hunter2").with_roles(["admin"]));
+///
+/// let app: Router = Router::new()
+///     .route("/", get(|user: AuthUser| async move { format!("Hello, {}!", user.id) }))
+///     .layer(RequireAuthLayer::<AuthBearerOrApiKey, _>::new(keys.clone()))
+///     .nest("/admin", keys.admin_router::<Admin>().layer(RequireBasicAuthLayer::new(admins)));
+/// ```
+///
+/// # Errors
+///
+/// These are the rejections keys can be given, all with `401 UNAUTHORIZED`:
+///
+/// - "The provided credentials are invalid" – The key isn't in the store or has the wrong secret
+/// - "API key has expired" – The key was made with a TTL which is over
+/// - "API key has been revoked" – The key was revoked
+pub struct ApiKeys<S> {
+    store: Arc<S>,
+    ttl: Option<Duration>,
+    quota: Option<Quota>,
+}
+
+impl<S> ApiKeys<S> {
+    /// Creates a new issuer using the given store, whose keys never expire
+    pub fn new(store: Arc<S>) -> Self {
+        Self {
+            store,
+            ttl: None,
+            quota: None,
+        }
+    }
+
+    /// Sets how long minted keys last for, returning it back
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Sets the quota of minted keys, returning it back
+    pub fn with_quota(mut self, quota: Quota) -> Self {
+        self.quota = Some(quota);
+        self
+    }
+
+    /// Gets the store the keys are kept in
+    pub fn store(&self) -> &Arc<S> {
+        &self.store
+    }
+}
+
+impl<S: ApiKeyStore> ApiKeys<S> {
+    /// Makes a new key for the user lasting for the TTL, giving back its record and the key itself
+    ///
+    /// The key can't be found again once this returns, as only a digest of its secret is stored, so hand it to the client straight away.
+    pub async fn mint(&self, name: impl Into<String>, user: AuthUser) -> (ApiKeyRecord, String) {
+        // TTLs too long to be a time never expire
+        let expires_at = self.ttl.and_then(|ttl| SystemTime::now().checked_add(ttl));
+        self.mint_expiring(name, user, expires_at).await
+    }
+
+    /// Makes a new key for the user like [mint](Self::mint), but expiring at the given time instead of after the TTL
+    pub async fn mint_expiring(
+        &self,
+        name: impl Into<String>,
+        user: AuthUser,
+        expires_at: Option<SystemTime>,
+    ) -> (ApiKeyRecord, String) {
+        self.mint_with(name.into(), user, expires_at, self.quota)
+            .await
+    }
+
+    /// Makes a new key for the user with everything given
+    async fn mint_with(
+        &self,
+        name: String,
+        user: AuthUser,
+        expires_at: Option<SystemTime>,
+        quota: Option<Quota>,
+    ) -> (ApiKeyRecord, String) {
+        let id = general_purpose::URL_SAFE_NO_PAD.encode(crypto::random(12));
+        let secret = crypto::random(32);
+        let record = ApiKeyRecord {
+            id: id.clone(),
+            name,
+            user,
+            secret_hash: crypto::sha256(&secret),
+            created_at: SystemTime::now(),
+            expires_at,
+            revoked_at: None,
+            last_used_at: None,
+            quota,
+        };
+        self.store.insert(record.clone()).await;
+
+        let key = format!("{}.{}", id, general_purpose::URL_SAFE_NO_PAD.encode(secret));
+        (record, key)
+    }
+
+    /// Revokes the key with the identifier, giving back whether there was one
+    pub async fn revoke(&self, id: &str) -> bool {
+        self.store.revoke(id, SystemTime::now()).await
+    }
+
+    /// Checks a key, giving back its record if it's accepted
+    async fn check(&self, key: &str) -> Result<ApiKeyRecord, Rejection> {
+        let invalid = (StatusCode::UNAUTHORIZED, ERR_INVALID_CREDENTIALS);
+        let (id, secret) = key.split_once('.').ok_or(invalid)?;
+        let secret = general_purpose::URL_SAFE_NO_PAD
+            .decode(secret)
+            .map_err(|_| invalid)?;
+        let record = self.store.get(id).await.ok_or(invalid)?;
+        if !constant_eq(&crypto::sha256(&secret), &record.secret_hash) {
+            return Err(invalid);
+        }
+
+        // Only say why a key isn't accepted once it's known to be theirs
+        let now = SystemTime::now();
+        if record.revoked_at.is_some() {
+            return Err((StatusCode::UNAUTHORIZED, ERR_API_KEY_REVOKED));
+        }
+        if record
+            .expires_at
+            .is_some_and(|expires_at| expires_at <= now)
+        {
+            return Err((StatusCode::UNAUTHORIZED, ERR_API_KEY_EXPIRED));
+        }
+        Ok(record)
+    }
+
+    /// Checks a key, giving back its user and recording when it was used if it's accepted
+    async fn accept(&self, key: &str, extensions: &mut Extensions) -> Result<AuthUser, Rejection> {
+        let mut record = self.check(key).await?;
+        let now = SystemTime::now();
+        let (store, id) = (self.store.clone(), record.id.clone());
+        match tokio::runtime::Handle::try_current() {
+            Ok(runtime) => {
+                runtime.spawn(async move { store.touch(&id, now).await });
+            }
+            // Outside of tokio there's nothing to run it in the background with
+            Err(_) => store.touch(&id, now).await,
+        }
+
+        record.last_used_at = Some(now);
+        let user = record.user.clone();
+        extensions.insert(CredentialId(record.id.clone()));
+        if let Some(expires_at) = record.expires_at {
+            extensions.insert(CredentialExpiry(expires_at));
+        }
+        if let Some(quota) = record.quota {
+            extensions.insert(quota);
+        }
+        extensions.insert(record);
+        Ok(user)
+    }
+
+    /// Makes a router for managing the keys, which only lets users with the role through
+    ///
+    /// It has these routes, taking and giving back JSON:
+    ///
+    /// - `GET /keys` – Lists the records of every key, without their secrets
+    /// - `POST /keys` – Makes a key from a body like `{"name": "ci", "user": "bot", "roles": [], "scopes": [], "expires_in": 86400, "quota": {"requests": 1000, "window": 3600}}`, responding with `201 CREATED` and the record along with the `key`, which is the only time it's shown. The user defaults to whoever made the request, the number of seconds it expires in to the TTL, and the quota, whose window is in seconds, to the one set using [with_quota](Self::with_quota)
+    /// - `DELETE /keys/{id}` – Revokes the key, responding with `204 NO CONTENT`, or `404 NOT FOUND` if there's no such key
+    ///
+    /// Times in records are given as seconds since the Unix epoch, or `null` if they haven't happened.
+    ///
+    /// Layer it with something which authenticates the admins, like a [RequireBasicAuthLayer](crate::RequireBasicAuthLayer), as requests without an [AuthUser] are rejected with `401 UNAUTHORIZED`. Bodies which aren't valid are rejected with `400 BAD REQUEST` and the message "API key request must be JSON with a name".
+    pub fn admin_router<R: Role + 'static>(&self) -> Router {
+        Router::new()
+            .route("/keys", get(list_keys::<S, R>).post(mint_key::<S, R>))
+            .route("/keys/{id}", delete(revoke_key::<S, R>))
+            .with_state(self.clone())
+    }
+}
+
+impl<S> Clone for ApiKeys<S> {
+    fn clone(&self) -> Self {
+        Self {
+            store: self.store.clone(),
+            ttl: self.ttl,
+            quota: self.quota,
+        }
+    }
+}
+
+impl<S: fmt::Debug> fmt::Debug for ApiKeys<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ApiKeys")
+            .field("store", &self.store)
+            .field("ttl", &self.ttl)
+            .field("quota", &self.quota)
+            .finish()
+    }
+}
+
+impl<S: ApiKeyStore, H: Sync> AuthValidator<AuthBearerOrApiKey<H>> for ApiKeys<S>
+where
+    H: ApiKeyHeader,
+{
+    async fn validate(&self, credentials: &AuthBearerOrApiKey<H>) -> Result<AuthUser, Rejection> {
+        self.validate_with_extensions(credentials, &mut Extensions::new())
+            .await
+    }
+
+    async fn validate_with_extensions(
+        &self,
+        credentials: &AuthBearerOrApiKey<H>,
+        extensions: &mut Extensions,
+    ) -> Result<AuthUser, Rejection> {
+        self.accept(&credentials.token, extensions).await
+    }
+}
+
+impl<S: ApiKeyStore, H: Sync> AuthValidator<AuthApiKey<H>> for ApiKeys<S>
+where
+    H: ApiKeyHeader,
+{
+    async fn validate(&self, credentials: &AuthApiKey<H>) -> Result<AuthUser, Rejection> {
+        self.validate_with_extensions(credentials, &mut Extensions::new())
+            .await
+    }
+
+    async fn validate_with_extensions(
+        &self,
+        credentials: &AuthApiKey<H>,
+        extensions: &mut Extensions,
+    ) -> Result<AuthUser, Rejection> {
+        self.accept(&credentials.key, extensions).await
+    }
+}
+
+impl<S: ApiKeyStore> AuthValidator<AuthBearer> for ApiKeys<S> {
+    async fn validate(&self, credentials: &AuthBearer) -> Result<AuthUser, Rejection> {
+        self.validate_with_extensions(credentials, &mut Extensions::new())
+            .await
+    }
+
+    async fn validate_with_extensions(
+        &self,
+        credentials: &AuthBearer,
+        extensions: &mut Extensions,
+    ) -> Result<AuthUser, Rejection> {
+        self.accept(&credentials.0, extensions).await
+    }
+}
+
+/// Lists the records of every key
+async fn list_keys<S: ApiKeyStore, R: Role>(
+    _: RequireRole<R>,
+    State(keys): State<ApiKeys<S>>,
+) -> Response {
+    let records = keys.store.list().await;
+    json_response(
+        StatusCode::OK,
+        Value::Array(records.iter().map(describe).collect()),
+    )
+}
+
+/// Makes a key, showing it once alongside its record
+async fn mint_key<S: ApiKeyStore, R: Role>(
+    admin: RequireRole<R>,
+    State(keys): State<ApiKeys<S>>,
+    body: Bytes,
+) -> Response {
+    let bad_request = (StatusCode::BAD_REQUEST, ERR_API_KEY_REQUEST);
+    let Ok(Value::Object(body)) = serde_json::from_slice(&body) else {
+        return bad_request.into_response();
+    };
+    let Some(name) = body.get("name").and_then(Value::as_str) else {
+        return bad_request.into_response();
+    };
+    let strings = |key: &str| match body.get(key) {
+        Some(Value::Array(values)) => values
+            .iter()
+            .map(|value| value.as_str().map(String::from))
+            .collect(),
+        Some(_) => None,
+        None => Some(vec![]),
+    };
+    let user = match body.get("user") {
+        Some(Value::String(id)) => id.clone(),
+        Some(_) => return bad_request.into_response(),
+        None => admin.user.id,
+    };
+    let (Some(roles), Some(scopes)) = (strings("roles"), strings("scopes")) else {
+        return bad_request.into_response();
+    };
+    let expires_at = match body.get("expires_in") {
+        Some(expires_in) => match expires_in
+            .as_u64()
+            .and_then(|secs| SystemTime::now().checked_add(Duration::from_secs(secs)))
+        {
+            Some(expires_at) => Some(expires_at),
+            None => return bad_request.into_response(),
+        },
+        None => keys.ttl.and_then(|ttl| SystemTime::now().checked_add(ttl)),
+    };
+    let quota = match body.get("quota") {
+        Some(quota) => match (quota["requests"].as_u64(), quota["window"].as_u64()) {
+            (Some(requests), Some(window)) => {
+                Some(Quota::new(requests, Duration::from_secs(window)))
+            }
+            _ => return bad_request.into_response(),
+        },
+        None => keys.quota,
+    };
+
+    let user = AuthUser::new(user).with_roles(roles).with_scopes(scopes);
+    let (record, key) = keys
+        .mint_with(name.to_string(), user, expires_at, quota)
+        .await;
+    let mut described = describe(&record);
+    described["key"] = Value::String(key);
+    json_response(StatusCode::CREATED, described)
+}
+
+/// Revokes a key
+async fn revoke_key<S: ApiKeyStore, R: Role>(
+    _: RequireRole<R>,
+    State(keys): State<ApiKeys<S>>,
+    Path(id): Path<String>,
+) -> StatusCode {
+    if keys.revoke(&id).await {
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
+
+/// Describes a key without its secret
+fn describe(record: &ApiKeyRecord) -> Value {
+    json!({
+        "id": record.id,
+        "name": record.name,
+        "user": record.user.id,
+        "roles": record.user.roles,
+        "scopes": record.user.scopes,
+        "created_at": unix_secs(record.created_at),
+        "expires_at": record.expires_at.map(unix_secs),
+        "revoked_at": record.revoked_at.map(unix_secs),
+        "last_used_at": record.last_used_at.map(unix_secs),
+        "quota": record.quota.map(|quota| json!({
+            "requests": quota.requests,
+            "window": quota.window.as_secs(),
+        })),
+    })
+}
+
+/// Gets the time as seconds since the Unix epoch
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+/// Responds with the JSON
+fn json_response(status: StatusCode, value: Value) -> Response {
+    (
+        status,
+        [(CONTENT_TYPE, "application/json")],
+        value.to_string(),
+    )
+        .into_response()
+}
@@ -2,10 +2,21 @@
 //!
 //! See [AuthBasic] for the most commonly-used data structure
 
-use crate::{get_header, Rejection, ERR_DECODE, ERR_DEFAULT, ERR_WRONG_BASIC};
-use axum_core::extract::FromRequestParts;
+#[cfg(feature = "fingerprint")]
+use crate::Fingerprint;
+use crate::{
+    error::{decode_optional, overwrite},
+    get_header, AuthScheme, Challenge, Rejection, ERR_DECODE, ERR_DEFAULT, ERR_NOT_EMAIL,
+    ERR_NO_COLON, ERR_NO_PASSWORD, ERR_WINDOWS_AUTH, ERR_WRONG_BASIC,
+};
+use axum_core::{
+    extract::{FromRequestParts, OptionalFromRequestParts},
+    response::IntoResponse,
+};
 use base64::{engine::general_purpose, Engine};
 use http::{request::Parts, StatusCode};
+use std::fmt;
+use unicode_normalization::UnicodeNormalization;
 
 /// Basic authentication extractor, containing an identifier as well as an optional password
 ///
@@ -34,11 +45,25 @@ use http::{request::Parts, StatusCode};
 ///
 /// - \`Authorization\` header could not be decoded – The header couldn't be decoded, probably missing a colon
 /// - \`Authorization\` header must be for basic authentication – Someone tried to use bearer auth instead of basic auth
+/// - \`Authorization\` header uses Windows authentication (NTLM or Negotiate) which isn't supported here – A Windows client tried to log in automatically
 /// - \`Authorization\` header is missing – The header was required but it wasn't found
 /// - \`Authorization\` header contains invalid characters – The header couldn't be processed because of invalid characters
-#[derive(Debug, PartialEq, Eq, Clone)]
+///
+/// # Debugging
+///
+/// The [Debug](fmt::Debug) output of this extractor never contains the password itself, only its length, so it's safe to log. With the `fingerprint` feature, it shows the `Fingerprint` of the password instead. This goes for the other basic auth extractors too.
+#[derive(PartialEq, Eq, Clone, Hash)]
 pub struct AuthBasic(pub (String, Option<String>));
 
+impl fmt::Debug for AuthBasic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (id, password) = &self.0;
+        f.debug_tuple("AuthBasic")
+            .field(&(id, password.as_deref().map(Redacted::new)))
+            .finish()
+    }
+}
+
 impl<B> FromRequestParts<B> for AuthBasic
 where
     B: Send + Sync,
@@ -50,7 +75,19 @@ where
     }
 }
 
+impl<B> OptionalFromRequestParts<B> for AuthBasic
+where
+    B: Send + Sync,
+{
+    type Rejection = Rejection;
+
+    async fn from_request_parts(parts: &mut Parts, _: &B) -> Result<Option<Self>, Self::Rejection> {
+        Self::decode_optional_request_parts(parts)
+    }
+}
+
 impl AuthBasicCustom for AuthBasic {
+    type Rejection = Rejection;
     const ERROR_CODE: StatusCode = ERR_DEFAULT;
     const ERROR_OVERWRITE: Option<&'static str> = None;
 
@@ -59,6 +96,296 @@ impl AuthBasicCustom for AuthBasic {
     }
 }
 
+impl AuthScheme for AuthBasic {
+    fn challenges() -> Vec<Challenge> {
+        vec![Challenge::basic("Restricted")]
+    }
+
+    fn extract(parts: &mut Parts) -> Result<Self, Rejection> {
+        Self::decode_request_parts(parts)
+    }
+}
+
+/// Basic authentication extractor following [RFC 7617](https://www.rfc-editor.org/rfc/rfc7617#section-2) strictly, rejecting credentials without a colon between the username and password
+///
+/// This is enabled via the `auth-basic` feature
+///
+/// [AuthBasic] accepts credentials without a colon as a username without a password, which hides client bugs like sending an API key on its own. This rejects them with a message saying exactly what was wrong, so the password is always there, even if it's empty.
+///
+/// # Example
+///
+/// ```no_run
+/// use axum_auth::AuthBasicStrict;
+///
+/// /// Takes basic auth details which always have a password
+/// async fn handler(AuthBasicStrict((id, password)): AuthBasicStrict) -> String {
+///     format!("User '{}' with a password of {} characters", id, password.len())
+/// }
+/// ```
+///
+/// # Errors
+///
+/// This can make all of the errors [AuthBasic] makes, as well as `400 BAD REQUEST` with the message "\`Authorization\` header must separate the username and password with a colon" if the decoded credentials have no colon.
+#[derive(PartialEq, Eq, Clone, Hash)]
+pub struct AuthBasicStrict(pub (String, String));
+
+impl fmt::Debug for AuthBasicStrict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (id, password) = &self.0;
+        f.debug_tuple("AuthBasicStrict")
+            .field(&(id, Redacted::new(password)))
+            .finish()
+    }
+}
+
+impl<B> FromRequestParts<B> for AuthBasicStrict
+where
+    B: Send + Sync,
+{
+    type Rejection = Rejection;
+
+    async fn from_request_parts(parts: &mut Parts, _: &B) -> Result<Self, Self::Rejection> {
+        Self::decode_request_parts(parts)
+    }
+}
+
+impl<B> OptionalFromRequestParts<B> for AuthBasicStrict
+where
+    B: Send + Sync,
+{
+    type Rejection = Rejection;
+
+    async fn from_request_parts(parts: &mut Parts, _: &B) -> Result<Option<Self>, Self::Rejection> {
+        Self::decode_optional_request_parts(parts)
+    }
+}
+
+impl AuthBasicCustom for AuthBasicStrict {
+    type Rejection = Rejection;
+    const ERROR_CODE: StatusCode = ERR_DEFAULT;
+    const ERROR_OVERWRITE: Option<&'static str> = None;
+    const REQUIRE_COLON: bool = true;
+
+    fn from_header((id, password): (String, Option<String>)) -> Self {
+        Self((id, password.unwrap_or_default()))
+    }
+}
+
+impl AuthScheme for AuthBasicStrict {
+    fn challenges() -> Vec<Challenge> {
+        AuthBasic::challenges()
+    }
+
+    fn extract(parts: &mut Parts) -> Result<Self, Rejection> {
+        Self::decode_request_parts(parts)
+    }
+}
+
+/// Basic authentication extractor for APIs whose usernames are always email addresses
+///
+/// This is enabled via the `auth-basic` feature
+///
+/// The username is checked to be a well-formed email address and its domain is lowercased, because domains are case-insensitive while the part before the `@` might not be.
+///
+/// # Example
+///
+/// ```no_run
+/// use axum_auth::AuthBasicEmail;
+///
+/// /// Takes basic auth details with an email as the username
+/// async fn handler(AuthBasicEmail((email, _)): AuthBasicEmail) -> String {
+///     format!("Welcome back, {}", email)
+/// }
+/// ```
+///
+/// # Errors
+///
+/// This can make all of the errors [AuthBasic] makes, as well as `400 BAD REQUEST` with the message "\`Authorization\` username must be an email address" if the username isn't an email address.
+#[derive(PartialEq, Eq, Clone, Hash)]
+pub struct AuthBasicEmail(pub (String, Option<String>));
+
+impl fmt::Debug for AuthBasicEmail {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (email, password) = &self.0;
+        f.debug_tuple("AuthBasicEmail")
+            .field(&(email, password.as_deref().map(Redacted::new)))
+            .finish()
+    }
+}
+
+impl<B> FromRequestParts<B> for AuthBasicEmail
+where
+    B: Send + Sync,
+{
+    type Rejection = Rejection;
+
+    async fn from_request_parts(parts: &mut Parts, _: &B) -> Result<Self, Self::Rejection> {
+        Self::extract(parts)
+    }
+}
+
+impl<B> OptionalFromRequestParts<B> for AuthBasicEmail
+where
+    B: Send + Sync,
+{
+    type Rejection = Rejection;
+
+    async fn from_request_parts(parts: &mut Parts, _: &B) -> Result<Option<Self>, Self::Rejection> {
+        decode_optional(parts, Self::extract)
+    }
+}
+
+impl AuthScheme for AuthBasicEmail {
+    fn challenges() -> Vec<Challenge> {
+        AuthBasic::challenges()
+    }
+
+    fn extract(parts: &mut Parts) -> Result<Self, Rejection> {
+        let AuthBasic((id, password)) = AuthBasic::decode_request_parts(parts)?;
+        let email = normalize_email(&id).ok_or((ERR_DEFAULT, ERR_NOT_EMAIL))?;
+        Ok(Self((email, password)))
+    }
+}
+
+/// Basic authentication extractor which keeps the exact bytes of the password, for validators hashing it with bcrypt, argon2, or similar
+///
+/// This is enabled via the `auth-basic` feature
+///
+/// [AuthBasic] rejects passwords which aren't valid UTF-8, but some clients send them in other encodings like Latin-1. This accepts any password, giving the bytes exactly as the client sent them in `password_bytes` and a lossy UTF-8 version of them in `password`, so hashes are always checked against the real password.
+///
+/// # Example
+///
+/// ```no_run
+/// use axum_auth::AuthBasicRaw;
+///
+/// /// Takes basic auth details and hashes the exact password
+/// async fn handler(auth: AuthBasicRaw) -> String {
+///     let bytes = auth.password_bytes.unwrap_or_default();
+///     format!("User '{}' sent a password of {} bytes", auth.username, bytes.len())
+/// }
+/// ```
+///
+/// # Errors
+///
+/// This can make all of the errors [AuthBasic] makes, except that only the username has to be valid UTF-8.
+#[derive(PartialEq, Eq, Clone, Hash)]
+pub struct AuthBasicRaw {
+    /// Username which was sent
+    pub username: String,
+    /// Password which was sent, with any invalid UTF-8 replaced by `�`
+    pub password: Option<String>,
+    /// Password exactly as it was sent, without any decoding
+    pub password_bytes: Option<Vec<u8>>,
+}
+
+impl fmt::Debug for AuthBasicRaw {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AuthBasicRaw")
+            .field("username", &self.username)
+            .field("password", &self.password.as_deref().map(Redacted::new))
+            .field(
+                "password_bytes",
+                &self.password_bytes.as_deref().map(Redacted::new),
+            )
+            .finish()
+    }
+}
+
+/// Password shown in the [Debug](fmt::Debug) output of the basic auth extractors without giving it away
+struct Redacted<'a>(&'a [u8]);
+
+impl<'a> Redacted<'a> {
+    /// Wraps the password to be redacted
+    fn new(password: &'a (impl AsRef<[u8]> + ?Sized)) -> Self {
+        Self(password.as_ref())
+    }
+}
+
+impl fmt::Debug for Redacted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        #[cfg(feature = "fingerprint")]
+        return fmt::Debug::fmt(&Fingerprint::of(self.0), f);
+        #[cfg(not(feature = "fingerprint"))]
+        write!(f, "[redacted], len: {}", self.0.len())
+    }
+}
+
+impl<B> FromRequestParts<B> for AuthBasicRaw
+where
+    B: Send + Sync,
+{
+    type Rejection = Rejection;
+
+    async fn from_request_parts(parts: &mut Parts, _: &B) -> Result<Self, Self::Rejection> {
+        Self::extract(parts)
+    }
+}
+
+impl<B> OptionalFromRequestParts<B> for AuthBasicRaw
+where
+    B: Send + Sync,
+{
+    type Rejection = Rejection;
+
+    async fn from_request_parts(parts: &mut Parts, _: &B) -> Result<Option<Self>, Self::Rejection> {
+        decode_optional(parts, Self::extract)
+    }
+}
+
+impl AuthScheme for AuthBasicRaw {
+    fn challenges() -> Vec<Challenge> {
+        AuthBasic::challenges()
+    }
+
+    fn extract(parts: &mut Parts) -> Result<Self, Rejection> {
+        let authorization = get_header(parts, ERR_DEFAULT)?;
+        match authorization.split_once(' ') {
+            Some(("Basic", contents)) => {
+                let (username, password_bytes) = decode_raw(contents, (ERR_DEFAULT, ERR_DECODE))?;
+                Ok(Self {
+                    username,
+                    password: password_bytes
+                        .as_deref()
+                        .map(|password| String::from_utf8_lossy(password).into_owned()),
+                    password_bytes,
+                })
+            }
+            Some(("NTLM" | "Negotiate", _)) => Err((ERR_DEFAULT, ERR_WINDOWS_AUTH)),
+            _ => Err((ERR_DEFAULT, ERR_WRONG_BASIC)),
+        }
+    }
+}
+
+/// Checks the syntax of an email address, giving it back with a lowercased domain if it's valid
+fn normalize_email(input: &str) -> Option<String> {
+    // Check the overall shape, e.g. `local@example.com`
+    let (local, domain) = input.rsplit_once('@')?;
+    if input.len() > 254 || local.is_empty() || local.len() > 64 {
+        return None;
+    }
+
+    // Check the local part has no whitespace, control characters, or dots in odd places
+    let local_valid = !local.starts_with('.')
+        && !local.ends_with('.')
+        && !local.contains("..")
+        && !local
+            .chars()
+            .any(|c| c.is_whitespace() || c.is_control() || "@\",:;<>[]()\\".contains(c));
+
+    // Check the domain is made of at least two valid labels
+    let labels = domain.split('.').collect::<Vec<_>>();
+    let domain_valid = labels.len() >= 2
+        && labels.iter().all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_alphanumeric() || c == '-')
+        });
+
+    (local_valid && domain_valid).then(|| format!("{}@{}", local, domain.to_lowercase()))
+}
+
 /// Custom extractor trait for basic auth allowing you to implement custom responses
 ///
 /// This is enabled via the `auth-basic` feature
@@ -87,6 +414,7 @@ impl AuthBasicCustom for AuthBasic {
 ///
 /// // This is where you define your custom options:
 /// impl AuthBasicCustom for MyCustomBasicAuth {
+///     type Rejection = Rejection; // <-- or your own error type, see the docs for this
 ///     const ERROR_CODE: StatusCode = StatusCode::IM_A_TEAPOT; // <-- define custom status code here
 ///     const ERROR_OVERWRITE: Option<&'static str> = None; // <-- define overwriting message here
 ///
@@ -100,7 +428,7 @@ impl AuthBasicCustom for AuthBasic {
 /// where
 ///     B: Send + Sync,
 /// {
-///     type Rejection = Rejection;
+///     type Rejection = Rejection; // <-- or your own error type, see the docs for this
 ///
 ///     async fn from_request_parts(parts: &mut Parts, _: &B) -> Result<Self, Self::Rejection> {
 ///         Self::decode_request_parts(parts)
@@ -112,13 +440,106 @@ impl AuthBasicCustom for AuthBasic {
 ///
 /// - There's no reason for the [FromRequestParts] to ever change out of this pattern unless you're doing something special
 /// - It's recommended to use the `struct BasicExample((String, Option<String>));` pattern because it makes using it from routes easy
+///
+/// # Optional extractors
+///
+/// To let handlers take `Option<MaybeBasicAuth>`, which is [None] when the header is missing but still errors when it's malformed, also implement [OptionalFromRequestParts]:
+///
+/// ```rust
+/// use axum_auth::{AuthBasicCustom, Rejection};
+/// use http::{request::Parts, StatusCode};
+/// use axum::extract::OptionalFromRequestParts;
+///
+/// struct MaybeBasicAuth((String, Option<String>));
+///
+/// impl AuthBasicCustom for MaybeBasicAuth {
+///     type Rejection = Rejection;
+///     const ERROR_CODE: StatusCode = StatusCode::BAD_REQUEST;
+///     const ERROR_OVERWRITE: Option<&'static str> = None;
+///
+///     fn from_header(contents: (String, Option<String>)) -> Self {
+///         Self(contents)
+///     }
+/// }
+///
+/// // Also boilerplate, copy and paste this:
+/// impl<B> OptionalFromRequestParts<B> for MaybeBasicAuth
+/// where
+///     B: Send + Sync,
+/// {
+///     type Rejection = Rejection; // <-- or your own error type, see the docs for this
+///
+///     async fn from_request_parts(parts: &mut Parts, _: &B) -> Result<Option<Self>, Self::Rejection> {
+///         Self::decode_optional_request_parts(parts)
+///     }
+/// }
+/// ```
 pub trait AuthBasicCustom: Sized {
+    /// Rejection given back when decoding fails, which is usually [Rejection](crate::Rejection) but can be an error type of your own
+    ///
+    /// Using your own type lets extractors return the same errors as the rest of your application. It only has to be made from a [Rejection](crate::Rejection), which [AuthErrorKind](crate::AuthErrorKind) can tell the kind of:
+    ///
+    /// ```rust
+    /// use axum::response::{IntoResponse, Response};
+    /// use axum_auth::{AuthBasicCustom, AuthErrorKind, Rejection};
+    /// use http::StatusCode;
+    ///
+    /// /// Error type of the whole application
+    /// enum AppError {
+    ///     Unauthenticated(AuthErrorKind),
+    /// }
+    ///
+    /// impl From<Rejection> for AppError {
+    ///     fn from(rejection: Rejection) -> Self {
+    ///         Self::Unauthenticated(AuthErrorKind::of(&rejection))
+    ///     }
+    /// }
+    ///
+    /// impl IntoResponse for AppError {
+    ///     fn into_response(self) -> Response {
+    ///         match self {
+    ///             Self::Unauthenticated(kind) => {
+    ///                 (StatusCode::UNAUTHORIZED, format!(r#"{{"error":"{:?}"}}"#, kind)).into_response()
+    ///             }
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// struct BasicExample((String, Option<String>));
+    ///
+    /// impl AuthBasicCustom for BasicExample {
+    ///     type Rejection = AppError;
+    ///     const ERROR_CODE: StatusCode = StatusCode::UNAUTHORIZED;
+    ///     const ERROR_OVERWRITE: Option<&'static str> = None;
+    ///
+    ///     fn from_header(contents: (String, Option<String>)) -> Self {
+    ///         Self(contents)
+    ///     }
+    /// }
+    /// ```
+    type Rejection: IntoResponse + From<Rejection>;
+
     /// Error code to use instead of the typical `400 BAD REQUEST` error
     const ERROR_CODE: StatusCode;
 
     /// Message to overwrite all default ones with if required, leave as [None] ideally
+    ///
+    /// Default messages tell clients whether the header was missing or malformed, which security reviews sometimes flag. Setting this to a single generic message, or `Some("")` for empty bodies, hides this; the hidden reason is still logged at the debug level with the `tracing` feature. Once overwritten, [AuthErrorKind::of](crate::AuthErrorKind::of) can't tell the kind of error anymore.
     const ERROR_OVERWRITE: Option<&'static str>;
 
+    /// Strict mode which rejects credentials with a missing or empty password, defaults to `false`
+    ///
+    /// This is useful for APIs where username-only basic auth is always a client bug. When rejected, the message is "\`Authorization\` header must contain a password".
+    const REQUIRE_PASSWORD: bool = false;
+
+    /// Strict mode which rejects credentials without a colon between the username and password, defaults to `false`
+    ///
+    /// These are invalid according to [RFC 7617](https://www.rfc-editor.org/rfc/rfc7617#section-2), but are accepted as a username without a password by default. When rejected, the message is "\`Authorization\` header must separate the username and password with a colon", which has its own [AuthErrorKind::MissingColon](crate::AuthErrorKind::MissingColon) so clients can tell it apart from headers which couldn't be decoded at all.
+    const REQUIRE_COLON: bool = false;
+
+    /// Normalization applied to the username before it reaches [AuthBasicCustom::from_header], defaults to [UsernameNormalization::NONE]
+    const USERNAME_NORMALIZATION: UsernameNormalization = UsernameNormalization::NONE;
+
     /// Converts provided header contents to new instance of self; you need to implement this
     ///
     /// # Example
@@ -126,12 +547,13 @@ pub trait AuthBasicCustom: Sized {
     /// With the typical `struct BasicExample((String, Option<String>));` pattern of structures, this can be implemented like so:
     ///
     /// ```rust
-    /// use axum_auth::AuthBasicCustom;
+    /// use axum_auth::{AuthBasicCustom, Rejection};
     /// use http::StatusCode;
     ///
     /// struct BasicExample((String, Option<String>));
     ///
     /// impl AuthBasicCustom for BasicExample {
+    ///     type Rejection = Rejection;
     ///     const ERROR_CODE: StatusCode = StatusCode::BAD_REQUEST;
     ///     const ERROR_OVERWRITE: Option<&'static str> = None;
     ///
@@ -145,19 +567,98 @@ pub trait AuthBasicCustom: Sized {
     fn from_header(contents: (String, Option<String>)) -> Self;
 
     /// Decodes bearer token content into new instance of self from axum body parts; this is automatically implemented
-    fn decode_request_parts(req: &mut Parts) -> Result<Self, Rejection> {
-        // Get authorization header
-        let authorization = get_header(req, Self::ERROR_CODE)?;
-
-        // Check that its well-formed basic auth then decode and return
-        let split = authorization.split_once(' ');
-        match split {
-            Some((name, contents)) if name == "Basic" => {
-                let decoded = decode(contents, (Self::ERROR_CODE, ERR_DECODE))?;
-                Ok(Self::from_header(decoded))
+    fn decode_request_parts(req: &mut Parts) -> Result<Self, Self::Rejection> {
+        decode_custom(req).map_err(|rejection| overwrite(rejection, Self::ERROR_OVERWRITE).into())
+    }
+
+    /// Decodes basic auth into new instance of self like [decode_request_parts](Self::decode_request_parts), giving back [None] if there's no header at all; this is automatically implemented
+    fn decode_optional_request_parts(req: &mut Parts) -> Result<Option<Self>, Self::Rejection> {
+        decode_optional(req, decode_custom)
+            .map_err(|rejection| overwrite(rejection, Self::ERROR_OVERWRITE).into())
+    }
+}
+
+/// Decodes basic auth for the custom extractor, before its rejection is converted into its own type
+fn decode_custom<T: AuthBasicCustom>(req: &mut Parts) -> Result<T, Rejection> {
+    // Get authorization header
+    let authorization = get_header(req, T::ERROR_CODE)?;
+
+    // Check that its well-formed basic auth then decode and return
+    let split = authorization.split_once(' ');
+    match split {
+        Some(("Basic", contents)) => {
+            let (id, password) = decode(contents, (T::ERROR_CODE, ERR_DECODE))?;
+
+            // Check the payload had a colon if we're conforming to the spec
+            if T::REQUIRE_COLON && password.is_none() {
+                return Err((T::ERROR_CODE, ERR_NO_COLON));
             }
-            _ => Err((Self::ERROR_CODE, ERR_WRONG_BASIC)),
+
+            let decoded = (T::USERNAME_NORMALIZATION.apply(id), password);
+
+            // Check there's a password if we're strict
+            if T::REQUIRE_PASSWORD && decoded.1.as_deref().unwrap_or_default().is_empty() {
+                return Err((T::ERROR_CODE, ERR_NO_PASSWORD));
+            }
+
+            Ok(T::from_header(decoded))
         }
+        Some(("NTLM" | "Negotiate", _)) => Err((T::ERROR_CODE, ERR_WINDOWS_AUTH)),
+        _ => Err((T::ERROR_CODE, ERR_WRONG_BASIC)),
+    }
+}
+
+/// Normalization steps for basic auth usernames, avoiding duplicate accounts and lookup misses from visually-identical usernames
+///
+/// This is enabled via the `auth-basic` feature
+///
+/// Steps are applied in the order of the fields, so trimming happens first and lowercasing happens last.
+///
+/// # Example
+///
+/// ```rust
+/// use axum_auth::UsernameNormalization;
+///
+/// let normalization = UsernameNormalization {
+///     trim: true,
+///     nfc: true,
+///     lowercase: true,
+/// };
+///
+/// // "e" followed by a combining acute accent becomes a single "é"
+/// assert_eq!(normalization.apply("  Ame\u{301}lie ".to_string()), "amélie");
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct UsernameNormalization {
+    /// Removes whitespace from the start and end
+    pub trim: bool,
+    /// Converts to Unicode normalization form C, so composed and decomposed characters are the same
+    pub nfc: bool,
+    /// Converts to lowercase, for deployments where usernames are case-insensitive
+    pub lowercase: bool,
+}
+
+impl UsernameNormalization {
+    /// Leaves usernames exactly as the client sent them
+    pub const NONE: Self = Self {
+        trim: false,
+        nfc: false,
+        lowercase: false,
+    };
+
+    /// Applies every enabled step to the username
+    pub fn apply(&self, username: String) -> String {
+        let mut username = username;
+        if self.trim {
+            username = username.trim().to_string();
+        }
+        if self.nfc {
+            username = username.nfc().collect();
+        }
+        if self.lowercase {
+            username = username.to_lowercase();
+        }
+        username
     }
 }
 
@@ -166,11 +667,37 @@ fn decode(input: &str, err: Rejection) -> Result<(String, Option<String>), Rejec
     // Decode from base64 into a string
     let decoded = general_purpose::STANDARD.decode(input).map_err(|_| err)?;
     let decoded = String::from_utf8(decoded).map_err(|_| err)?;
+    Ok(split(&decoded))
+}
 
-    // Return depending on if password is present
-    Ok(if let Some((id, password)) = decoded.split_once(':') {
+/// Decodes the two parts of basic auth using the colon, leaving the password as bytes so only the username has to be UTF-8
+fn decode_raw(input: &str, err: Rejection) -> Result<(String, Option<Vec<u8>>), Rejection> {
+    let decoded = general_purpose::STANDARD.decode(input).map_err(|_| err)?;
+    let (id, password) = match decoded.iter().position(|byte| *byte == b':') {
+        Some(colon) => (&decoded[..colon], Some(decoded[colon + 1..].to_vec())),
+        None => (&decoded[..], None),
+    };
+    let id = String::from_utf8(id.to_vec()).map_err(|_| err)?;
+    Ok((id, password))
+}
+
+/// Decodes the two parts of basic auth like browsers send them, which is Latin-1 if it isn't UTF-8
+///
+/// Browsers only use UTF-8 when the challenge asked for it, and some older ones never do, so any byte sequence is accepted as Latin-1 instead of being rejected.
+pub(crate) fn decode_browser(input: &str) -> Option<(String, Option<String>)> {
+    let decoded = general_purpose::STANDARD.decode(input).ok()?;
+    let decoded = match String::from_utf8(decoded) {
+        Ok(decoded) => decoded,
+        Err(err) => err.into_bytes().into_iter().map(char::from).collect(),
+    };
+    Some(split(&decoded))
+}
+
+/// Splits decoded basic auth into the identifier and password, depending on if the password is present
+fn split(decoded: &str) -> (String, Option<String>) {
+    if let Some((id, password)) = decoded.split_once(':') {
         (id.to_string(), Some(password.to_string()))
     } else {
         (decoded.to_string(), None)
-    })
+    }
 }
@@ -2,9 +2,24 @@
 //!
 //! See [AuthBearer] for the most commonly-used data structure
 
-use crate::{Rejection, ERR_CHARS, ERR_DEFAULT, ERR_MISSING, ERR_WRONG_BEARER};
-use axum_core::extract::FromRequestParts;
-use http::{header::AUTHORIZATION, request::Parts, StatusCode};
+#[cfg(feature = "fingerprint")]
+use crate::Fingerprint;
+#[cfg(feature = "uuid")]
+use crate::ERR_NOT_UUID;
+use crate::{
+    credential_header::credential_header,
+    error::{decode_optional, overwrite},
+    AuthScheme, Challenge, Rejection, ERR_CHARS, ERR_DEFAULT, ERR_MALFORMED_TOKEN, ERR_MISSING,
+    ERR_TOKEN_PREFIX, ERR_WEAK_TOKEN, ERR_WINDOWS_AUTH, ERR_WRONG_BEARER,
+};
+#[cfg(feature = "checksum-token")]
+use crate::{ChecksumToken, ERR_TOKEN_CHECKSUM};
+use axum_core::{
+    extract::{FromRequestParts, OptionalFromRequestParts},
+    response::IntoResponse,
+};
+use http::{request::Parts, StatusCode};
+use std::fmt;
 
 /// Bearer token extractor which contains the innards of a bearer header as a string
 ///
@@ -28,11 +43,62 @@ use http::{header::AUTHORIZATION, request::Parts, StatusCode};
 /// There are a few errors which this extractor can make. By default, all invalid responses are `400 BAD REQUEST` with one of these messages:
 ///
 /// - \`Authorization\` header must be a bearer token – Somebody tried to but basic auth here instead of bearer
+/// - \`Authorization\` header uses Windows authentication (NTLM or Negotiate) which isn't supported here – A Windows client tried to log in automatically
 /// - \`Authorization\` header is missing – The header was required but it wasn't found
 /// - \`Authorization\` header contains invalid characters – The header couldn't be processed because of invalid characters
-#[derive(Debug, PartialEq, Eq, Clone)]
+///
+/// # Debugging
+///
+/// The [Debug](fmt::Debug) output of this extractor never contains the token itself, only its length, so it's safe to log. With the `fingerprint` feature, it shows the `Fingerprint` of the token instead, so the same token can be followed across logs. Use [AuthBearer::unredacted] if you really need the full token printed, e.g. inside of tests:
+///
+/// ```
+/// use axum_auth::AuthBearer;
+///
+/// let bearer = AuthBearer("secret".to_string());
+/// # #[cfg(not(feature = "fingerprint"))]
+/// assert_eq!(format!("{:?}", bearer), "AuthBearer([redacted], len: 6)");
+/// assert_eq!(format!("{:?}", bearer.unredacted()), "AuthBearer(\"secret\")");
+/// ```
+#[derive(PartialEq, Eq, Clone, Hash)]
 pub struct AuthBearer(pub String);
 
+impl AuthBearer {
+    /// Opts into the full [Debug](fmt::Debug) output for this token, which is redacted by default
+    ///
+    /// Try to keep this to tests, as anything printed with this will end up in your logs
+    pub fn unredacted(&self) -> impl fmt::Debug + '_ {
+        Unredacted(self)
+    }
+
+    /// Fingerprint of this token which is safe to log, see [Fingerprint] for more info
+    ///
+    /// This is enabled via the `fingerprint` feature alongside a crypto backend
+    #[cfg(feature = "fingerprint")]
+    pub fn fingerprint(&self) -> Fingerprint {
+        Fingerprint::of(&self.0)
+    }
+}
+
+impl fmt::Debug for AuthBearer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut tuple = f.debug_tuple("AuthBearer");
+        #[cfg(feature = "fingerprint")]
+        tuple.field(&self.fingerprint());
+        #[cfg(not(feature = "fingerprint"))]
+        tuple.field(&format_args!("[redacted], len: {}", self.0.len()));
+        tuple.finish()
+    }
+}
+
+/// Full [Debug](fmt::Debug) output for a bearer token, see [AuthBearer::unredacted]
+struct Unredacted<'a>(&'a AuthBearer);
+
+impl fmt::Debug for Unredacted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("AuthBearer").field(&self.0 .0).finish()
+    }
+}
+
 impl<B> FromRequestParts<B> for AuthBearer
 where
     B: Send + Sync,
@@ -44,7 +110,19 @@ where
     }
 }
 
+impl<B> OptionalFromRequestParts<B> for AuthBearer
+where
+    B: Send + Sync,
+{
+    type Rejection = Rejection;
+
+    async fn from_request_parts(parts: &mut Parts, _: &B) -> Result<Option<Self>, Self::Rejection> {
+        Self::decode_optional_request_parts(parts)
+    }
+}
+
 impl AuthBearerCustom for AuthBearer {
+    type Rejection = Rejection;
     const ERROR_CODE: StatusCode = ERR_DEFAULT;
     const ERROR_OVERWRITE: Option<&'static str> = None;
 
@@ -53,6 +131,90 @@ impl AuthBearerCustom for AuthBearer {
     }
 }
 
+impl AuthScheme for AuthBearer {
+    fn challenges() -> Vec<Challenge> {
+        vec![Challenge::bearer()]
+    }
+
+    fn extract(parts: &mut Parts) -> Result<Self, Rejection> {
+        Self::decode_request_parts(parts)
+    }
+}
+
+/// Bearer token extractor for session-token APIs whose tokens are always UUIDs
+///
+/// This is enabled via the `uuid` feature
+///
+/// # Example
+///
+/// ```no_run
+/// use axum_auth::AuthBearerUuid;
+///
+/// /// Looks up the session for the token
+/// async fn handler(AuthBearerUuid(session): AuthBearerUuid) -> String {
+///     format!("Found session {}", session)
+/// }
+/// ```
+///
+/// # Errors
+///
+/// This can make all of the errors [AuthBearer] makes, as well as `400 BAD REQUEST` with the message "\`Authorization\` bearer token must be a UUID" if the token couldn't be parsed.
+///
+/// The [Debug](fmt::Debug) output is redacted in the same way as for [AuthBearer].
+#[cfg(feature = "uuid")]
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub struct AuthBearerUuid(pub uuid::Uuid);
+
+#[cfg(feature = "uuid")]
+impl fmt::Debug for AuthBearerUuid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let token = self.0.to_string();
+        let mut tuple = f.debug_tuple("AuthBearerUuid");
+        #[cfg(feature = "fingerprint")]
+        tuple.field(&Fingerprint::of(&token));
+        #[cfg(not(feature = "fingerprint"))]
+        tuple.field(&format_args!("[redacted], len: {}", token.len()));
+        tuple.finish()
+    }
+}
+
+#[cfg(feature = "uuid")]
+impl<B> FromRequestParts<B> for AuthBearerUuid
+where
+    B: Send + Sync,
+{
+    type Rejection = Rejection;
+
+    async fn from_request_parts(parts: &mut Parts, _: &B) -> Result<Self, Self::Rejection> {
+        Self::extract(parts)
+    }
+}
+
+#[cfg(feature = "uuid")]
+impl<B> OptionalFromRequestParts<B> for AuthBearerUuid
+where
+    B: Send + Sync,
+{
+    type Rejection = Rejection;
+
+    async fn from_request_parts(parts: &mut Parts, _: &B) -> Result<Option<Self>, Self::Rejection> {
+        decode_optional(parts, Self::extract)
+    }
+}
+
+#[cfg(feature = "uuid")]
+impl AuthScheme for AuthBearerUuid {
+    fn challenges() -> Vec<Challenge> {
+        AuthBearer::challenges()
+    }
+
+    fn extract(parts: &mut Parts) -> Result<Self, Rejection> {
+        let AuthBearer(token) = AuthBearer::decode_request_parts(parts)?;
+        let uuid = uuid::Uuid::try_parse(&token).map_err(|_| (ERR_DEFAULT, ERR_NOT_UUID))?;
+        Ok(Self(uuid))
+    }
+}
+
 /// Custom extractor trait for bearer allowing you to implement custom responses
 ///
 /// This is enabled via the `auth-bearer` feature
@@ -81,6 +243,7 @@ impl AuthBearerCustom for AuthBearer {
 ///
 /// // This is where you define your custom options:
 /// impl AuthBearerCustom for MyCustomBearerAuth {
+///     type Rejection = Rejection; // <-- or your own error type, see the docs for this
 ///     const ERROR_CODE: StatusCode = StatusCode::IM_A_TEAPOT; // <-- define custom status code here
 ///     const ERROR_OVERWRITE: Option<&'static str> = None; // <-- define overwriting message here
 ///
@@ -94,7 +257,7 @@ impl AuthBearerCustom for AuthBearer {
 /// where
 ///     B: Send + Sync,
 /// {
-///     type Rejection = Rejection;
+///     type Rejection = Rejection; // <-- or your own error type, see the docs for this
 ///
 ///     async fn from_request_parts(parts: &mut Parts, _: &B) -> Result<Self, Self::Rejection> {
 ///         Self::decode_request_parts(parts)
@@ -106,13 +269,106 @@ impl AuthBearerCustom for AuthBearer {
 ///
 /// - There's no reason for the [FromRequestParts] to ever change out of this pattern unless you're doing something special
 /// - It's recommended to use the `struct BearerExample(String);` pattern because it makes using it from routes easy
+///
+/// # Optional extractors
+///
+/// To let handlers take `Option<MaybeBearer>`, which is [None] when the header is missing but still errors when it's malformed, also implement [OptionalFromRequestParts]:
+///
+/// ```rust
+/// use axum_auth::{AuthBearerCustom, Rejection};
+/// use http::{request::Parts, StatusCode};
+/// use axum::extract::OptionalFromRequestParts;
+///
+/// struct MaybeBearer(String);
+///
+/// impl AuthBearerCustom for MaybeBearer {
+///     type Rejection = Rejection;
+///     const ERROR_CODE: StatusCode = StatusCode::BAD_REQUEST;
+///     const ERROR_OVERWRITE: Option<&'static str> = None;
+///
+///     fn from_header(contents: &str) -> Self {
+///         Self(contents.to_string())
+///     }
+/// }
+///
+/// // Also boilerplate, copy and paste this:
+/// impl<B> OptionalFromRequestParts<B> for MaybeBearer
+/// where
+///     B: Send + Sync,
+/// {
+///     type Rejection = Rejection; // <-- or your own error type, see the docs for this
+///
+///     async fn from_request_parts(parts: &mut Parts, _: &B) -> Result<Option<Self>, Self::Rejection> {
+///         Self::decode_optional_request_parts(parts)
+///     }
+/// }
+/// ```
 pub trait AuthBearerCustom: Sized {
+    /// Rejection given back when decoding fails, which is usually [Rejection](crate::Rejection) but can be an error type of your own which can be made from one, see [AuthBasicCustom::Rejection](crate::AuthBasicCustom::Rejection) for an example
+    type Rejection: IntoResponse + From<Rejection>;
+
     /// Error code to use instead of the typical `400 BAD REQUEST` error
     const ERROR_CODE: StatusCode;
 
     /// Message to overwrite all default ones with if required, leave as [None] ideally
+    ///
+    /// This hides whether the header was missing or malformed, see [AuthBasicCustom::ERROR_OVERWRITE](crate::AuthBasicCustom::ERROR_OVERWRITE) for more information.
     const ERROR_OVERWRITE: Option<&'static str>;
 
+    /// Prefix the token must start with, defaults to [None]
+    ///
+    /// Providers often prefix keys by environment (e.g. `sk_live_` and `sk_test_`), so setting this means a test key sent to production gets `401 UNAUTHORIZED` with the message "\`Authorization\` bearer token has the wrong prefix, check it's meant for this environment" instead of a generic failure. This is checked before the shape, which still applies to the whole token.
+    const REQUIRED_PREFIX: Option<&'static str> = None;
+
+    /// Shape the token must have, defaults to [TokenShape::ANY]
+    ///
+    /// Obviously malformed tokens are rejected with `401 UNAUTHORIZED` and the message "\`Authorization\` bearer token is malformed" before they reach [AuthBearerCustom::from_header], saving a store lookup.
+    const TOKEN_SHAPE: TokenShape = TokenShape::ANY;
+
+    /// Minimum Shannon entropy of the token in bits per character, defaults to [None]
+    ///
+    /// This catches placeholder values like `undefined` or `null` which happen to fit the shape. Random hex tokens are close to 4 bits and random base64 tokens close to 6, so a floor of `3.0` suits most deployments when combined with a minimum length like in [TokenShape::STRICT]. When rejected, the response is `401 UNAUTHORIZED` with the message "\`Authorization\` bearer token is too predictable".
+    const MIN_ENTROPY: Option<f64> = None;
+
+    /// Checksummed format the token must have, defaults to [None]; this is enabled via the `checksum-token` feature
+    ///
+    /// Tokens with typos or made-up checksums are rejected with `401 UNAUTHORIZED` and the message "\`Authorization\` bearer token has an invalid checksum, check it was copied correctly", so they never reach a store or an upstream API like GitHub's. This is checked after [AuthBearerCustom::TOKEN_SHAPE].
+    #[cfg(feature = "checksum-token")]
+    const CHECKSUM_TOKEN: Option<ChecksumToken> = None;
+
+    /// Pattern the token must fully match, defaults to no pattern; this is enabled via the `regex` feature
+    ///
+    /// This is checked after [AuthBearerCustom::TOKEN_SHAPE] and rejects in the same way. Keep the compiled regex in a static so it's only built once:
+    ///
+    /// ```rust
+    /// use axum_auth::{AuthBearerCustom, Rejection};
+    /// use http::StatusCode;
+    /// use regex::Regex;
+    /// use std::sync::LazyLock;
+    ///
+    /// static PATTERN: LazyLock<Regex> = LazyLock::new(|| Regex::new("^tok_[a-z0-9]{32}$").unwrap());
+    ///
+    /// struct BearerExample(String);
+    ///
+    /// impl AuthBearerCustom for BearerExample {
+    ///     type Rejection = Rejection;
+    ///     const ERROR_CODE: StatusCode = StatusCode::BAD_REQUEST;
+    ///     const ERROR_OVERWRITE: Option<&'static str> = None;
+    ///
+    ///     fn token_pattern() -> Option<&'static Regex> {
+    ///         Some(&PATTERN)
+    ///     }
+    ///
+    ///     fn from_header(contents: &str) -> Self {
+    ///         Self(contents.to_string())
+    ///     }
+    /// }
+    /// ```
+    #[cfg(feature = "regex")]
+    fn token_pattern() -> Option<&'static regex::Regex> {
+        None
+    }
+
     /// Converts provided header contents to new instance of self; you need to implement this
     ///
     /// # Example
@@ -120,12 +376,13 @@ pub trait AuthBearerCustom: Sized {
     /// With the typical `struct BearerExample(String);` pattern of structures, this can be implemented like so:
     ///
     /// ```rust
-    /// use axum_auth::AuthBearerCustom;
+    /// use axum_auth::{AuthBearerCustom, Rejection};
     /// use http::StatusCode;
     ///
     /// struct BearerExample(String);
     ///
     /// impl AuthBearerCustom for BearerExample {
+    ///     type Rejection = Rejection;
     ///     const ERROR_CODE: StatusCode = StatusCode::BAD_REQUEST;
     ///     const ERROR_OVERWRITE: Option<&'static str> = None;
     ///
@@ -139,24 +396,166 @@ pub trait AuthBearerCustom: Sized {
     fn from_header(contents: &str) -> Self;
 
     /// Decodes bearer token content into new instance of self from axum body parts; this is automatically implemented
-    fn decode_request_parts(req: &mut Parts) -> Result<Self, Rejection> {
-        // Get authorization header
-        let authorization = req
-            .headers
-            .get(AUTHORIZATION)
-            .ok_or((Self::ERROR_CODE, ERR_MISSING))?
-            .to_str()
-            .map_err(|_| (Self::ERROR_CODE, ERR_CHARS))?;
-
-        // Check that its a well-formed bearer and return
-        let split = authorization.split_once(' ');
-        match split {
-            // Found proper bearer
-            Some((name, contents)) if name == "Bearer" => Ok(Self::from_header(contents)),
-            // Found empty bearer; sometimes request libraries format them as this
-            _ if authorization == "Bearer" => Ok(Self::from_header("")),
-            // Found nothing
-            _ => Err((Self::ERROR_CODE, ERR_WRONG_BEARER)),
+    fn decode_request_parts(req: &mut Parts) -> Result<Self, Self::Rejection> {
+        decode_custom(req).map_err(|rejection| overwrite(rejection, Self::ERROR_OVERWRITE).into())
+    }
+
+    /// Decodes bearer token content into new instance of self like [decode_request_parts](Self::decode_request_parts), giving back [None] if there's no header at all; this is automatically implemented
+    fn decode_optional_request_parts(req: &mut Parts) -> Result<Option<Self>, Self::Rejection> {
+        decode_optional(req, decode_custom)
+            .map_err(|rejection| overwrite(rejection, Self::ERROR_OVERWRITE).into())
+    }
+}
+
+/// Decodes the bearer token for the custom extractor, before its rejection is converted into its own type
+fn decode_custom<T: AuthBearerCustom>(req: &mut Parts) -> Result<T, Rejection> {
+    // Get authorization header
+    let authorization = req
+        .headers
+        .get(credential_header(req))
+        .ok_or((T::ERROR_CODE, ERR_MISSING))?
+        .to_str()
+        .map_err(|_| (T::ERROR_CODE, ERR_CHARS))?;
+
+    // Check that its a well-formed bearer
+    let split = authorization.split_once(' ');
+    let contents = match split {
+        // Found proper bearer
+        Some(("Bearer", contents)) => contents,
+        // Found empty bearer; sometimes request libraries format them as this
+        _ if authorization == "Bearer" => "",
+        // Found windows authentication from a client logging in automatically
+        Some(("NTLM" | "Negotiate", _)) => return Err((T::ERROR_CODE, ERR_WINDOWS_AUTH)),
+        // Found nothing
+        _ => return Err((T::ERROR_CODE, ERR_WRONG_BEARER)),
+    };
+
+    // Check the token looks right before returning
+    if T::REQUIRED_PREFIX.is_some_and(|prefix| !contents.starts_with(prefix)) {
+        return Err((StatusCode::UNAUTHORIZED, ERR_TOKEN_PREFIX));
+    }
+    if !T::TOKEN_SHAPE.matches(contents) {
+        return Err((StatusCode::UNAUTHORIZED, ERR_MALFORMED_TOKEN));
+    }
+    if T::MIN_ENTROPY.is_some_and(|min| entropy(contents) < min) {
+        return Err((StatusCode::UNAUTHORIZED, ERR_WEAK_TOKEN));
+    }
+    #[cfg(feature = "checksum-token")]
+    if T::CHECKSUM_TOKEN.is_some_and(|format| !format.verify(contents)) {
+        return Err((StatusCode::UNAUTHORIZED, ERR_TOKEN_CHECKSUM));
+    }
+    #[cfg(feature = "regex")]
+    if T::token_pattern().is_some_and(|pattern| !pattern.is_match(contents)) {
+        return Err((StatusCode::UNAUTHORIZED, ERR_MALFORMED_TOKEN));
+    }
+    Ok(T::from_header(contents))
+}
+
+/// Length range and character set a token must have, used to reject obviously malformed tokens early
+///
+/// This is enabled via the `auth-bearer` feature
+///
+/// # Example
+///
+/// ```rust
+/// use axum_auth::{TokenCharset, TokenShape};
+///
+/// // Tokens of 32 to 64 hex characters
+/// let shape = TokenShape {
+///     min_len: 32,
+///     max_len: 64,
+///     charset: TokenCharset::Hex,
+/// };
+///
+/// assert!(shape.matches("9f86d081884c7d659a2feaa0c55ad015"));
+/// assert!(!shape.matches("undefined"));
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct TokenShape {
+    /// Minimum length of the token in bytes
+    pub min_len: usize,
+    /// Maximum length of the token in bytes
+    pub max_len: usize,
+    /// Characters allowed in the token
+    pub charset: TokenCharset,
+}
+
+impl TokenShape {
+    /// Accepts any token, even an empty one
+    pub const ANY: Self = Self {
+        min_len: 0,
+        max_len: usize::MAX,
+        charset: TokenCharset::Any,
+    };
+
+    /// Strict shape for generated tokens, needing at least 16 characters using the `token68` syntax
+    pub const STRICT: Self = Self {
+        min_len: 16,
+        max_len: 4096,
+        charset: TokenCharset::Token68,
+    };
+
+    /// Checks if the token has this shape
+    pub fn matches(&self, token: &str) -> bool {
+        (self.min_len..=self.max_len).contains(&token.len()) && self.charset.matches(token)
+    }
+}
+
+/// Set of characters a token may be made of, see [TokenShape]
+///
+/// This is enabled via the `auth-bearer` feature
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TokenCharset {
+    /// Any characters at all
+    Any,
+    /// ASCII letters and digits
+    Alphanumeric,
+    /// Hexadecimal digits in either case
+    Hex,
+    /// Standard base64, with optional `=` padding at the end
+    Base64,
+    /// URL-safe base64, with optional `=` padding at the end
+    Base64Url,
+    /// The `token68` syntax from [RFC 7235](https://www.rfc-editor.org/rfc/rfc7235#section-2.1) which bearer tokens use
+    Token68,
+}
+
+impl TokenCharset {
+    /// Checks if the token is only made of characters from this set
+    pub fn matches(&self, token: &str) -> bool {
+        // Padding is only allowed at the end for the base64-like sets
+        let unpadded = token.trim_end_matches('=');
+        match self {
+            Self::Any => true,
+            Self::Alphanumeric => token.bytes().all(|b| b.is_ascii_alphanumeric()),
+            Self::Hex => token.bytes().all(|b| b.is_ascii_hexdigit()),
+            Self::Base64 => unpadded
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b"+/".contains(&b)),
+            Self::Base64Url => unpadded
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b"-_".contains(&b)),
+            Self::Token68 => unpadded
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b"-._~+/".contains(&b)),
         }
     }
 }
+
+/// Calculates the Shannon entropy of the token in bits per character
+fn entropy(token: &str) -> f64 {
+    let mut counts = [0usize; 256];
+    for byte in token.bytes() {
+        counts[byte as usize] += 1;
+    }
+
+    let len = token.len() as f64;
+    counts
+        .iter()
+        .filter(|count| **count > 0)
+        .map(|count| {
+            let probability = *count as f64 / len;
+            -probability * probability.log2()
+        })
+        .sum()
+}
@@ -0,0 +1,559 @@
+//! Implementation of [RFC 7616](https://www.rfc-editor.org/rfc/rfc7616) HTTP Digest access authentication
+//!
+//! See [AuthDigest] and [DigestAuthLayer] for the most commonly-used data structures
+
+use crate::{
+    challenge::parse_params,
+    constant_eq, crypto, get_header,
+    replay::{signed_nonce, signed_nonce_issued},
+    AuthUser, Challenge, MemoryNonceStore, NonceStore, Rejection, Unauthorized,
+    ERR_DIGEST_MALFORMED, ERR_DIGEST_NONCE, ERR_DIGEST_URI, ERR_INVALID_CREDENTIALS,
+    ERR_WRONG_DIGEST,
+};
+use axum_core::{
+    extract::FromRequestParts,
+    response::{IntoResponse, Response},
+};
+use base64::{engine::general_purpose, Engine};
+use http::{request::Parts, Method, Request, StatusCode};
+use std::{
+    collections::HashMap,
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::{Duration, SystemTime},
+};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// Name of the scheme, which is also what goes into the headers
+const SCHEME: &str = "Digest";
+
+/// Hash algorithm advertised in challenges, which is the only one supported alongside its session variant
+const ALGORITHM: &str = "SHA-256";
+
+/// Session variant of the algorithm, where the password hash is also bound to the nonces
+const ALGORITHM_SESS: &str = "SHA-256-sess";
+
+/// Quality of protection covering the method and uri, which is the only one supported
+const QOP_AUTH: &str = "auth";
+
+/// Digest extractor containing the response a client sent for a challenge, before it has been checked
+///
+/// This is enabled via the `auth-digest` feature alongside a crypto backend
+///
+/// This only parses the `Authorization` header, so it's mostly useful behind a [DigestAuthLayer] which checks the response and sends challenges. To check the response yourself, look up the password hash of the user and give it to [verify](Self::verify).
+///
+/// # Example
+///
+/// ```no_run
+/// use axum_auth::AuthDigest;
+///
+/// /// Handler behind a digest layer, which already checked the response
+/// async fn handler(digest: AuthDigest) -> String {
+///     format!("Hello, {}! You logged in to {}", digest.username, digest.realm)
+/// }
+/// ```
+///
+/// # Errors
+///
+/// There are a few errors which this extractor can make, all of which are `400 BAD REQUEST`:
+///
+/// - \`Authorization\` header must be for digest authentication – Some other scheme was used instead
+/// - \`Authorization\` header isn't a valid digest response – A parameter was missing, or an unsupported algorithm, quality of protection, or `userhash` was used
+/// - \`Authorization\` header is missing – The header was required but it wasn't found
+/// - \`Authorization\` header contains invalid characters – The header couldn't be processed because of invalid characters
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct AuthDigest {
+    /// Username of the client
+    pub username: String,
+    /// Realm the client is logging in to
+    pub realm: String,
+    /// Nonce of the challenge being answered
+    pub nonce: String,
+    /// Request target the response was made for, such as `/dir/index.html`
+    pub uri: String,
+    /// Hex-encoded hash proving the client knows the password
+    pub response: String,
+    /// Whether the session variant of the algorithm was used
+    pub session: bool,
+    /// Nonce picked by the client
+    pub cnonce: String,
+    /// How many times the client has used the nonce, in hex
+    pub nc: String,
+    /// Opaque value of the challenge being answered, if it had one
+    pub opaque: Option<String>,
+}
+
+impl AuthDigest {
+    /// Hashes the username, realm, and password of a user, which is what a [DigestUser] keeps instead of the password
+    pub fn ha1(username: &str, realm: &str, password: &str) -> String {
+        hash(&format!("{}:{}:{}", username, realm, password))
+    }
+
+    /// Checks the response was made for the method using the password hash of the user, in constant time
+    pub fn verify(&self, method: &Method, ha1: &str) -> bool {
+        let ha1 = if self.session {
+            hash(&format!("{}:{}:{}", ha1, self.nonce, self.cnonce))
+        } else {
+            ha1.to_string()
+        };
+        let ha2 = hash(&format!("{}:{}", method, self.uri));
+        let expected = hash(&format!(
+            "{}:{}:{}:{}:{}:{}",
+            ha1, self.nonce, self.nc, self.cnonce, QOP_AUTH, ha2
+        ));
+        constant_eq(expected.as_bytes(), self.response.as_bytes())
+    }
+
+    /// Decodes the digest response from the [Parts] of a request
+    pub fn decode_request_parts(parts: &mut Parts) -> Result<Self, Rejection> {
+        let malformed = (StatusCode::BAD_REQUEST, ERR_DIGEST_MALFORMED);
+
+        // Get the parameters from the header, e.g. `Digest username="alice", ...`
+        let authorization = get_header(parts, StatusCode::BAD_REQUEST)?;
+        let params = match authorization.split_once(' ') {
+            Some((scheme, params)) if scheme.eq_ignore_ascii_case(SCHEME) => parse_params(params),
+            _ => return Err((StatusCode::BAD_REQUEST, ERR_WRONG_DIGEST)),
+        };
+        let mut params = params.into_iter().collect::<HashMap<_, _>>();
+        let mut take = |name: &str| params.remove(name).ok_or(malformed);
+
+        // Only the algorithms and quality of protection in the challenges are supported
+        let session = match take("algorithm")? {
+            algorithm if algorithm.eq_ignore_ascii_case(ALGORITHM) => false,
+            algorithm if algorithm.eq_ignore_ascii_case(ALGORITHM_SESS) => true,
+            _ => return Err(malformed),
+        };
+        if take("qop")? != QOP_AUTH || take("userhash").is_ok_and(|userhash| userhash != "false") {
+            return Err(malformed);
+        }
+        Ok(Self {
+            username: take("username")?,
+            realm: take("realm")?,
+            nonce: take("nonce")?,
+            uri: take("uri")?,
+            response: take("response")?.to_ascii_lowercase(),
+            session,
+            cnonce: take("cnonce")?,
+            nc: take("nc")?,
+            opaque: take("opaque").ok(),
+        })
+    }
+}
+
+impl<B> FromRequestParts<B> for AuthDigest
+where
+    B: Send + Sync,
+{
+    type Rejection = Rejection;
+
+    async fn from_request_parts(parts: &mut Parts, _: &B) -> Result<Self, Self::Rejection> {
+        Self::decode_request_parts(parts)
+    }
+}
+
+/// User which can log in using digest auth, keeping the hash of their password alongside the identity they log in as
+///
+/// This is enabled via the `auth-digest` feature alongside a crypto backend
+///
+/// Digest auth needs the password of the user to check a response, so the hash of the username, realm, and password (called `HA1` by the RFC) is kept instead, which only works for that realm. The hash is redacted from the [Debug](fmt::Debug) output so stores can be logged safely.
+#[derive(PartialEq, Eq, Clone)]
+pub struct DigestUser {
+    ha1: String,
+    user: AuthUser,
+}
+
+impl DigestUser {
+    /// Creates a new user from the password hash made by [AuthDigest::ha1], logging in as the given user
+    pub fn new(ha1: impl Into<String>, user: AuthUser) -> Self {
+        Self {
+            ha1: ha1.into(),
+            user,
+        }
+    }
+
+    /// Creates a new user from their password for the realm, logging in as a user with the same username
+    pub fn from_password(username: &str, realm: &str, password: &str) -> Self {
+        Self::new(
+            AuthDigest::ha1(username, realm, password),
+            AuthUser::new(username),
+        )
+    }
+}
+
+impl fmt::Debug for DigestUser {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DigestUser")
+            .field("ha1", &"[redacted]")
+            .field("user", &self.user)
+            .finish()
+    }
+}
+
+/// Source of the users which can log in using digest auth, looked up by their username and the realm
+///
+/// The in-memory [DigestUsers] works for users known upfront, implement this yourself to look users up in a database.
+pub trait DigestUserStore: Send + Sync + 'static {
+    /// Looks up the user, giving [None] if they don't exist; you need to implement this
+    fn user(&self, username: &str, realm: &str) -> impl Future<Output = Option<DigestUser>> + Send;
+}
+
+/// In-memory [DigestUserStore] of users known upfront, for a single realm
+///
+/// This is enabled via the `auth-digest` feature alongside a crypto backend
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct DigestUsers {
+    users: HashMap<String, DigestUser>,
+}
+
+impl DigestUsers {
+    /// Creates a new empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a user to the store, returning it back
+    pub fn with_user(mut self, username: impl Into<String>, user: DigestUser) -> Self {
+        self.insert(username, user);
+        self
+    }
+
+    /// Adds a user to the store in-place, replacing any existing user with the same username
+    pub fn insert(&mut self, username: impl Into<String>, user: DigestUser) {
+        self.users.insert(username.into(), user);
+    }
+
+    /// Removes a user from the store, giving it back if it existed
+    pub fn remove(&mut self, username: &str) -> Option<DigestUser> {
+        self.users.remove(username)
+    }
+}
+
+impl DigestUserStore for DigestUsers {
+    async fn user(&self, username: &str, _: &str) -> Option<DigestUser> {
+        self.users.get(username).cloned()
+    }
+}
+
+/// Nonces and opaque values handed out in digest challenges, and checked when clients answer them
+///
+/// This is enabled via the `auth-digest` feature alongside a crypto backend
+///
+/// Nonces are stateless, carrying when they were made and signed by the server using a secret, so they don't have to be remembered until they're answered. Once a nonce is older than the max age, clients are told it's stale so they answer a new one without asking the user again. Every nonce count a client uses is remembered in a [NonceStore] until the nonce expires, so captured responses can't be sent again.
+///
+/// The opaque value is sent back unchanged by clients, and responses with any other one are rejected.
+///
+/// # Example
+///
+/// ```rust
+/// use axum_auth::DigestNonces;
+/// use std::time::Duration;
+///
+/// /// Nonces which work on every server and last for 10 minutes
+/// let nonces = DigestNonces::new()
+///     .with_secret("shared-secret-of-every-server")
+///     .with_opaque("shared-opaque-of-every-server")
+///     .with_max_age(Duration::from_secs(10 * 60));
+/// ```
+pub struct DigestNonces<N = MemoryNonceStore> {
+    store: Arc<N>,
+    secret: Arc<[u8]>,
+    opaque: Arc<str>,
+    max_age: Duration,
+}
+
+impl DigestNonces {
+    /// Creates new nonces using an in-memory nonce store, with a random secret and opaque value
+    ///
+    /// The random secret means nonces only work on the server which made them, use [with_secret](Self::with_secret) and [with_opaque](Self::with_opaque) if you have many servers.
+    pub fn new() -> Self {
+        Self {
+            store: Arc::new(MemoryNonceStore::new()),
+            secret: Arc::from(crypto::random(32)),
+            opaque: Arc::from(general_purpose::URL_SAFE_NO_PAD.encode(crypto::random(16))),
+            max_age: Self::DEFAULT_MAX_AGE,
+        }
+    }
+}
+
+impl<N> DigestNonces<N> {
+    /// Default amount of time nonces can be used for, which is 5 minutes
+    pub const DEFAULT_MAX_AGE: Duration = Duration::from_secs(5 * 60);
+
+    /// Sets the secret nonces are signed with, which has to be the same on every server, returning it back
+    pub fn with_secret(mut self, secret: impl AsRef<[u8]>) -> Self {
+        self.secret = Arc::from(secret.as_ref());
+        self
+    }
+
+    /// Sets the opaque value sent in challenges, which has to be the same on every server, returning it back
+    pub fn with_opaque(mut self, opaque: impl AsRef<str>) -> Self {
+        self.opaque = Arc::from(opaque.as_ref());
+        self
+    }
+
+    /// Sets how long nonces can be used for before clients are told they're stale, returning it back
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    /// Sets the store remembering which nonce counts have been used, returning it back
+    pub fn with_nonce_store<N2>(self, store: N2) -> DigestNonces<N2> {
+        DigestNonces {
+            store: Arc::new(store),
+            secret: self.secret,
+            opaque: self.opaque,
+            max_age: self.max_age,
+        }
+    }
+
+    /// Makes a fresh nonce, signed so it doesn't have to be remembered
+    fn generate(&self) -> String {
+        signed_nonce(&self.secret)
+    }
+
+    /// Gets when the nonce expires if it was made by us, even if that has already passed
+    fn expires(&self, nonce: &str) -> Option<SystemTime> {
+        signed_nonce_issued(&self.secret, nonce)?.checked_add(self.max_age)
+    }
+}
+
+impl Default for DigestNonces {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<N> Clone for DigestNonces<N> {
+    fn clone(&self) -> Self {
+        Self {
+            store: self.store.clone(),
+            secret: self.secret.clone(),
+            opaque: self.opaque.clone(),
+            max_age: self.max_age,
+        }
+    }
+}
+
+impl<N> fmt::Debug for DigestNonces<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DigestNonces")
+            .field("opaque", &self.opaque)
+            .field("max_age", &self.max_age)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Layer authenticating every request using digest auth, where clients prove they know the password without sending it
+///
+/// This is enabled via the `auth-digest` feature alongside a crypto backend
+///
+/// Requests without a valid response get a `401 UNAUTHORIZED` with a fresh challenge from the [DigestNonces], which browsers answer by asking the user for their password. Responses are checked against the password hash from the [DigestUserStore], and once authenticated, the [AuthUser] of the user is inserted into the request extensions.
+///
+/// Only the `SHA-256` and `SHA-256-sess` algorithms with the `auth` quality of protection are supported, as `MD5` is broken. Usernames can't be hashed using `userhash`.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{routing::get, Router};
+/// use axum_auth::{AuthUser, DigestAuthLayer, DigestUser, DigestUsers};
+///
+/// let users = DigestUsers::new().with_user(
+///     "alice",
+///     DigestUser::from_password("alice", "Admin area", "hunter2"),
+/// );
+///
+/// let app: Router = Router::new()
+///     .route("/", get(|user: AuthUser| async move { format!("Hello, {}!", user.id) }))
+///     .layer(DigestAuthLayer::new("Admin area", users));
+/// ```
+///
+/// # Errors
+///
+/// All errors are `401 UNAUTHORIZED` with a fresh challenge, alongside one of these messages:
+///
+/// - \`Authorization\` header must be for digest authentication – Some other scheme was used instead
+/// - \`Authorization\` header isn't a valid digest response – A parameter was missing, or an unsupported algorithm, quality of protection, or `userhash` was used
+/// - Digest response was made for another request – The `uri` wasn't the target of the request
+/// - Digest nonce has expired or was already used – The nonce wasn't made by this server, its count was used before, or it's too old, in which case the challenge is marked as `stale`
+/// - The provided credentials are invalid – The user doesn't exist, the realm was wrong, or the response didn't match the password
+/// - \`Authorization\` header is missing – The header was required but it wasn't found
+/// - \`Authorization\` header contains invalid characters – The header couldn't be processed because of invalid characters
+pub struct DigestAuthLayer<U, N = MemoryNonceStore> {
+    users: Arc<U>,
+    nonces: DigestNonces<N>,
+    realm: Arc<str>,
+}
+
+impl<U> DigestAuthLayer<U> {
+    /// Creates a new layer for the realm using the given users, with [DigestNonces::new] for the nonces
+    pub fn new(realm: impl AsRef<str>, users: U) -> Self {
+        Self {
+            users: Arc::new(users),
+            nonces: DigestNonces::new(),
+            realm: Arc::from(realm.as_ref()),
+        }
+    }
+}
+
+impl<U, N> DigestAuthLayer<U, N> {
+    /// Sets the nonces handed out in challenges, returning it back
+    pub fn with_nonces<N2>(self, nonces: DigestNonces<N2>) -> DigestAuthLayer<U, N2> {
+        DigestAuthLayer {
+            users: self.users,
+            nonces,
+            realm: self.realm,
+        }
+    }
+}
+
+impl<U, N> Clone for DigestAuthLayer<U, N> {
+    fn clone(&self) -> Self {
+        Self {
+            users: self.users.clone(),
+            nonces: self.nonces.clone(),
+            realm: self.realm.clone(),
+        }
+    }
+}
+
+impl<S, U, N> Layer<S> for DigestAuthLayer<U, N> {
+    type Service = DigestAuth<S, U, N>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        DigestAuth {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+/// Service created by [DigestAuthLayer], see it for more information
+pub struct DigestAuth<S, U, N> {
+    inner: S,
+    layer: DigestAuthLayer<U, N>,
+}
+
+impl<S: Clone, U, N> Clone for DigestAuth<S, U, N> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            layer: self.layer.clone(),
+        }
+    }
+}
+
+impl<S, U, N, B> Service<Request<B>> for DigestAuth<S, U, N>
+where
+    S: Service<Request<B>, Response = Response> + Clone + Send + 'static,
+    S::Future: Send,
+    U: DigestUserStore,
+    N: NonceStore,
+    B: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<B>) -> Self::Future {
+        // Take the service which was driven to readiness, leaving a fresh clone behind
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let layer = self.layer.clone();
+
+        Box::pin(async move {
+            let (mut parts, body) = req.into_parts();
+
+            // Verify the response and keep the identity for later
+            match verify(&layer, &mut parts).await {
+                Ok(user) => {
+                    parts.extensions.insert(user);
+                    inner.call(Request::from_parts(parts, body)).await
+                }
+                Err((message, stale)) => Ok(Unauthorized::new()
+                    .with_challenge(challenge(&layer, stale))
+                    .with_message(message)
+                    .into_response()),
+            }
+        })
+    }
+}
+
+/// Makes a fresh challenge, marked as stale if the client only needs to answer it again with the same password
+fn challenge<U, N>(layer: &DigestAuthLayer<U, N>, stale: bool) -> Challenge {
+    let challenge = Challenge::new(SCHEME)
+        .with_param("realm", &*layer.realm)
+        .with_param("qop", QOP_AUTH)
+        .with_param("algorithm", ALGORITHM)
+        .with_param("nonce", layer.nonces.generate())
+        .with_param("opaque", &*layer.nonces.opaque)
+        .with_param("charset", "UTF-8");
+    if stale {
+        challenge.with_param("stale", "true")
+    } else {
+        challenge
+    }
+}
+
+/// Checks the response in the header, giving back the identity of the user or the message and whether the nonce was stale
+async fn verify<U: DigestUserStore, N: NonceStore>(
+    layer: &DigestAuthLayer<U, N>,
+    parts: &mut Parts,
+) -> Result<AuthUser, (&'static str, bool)> {
+    let digest = AuthDigest::decode_request_parts(parts).map_err(|(_, msg)| (msg, false))?;
+
+    // Check the response was made for this request and realm
+    let target = parts
+        .uri
+        .path_and_query()
+        .map_or(parts.uri.path(), |target| target.as_str());
+    if digest.uri != target {
+        return Err((ERR_DIGEST_URI, false));
+    }
+    if digest.realm != *layer.realm {
+        return Err((ERR_INVALID_CREDENTIALS, false));
+    }
+
+    // Check the client knows the password before saying anything about the nonce
+    let user = layer
+        .users
+        .user(&digest.username, &layer.realm)
+        .await
+        .ok_or((ERR_INVALID_CREDENTIALS, false))?;
+    if !digest.verify(&parts.method, &user.ha1) {
+        return Err((ERR_INVALID_CREDENTIALS, false));
+    }
+
+    // Check the nonce was made by us, is recent, and this count hasn't been used before
+    let nonces = &layer.nonces;
+    let expires = nonces
+        .expires(&digest.nonce)
+        .ok_or((ERR_DIGEST_NONCE, false))?;
+    if digest.opaque.as_deref() != Some(&*nonces.opaque) {
+        return Err((ERR_DIGEST_NONCE, false));
+    }
+    if expires < SystemTime::now() {
+        return Err((ERR_DIGEST_NONCE, true));
+    }
+    let count = format!("{}:{}", digest.nonce, digest.nc.to_ascii_lowercase());
+    if !nonces.store.insert(&count, expires).await {
+        return Err((ERR_DIGEST_NONCE, false));
+    }
+    Ok(user.user)
+}
+
+/// Hashes the data using SHA-256, giving it back in lowercase hex like digest auth uses
+fn hash(data: &str) -> String {
+    crypto::sha256(data.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
@@ -0,0 +1,83 @@
+//! Implementation of the `Negotiate` scheme used for SPNEGO/Kerberos single sign-on
+//!
+//! See [AuthNegotiate] for the most commonly-used data structure
+
+use crate::{
+    get_header, AuthScheme, Challenge, Rejection, Unauthorized, ERR_DECODE, ERR_WRONG_NEGOTIATE,
+};
+use axum_core::extract::FromRequestParts;
+use base64::{engine::general_purpose, Engine};
+use http::{request::Parts, StatusCode};
+
+/// Negotiate extractor containing the raw GSSAPI token sent by the client, used for Kerberos single sign-on in intranets
+///
+/// This is enabled via the `auth-negotiate` feature
+///
+/// Browsers only attempt Kerberos once they're challenged, so every rejection is a `401 UNAUTHORIZED` with `WWW-Authenticate: Negotiate`. The token itself should be handed to your GSSAPI library of choice (e.g. [libgssapi](https://docs.rs/libgssapi)), which is best done by implementing an [AuthValidator](crate::AuthValidator) for this extractor and using it in a [RequireAuthLayer](crate::RequireAuthLayer).
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{routing::get, Router};
+/// use axum_auth::{AuthNegotiate, AuthUser, AuthValidator, Rejection, RequireAuthLayer};
+///
+/// /// Accepts Kerberos tickets using the keytab of the server
+/// struct Kerberos;
+///
+/// impl AuthValidator<AuthNegotiate> for Kerberos {
+///     async fn validate(&self, credentials: &AuthNegotiate) -> Result<AuthUser, Rejection> {
+///         // Pass `credentials.0` to the security context of your GSSAPI library here
+///         Ok(AuthUser::new("alice@EXAMPLE.COM"))
+///     }
+/// }
+///
+/// let app: Router = Router::new()
+///     .route("/", get(|user: AuthUser| async move { format!("Hello, {}!", user.id) }))
+///     .layer(RequireAuthLayer::<AuthNegotiate, _>::new(Kerberos));
+/// ```
+///
+/// # Errors
+///
+/// There are a few errors which this extractor can make, all of which are `401 UNAUTHORIZED` with a `Negotiate` challenge:
+///
+/// - \`Authorization\` header must be for negotiate authentication – Some other scheme was used instead
+/// - \`Authorization\` header could not be decoded – The token wasn't valid base64
+/// - \`Authorization\` header is missing – The header was required but it wasn't found
+/// - \`Authorization\` header contains invalid characters – The header couldn't be processed because of invalid characters
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct AuthNegotiate(pub Vec<u8>);
+
+impl<B> FromRequestParts<B> for AuthNegotiate
+where
+    B: Send + Sync,
+{
+    type Rejection = Unauthorized;
+
+    async fn from_request_parts(parts: &mut Parts, _: &B) -> Result<Self, Self::Rejection> {
+        Self::extract(parts).map_err(|(_, message)| {
+            Unauthorized::new()
+                .with_challenges(Self::challenges())
+                .with_message(message)
+        })
+    }
+}
+
+impl AuthScheme for AuthNegotiate {
+    fn challenges() -> Vec<Challenge> {
+        vec![Challenge::new("Negotiate")]
+    }
+
+    fn extract(parts: &mut Parts) -> Result<Self, Rejection> {
+        // Get authorization header
+        let authorization = get_header(parts, StatusCode::UNAUTHORIZED)?;
+
+        // Check that its a negotiate token then decode and return
+        match authorization.split_once(' ') {
+            Some(("Negotiate", contents)) => general_purpose::STANDARD
+                .decode(contents.trim())
+                .map(Self)
+                .map_err(|_| (StatusCode::UNAUTHORIZED, ERR_DECODE)),
+            _ => Err((StatusCode::UNAUTHORIZED, ERR_WRONG_NEGOTIATE)),
+        }
+    }
+}
@@ -0,0 +1,123 @@
+//! Implementation of the staged NTLM handshake used by Windows clients
+//!
+//! See [AuthNtlm] for the most commonly-used data structure
+
+use crate::{
+    get_header, AuthScheme, Challenge, Rejection, Unauthorized, ERR_DECODE, ERR_NTLM_MESSAGE,
+    ERR_WRONG_NTLM,
+};
+use axum_core::extract::FromRequestParts;
+use base64::{engine::general_purpose, Engine};
+use http::{request::Parts, StatusCode};
+
+/// Signature every NTLM message starts with
+const SIGNATURE: &[u8] = b"NTLMSSP\0";
+
+/// NTLM extractor containing a raw message sent by a Windows client, letting handlers take part in the multi-round handshake
+///
+/// This is enabled via the `auth-ntlm` feature
+///
+/// NTLM takes two rounds, both of which arrive here:
+///
+/// 1. The client sends a [NtlmStage::Negotiate] message, which your NTLM library answers with a challenge message; send it back using [AuthNtlm::challenge]
+/// 2. The client sends a [NtlmStage::Authenticate] message answering that challenge, which your NTLM library verifies
+///
+/// Messages are accepted using both the `NTLM` scheme and the `Negotiate` scheme, because browsers fall back to NTLM inside `Negotiate` when Kerberos isn't available. NTLM authenticates the connection rather than the request, so the same connection has to be used for both rounds; make sure nothing in front of your server pools connections between clients.
+///
+/// # Example
+///
+/// ```no_run
+/// use axum::response::{IntoResponse, Response};
+/// use axum_auth::{AuthNtlm, NtlmStage};
+///
+/// /// Takes part in the handshake using an NTLM library
+/// async fn handler(ntlm: AuthNtlm) -> Response {
+///     match ntlm.stage {
+///         NtlmStage::Negotiate => {
+///             let challenge = vec![]; // <-- create the challenge message from `ntlm.message` here
+///             AuthNtlm::challenge(&challenge).into_response()
+///         }
+///         NtlmStage::Authenticate => {
+///             // Verify `ntlm.message` against the challenge you sent here
+///             "Welcome!".into_response()
+///         }
+///     }
+/// }
+/// ```
+///
+/// # Errors
+///
+/// There are a few errors which this extractor can make, all of which are `401 UNAUTHORIZED` with an `NTLM` challenge:
+///
+/// - \`Authorization\` header must be for NTLM authentication – Some other scheme was used instead
+/// - \`Authorization\` header isn't a valid NTLM negotiate or authenticate message – The message was malformed or a challenge message
+/// - \`Authorization\` header could not be decoded – The message wasn't valid base64
+/// - \`Authorization\` header is missing – The header was required but it wasn't found
+/// - \`Authorization\` header contains invalid characters – The header couldn't be processed because of invalid characters
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct AuthNtlm {
+    /// Stage of the handshake the client is at
+    pub stage: NtlmStage,
+    /// Entire raw message, including its signature and type
+    pub message: Vec<u8>,
+}
+
+impl AuthNtlm {
+    /// Creates the `401 UNAUTHORIZED` response carrying the challenge message for the second round
+    pub fn challenge(message: &[u8]) -> Unauthorized {
+        Unauthorized::new().with_challenge(
+            Challenge::new("NTLM").with_token68(general_purpose::STANDARD.encode(message)),
+        )
+    }
+}
+
+/// Stage of the NTLM handshake a client message belongs to
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum NtlmStage {
+    /// First message from the client, known as type 1, asking for a challenge
+    Negotiate,
+    /// Last message from the client, known as type 3, answering the challenge
+    Authenticate,
+}
+
+impl<B> FromRequestParts<B> for AuthNtlm
+where
+    B: Send + Sync,
+{
+    type Rejection = Unauthorized;
+
+    async fn from_request_parts(parts: &mut Parts, _: &B) -> Result<Self, Self::Rejection> {
+        Self::extract(parts).map_err(|(_, message)| {
+            Unauthorized::new()
+                .with_challenges(Self::challenges())
+                .with_message(message)
+        })
+    }
+}
+
+impl AuthScheme for AuthNtlm {
+    fn challenges() -> Vec<Challenge> {
+        vec![Challenge::new("NTLM")]
+    }
+
+    fn extract(parts: &mut Parts) -> Result<Self, Rejection> {
+        // Get authorization header
+        let authorization = get_header(parts, StatusCode::UNAUTHORIZED)?;
+
+        // Check that its an ntlm message and decode it
+        let message = match authorization.split_once(' ') {
+            Some(("NTLM" | "Negotiate", contents)) => general_purpose::STANDARD
+                .decode(contents.trim())
+                .map_err(|_| (StatusCode::UNAUTHORIZED, ERR_DECODE))?,
+            _ => return Err((StatusCode::UNAUTHORIZED, ERR_WRONG_NTLM)),
+        };
+
+        // Find the stage from the message type following the signature
+        let stage = match message.strip_prefix(SIGNATURE) {
+            Some([1, 0, 0, 0, ..]) => NtlmStage::Negotiate,
+            Some([3, 0, 0, 0, ..]) => NtlmStage::Authenticate,
+            _ => return Err((StatusCode::UNAUTHORIZED, ERR_NTLM_MESSAGE)),
+        };
+        Ok(Self { stage, message })
+    }
+}
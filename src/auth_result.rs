@@ -0,0 +1,75 @@
+//! Implementation of the soft-fail extractor which never rejects
+//!
+//! See [AuthResult] for the most commonly-used data structure
+
+use crate::{error::rejection_response, AuthErrorKind};
+use axum_core::extract::FromRequestParts;
+use http::request::Parts;
+use std::{convert::Infallible, ops::Deref};
+
+/// Soft-fail extractor wrapping another extractor, which never rejects and instead gives the handler the credentials or the [AuthErrorKind] of why there weren't any
+///
+/// This suits endpoints with their own fallback, like showing a public view to visitors who aren't logged in, while still telling an expired login apart from a missing one. The kind is also stored in the request extensions like with optional extractors, so later extractors and middleware can see it.
+///
+/// # Example
+///
+/// ```no_run
+/// use axum_auth::{AuthBearer, AuthErrorKind, AuthResult};
+///
+/// async fn handler(AuthResult(auth): AuthResult<AuthBearer>) -> String {
+///     match auth {
+///         Ok(AuthBearer(token)) => format!("Private view for {}", token),
+///         Err(AuthErrorKind::Expired) => "Your login has expired, public view".to_string(),
+///         Err(_) => "Public view".to_string(),
+///     }
+/// }
+/// ```
+///
+/// Rejections made outside of this crate which aren't tagged with a kind become [AuthErrorKind::Other].
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct AuthResult<T>(pub Result<T, AuthErrorKind>);
+
+impl<T> AuthResult<T> {
+    /// Gives back the inner result
+    pub fn into_inner(self) -> Result<T, AuthErrorKind> {
+        self.0
+    }
+}
+
+impl<T> Deref for AuthResult<T> {
+    type Target = Result<T, AuthErrorKind>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> From<AuthResult<T>> for Result<T, AuthErrorKind> {
+    fn from(result: AuthResult<T>) -> Self {
+        result.0
+    }
+}
+
+impl<T, B> FromRequestParts<B> for AuthResult<T>
+where
+    T: FromRequestParts<B> + Send,
+    T::Rejection: 'static,
+    B: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &B) -> Result<Self, Self::Rejection> {
+        match T::from_request_parts(parts, state).await {
+            Ok(credentials) => Ok(Self(Ok(credentials))),
+            Err(rejection) => {
+                let kind = rejection_response(rejection)
+                    .extensions()
+                    .get::<AuthErrorKind>()
+                    .copied()
+                    .unwrap_or(AuthErrorKind::Other);
+                parts.extensions.insert(kind);
+                Ok(Self(Err(kind)))
+            }
+        }
+    }
+}
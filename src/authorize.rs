@@ -0,0 +1,177 @@
+//! Implementation of the policy hook which runs after authentication
+//!
+//! See [Authorizer] and [AuthorizeLayer] for the most commonly-used data structures
+
+use crate::{error::rejection_response, AuthUser, ERR_FORBIDDEN, ERR_NO_USER};
+use axum_core::response::Response;
+use http::{request::Parts, Request, StatusCode};
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// Outcome of an [Authorizer] deciding on a request
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Decision {
+    /// The user may continue on to the handler
+    Allow,
+    /// The user is rejected with `403 FORBIDDEN`
+    Deny,
+}
+
+impl From<bool> for Decision {
+    fn from(allowed: bool) -> Self {
+        if allowed {
+            Self::Allow
+        } else {
+            Self::Deny
+        }
+    }
+}
+
+/// Policy hook deciding if an authenticated user may access a request, letting you plug external policy engines in
+///
+/// # Example
+///
+/// This is what a simple authorizer only allowing admins to write looks like:
+///
+/// ```rust
+/// use axum_auth::{AuthUser, Authorizer, Decision};
+/// use http::{request::Parts, Method};
+///
+/// /// Lets everyone read but only admins write
+/// struct AdminWrites;
+///
+/// impl Authorizer for AdminWrites {
+///     async fn authorize(&self, user: &AuthUser, req: &Parts) -> Decision {
+///         (req.method == Method::GET || user.has_role("admin")).into()
+///     }
+/// }
+/// ```
+///
+/// To use it, wrap your routes in an [AuthorizeLayer] after the layer or middleware which establishes the [AuthUser].
+pub trait Authorizer: Send + Sync + 'static {
+    /// Decides if the given user may access the request; you need to implement this
+    fn authorize(&self, user: &AuthUser, req: &Parts) -> impl Future<Output = Decision> + Send;
+}
+
+/// Layer running an [Authorizer] for every request once the identity has been established
+///
+/// This doesn't depend on any auth scheme, so anything which inserts an [AuthUser] into the request extensions beforehand works with it.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{routing::get, Router};
+/// use axum_auth::{AuthUser, AuthorizeLayer, Authorizer, Decision};
+/// use http::request::Parts;
+///
+/// struct OnlyAdmins;
+///
+/// impl Authorizer for OnlyAdmins {
+///     async fn authorize(&self, user: &AuthUser, _: &Parts) -> Decision {
+///         user.has_role("admin").into()
+///     }
+/// }
+///
+/// let app: Router = Router::new()
+///     .route("/", get(|| async { "Hello, admin!" }))
+///     .layer(AuthorizeLayer::new(OnlyAdmins));
+///     // <-- add your identity layer here so it runs first
+/// ```
+///
+/// # Errors
+///
+/// There are a few errors which this layer can make:
+///
+/// - `401 UNAUTHORIZED` with "No authenticated user was found for this request" – No [AuthUser] was inserted before this layer
+/// - `403 FORBIDDEN` with "You are not allowed to access this resource" – The [Authorizer] gave a [Decision::Deny]
+pub struct AuthorizeLayer<Z> {
+    authorizer: Arc<Z>,
+}
+
+impl<Z> AuthorizeLayer<Z> {
+    /// Creates a new layer from the given authorizer
+    pub fn new(authorizer: Z) -> Self {
+        Self {
+            authorizer: Arc::new(authorizer),
+        }
+    }
+}
+
+impl<Z> Clone for AuthorizeLayer<Z> {
+    fn clone(&self) -> Self {
+        Self {
+            authorizer: self.authorizer.clone(),
+        }
+    }
+}
+
+impl<S, Z> Layer<S> for AuthorizeLayer<Z> {
+    type Service = Authorize<S, Z>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Authorize {
+            inner,
+            authorizer: self.authorizer.clone(),
+        }
+    }
+}
+
+/// Service created by [AuthorizeLayer], see it for more information
+pub struct Authorize<S, Z> {
+    inner: S,
+    authorizer: Arc<Z>,
+}
+
+impl<S: Clone, Z> Clone for Authorize<S, Z> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            authorizer: self.authorizer.clone(),
+        }
+    }
+}
+
+impl<S, Z, B> Service<Request<B>> for Authorize<S, Z>
+where
+    S: Service<Request<B>, Response = Response> + Clone + Send + 'static,
+    S::Future: Send,
+    Z: Authorizer,
+    B: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<B>) -> Self::Future {
+        // Take the service which was driven to readiness, leaving a fresh clone behind
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let authorizer = self.authorizer.clone();
+
+        Box::pin(async move {
+            let (parts, body) = req.into_parts();
+
+            // Ask the authorizer about the established identity
+            let decision = match parts.extensions.get::<AuthUser>() {
+                Some(user) => authorizer.authorize(user, &parts).await,
+                None => return Ok(rejection_response((StatusCode::UNAUTHORIZED, ERR_NO_USER))),
+            };
+
+            // Continue or reject depending on the decision
+            match decision {
+                Decision::Allow => inner.call(Request::from_parts(parts, body)).await,
+                Decision::Deny => Ok(rejection_response((StatusCode::FORBIDDEN, ERR_FORBIDDEN))),
+            }
+        })
+    }
+}
@@ -0,0 +1,319 @@
+//! Implementation of binding tokens to the device or client certificate they were issued for
+//!
+//! See [DeviceBound] for the most commonly-used data structure
+
+use crate::{
+    constant_eq, crypto, AuthJwt, AuthUser, AuthValidator, Rejection, ERR_BINDING_MISMATCH,
+    ERR_CERT_MISMATCH, ERR_NO_CLIENT_CERT,
+};
+use axum_core::extract::FromRequestParts;
+use base64::{engine::general_purpose, Engine};
+use http::{request::Parts, Extensions, HeaderMap, HeaderName, StatusCode};
+use serde_json::Value;
+use std::fmt;
+
+/// Certificate the client presented during the TLS handshake, used to check certificate-bound tokens
+///
+/// This is enabled via the `jwt` feature alongside a crypto backend
+///
+/// Axum doesn't terminate TLS itself, so whatever accepts the connection has to insert this into the request extensions, like a `tower` middleware reading the peer certificates of a `rustls` connection, or one parsing the certificate a reverse proxy forwards using [from_pem](Self::from_pem).
+///
+/// # Example
+///
+/// ```no_run
+/// use axum_auth::ClientCertificate;
+///
+/// async fn handler(cert: ClientCertificate) -> String {
+///     format!("Client certificate: {}", cert.thumbprint())
+/// }
+/// ```
+///
+/// # Errors
+///
+/// If no certificate was inserted into the request, this extractor responds with `401 UNAUTHORIZED` and the message "No client certificate was presented".
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct ClientCertificate(pub Vec<u8>);
+
+impl ClientCertificate {
+    /// Parses a PEM-encoded certificate, like one forwarded by a reverse proxy, giving back [None] if it isn't one
+    pub fn from_pem(pem: &str) -> Option<Self> {
+        let body = pem
+            .trim()
+            .strip_prefix("-----BEGIN CERTIFICATE-----")?
+            .strip_suffix("-----END CERTIFICATE-----")?;
+        let body: String = body.split_whitespace().collect();
+        general_purpose::STANDARD.decode(body).ok().map(Self)
+    }
+
+    /// Gets the SHA-256 thumbprint of the DER-encoded certificate as unpadded base64url, which is how `x5t#S256` confirmations are written
+    pub fn thumbprint(&self) -> String {
+        general_purpose::URL_SAFE_NO_PAD.encode(crypto::sha256(&self.0))
+    }
+}
+
+impl<B> FromRequestParts<B> for ClientCertificate
+where
+    B: Send + Sync,
+{
+    type Rejection = Rejection;
+
+    async fn from_request_parts(parts: &mut Parts, _: &B) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<ClientCertificate>()
+            .cloned()
+            .ok_or((StatusCode::UNAUTHORIZED, ERR_NO_CLIENT_CERT))
+    }
+}
+
+/// Extractor wrapping another extractor, keeping the request headers and [ClientCertificate] around so a [DeviceBound] validator can compare them against the token
+///
+/// This is enabled via the `jwt` feature alongside a crypto backend
+///
+/// # Example
+///
+/// ```no_run
+/// use axum_auth::{AuthBearer, AuthBound};
+///
+/// async fn handler(auth: AuthBound<AuthBearer>) -> String {
+///     format!("Found a bearer token: {}", auth.credential.0)
+/// }
+/// ```
+///
+/// # Errors
+///
+/// This extractor only fails if the wrapped extractor fails, returning its rejection as-is
+pub struct AuthBound<A> {
+    /// Credential from the wrapped extractor
+    pub credential: A,
+    headers: HeaderMap,
+    certificate: Option<ClientCertificate>,
+}
+
+impl<A> AuthBound<A> {
+    /// Creates the extractor from credentials and the headers of the request they were sent with
+    pub fn new(credential: A, headers: HeaderMap) -> Self {
+        Self {
+            credential,
+            headers,
+            certificate: None,
+        }
+    }
+
+    /// Sets the certificate the client presented when sending the credentials, returning it back
+    pub fn with_certificate(mut self, certificate: ClientCertificate) -> Self {
+        self.certificate = Some(certificate);
+        self
+    }
+
+    /// Gets the headers of the request the credentials were sent with
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// Gets the certificate the client presented, if there was one
+    pub fn certificate(&self) -> Option<&ClientCertificate> {
+        self.certificate.as_ref()
+    }
+}
+
+impl<A: fmt::Debug> fmt::Debug for AuthBound<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Headers are left out as they'd show the credentials again
+        f.debug_struct("AuthBound")
+            .field("credential", &self.credential)
+            .field(
+                "certificate",
+                &self.certificate.as_ref().map(|cert| cert.thumbprint()),
+            )
+            .finish_non_exhaustive()
+    }
+}
+
+impl<A: Clone> Clone for AuthBound<A> {
+    fn clone(&self) -> Self {
+        Self {
+            credential: self.credential.clone(),
+            headers: self.headers.clone(),
+            certificate: self.certificate.clone(),
+        }
+    }
+}
+
+impl<A, B> FromRequestParts<B> for AuthBound<A>
+where
+    A: FromRequestParts<B> + Send,
+    B: Send + Sync,
+{
+    type Rejection = A::Rejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &B) -> Result<Self, Self::Rejection> {
+        let credential = A::from_request_parts(parts, state).await?;
+        Ok(Self {
+            credential,
+            headers: parts.headers.clone(),
+            certificate: parts.extensions.get::<ClientCertificate>().cloned(),
+        })
+    }
+}
+
+/// Claim of a token which has to match a header of the request it was sent with
+#[derive(Debug, Clone)]
+struct Binding {
+    claim: String,
+    header: HeaderName,
+    hashed: bool,
+}
+
+impl Binding {
+    /// Checks if the claim matches the header, which both have to be present
+    fn matches(&self, jwt: &AuthJwt, headers: &HeaderMap) -> bool {
+        let (Some(claim), Some(header)) = (
+            jwt.claims.get(&self.claim).and_then(|claim| claim.as_str()),
+            headers.get(&self.header),
+        ) else {
+            return false;
+        };
+        if self.hashed {
+            let digest = general_purpose::URL_SAFE_NO_PAD.encode(crypto::sha256(header.as_bytes()));
+            constant_eq(claim.as_bytes(), digest.as_bytes())
+        } else {
+            constant_eq(claim.as_bytes(), header.as_bytes())
+        }
+    }
+}
+
+/// Validator wrapping a JWT validator, which also checks that the token is being used from the device it was issued to
+///
+/// This is enabled via the `jwt` feature alongside a crypto backend
+///
+/// Identity providers can bind a token to a device by putting something about it into a claim, like the device identifier the app sends in a header, or a digest of its `User-Agent`. Tokens copied onto another device are then rejected, as the headers they're sent with won't match anymore. Every binding has to match, and tokens without the claim are rejected too, so only use this on routes where every token is bound.
+///
+/// Tokens can also be bound to the client certificate used for mutual TLS as described in [RFC 8705](https://www.rfc-editor.org/rfc/rfc8705), using [with_certificate_binding](Self::with_certificate_binding). Stolen tokens are then useless without the private key of the certificate.
+///
+/// The wrapped validator has to store the [AuthJwt] in the request extensions like [JwtValidator](crate::JwtValidator) does, and is used with the [AuthBound] extractor so the headers are around to compare against.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{routing::get, Router};
+/// use axum_auth::{AuthBearer, AuthBound, AuthUser, DeviceBound, JwtValidator, RequireAuthLayer};
+///
+/// let validator = DeviceBound::new(JwtValidator::new().with_discovery("https://auth.example.com"))
+///     .with_binding("device_id", "x-device-id")
+///     .with_hashed_binding("ua_hash", "user-agent");
+///
+/// let app: Router = Router::new()
+///     .route("/", get(|user: AuthUser| async move { format!("Hello, {}!", user.id) }))
+///     .layer(RequireAuthLayer::<AuthBound<AuthBearer>, _>::new(validator));
+/// ```
+///
+/// # Errors
+///
+/// Tokens which the wrapped validator accepted but don't match their bindings are rejected with `401 UNAUTHORIZED` and one of these messages:
+///
+/// - Token is bound to another device – A claim didn't match the header it's bound to
+/// - Token is bound to another client certificate – The `cnf` claim didn't have the thumbprint of the [ClientCertificate], or no certificate was presented
+#[derive(Debug, Clone)]
+pub struct DeviceBound<V> {
+    inner: V,
+    bindings: Vec<Binding>,
+    certificate: bool,
+}
+
+impl<V> DeviceBound<V> {
+    /// Wraps the validator without any bindings
+    pub fn new(inner: V) -> Self {
+        Self {
+            inner,
+            bindings: vec![],
+            certificate: false,
+        }
+    }
+
+    /// Requires the claim to be the same as the header, like a device identifier, returning it back
+    ///
+    /// # Panics
+    ///
+    /// This panics if the header isn't a valid header name.
+    pub fn with_binding(mut self, claim: impl Into<String>, header: &str) -> Self {
+        self.bindings.push(Binding {
+            claim: claim.into(),
+            header: HeaderName::try_from(header).expect("binding header isn't a valid name"),
+            hashed: false,
+        });
+        self
+    }
+
+    /// Requires the claim to be the SHA-256 digest of the header, encoded as unpadded base64url, returning it back
+    ///
+    /// This is meant for headers which are too long or revealing to put into a token as-is, like the `User-Agent`.
+    ///
+    /// # Panics
+    ///
+    /// This panics if the header isn't a valid header name.
+    pub fn with_hashed_binding(mut self, claim: impl Into<String>, header: &str) -> Self {
+        self.bindings.push(Binding {
+            claim: claim.into(),
+            header: HeaderName::try_from(header).expect("binding header isn't a valid name"),
+            hashed: true,
+        });
+        self
+    }
+
+    /// Requires the `x5t#S256` thumbprint of the `cnf` claim to match the [ClientCertificate] of the request, returning it back
+    pub fn with_certificate_binding(mut self) -> Self {
+        self.certificate = true;
+        self
+    }
+}
+
+impl<C, V> AuthValidator<AuthBound<C>> for DeviceBound<V>
+where
+    C: Sync,
+    V: AuthValidator<C>,
+{
+    async fn validate(&self, credentials: &AuthBound<C>) -> Result<AuthUser, Rejection> {
+        self.validate_with_extensions(credentials, &mut Extensions::new())
+            .await
+    }
+
+    async fn validate_with_extensions(
+        &self,
+        credentials: &AuthBound<C>,
+        extensions: &mut Extensions,
+    ) -> Result<AuthUser, Rejection> {
+        let user = self
+            .inner
+            .validate_with_extensions(&credentials.credential, extensions)
+            .await?;
+        let jwt = extensions
+            .get::<AuthJwt>()
+            .ok_or((StatusCode::UNAUTHORIZED, ERR_BINDING_MISMATCH))?;
+        if !self
+            .bindings
+            .iter()
+            .all(|binding| binding.matches(jwt, &credentials.headers))
+        {
+            return Err((StatusCode::UNAUTHORIZED, ERR_BINDING_MISMATCH));
+        }
+        if self.certificate && !certificate_matches(jwt, credentials.certificate.as_ref()) {
+            return Err((StatusCode::UNAUTHORIZED, ERR_CERT_MISMATCH));
+        }
+        Ok(user)
+    }
+}
+
+/// Checks if the `x5t#S256` confirmation of the token is the thumbprint of the certificate, which both have to be present
+fn certificate_matches(jwt: &AuthJwt, certificate: Option<&ClientCertificate>) -> bool {
+    let (Some(thumbprint), Some(certificate)) = (
+        jwt.claims
+            .get("cnf")
+            .and_then(|cnf| cnf.get("x5t#S256"))
+            .and_then(Value::as_str),
+        certificate,
+    ) else {
+        return false;
+    };
+    constant_eq(thumbprint.as_bytes(), certificate.thumbprint().as_bytes())
+}
@@ -0,0 +1,259 @@
+//! Implementation of basic authentication for pages people visit in their browser
+//!
+//! See [BrowserBasicAuthLayer] for the most commonly-used data structure
+
+use crate::{
+    auth_basic::decode_browser, get_header, AuthBasic, AuthValidator, BasicUsers, Challenge,
+    Unauthorized, ERR_DECODE, ERR_LOGGED_OUT, ERR_WRONG_BASIC,
+};
+use axum_core::response::{IntoResponse, Response};
+use http::{header::AUTHORIZATION, HeaderMap, Request, StatusCode};
+use std::{
+    future::{ready, Future, Ready},
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// Layer protecting human-facing pages with the browser's own login prompt
+///
+/// This is enabled via the `auth-basic` feature
+///
+/// Unlike [RequireBasicAuthLayer](crate::RequireBasicAuthLayer), which gives API clients a `400 BAD REQUEST` for malformed headers, every failure here is a `401 UNAUTHORIZED` with a `WWW-Authenticate: Basic realm="...", charset="UTF-8"` challenge so the browser always asks again. Credentials from browsers which still send Latin-1 are decoded as such instead of being rejected.
+///
+/// Browsers keep sending basic credentials until they see a `401 UNAUTHORIZED`, so there's no real way to log out. Setting [with_logout_path](Self::with_logout_path) makes that path always give one, which clears the cached credentials in most browsers.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{routing::get, Router};
+/// use axum_auth::{AuthUser, BasicUser, BasicUsers, BrowserBasicAuthLayer};
+///
+/// let users = BasicUsers::new().with_user("alice", BasicUser::new("hunter2"));
+///
+/// let app: Router = Router::new()
+///     .route("/", get(|user: AuthUser| async move { format!("Hello, {}!", user.id) }))
+///     .layer(BrowserBasicAuthLayer::new("Dashboard", users).with_logout_path("/logout"));
+/// ```
+///
+/// # Errors
+///
+/// All errors are `401 UNAUTHORIZED` with the challenge, alongside one of these messages:
+///
+/// - You have been logged out – The logout path was visited
+/// - \`Authorization\` header must be for basic authentication – Some other scheme was used instead
+/// - \`Authorization\` header could not be decoded – The header wasn't valid base64
+/// - \`Authorization\` header is missing – The header was required but it wasn't found
+/// - \`Authorization\` header contains invalid characters – The header couldn't be processed because of invalid characters
+/// - Whatever the [AuthValidator] rejected the credentials with, such as "The provided credentials are invalid"
+pub struct BrowserBasicAuthLayer<V = BasicUsers> {
+    validator: Arc<V>,
+    realm: Arc<str>,
+    logout_path: Option<Arc<str>>,
+}
+
+impl<V> BrowserBasicAuthLayer<V> {
+    /// Creates a new layer showing the realm in the browser's prompt and checking credentials with the given validator
+    pub fn new(realm: impl AsRef<str>, validator: V) -> Self {
+        Self {
+            validator: Arc::new(validator),
+            realm: Arc::from(realm.as_ref()),
+            logout_path: None,
+        }
+    }
+
+    /// Sets the path which always gives `401 UNAUTHORIZED` so browsers forget the credentials, returning it back
+    pub fn with_logout_path(mut self, path: impl AsRef<str>) -> Self {
+        self.logout_path = Some(Arc::from(path.as_ref()));
+        self
+    }
+
+    /// Makes the response asking the browser for credentials
+    fn unauthorized(&self, message: &'static str) -> Response {
+        Unauthorized::new()
+            .with_challenge(Challenge::basic(&*self.realm))
+            .with_message(message)
+            .into_response()
+    }
+}
+
+impl<V> Clone for BrowserBasicAuthLayer<V> {
+    fn clone(&self) -> Self {
+        Self {
+            validator: self.validator.clone(),
+            realm: self.realm.clone(),
+            logout_path: self.logout_path.clone(),
+        }
+    }
+}
+
+impl<S, V> Layer<S> for BrowserBasicAuthLayer<V> {
+    type Service = BrowserBasicAuth<S, V>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        BrowserBasicAuth {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+/// Service created by [BrowserBasicAuthLayer], see it for more information
+pub struct BrowserBasicAuth<S, V> {
+    inner: S,
+    layer: BrowserBasicAuthLayer<V>,
+}
+
+impl<S: Clone, V> Clone for BrowserBasicAuth<S, V> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            layer: self.layer.clone(),
+        }
+    }
+}
+
+impl<S, V, B> Service<Request<B>> for BrowserBasicAuth<S, V>
+where
+    S: Service<Request<B>, Response = Response> + Clone + Send + 'static,
+    S::Future: Send,
+    V: AuthValidator<AuthBasic>,
+    B: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<B>) -> Self::Future {
+        // Take the service which was driven to readiness, leaving a fresh clone behind
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let layer = self.layer.clone();
+
+        Box::pin(async move {
+            let (mut parts, body) = req.into_parts();
+
+            // Always ask again on the logout path so the browser forgets the credentials
+            if layer.logout_path.as_deref() == Some(parts.uri.path()) {
+                return Ok(layer.unauthorized(ERR_LOGGED_OUT));
+            }
+
+            // Get the credentials, decoding them like a browser encoded them
+            let credentials = match get_header(&mut parts, StatusCode::UNAUTHORIZED) {
+                Ok(authorization) => match authorization.split_once(' ') {
+                    Some(("Basic", contents)) => decode_browser(contents).ok_or(ERR_DECODE),
+                    _ => Err(ERR_WRONG_BASIC),
+                },
+                Err((_, message)) => Err(message),
+            };
+            let credentials = match credentials {
+                Ok(credentials) => AuthBasic(credentials),
+                Err(message) => return Ok(layer.unauthorized(message)),
+            };
+
+            // Validate them and keep the identity for later
+            match layer
+                .validator
+                .validate_with_extensions(&credentials, &mut parts.extensions)
+                .await
+            {
+                Ok(user) => {
+                    parts.extensions.insert(user);
+                    inner.call(Request::from_parts(parts, body)).await
+                }
+                Err((_, message)) => Ok(layer.unauthorized(message)),
+            }
+        })
+    }
+}
+
+/// Handler for a logout link on pages using the browser's basic auth prompt
+///
+/// This is enabled via the `auth-basic` feature
+///
+/// Browsers keep sending cached basic credentials until they're told otherwise, so logging out needs one of these tricks:
+///
+/// - Returning `401 UNAUTHORIZED` for the realm, which makes most browsers forget the credentials; this is what happens by default
+/// - Returning `401 UNAUTHORIZED` for a changed realm using [with_changed_realm](Self::with_changed_realm), so browsers which remember credentials per realm don't send the old ones again
+/// - Accepting dummy credentials using [with_dummy_credentials](Self::with_dummy_credentials), so a script on the page can send them with `fetch` to overwrite the cached ones without showing a prompt
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{routing::get, Router};
+/// use axum_auth::{AuthUser, BasicLogout, BasicUser, BasicUsers, BrowserBasicAuthLayer};
+///
+/// let users = BasicUsers::new().with_user("alice", BasicUser::new("hunter2"));
+/// let logout = BasicLogout::new("Dashboard").with_dummy_credentials("logout", "logout");
+///
+/// let app: Router = Router::new()
+///     .route("/", get(|user: AuthUser| async move { format!("Hello, {}!", user.id) }))
+///     .layer(BrowserBasicAuthLayer::new("Dashboard", users))
+///     .route("/logout", get(logout.handler()));
+/// ```
+///
+/// # Errors
+///
+/// Unless the dummy credentials were sent, this always gives `401 UNAUTHORIZED` with the challenge and the message "You have been logged out". With them, it's a `200 OK` with the same message.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct BasicLogout {
+    realm: String,
+    dummy: Option<(String, String)>,
+}
+
+impl BasicLogout {
+    /// Creates a new logout for the realm used by the browser's prompt
+    pub fn new(realm: impl Into<String>) -> Self {
+        Self {
+            realm: realm.into(),
+            dummy: None,
+        }
+    }
+
+    /// Challenges for another realm instead, like `"Dashboard (logged out)"`, returning it back
+    pub fn with_changed_realm(mut self, realm: impl Into<String>) -> Self {
+        self.realm = realm.into();
+        self
+    }
+
+    /// Accepts these credentials, which should never belong to a real user, to overwrite the ones the browser cached, returning it back
+    pub fn with_dummy_credentials(
+        mut self,
+        username: impl Into<String>,
+        password: impl Into<String>,
+    ) -> Self {
+        self.dummy = Some((username.into(), password.into()));
+        self
+    }
+
+    /// Responds to the logout request with the headers it was sent with
+    pub fn respond(&self, headers: &HeaderMap) -> Response {
+        let dummy = headers
+            .get(AUTHORIZATION)
+            .and_then(|authorization| authorization.to_str().ok())
+            .and_then(|authorization| authorization.strip_prefix("Basic "))
+            .and_then(decode_browser);
+        match (&self.dummy, dummy) {
+            (Some((username, password)), Some((id, Some(sent))))
+                if *username == id && *password == sent =>
+            {
+                ERR_LOGGED_OUT.into_response()
+            }
+            _ => Unauthorized::new()
+                .with_challenge(Challenge::basic(&self.realm))
+                .with_message(ERR_LOGGED_OUT)
+                .into_response(),
+        }
+    }
+
+    /// Turns this into a handler for the logout route
+    pub fn handler(self) -> impl Fn(HeaderMap) -> Ready<Response> + Clone + Send + Sync + 'static {
+        move |headers| ready(self.respond(&headers))
+    }
+}
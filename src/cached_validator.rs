@@ -0,0 +1,441 @@
+//! Implementation of caching the results of any validator
+//!
+//! See [CachedValidator] for the most commonly-used data structure
+
+use crate::{crypto, AuthUser, AuthValidator, CredentialExpiry, Rejection};
+use http::Extensions;
+use std::{
+    collections::HashMap,
+    future::Future,
+    hash::{Hash, Hasher},
+    sync::Mutex,
+    time::{Duration, Instant, SystemTime},
+};
+
+/// Wrapper around an [AuthValidator] remembering successful validations for a while, cutting the load on databases and identity providers for hot endpoints
+///
+/// This is enabled via the `validator-cache` feature alongside a crypto backend
+///
+/// Credentials are keyed by a SHA-256 digest of their [Hash] output, so the secrets themselves are never kept around. Anything the inner validator stored in the request extensions, like the claims of a token, is remembered too and given back on every hit.
+///
+/// Cached identities are trusted until the entry expires, so revoking a credential only takes effect after the TTL. Keep the TTL shorter than the lifetime of the credentials being cached, which is done for you when the inner validator gives a [CredentialExpiry](crate::CredentialExpiry).
+///
+/// Failed validations can also be remembered using [with_negative_ttl](Self::with_negative_ttl), so a flood of requests with the same bad token doesn't hammer a remote validator like an introspection endpoint. Keep this TTL short, as newly-issued credentials are rejected until it expires if they were tried before.
+///
+/// When the identity provider is down, successful validations which have expired can be kept using for a while longer with [with_stale_if_unavailable](Self::with_stale_if_unavailable). This trades strict freshness for availability, so it's never done by default.
+///
+/// Validations are kept in a [MemoryValidationCache] by default, which is local to this server. Use [with_cache](Self::with_cache) to keep them somewhere else, like `MokaValidationCache` with the `moka` feature or your own [ValidationCache] shared between many servers.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{routing::get, Router};
+/// use axum_auth::{AuthUser, BasicUser, BasicUsers, CachedValidator, RequireBasicAuthLayer};
+/// use std::time::Duration;
+///
+/// let users = BasicUsers::new().with_user("alice", BasicUser::new("hunter2"));
+/// let cached = CachedValidator::new(users)
+///     .with_ttl(Duration::from_secs(30))
+///     .with_negative_ttl(Duration::from_secs(5))
+///     .with_max_size(1_000);
+///
+/// let app: Router = Router::new()
+///     .route("/", get(|user: AuthUser| async move { format!("Hello, {}!", user.id) }))
+///     .layer(RequireBasicAuthLayer::new(cached));
+/// ```
+///
+/// # Errors
+///
+/// Rejections from the inner validator are given back as-is. They're only cached if a negative TTL was set and they're client errors like `401 UNAUTHORIZED`, so server errors like an unreachable identity provider are always retried.
+///
+/// Server errors are the only rejections which can be answered using an expired validation, and only once [with_stale_if_unavailable](Self::with_stale_if_unavailable) has been set.
+#[derive(Debug)]
+pub struct CachedValidator<V, S = MemoryValidationCache> {
+    inner: V,
+    ttl: Duration,
+    negative_ttl: Option<Duration>,
+    stale_if_unavailable: Option<Duration>,
+    cache: S,
+}
+
+/// Validation remembered by a [ValidationCache], along with the extensions it stored if it was successful
+///
+/// Caches which serialize validations to share them between servers may drop the extensions, as only the [AuthUser] is needed to answer a hit.
+#[derive(Debug, Clone)]
+pub struct CachedValidation {
+    /// Outcome of the validation, with the extensions stored by the validator if it was successful
+    pub result: Result<(AuthUser, Extensions), Rejection>,
+    /// Time after which the validation is no longer fresh, though it may still be kept for use while the validator is unavailable
+    pub expires: SystemTime,
+}
+
+/// Store remembering validations for a [CachedValidator], keyed by a digest of the credentials
+///
+/// The in-memory [MemoryValidationCache] works for a single server, implement this yourself to share validations between many (e.g. using Redis `SET PX`).
+pub trait ValidationCache: Send + Sync + 'static {
+    /// Gets the validation remembered for the key, if it hasn't been forgotten yet; you need to implement this
+    fn get(&self, key: &[u8]) -> impl Future<Output = Option<CachedValidation>> + Send;
+
+    /// Remembers the validation for the key, forgetting it once the TTL is over; you need to implement this
+    fn insert_with_ttl(
+        &self,
+        key: &[u8],
+        validation: CachedValidation,
+        ttl: Duration,
+    ) -> impl Future<Output = ()> + Send;
+
+    /// Forgets the validation remembered for the key, if there is one; you need to implement this
+    fn invalidate(&self, key: &[u8]) -> impl Future<Output = ()> + Send;
+}
+
+/// In-memory [ValidationCache] with a maximum size, after which the validations closest to being forgotten are evicted first
+#[derive(Debug)]
+pub struct MemoryValidationCache {
+    max_size: usize,
+    entries: Mutex<HashMap<Vec<u8>, (Instant, CachedValidation)>>,
+}
+
+impl MemoryValidationCache {
+    /// Default maximum number of remembered validations, which is 10,000
+    pub const DEFAULT_MAX_SIZE: usize = 10_000;
+
+    /// Creates a new empty cache, using the default maximum size
+    pub fn new() -> Self {
+        Self {
+            max_size: Self::DEFAULT_MAX_SIZE,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Sets the maximum number of remembered validations, returning it back
+    pub fn with_max_size(mut self, max_size: usize) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Forgets every remembered validation
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+}
+
+impl Default for MemoryValidationCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ValidationCache for MemoryValidationCache {
+    async fn get(&self, key: &[u8]) -> Option<CachedValidation> {
+        let mut entries = self.entries.lock().unwrap();
+        match entries.get(key) {
+            Some((kept_until, validation)) if *kept_until > Instant::now() => {
+                Some(validation.clone())
+            }
+            Some(_) => {
+                entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    async fn insert_with_ttl(&self, key: &[u8], validation: CachedValidation, ttl: Duration) {
+        if self.max_size == 0 {
+            return;
+        }
+        let now = Instant::now();
+        let mut entries = self.entries.lock().unwrap();
+
+        // Forget expired entries first, then the ones closest to being forgotten
+        if entries.len() >= self.max_size && !entries.contains_key(key) {
+            entries.retain(|_, (kept_until, _)| *kept_until > now);
+            while entries.len() >= self.max_size {
+                let soonest = entries
+                    .iter()
+                    .min_by_key(|(_, (kept_until, _))| *kept_until)
+                    .map(|(key, _)| key.clone());
+                match soonest {
+                    Some(soonest) => entries.remove(&soonest),
+                    None => break,
+                };
+            }
+        }
+
+        entries.insert(key.to_vec(), (now + ttl, validation));
+    }
+
+    async fn invalidate(&self, key: &[u8]) {
+        self.entries.lock().unwrap().remove(key);
+    }
+}
+
+/// [ValidationCache] backed by [moka](https://docs.rs/moka), a concurrent cache which evicts by recency and frequency instead of locking a single map
+///
+/// This is enabled via the `moka` feature alongside a crypto backend
+///
+/// # Example
+///
+/// ```rust
+/// use axum_auth::{BasicUser, BasicUsers, CachedValidator, MokaValidationCache};
+///
+/// let users = BasicUsers::new().with_user("alice", BasicUser::new("hunter2"));
+/// let cached = CachedValidator::new(users).with_cache(MokaValidationCache::new(100_000));
+/// ```
+#[cfg(feature = "moka")]
+#[derive(Debug, Clone)]
+pub struct MokaValidationCache {
+    cache: moka::future::Cache<Vec<u8>, (CachedValidation, Duration)>,
+}
+
+#[cfg(feature = "moka")]
+impl MokaValidationCache {
+    /// Creates a new empty cache holding at most the given number of validations
+    pub fn new(max_capacity: u64) -> Self {
+        Self {
+            cache: moka::future::Cache::builder()
+                .max_capacity(max_capacity)
+                .expire_after(MokaExpiry)
+                .build(),
+        }
+    }
+
+    /// Gets the underlying moka cache, e.g. to check how many validations it holds
+    pub fn inner(&self) -> &moka::future::Cache<Vec<u8>, (CachedValidation, Duration)> {
+        &self.cache
+    }
+}
+
+#[cfg(feature = "moka")]
+impl ValidationCache for MokaValidationCache {
+    async fn get(&self, key: &[u8]) -> Option<CachedValidation> {
+        self.cache.get(key).await.map(|(validation, _)| validation)
+    }
+
+    async fn insert_with_ttl(&self, key: &[u8], validation: CachedValidation, ttl: Duration) {
+        self.cache.insert(key.to_vec(), (validation, ttl)).await;
+    }
+
+    async fn invalidate(&self, key: &[u8]) {
+        self.cache.invalidate(key).await;
+    }
+}
+
+/// Expiry policy of a [MokaValidationCache], forgetting each validation after the TTL it was inserted with
+#[cfg(feature = "moka")]
+struct MokaExpiry;
+
+#[cfg(feature = "moka")]
+impl moka::Expiry<Vec<u8>, (CachedValidation, Duration)> for MokaExpiry {
+    fn expire_after_create(
+        &self,
+        _key: &Vec<u8>,
+        value: &(CachedValidation, Duration),
+        _created_at: Instant,
+    ) -> Option<Duration> {
+        Some(value.1)
+    }
+
+    fn expire_after_update(
+        &self,
+        _key: &Vec<u8>,
+        value: &(CachedValidation, Duration),
+        _updated_at: Instant,
+        _duration_until_expiry: Option<Duration>,
+    ) -> Option<Duration> {
+        Some(value.1)
+    }
+}
+
+impl<V> CachedValidator<V> {
+    /// Default maximum number of remembered validations, which is 10,000
+    pub const DEFAULT_MAX_SIZE: usize = MemoryValidationCache::DEFAULT_MAX_SIZE;
+
+    /// Creates a new in-memory cache in front of the given validator, using the default TTL and maximum size
+    pub fn new(inner: V) -> Self {
+        Self {
+            inner,
+            ttl: Self::DEFAULT_TTL,
+            negative_ttl: None,
+            stale_if_unavailable: None,
+            cache: MemoryValidationCache::new(),
+        }
+    }
+
+    /// Sets the maximum number of remembered validations, after which the ones closest to expiring are forgotten first, returning it back
+    pub fn with_max_size(mut self, max_size: usize) -> Self {
+        self.cache = self.cache.with_max_size(max_size);
+        self
+    }
+
+    /// Forgets every remembered validation, e.g. after revoking credentials
+    pub fn clear(&self) {
+        self.cache.clear();
+    }
+}
+
+impl<V, S> CachedValidator<V, S> {
+    /// Default amount of time successful validations are remembered for, which is 1 minute
+    pub const DEFAULT_TTL: Duration = Duration::from_secs(60);
+
+    /// Sets how long successful validations are remembered for, returning it back
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Sets how long failed validations are remembered for, which they aren't by default, returning it back
+    pub fn with_negative_ttl(mut self, negative_ttl: Duration) -> Self {
+        self.negative_ttl = Some(negative_ttl);
+        self
+    }
+
+    /// Keeps using successful validations for up to this long after they've expired, but only when the inner validator rejects with a server error, returning it back
+    ///
+    /// This keeps users logged in while the identity provider is down, such as when a `CircuitBreaker` around it is open with the `circuit-breaker` feature, at the cost of accepting credentials which might have been revoked since. Every time an expired validation is used, it's logged at the warn level with the `tracing` feature for auditing.
+    pub fn with_stale_if_unavailable(mut self, grace: Duration) -> Self {
+        self.stale_if_unavailable = Some(grace);
+        self
+    }
+
+    /// Keeps validations in the given cache instead, returning it back
+    pub fn with_cache<T: ValidationCache>(self, cache: T) -> CachedValidator<V, T> {
+        CachedValidator {
+            inner: self.inner,
+            ttl: self.ttl,
+            negative_ttl: self.negative_ttl,
+            stale_if_unavailable: self.stale_if_unavailable,
+            cache,
+        }
+    }
+
+    /// Gets the validator being cached
+    pub fn inner(&self) -> &V {
+        &self.inner
+    }
+
+    /// Gets the cache validations are kept in
+    pub fn cache(&self) -> &S {
+        &self.cache
+    }
+}
+
+impl<V, S: ValidationCache> CachedValidator<V, S> {
+    /// Forgets the remembered validation of the given credentials, e.g. after revoking them
+    pub async fn invalidate(&self, credentials: &impl Hash) {
+        self.cache.invalidate(&digest(credentials)).await;
+    }
+
+    /// Remembers the validation if it should be, keeping successful ones past their expiry if they can be used while the validator is unavailable
+    async fn remember(&self, key: &[u8], result: &Result<(AuthUser, Extensions), Rejection>) {
+        let (ttl, kept_for) = match result {
+            Ok(_) => (
+                self.ttl,
+                self.ttl + self.stale_if_unavailable.unwrap_or_default(),
+            ),
+            Err((status, _)) if status.is_client_error() => match self.negative_ttl {
+                Some(negative_ttl) => (negative_ttl, negative_ttl),
+                None => return,
+            },
+            Err(_) => return,
+        };
+
+        // Never remember credentials for longer than they're valid
+        let (ttl, kept_for) = match result
+            .as_ref()
+            .ok()
+            .and_then(|(_, extensions)| extensions.get::<CredentialExpiry>())
+        {
+            Some(expiry) if expiry.remaining().is_zero() => return,
+            Some(expiry) => (expiry.cap(ttl), expiry.cap(kept_for)),
+            None => (ttl, kept_for),
+        };
+        let validation = CachedValidation {
+            result: result.clone(),
+            expires: SystemTime::now() + ttl,
+        };
+        self.cache.insert_with_ttl(key, validation, kept_for).await;
+    }
+}
+
+impl<C, V, S> AuthValidator<C> for CachedValidator<V, S>
+where
+    C: Hash + Sync,
+    V: AuthValidator<C>,
+    S: ValidationCache,
+{
+    async fn validate(&self, credentials: &C) -> Result<AuthUser, Rejection> {
+        self.validate_with_extensions(credentials, &mut Extensions::new())
+            .await
+    }
+
+    async fn validate_with_extensions(
+        &self,
+        credentials: &C,
+        extensions: &mut Extensions,
+    ) -> Result<AuthUser, Rejection> {
+        // Use the remembered validation if it's still fresh
+        let key = digest(credentials);
+        let remembered = self.cache.get(&key).await;
+        if let Some(validation) = &remembered {
+            if validation.expires > SystemTime::now() {
+                let (user, cached) = validation.result.clone()?;
+                extensions.extend(cached);
+                return Ok(user);
+            }
+        }
+
+        // Otherwise validate and remember it
+        let mut fresh = Extensions::new();
+        let result = self
+            .inner
+            .validate_with_extensions(credentials, &mut fresh)
+            .await
+            .map(|user| (user, fresh));
+        self.remember(&key, &result).await;
+        let (user, fresh) = match result {
+            Ok(validated) => validated,
+            // Fall back to an expired validation while the validator is unavailable, if that's allowed
+            Err((status, message)) if status.is_server_error() => {
+                let (user, stale) = self
+                    .stale_if_unavailable
+                    .zip(remembered)
+                    .filter(|(grace, validation)| validation.expires + *grace > SystemTime::now())
+                    .and_then(|(_, validation)| validation.result.ok())
+                    .ok_or((status, message))?;
+                #[cfg(feature = "tracing")]
+                tracing::warn!(
+                    user = user.id,
+                    reason = message,
+                    "accepted an expired validation while the validator is unavailable"
+                );
+                (user, stale)
+            }
+            Err(rejection) => return Err(rejection),
+        };
+        extensions.extend(fresh);
+        Ok(user)
+    }
+}
+
+/// Digests the credentials with SHA-256 so they can be used as a key without keeping the secret
+fn digest(credentials: &impl Hash) -> Vec<u8> {
+    let mut bytes = Bytes::default();
+    credentials.hash(&mut bytes);
+    crypto::sha256(&bytes.0)
+}
+
+/// Hasher collecting everything written to it, so it can be digested with SHA-256 instead
+#[derive(Default)]
+struct Bytes(Vec<u8>);
+
+impl Hasher for Bytes {
+    fn finish(&self) -> u64 {
+        // Never used, the collected bytes are digested instead
+        0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.extend_from_slice(bytes);
+    }
+}
@@ -0,0 +1,85 @@
+//! Implementation of the [Casbin](https://casbin.org/) enforcement integration
+//!
+//! See [CasbinAuthorizer] for the most commonly-used data structure
+
+use crate::{AuthUser, Authorizer, Decision};
+use casbin::{CoreApi, Enforcer};
+use http::request::Parts;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// Authorizer checking every request with a Casbin enforcer
+///
+/// This is enabled via the `casbin` feature
+///
+/// # Request mapping
+///
+/// Each request is enforced as `(subject, object, action)` like so:
+///
+/// - Subject – The `id` of the [AuthUser], use `g` policies in your model to give users roles
+/// - Object – The path of the request, e.g. `/api/posts`
+/// - Action – The method of the request, e.g. `GET`
+///
+/// # Example
+///
+/// ```no_run
+/// use axum::{routing::get, Router};
+/// use axum_auth::{AuthorizeLayer, CasbinAuthorizer};
+/// use casbin::{CoreApi, Enforcer};
+///
+/// # async fn run() {
+/// let enforcer = Enforcer::new("model.conf", "policy.csv").await.unwrap();
+/// let authorizer = CasbinAuthorizer::new(enforcer);
+///
+/// let app: Router = Router::new()
+///     .route("/", get(|| async { "Hello, world!" }))
+///     .layer(AuthorizeLayer::new(authorizer.clone()));
+///
+/// // Later on, e.g. from an admin route or signal handler:
+/// authorizer.reload_policy().await.unwrap();
+/// # }
+/// ```
+///
+/// # Reloading
+///
+/// Clones of this authorizer share the same enforcer, so you can keep one around to reload from:
+///
+/// - [CasbinAuthorizer::reload_policy] – Reloads the policy from the enforcer's adapter
+/// - [CasbinAuthorizer::replace] – Swaps the whole enforcer, e.g. after the model has changed
+///
+/// Requests being checked during a reload wait for it to finish, so they never see a half-loaded policy.
+///
+/// # Errors
+///
+/// If the enforcer denies the request or fails to evaluate it, the [Decision::Deny] leads to a `403 FORBIDDEN`
+#[derive(Clone)]
+pub struct CasbinAuthorizer {
+    enforcer: Arc<RwLock<Enforcer>>,
+}
+
+impl CasbinAuthorizer {
+    /// Creates a new authorizer from an already-loaded enforcer
+    pub fn new(enforcer: Enforcer) -> Self {
+        Self {
+            enforcer: Arc::new(RwLock::new(enforcer)),
+        }
+    }
+
+    /// Reloads the policy from the adapter the enforcer was made with
+    pub async fn reload_policy(&self) -> casbin::Result<()> {
+        self.enforcer.write().await.load_policy().await
+    }
+
+    /// Replaces the enforcer entirely, which is how a changed model can be loaded
+    pub async fn replace(&self, enforcer: Enforcer) {
+        *self.enforcer.write().await = enforcer;
+    }
+}
+
+impl Authorizer for CasbinAuthorizer {
+    async fn authorize(&self, user: &AuthUser, req: &Parts) -> Decision {
+        let enforcer = self.enforcer.read().await;
+        let allowed = enforcer.enforce((user.id.as_str(), req.uri.path(), req.method.as_str()));
+        allowed.unwrap_or(false).into()
+    }
+}
@@ -0,0 +1,149 @@
+//! Implementation of the [Cedar](https://www.cedarpolicy.com/) policy engine integration
+//!
+//! See [CedarAuthorizer] for the most commonly-used data structure
+
+use crate::{AuthUser, Authorizer, Decision};
+use cedar_policy::{
+    Context, Entities, Entity, EntityId, EntityTypeName, EntityUid, PolicySet, Request,
+    RestrictedExpression,
+};
+use http::request::Parts;
+use std::{collections::HashSet, str::FromStr};
+
+/// Extra context for a Cedar request, insert this into the request extensions from your own middleware
+///
+/// Every pair is added to the `context` record of the Cedar request alongside the defaults described in [CedarAuthorizer].
+#[derive(Debug, Clone, Default)]
+pub struct CedarContext(pub Vec<(String, RestrictedExpression)>);
+
+/// Authorizer evaluating every request against a set of Cedar policies
+///
+/// This is enabled via the `cedar` feature
+///
+/// # Request mapping
+///
+/// Each request is converted into a Cedar request like so:
+///
+/// - Principal – `User::"<id>"` of the [AuthUser], with every role as a parent `Role::"<role>"` entity
+/// - Action – `Action::"<method>"`, e.g. `Action::"GET"`
+/// - Resource – `Path::"<path>"`, e.g. `Path::"/api/posts"`
+/// - Context – A record containing `method`, `path`, `roles`, and `scopes`, as well as anything added using a [CedarContext] extension
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{routing::get, Router};
+/// use axum_auth::{AuthorizeLayer, CedarAuthorizer};
+/// use cedar_policy::PolicySet;
+///
+/// let policies: PolicySet = r#"
+///     permit(principal, action == Action::"GET", resource);
+///     permit(principal in Role::"admin", action, resource);
+/// "#.parse().unwrap();
+/// let authorizer = CedarAuthorizer::new(policies);
+///
+/// let app: Router = Router::new()
+///     .route("/", get(|| async { "Hello, world!" }).post(|| async { "Hello, admin!" }))
+///     .layer(AuthorizeLayer::new(authorizer));
+/// ```
+///
+/// # Errors
+///
+/// If the policies deny the request or the request couldn't be converted, the [Decision::Deny] leads to a `403 FORBIDDEN`
+pub struct CedarAuthorizer {
+    policies: PolicySet,
+    entities: Entities,
+    authorizer: cedar_policy::Authorizer,
+}
+
+impl CedarAuthorizer {
+    /// Creates a new authorizer from an already-loaded policy set
+    pub fn new(policies: PolicySet) -> Self {
+        Self {
+            policies,
+            entities: Entities::empty(),
+            authorizer: cedar_policy::Authorizer::new(),
+        }
+    }
+
+    /// Sets entities (e.g. resources and their hierarchy) which policies can refer to, returning it back
+    pub fn with_entities(mut self, entities: Entities) -> Self {
+        self.entities = entities;
+        self
+    }
+
+    /// Converts the user and request into a Cedar request alongside the entities needed to evaluate it
+    fn convert(&self, user: &AuthUser, req: &Parts) -> Option<(Request, Entities)> {
+        // Make the principal with its roles as parents
+        let principal = uid("User", &user.id)?;
+        let roles = user
+            .roles
+            .iter()
+            .map(|role| uid("Role", role))
+            .collect::<Option<HashSet<_>>>()?;
+        let entity = Entity::new_no_attrs(principal.clone(), roles);
+
+        // Make the action and resource from the request
+        let method = req.method.as_str();
+        let path = req.uri.path();
+        let action = uid("Action", method)?;
+        let resource = uid("Path", path)?;
+
+        // Make the context with defaults and any extras
+        let strings = |values: &[String]| {
+            RestrictedExpression::new_set(
+                values
+                    .iter()
+                    .map(|value| RestrictedExpression::new_string(value.clone())),
+            )
+        };
+        let mut pairs = vec![
+            (
+                "method".to_string(),
+                RestrictedExpression::new_string(method.to_string()),
+            ),
+            (
+                "path".to_string(),
+                RestrictedExpression::new_string(path.to_string()),
+            ),
+            ("roles".to_string(), strings(&user.roles)),
+            ("scopes".to_string(), strings(&user.scopes)),
+        ];
+        if let Some(extra) = req.extensions.get::<CedarContext>() {
+            pairs.extend(extra.0.iter().cloned());
+        }
+        let context = Context::from_pairs(pairs).ok()?;
+
+        // Add the principal to the entities unless it's already been provided
+        let entities = if self.entities.get(&principal).is_some() {
+            self.entities.clone()
+        } else {
+            self.entities.clone().add_entities([entity], None).ok()?
+        };
+
+        let request = Request::new(principal, action, resource, context, None).ok()?;
+        Some((request, entities))
+    }
+}
+
+impl Authorizer for CedarAuthorizer {
+    async fn authorize(&self, user: &AuthUser, req: &Parts) -> Decision {
+        match self.convert(user, req) {
+            Some((request, entities)) => {
+                let response = self
+                    .authorizer
+                    .is_authorized(&request, &self.policies, &entities);
+                (response.decision() == cedar_policy::Decision::Allow).into()
+            }
+            None => Decision::Deny,
+        }
+    }
+}
+
+/// Makes an entity identifier from its type name and identifier
+fn uid(kind: &str, id: &str) -> Option<EntityUid> {
+    Some(EntityUid::from_type_name_and_id(
+        EntityTypeName::from_str(kind).ok()?,
+        EntityId::new(id),
+    ))
+}
@@ -0,0 +1,546 @@
+//! Implementation of `WWW-Authenticate` and `Proxy-Authenticate` challenges and negotiation between several schemes
+//!
+//! See [AuthEither] for the most commonly-used data structure
+
+use crate::{
+    credential_header::credential_header, AuthErrorKind, Rejection, ERR_MISSING,
+    ERR_UNSUPPORTED_SCHEME,
+};
+use axum_core::{
+    extract::FromRequestParts,
+    response::{IntoResponse, Response},
+};
+use http::{
+    header::{PROXY_AUTHENTICATE, WWW_AUTHENTICATE},
+    request::Parts,
+    HeaderName, HeaderValue, StatusCode,
+};
+use std::fmt;
+
+/// Single challenge telling the client which scheme (and parameters) it may authenticate with
+///
+/// Challenges are formatted as described in [RFC 9110](https://www.rfc-editor.org/rfc/rfc9110#section-11.6.1), with every parameter value quoted and escaped.
+///
+/// # Example
+///
+/// ```rust
+/// use axum_auth::Challenge;
+///
+/// let challenge = Challenge::basic("Admin area");
+/// assert_eq!(challenge.to_string(), r#"Basic realm="Admin area", charset="UTF-8""#);
+///
+/// let challenge = Challenge::new("Bearer").with_param("error", "invalid_token");
+/// assert_eq!(challenge.to_string(), r#"Bearer error="invalid_token""#);
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Challenge {
+    scheme: String,
+    token68: Option<String>,
+    params: Vec<(String, String)>,
+}
+
+impl Challenge {
+    /// Creates a new challenge for the scheme without any parameters
+    pub fn new(scheme: impl Into<String>) -> Self {
+        Self {
+            scheme: scheme.into(),
+            token68: None,
+            params: vec![],
+        }
+    }
+
+    /// Creates a basic auth challenge for the realm, also telling browsers to encode credentials as UTF-8
+    pub fn basic(realm: impl Into<String>) -> Self {
+        Self::new("Basic")
+            .with_param("realm", realm)
+            .with_param("charset", "UTF-8")
+    }
+
+    /// Creates a bearer auth challenge without any parameters
+    pub fn bearer() -> Self {
+        Self::new("Bearer")
+    }
+
+    /// Adds a parameter to the challenge, returning it back
+    pub fn with_param(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.params.push((name.into(), value.into()));
+        self
+    }
+
+    /// Sets the opaque token sent instead of parameters, as used by `Negotiate` for its continuation tokens, returning it back
+    ///
+    /// Challenges can either have a token or parameters, so any parameters are left out once this is set.
+    pub fn with_token68(mut self, token68: impl Into<String>) -> Self {
+        self.token68 = Some(token68.into());
+        self
+    }
+
+    /// Scheme of the challenge, such as `Basic`
+    pub fn scheme(&self) -> &str {
+        &self.scheme
+    }
+
+    /// Opaque token of the challenge, if it has one
+    pub fn token68(&self) -> Option<&str> {
+        self.token68.as_deref()
+    }
+
+    /// Parameters of the challenge in the order they were added
+    pub fn params(&self) -> &[(String, String)] {
+        &self.params
+    }
+}
+
+impl fmt::Display for Challenge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.scheme)?;
+        if let Some(token68) = &self.token68 {
+            return write!(f, " {}", token68);
+        }
+        for (index, (name, value)) in self.params.iter().enumerate() {
+            let separator = if index == 0 { " " } else { ", " };
+            let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+            write!(f, "{}{}=\"{}\"", separator, name, value)?;
+        }
+        Ok(())
+    }
+}
+
+impl Challenge {
+    /// Parses every challenge in a `WWW-Authenticate` or `Proxy-Authenticate` header, as sent by servers to clients
+    ///
+    /// Challenges can have either a token68 or auth parameters, whose names are lowercased since they're case-insensitive. Anything malformed is skipped over, so this never fails; headers which are sent more than once should have each value parsed.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use axum_auth::Challenge;
+    ///
+    /// let challenges = Challenge::parse_all(r#"Bearer realm="api", error="invalid_token", Negotiate YII="#);
+    ///
+    /// assert_eq!(challenges.len(), 2);
+    /// assert_eq!(challenges[0].scheme(), "Bearer");
+    /// assert_eq!(challenges[0].param("error"), Some("invalid_token"));
+    /// assert_eq!(challenges[1].token68(), Some("YII="));
+    /// ```
+    pub fn parse_all(header: &str) -> Vec<Self> {
+        let mut challenges = vec![];
+        let mut rest = header;
+        loop {
+            rest = rest.trim_start_matches(|c: char| c == ',' || c.is_ascii_whitespace());
+            if rest.is_empty() {
+                break;
+            }
+
+            // Get the scheme, skipping to the next challenge if there isn't one
+            let (scheme, after) = take_token(rest);
+            if scheme.is_empty() {
+                rest = rest.find(',').map_or("", |index| &rest[index..]);
+                continue;
+            }
+            let mut challenge = Self::new(scheme);
+            rest = after;
+
+            // Get the token68 or parameters, which have to be after a space
+            let spaced = rest.trim_start_matches(|c: char| c.is_ascii_whitespace());
+            if spaced.len() != rest.len() {
+                rest = spaced;
+                if let Some((token68, after)) = take_token68(rest) {
+                    challenge.token68 = Some(token68.to_string());
+                    rest = after;
+                } else {
+                    while starts_with_param(rest) {
+                        let (name, after) = take_token(rest);
+                        let after = after.trim_start()[1..].trim_start();
+                        let (value, after) = match after.strip_prefix('"') {
+                            Some(quoted) => parse_quoted(quoted),
+                            None => {
+                                let end = after
+                                    .find(|c: char| c == ',' || c.is_ascii_whitespace())
+                                    .unwrap_or(after.len());
+                                (after[..end].to_string(), &after[end..])
+                            }
+                        };
+                        challenge.params.push((name.to_ascii_lowercase(), value));
+
+                        // Carry on only if the next item is another parameter
+                        rest = after.trim_start();
+                        match rest.strip_prefix(',') {
+                            Some(next) if starts_with_param(next.trim_start()) => {
+                                rest = next.trim_start();
+                            }
+                            _ => break,
+                        }
+                    }
+                }
+            }
+            challenges.push(challenge);
+        }
+        challenges
+    }
+
+    /// Gets the value of a parameter, ignoring the case of its name
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(param, _)| param.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Builder for `401 UNAUTHORIZED` responses advertising every accepted scheme in one `WWW-Authenticate` header
+///
+/// # Example
+///
+/// ```rust
+/// use axum_auth::{Challenge, Unauthorized};
+/// use axum::response::IntoResponse;
+///
+/// let resp = Unauthorized::new()
+///     .with_challenge(Challenge::bearer())
+///     .with_challenge(Challenge::basic("API"))
+///     .with_message("Please log in")
+///     .into_response();
+///
+/// assert_eq!(
+///     resp.headers()["www-authenticate"],
+///     r#"Bearer, Basic realm="API", charset="UTF-8""#
+/// );
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Unauthorized {
+    challenges: Vec<Challenge>,
+    message: &'static str,
+}
+
+impl Unauthorized {
+    /// Creates a new response without any challenges or message
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a challenge to the response, returning it back
+    pub fn with_challenge(mut self, challenge: Challenge) -> Self {
+        self.challenges.push(challenge);
+        self
+    }
+
+    /// Adds many challenges to the response in order, returning it back
+    pub fn with_challenges(mut self, challenges: impl IntoIterator<Item = Challenge>) -> Self {
+        self.challenges.extend(challenges);
+        self
+    }
+
+    /// Sets the message of the response body, returning it back
+    pub fn with_message(mut self, message: &'static str) -> Self {
+        self.message = message;
+        self
+    }
+}
+
+impl IntoResponse for Unauthorized {
+    fn into_response(self) -> Response {
+        challenge_response(
+            StatusCode::UNAUTHORIZED,
+            WWW_AUTHENTICATE,
+            &self.challenges,
+            self.message,
+        )
+    }
+}
+
+/// Builder for `407 PROXY AUTHENTICATION REQUIRED` responses advertising every accepted scheme in one `Proxy-Authenticate` header
+///
+/// This mirrors [Unauthorized] for proxies, which have to use their own status and header so they don't get mixed up with the authentication of the server behind them.
+///
+/// # Example
+///
+/// ```rust
+/// use axum_auth::{Challenge, ProxyAuthenticationRequired};
+/// use axum::response::IntoResponse;
+///
+/// let resp = ProxyAuthenticationRequired::new()
+///     .with_challenge(Challenge::basic("Corporate proxy"))
+///     .into_response();
+///
+/// assert_eq!(resp.status().as_u16(), 407);
+/// assert_eq!(
+///     resp.headers()["proxy-authenticate"],
+///     r#"Basic realm="Corporate proxy", charset="UTF-8""#
+/// );
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct ProxyAuthenticationRequired {
+    challenges: Vec<Challenge>,
+    message: &'static str,
+}
+
+impl ProxyAuthenticationRequired {
+    /// Creates a new response without any challenges or message
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a challenge to the response, returning it back
+    pub fn with_challenge(mut self, challenge: Challenge) -> Self {
+        self.challenges.push(challenge);
+        self
+    }
+
+    /// Adds many challenges to the response in order, returning it back
+    pub fn with_challenges(mut self, challenges: impl IntoIterator<Item = Challenge>) -> Self {
+        self.challenges.extend(challenges);
+        self
+    }
+
+    /// Sets the message of the response body, returning it back
+    pub fn with_message(mut self, message: &'static str) -> Self {
+        self.message = message;
+        self
+    }
+}
+
+impl IntoResponse for ProxyAuthenticationRequired {
+    fn into_response(self) -> Response {
+        challenge_response(
+            StatusCode::PROXY_AUTHENTICATION_REQUIRED,
+            PROXY_AUTHENTICATE,
+            &self.challenges,
+            self.message,
+        )
+    }
+}
+
+/// Creates a response with every challenge in one header, leaving the header out if there aren't any, and the [AuthErrorKind] of the message in its extensions
+fn challenge_response(
+    status: StatusCode,
+    header: HeaderName,
+    challenges: &[Challenge],
+    message: &'static str,
+) -> Response {
+    let mut resp = (status, message).into_response();
+    resp.extensions_mut()
+        .insert(AuthErrorKind::from_message(message));
+    if let Some(value) = challenge_header(challenges) {
+        resp.headers_mut().insert(header, value);
+    }
+    resp
+}
+
+/// Joins every challenge into one header value, giving [None] if there aren't any or they can't be sent
+pub(crate) fn challenge_header(challenges: &[Challenge]) -> Option<HeaderValue> {
+    let challenges = challenges
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ");
+    if challenges.is_empty() {
+        return None;
+    }
+    HeaderValue::from_str(&challenges).ok()
+}
+
+/// Extractor which knows its own challenges, allowing it to be negotiated with others in [AuthEither]
+///
+/// This is implemented for the extractors of this crate, but you can implement it for your own custom extractors too.
+///
+/// # Example
+///
+/// ```rust
+/// use axum_auth::{AuthBearer, AuthBearerCustom, AuthScheme, Challenge, Rejection};
+/// use http::request::Parts;
+///
+/// /// Bearer auth which advertises the realm it belongs to
+/// struct RealmBearer(String);
+///
+/// impl AuthScheme for RealmBearer {
+///     fn challenges() -> Vec<Challenge> {
+///         vec![Challenge::bearer().with_param("realm", "example")]
+///     }
+///
+///     fn extract(parts: &mut Parts) -> Result<Self, Rejection> {
+///         AuthBearer::decode_request_parts(parts).map(|bearer| Self(bearer.0))
+///     }
+/// }
+/// ```
+pub trait AuthScheme: Sized {
+    /// Challenges to advertise when this scheme fails; you need to implement this
+    fn challenges() -> Vec<Challenge>;
+
+    /// Extracts the credentials from the request; you need to implement this
+    fn extract(parts: &mut Parts) -> Result<Self, Rejection>;
+}
+
+/// Negotiator accepting either of two schemes, trying them in priority order
+///
+/// If neither scheme accepts the request, the response is a `401 UNAUTHORIZED` advertising every challenge from both schemes in one `WWW-Authenticate` header. Negotiators can be nested to accept more than two schemes, such as `AuthEither<AuthBearer, AuthEither<AuthBasic, MyApiKey>>`.
+///
+/// # Example
+///
+/// ```no_run
+/// use axum_auth::{AuthBasic, AuthBearer, AuthEither};
+///
+/// /// Accepts bearer tokens from services and basic auth from people
+/// async fn handler(auth: AuthEither<AuthBearer, AuthBasic>) -> String {
+///     match auth {
+///         AuthEither::Left(AuthBearer(token)) => format!("Service with token {}", token),
+///         AuthEither::Right(AuthBasic((id, _))) => format!("Person called {}", id),
+///     }
+/// }
+/// ```
+///
+/// # Errors
+///
+/// All errors are `401 UNAUTHORIZED` with the combined challenges. The message is picked based on the scheme the client tried to use:
+///
+/// - \`Authorization\` header is missing – The header was required but it wasn't found
+/// - \`Authorization\` header uses an unsupported scheme – The header was for a scheme neither side accepts
+/// - Otherwise, the message from the scheme the header was meant for
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum AuthEither<L, R> {
+    /// Request was accepted by the first, preferred scheme
+    Left(L),
+    /// Request was accepted by the second scheme
+    Right(R),
+}
+
+impl<L: AuthScheme, R: AuthScheme> AuthScheme for AuthEither<L, R> {
+    fn challenges() -> Vec<Challenge> {
+        let mut challenges = L::challenges();
+        challenges.extend(R::challenges());
+        challenges
+    }
+
+    fn extract(parts: &mut Parts) -> Result<Self, Rejection> {
+        // Try both schemes in order
+        let left = match L::extract(parts) {
+            Ok(left) => return Ok(Self::Left(left)),
+            Err(err) => err,
+        };
+        let right = match R::extract(parts) {
+            Ok(right) => return Ok(Self::Right(right)),
+            Err(err) => err,
+        };
+
+        // Give back the error from the scheme the client was trying to use
+        let scheme = match parts.headers.get(credential_header(parts)) {
+            Some(value) => value
+                .to_str()
+                .ok()
+                .and_then(|value| value.split(' ').next())
+                .unwrap_or_default(),
+            None => return Err((StatusCode::UNAUTHORIZED, ERR_MISSING)),
+        };
+        let accepts = |challenges: Vec<Challenge>| {
+            challenges
+                .iter()
+                .any(|challenge| challenge.scheme.eq_ignore_ascii_case(scheme))
+        };
+        if accepts(L::challenges()) {
+            Err((StatusCode::UNAUTHORIZED, left.1))
+        } else if accepts(R::challenges()) {
+            Err((StatusCode::UNAUTHORIZED, right.1))
+        } else {
+            Err((StatusCode::UNAUTHORIZED, ERR_UNSUPPORTED_SCHEME))
+        }
+    }
+}
+
+impl<L, R, S> FromRequestParts<S> for AuthEither<L, R>
+where
+    L: AuthScheme + Send,
+    R: AuthScheme + Send,
+    S: Send + Sync,
+{
+    type Rejection = Unauthorized;
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        Self::extract(parts).map_err(|(_, message)| {
+            Unauthorized::new()
+                .with_challenges(Self::challenges())
+                .with_message(message)
+        })
+    }
+}
+
+/// Unescapes a quoted string which has already had its opening quote removed, alongside what's after its closing quote
+fn parse_quoted(quoted: &str) -> (String, &str) {
+    let mut value = String::new();
+    let mut chars = quoted.char_indices();
+    let mut end = quoted.len();
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '\\' => value.extend(chars.next().map(|(_, c)| c)),
+            '"' => {
+                end = index + 1;
+                break;
+            }
+            _ => value.push(c),
+        }
+    }
+    (value, &quoted[end..])
+}
+
+/// Splits off the longest token from the start of the input, which may be empty
+fn take_token(input: &str) -> (&str, &str) {
+    let end = input
+        .find(|c: char| !(c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c)))
+        .unwrap_or(input.len());
+    input.split_at(end)
+}
+
+/// Splits off a token68 from the start of the input if it's the only thing in its challenge
+fn take_token68(input: &str) -> Option<(&str, &str)> {
+    let body = input
+        .find(|c: char| !(c.is_ascii_alphanumeric() || "-._~+/".contains(c)))
+        .unwrap_or(input.len());
+    let end = body + input[body..].len() - input[body..].trim_start_matches('=').len();
+    let (token68, after) = input.split_at(end);
+    let finished = after.trim_start().is_empty() || after.trim_start().starts_with(',');
+    (body > 0 && finished).then_some((token68, after))
+}
+
+/// Checks if the input starts with an auth parameter, rather than the scheme of the next challenge
+fn starts_with_param(input: &str) -> bool {
+    let (name, after) = take_token(input);
+    !name.is_empty() && after.trim_start().starts_with('=')
+}
+
+/// Parses comma-separated auth parameters like `realm="example", data=abc=` into names and unquoted values
+///
+/// Names are lowercased since they're case-insensitive, and malformed parameters are skipped.
+#[cfg(any(
+    feature = "auth-digest",
+    feature = "gateway-signature",
+    feature = "hoba",
+    feature = "scram",
+    feature = "vapid"
+))]
+pub(crate) fn parse_params(input: &str) -> Vec<(String, String)> {
+    let mut params = vec![];
+    let mut rest = input.trim_start();
+    while !rest.is_empty() {
+        // Get the name up to the equals sign
+        let Some((name, after)) = rest.split_once('=') else {
+            break;
+        };
+        let name = name.trim().to_ascii_lowercase();
+        let after = after.trim_start();
+
+        // Get the value, unescaping it if it's quoted
+        let (value, after) = if let Some(quoted) = after.strip_prefix('"') {
+            parse_quoted(quoted)
+        } else {
+            let end = after.find(',').unwrap_or(after.len());
+            (after[..end].trim().to_string(), &after[end..])
+        };
+        if !name.is_empty() {
+            params.push((name, value));
+        }
+
+        // Move onto the next parameter
+        rest = after.trim_start();
+        rest = rest.strip_prefix(',').unwrap_or(rest).trim_start();
+    }
+    params
+}
@@ -0,0 +1,139 @@
+//! Implementation of prefixed tokens with a CRC32 checksum, in the format GitHub uses for its tokens
+//!
+//! See [ChecksumToken] for the most commonly-used data structure
+
+use crate::crypto;
+
+/// Characters used for the random part and checksum, in the order GitHub encodes base62 with
+const BASE62: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// Length of the base62-encoded checksum at the end of a token
+const CHECKSUM_LEN: usize = 6;
+
+/// Format of tokens made of a prefix, random base62 characters, and a base62 CRC32 checksum of the random part, like GitHub's `ghp_` tokens
+///
+/// This is enabled via the `checksum-token` feature alongside a crypto backend
+///
+/// The checksum lets tokens with typos, or made up ones, be rejected locally before calling an API or looking them up in a store, and the prefix lets secret scanners find leaked tokens. The same format can be used to generate tokens for your own API keys.
+///
+/// # Example
+///
+/// ```rust
+/// use axum_auth::ChecksumToken;
+///
+/// const API_KEY: ChecksumToken = ChecksumToken::new("myapp_", 30);
+///
+/// let key = API_KEY.generate();
+/// assert!(API_KEY.verify(&key));
+/// assert!(!API_KEY.verify("myapp_000000000000000000000000000000000000"));
+/// ```
+///
+/// Use [AuthBearerCustom::CHECKSUM_TOKEN](crate::AuthBearerCustom::CHECKSUM_TOKEN) to check bearer tokens as they're extracted.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ChecksumToken {
+    prefix: &'static str,
+    random_len: usize,
+}
+
+impl ChecksumToken {
+    /// GitHub classic personal access tokens, starting with `ghp_`
+    pub const GITHUB_PAT: Self = Self::new("ghp_", 30);
+
+    /// GitHub OAuth access tokens, starting with `gho_`
+    pub const GITHUB_OAUTH: Self = Self::new("gho_", 30);
+
+    /// GitHub App user access tokens, starting with `ghu_`
+    pub const GITHUB_USER_TO_SERVER: Self = Self::new("ghu_", 30);
+
+    /// GitHub App installation access tokens, starting with `ghs_`
+    pub const GITHUB_SERVER_TO_SERVER: Self = Self::new("ghs_", 30);
+
+    /// GitHub App refresh tokens, starting with `ghr_`
+    pub const GITHUB_REFRESH: Self = Self::new("ghr_", 30);
+
+    /// Creates a new format with the prefix and number of random characters before the checksum
+    pub const fn new(prefix: &'static str, random_len: usize) -> Self {
+        Self { prefix, random_len }
+    }
+
+    /// Gets the prefix tokens start with
+    pub const fn prefix(&self) -> &'static str {
+        self.prefix
+    }
+
+    /// Checks if the token has this format and a correct checksum
+    pub fn verify(&self, token: &str) -> bool {
+        let Some(body) = token.strip_prefix(self.prefix) else {
+            return false;
+        };
+        if body.len() != self.random_len + CHECKSUM_LEN
+            || !body.bytes().all(|b| BASE62.contains(&b))
+        {
+            return false;
+        }
+        let (random, checksum) = body.split_at(self.random_len);
+        checksum == encode_checksum(random.as_bytes())
+    }
+
+    /// Generates a new random token in this format
+    pub fn generate(&self) -> String {
+        let mut random = String::with_capacity(self.random_len);
+        while random.len() < self.random_len {
+            // Skip bytes past the last multiple of 62 so every character is equally likely
+            random.extend(
+                crypto::random(self.random_len)
+                    .into_iter()
+                    .filter(|byte| *byte < 248)
+                    .map(|byte| BASE62[byte as usize % 62] as char)
+                    .take(self.random_len - random.len()),
+            );
+        }
+        let checksum = encode_checksum(random.as_bytes());
+        format!("{}{}{}", self.prefix, random, checksum)
+    }
+
+    /// Checks if the token looks like any kind of GitHub token, including fine-grained `github_pat_` tokens
+    ///
+    /// Fine-grained tokens don't have a public checksum, so only their shape is checked.
+    pub fn is_github_token(token: &str) -> bool {
+        let classic = [
+            Self::GITHUB_PAT,
+            Self::GITHUB_OAUTH,
+            Self::GITHUB_USER_TO_SERVER,
+            Self::GITHUB_SERVER_TO_SERVER,
+            Self::GITHUB_REFRESH,
+        ];
+        let fine_grained = token
+            .strip_prefix("github_pat_")
+            .and_then(|body| body.split_once('_'))
+            .is_some_and(|(id, secret)| {
+                id.len() == 22
+                    && secret.len() == 59
+                    && id
+                        .bytes()
+                        .chain(secret.bytes())
+                        .all(|b| BASE62.contains(&b))
+            });
+        fine_grained || classic.iter().any(|format| format.verify(token))
+    }
+}
+
+/// Encodes the CRC32 of the data as zero-padded base62
+fn encode_checksum(data: &[u8]) -> String {
+    let mut checksum = crc32(data);
+    let mut encoded = [b'0'; CHECKSUM_LEN];
+    for digit in encoded.iter_mut().rev() {
+        *digit = BASE62[(checksum % 62) as usize];
+        checksum /= 62;
+    }
+    encoded.iter().map(|b| *b as char).collect()
+}
+
+/// Calculates the CRC32 (IEEE) of the data
+fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0u32, |crc, byte| {
+        (0..8).fold(crc ^ *byte as u32, |crc, _| {
+            (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg())
+        })
+    })
+}
@@ -0,0 +1,214 @@
+//! Implementation of failing fast while the service behind a validator is down
+//!
+//! See [CircuitBreaker] for the most commonly-used data structure
+
+use crate::{AuthUser, AuthValidator, Rejection, ERR_CIRCUIT_OPEN};
+use http::{Extensions, StatusCode};
+#[cfg(feature = "metrics")]
+use std::borrow::Cow;
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Wrapper around an [AuthValidator] which stops calling it for a while after it keeps failing, so a dead identity provider fails requests fast instead of piling up timeouts
+///
+/// This is enabled via the `circuit-breaker` feature
+///
+/// Only server errors like `503 SERVICE UNAVAILABLE` count as failures, as a client sending bad credentials says nothing about whether the identity provider is up. The circuit goes through these states:
+///
+/// - [Closed](CircuitState::Closed) – Every validation is passed on, counting the failures in a row
+/// - [Open](CircuitState::Open) – Once there have been too many failures in a row, validations are rejected straight away with the fallback response until the open duration is over
+/// - [HalfOpen](CircuitState::HalfOpen) – After that, one validation is let through to probe the validator, closing the circuit if it works and opening it again if it doesn't
+///
+/// With the `metrics` feature, the state is recorded as the `axum_auth_circuit_state` gauge (`0` closed, `1` half-open, `2` open), and validations rejected without calling the validator are counted in `axum_auth_circuit_rejections_total`, both labelled with the `validator` name.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{routing::get, Router};
+/// use axum_auth::{AuthUser, BasicUser, BasicUsers, CircuitBreaker, RequireBasicAuthLayer};
+/// use std::time::Duration;
+///
+/// let users = BasicUsers::new().with_user("alice", BasicUser::new("hunter2"));
+/// let breaker = CircuitBreaker::new(users)
+///     .with_failure_threshold(3)
+///     .with_open_duration(Duration::from_secs(10));
+///
+/// let app: Router = Router::new()
+///     .route("/", get(|user: AuthUser| async move { format!("Hello, {}!", user.id) }))
+///     .layer(RequireBasicAuthLayer::new(breaker));
+/// ```
+///
+/// # Errors
+///
+/// Rejections from the inner validator are given back as-is. While the circuit is open, validations are rejected with `503 SERVICE UNAVAILABLE` and the message "Credentials can't be checked right now, try again later", or the fallback given using [with_fallback](Self::with_fallback).
+#[derive(Debug)]
+pub struct CircuitBreaker<V> {
+    inner: V,
+    failure_threshold: u32,
+    open_duration: Duration,
+    fallback: Rejection,
+    #[cfg(feature = "metrics")]
+    name: Cow<'static, str>,
+    state: Mutex<State>,
+}
+
+/// State of a [CircuitBreaker]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum CircuitState {
+    /// Validations are passed on to the validator
+    Closed,
+    /// Validations are rejected without calling the validator
+    Open,
+    /// One validation is being let through to check if the validator works again
+    HalfOpen,
+}
+
+/// Internal state of a [CircuitBreaker], with what's needed to move to the next one
+#[derive(Debug, Clone, Copy)]
+enum State {
+    Closed { failures: u32 },
+    Open { until: Instant },
+    HalfOpen { probed: Instant },
+}
+
+impl<V> CircuitBreaker<V> {
+    /// Default number of failures in a row before the circuit opens, which is 5
+    pub const DEFAULT_FAILURE_THRESHOLD: u32 = 5;
+
+    /// Default time the circuit stays open before probing the validator again, which is 30 seconds
+    pub const DEFAULT_OPEN_DURATION: Duration = Duration::from_secs(30);
+
+    /// Creates a closed circuit around the given validator, using the default threshold and open duration
+    pub fn new(inner: V) -> Self {
+        Self {
+            inner,
+            failure_threshold: Self::DEFAULT_FAILURE_THRESHOLD,
+            open_duration: Self::DEFAULT_OPEN_DURATION,
+            fallback: (StatusCode::SERVICE_UNAVAILABLE, ERR_CIRCUIT_OPEN),
+            #[cfg(feature = "metrics")]
+            name: Cow::Borrowed("default"),
+            state: Mutex::new(State::Closed { failures: 0 }),
+        }
+    }
+
+    /// Sets the number of failures in a row before the circuit opens, returning it back
+    pub fn with_failure_threshold(mut self, failure_threshold: u32) -> Self {
+        self.failure_threshold = failure_threshold.max(1);
+        self
+    }
+
+    /// Sets how long the circuit stays open before probing the validator again, returning it back
+    pub fn with_open_duration(mut self, open_duration: Duration) -> Self {
+        self.open_duration = open_duration;
+        self
+    }
+
+    /// Sets the rejection given while the circuit is open, returning it back
+    pub fn with_fallback(mut self, fallback: Rejection) -> Self {
+        self.fallback = fallback;
+        self
+    }
+
+    /// Sets the name used for the `validator` label of metrics, returning it back
+    ///
+    /// This is enabled via the `metrics` feature
+    #[cfg(feature = "metrics")]
+    pub fn with_name(mut self, name: impl Into<Cow<'static, str>>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Gets the validator being protected
+    pub fn inner(&self) -> &V {
+        &self.inner
+    }
+
+    /// Gets the current state of the circuit
+    pub fn state(&self) -> CircuitState {
+        match *self.state.lock().unwrap() {
+            State::Closed { .. } => CircuitState::Closed,
+            State::Open { until } if until > Instant::now() => CircuitState::Open,
+            State::Open { .. } | State::HalfOpen { .. } => CircuitState::HalfOpen,
+        }
+    }
+
+    /// Checks if a validation can be passed on, moving to half-open once the circuit has been open for long enough
+    fn admit(&self) -> bool {
+        let now = Instant::now();
+        let mut state = self.state.lock().unwrap();
+        let admitted = match *state {
+            State::Closed { .. } => return true,
+            State::Open { until } => until <= now,
+            // Probe again if the last probe never finished, like when it was cancelled
+            State::HalfOpen { probed } => probed + self.open_duration <= now,
+        };
+        if admitted {
+            *state = State::HalfOpen { probed: now };
+        }
+        self.record_state(&state);
+        admitted
+    }
+
+    /// Records the outcome of a validation which was passed on, opening or closing the circuit
+    fn record(&self, failed: bool) {
+        let mut state = self.state.lock().unwrap();
+        *state = match (*state, failed) {
+            (_, false) => State::Closed { failures: 0 },
+            (State::Closed { failures }, true) if failures + 1 < self.failure_threshold => {
+                State::Closed {
+                    failures: failures + 1,
+                }
+            }
+            (_, true) => State::Open {
+                until: Instant::now() + self.open_duration,
+            },
+        };
+        self.record_state(&state);
+    }
+
+    /// Records the state in the metrics if they're enabled
+    fn record_state(&self, state: &State) {
+        #[cfg(feature = "metrics")]
+        metrics::gauge!("axum_auth_circuit_state", "validator" => self.name.to_string()).set(
+            match state {
+                State::Closed { .. } => 0.0,
+                State::HalfOpen { .. } => 1.0,
+                State::Open { .. } => 2.0,
+            },
+        );
+        #[cfg(not(feature = "metrics"))]
+        let _ = state;
+    }
+}
+
+impl<C, V> AuthValidator<C> for CircuitBreaker<V>
+where
+    C: Sync,
+    V: AuthValidator<C>,
+{
+    async fn validate(&self, credentials: &C) -> Result<AuthUser, Rejection> {
+        self.validate_with_extensions(credentials, &mut Extensions::new())
+            .await
+    }
+
+    async fn validate_with_extensions(
+        &self,
+        credentials: &C,
+        extensions: &mut Extensions,
+    ) -> Result<AuthUser, Rejection> {
+        if !self.admit() {
+            #[cfg(feature = "metrics")]
+            metrics::counter!("axum_auth_circuit_rejections_total", "validator" => self.name.to_string())
+                .increment(1);
+            return Err(self.fallback);
+        }
+        let result = self
+            .inner
+            .validate_with_extensions(credentials, extensions)
+            .await;
+        self.record(matches!(&result, Err((status, _)) if status.is_server_error()));
+        result
+    }
+}
@@ -0,0 +1,224 @@
+//! Implementation of getting OAuth2 client credentials tokens for calls this service makes to others
+//!
+//! See [ClientCredentials] for the most commonly-used data structure
+
+use crate::{Rejection, ERR_CLIENT_CREDENTIALS_REFUSED, ERR_CLIENT_CREDENTIALS_UNAVAILABLE};
+use http::{header::AUTHORIZATION, HeaderMap, HeaderValue, StatusCode};
+use serde_json::Value;
+use std::{
+    fmt,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::sync::Mutex;
+
+/// Grant type asking the identity provider for a token on behalf of the client itself
+const GRANT_TYPE: &str = "client_credentials";
+
+/// Bearer token this service sends to another, like one from [ClientCredentials]
+///
+/// This is enabled via the `client-credentials` feature
+///
+/// The token never shows up in the `Debug` output, and the header values it makes are marked as sensitive.
+#[derive(Clone, PartialEq, Eq)]
+pub struct BearerToken(pub String);
+
+impl BearerToken {
+    /// Gets the token as it's sent, without the `Bearer` scheme
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Makes the sensitive `Authorization` header value sending the token, which is `None` if it can't be sent in a header
+    pub fn to_header_value(&self) -> Option<HeaderValue> {
+        let mut value = HeaderValue::from_str(&format!("Bearer {}", self.0)).ok()?;
+        value.set_sensitive(true);
+        Some(value)
+    }
+
+    /// Makes the headers sending the token, which are empty if it can't be sent in a header
+    pub fn to_headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        if let Some(value) = self.to_header_value() {
+            headers.insert(AUTHORIZATION, value);
+        }
+        headers
+    }
+}
+
+impl fmt::Debug for BearerToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("BearerToken").field(&"..").finish()
+    }
+}
+
+/// Provider of OAuth2 client credentials tokens for this service to call others with, getting a new one shortly before the last expires
+///
+/// This is enabled via the `client-credentials` feature
+///
+/// The token is remembered until shortly before the `expires_in` the endpoint gave, and tokens without an `expires_in` are asked for every time. Only one request for a new token is made at once, with any other calls waiting for it instead of asking as well. Clones share the same remembered token, so a single provider can be put into the state of the app.
+///
+/// # Example
+///
+/// ```no_run
+/// use axum_auth::{ClientCredentials, Rejection};
+/// use http::StatusCode;
+///
+/// /// Gets the invoices of the customer from the billing service
+/// async fn invoices(credentials: &ClientCredentials, customer: &str) -> Result<String, Rejection> {
+///     let token = credentials.token().await?;
+///     let unavailable = (StatusCode::BAD_GATEWAY, "Billing service couldn't be reached");
+///     reqwest::Client::new()
+///         .get(format!("https://billing.example.com/customers/{}/invoices", customer))
+///         .headers(token.to_headers())
+///         .send()
+///         .await
+///         .map_err(|_| unavailable)?
+///         .text()
+///         .await
+///         .map_err(|_| unavailable)
+/// }
+///
+/// let credentials = ClientCredentials::new("https://auth.example.com/oauth2/token", "orders", "orders-secret")
+///     .with_scope("invoices:read");
+/// ```
+///
+/// # Errors
+///
+/// If the endpoint refuses to give a token with `400 BAD REQUEST` or `401 UNAUTHORIZED`, like for an `invalid_client`, this is `502 BAD GATEWAY` with the message "Client credentials were refused by the token endpoint". If the endpoint can't be reached or gives back anything else without a token, this is `502 BAD GATEWAY` with the message "Token endpoint couldn't be reached".
+#[derive(Clone)]
+pub struct ClientCredentials {
+    endpoint: String,
+    client_id: String,
+    client_secret: String,
+    scope: Option<String>,
+    audience: Option<String>,
+    margin: Duration,
+    client: reqwest::Client,
+    current: Arc<Mutex<Option<Current>>>,
+}
+
+/// Token given back by the endpoint, remembered until shortly before it expires
+#[derive(Debug)]
+struct Current {
+    token: BearerToken,
+    fresh_until: Instant,
+}
+
+impl ClientCredentials {
+    /// Default time before expiring that tokens stop being used, which is 30 seconds
+    pub const DEFAULT_MARGIN: Duration = Duration::from_secs(30);
+
+    /// Creates a new provider getting tokens from the token endpoint at the URL, authenticating with basic auth
+    pub fn new(
+        endpoint: impl Into<String>,
+        client_id: impl Into<String>,
+        client_secret: impl Into<String>,
+    ) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            client_id: client_id.into(),
+            client_secret: client_secret.into(),
+            scope: None,
+            audience: None,
+            margin: Self::DEFAULT_MARGIN,
+            client: reqwest::Client::new(),
+            current: Arc::default(),
+        }
+    }
+
+    /// Asks for tokens to have the space-separated scopes, returning it back
+    pub fn with_scope(mut self, scope: impl Into<String>) -> Self {
+        self.scope = Some(scope.into());
+        self
+    }
+
+    /// Asks for tokens meant for the audience, which some identity providers like Auth0 need, returning it back
+    pub fn with_audience(mut self, audience: impl Into<String>) -> Self {
+        self.audience = Some(audience.into());
+        self
+    }
+
+    /// Sets how long before expiring that tokens stop being used, so they don't expire on the way to the other service, returning it back
+    pub fn with_margin(mut self, margin: Duration) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    /// Gets a token to call other services with, using the remembered one if it's still fresh
+    ///
+    /// # Errors
+    ///
+    /// See the errors of [ClientCredentials] for the rejections this can give.
+    pub async fn token(&self) -> Result<BearerToken, Rejection> {
+        let mut current = self.current.lock().await;
+        if let Some(current) = current.as_ref() {
+            if current.fresh_until > Instant::now() {
+                return Ok(current.token.clone());
+            }
+        }
+
+        // Ask the endpoint for a new token
+        let unavailable = (StatusCode::BAD_GATEWAY, ERR_CLIENT_CREDENTIALS_UNAVAILABLE);
+        let mut form = vec![("grant_type", GRANT_TYPE)];
+        if let Some(scope) = &self.scope {
+            form.push(("scope", scope));
+        }
+        if let Some(audience) = &self.audience {
+            form.push(("audience", audience));
+        }
+        let resp = self
+            .client
+            .post(&self.endpoint)
+            .basic_auth(&self.client_id, Some(&self.client_secret))
+            .form(&form)
+            .send()
+            .await
+            .map_err(|_| unavailable)?;
+        if matches!(
+            resp.status(),
+            StatusCode::BAD_REQUEST | StatusCode::UNAUTHORIZED
+        ) {
+            return Err((StatusCode::BAD_GATEWAY, ERR_CLIENT_CREDENTIALS_REFUSED));
+        } else if !resp.status().is_success() {
+            return Err(unavailable);
+        }
+        let json: Value = resp
+            .bytes()
+            .await
+            .ok()
+            .and_then(|body| serde_json::from_slice(&body).ok())
+            .ok_or(unavailable)?;
+        let token = json
+            .get("access_token")
+            .and_then(Value::as_str)
+            .map(|token| BearerToken(token.to_string()))
+            .ok_or(unavailable)?;
+
+        // Remember it until shortly before it expires, unless it lasts too long to be a time
+        *current = json
+            .get("expires_in")
+            .and_then(Value::as_u64)
+            .and_then(|expires_in| {
+                Instant::now()
+                    .checked_add(Duration::from_secs(expires_in).saturating_sub(self.margin))
+            })
+            .map(|fresh_until| Current {
+                token: token.clone(),
+                fresh_until,
+            });
+        Ok(token)
+    }
+}
+
+impl fmt::Debug for ClientCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClientCredentials")
+            .field("endpoint", &self.endpoint)
+            .field("client_id", &self.client_id)
+            .field("scope", &self.scope)
+            .field("audience", &self.audience)
+            .field("margin", &self.margin)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,123 @@
+//! Implementation of [RFC 9530](https://www.rfc-editor.org/rfc/rfc9530) `Content-Digest` verification
+//!
+//! See [DigestVerified] for the most commonly-used data structure
+
+use crate::{
+    crypto, error::rejection_response, Rejection, ERR_DEFAULT, ERR_DIGEST_ALGORITHM,
+    ERR_DIGEST_BODY, ERR_DIGEST_MISMATCH, ERR_DIGEST_MISSING,
+};
+use axum_core::{
+    extract::{FromRequest, FromRequestParts, Request},
+    response::{IntoResponse, Response},
+};
+use base64::{engine::general_purpose, Engine};
+use bytes::Bytes;
+
+/// Name of the header containing the digests of the body
+const CONTENT_DIGEST: &str = "content-digest";
+
+/// Extractor running an auth extractor and then verifying the body against its `Content-Digest` header
+///
+/// This is enabled via the `content-digest` feature alongside a crypto backend
+///
+/// When a signature scheme covers the `Content-Digest` header, checking the signature alone only proves the header wasn't tampered with. This also recomputes the digest of the body, so once extracted both the credential and the body are known to be exactly what the client sent. Use `DigestVerified<()>` if you only need the body checked.
+///
+/// Both `sha-256` and `sha-512` are supported; every supported digest present must match and at least one has to be present.
+///
+/// # Example
+///
+/// ```no_run
+/// use axum_auth::{AuthBearer, DigestVerified};
+///
+/// /// Webhook receiver which only accepts bodies matching their digest
+/// async fn handler(verified: DigestVerified<AuthBearer>) -> String {
+///     format!("Got {} bytes from {}", verified.body.len(), verified.credential.0)
+/// }
+/// ```
+///
+/// # Errors
+///
+/// If the wrapped extractor fails, its rejection is given as-is. Otherwise, all invalid responses are `400 BAD REQUEST` with one of these messages:
+///
+/// - \`Content-Digest\` header is missing – The header was required but it wasn't found
+/// - \`Content-Digest\` header has no supported algorithm – Only unknown algorithms or malformed values were found
+/// - Request body doesn't match its \`Content-Digest\` – The body was changed or the digest was calculated wrongly
+/// - Request body could not be read – The body couldn't be buffered, e.g. it was too large
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct DigestVerified<A> {
+    /// Credential from the wrapped extractor
+    pub credential: A,
+    /// Body which was verified against the digest
+    pub body: Bytes,
+}
+
+impl<A, S> FromRequest<S> for DigestVerified<A>
+where
+    A: FromRequestParts<S> + Send,
+    A::Rejection: IntoResponse,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let (mut parts, body) = req.into_parts();
+
+        // Check the credential first so unauthenticated bodies aren't buffered
+        let credential = A::from_request_parts(&mut parts, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+
+        // Get the digest header before reading the body
+        let header = parts
+            .headers
+            .get(CONTENT_DIGEST)
+            .ok_or_else(|| rejection_response((ERR_DEFAULT, ERR_DIGEST_MISSING)))?
+            .to_str()
+            .map_err(|_| rejection_response((ERR_DEFAULT, ERR_DIGEST_ALGORITHM)))?
+            .to_string();
+
+        // Buffer and verify the body
+        let req = Request::from_parts(parts, body);
+        let body = Bytes::from_request(req, state)
+            .await
+            .map_err(|_| rejection_response((ERR_DEFAULT, ERR_DIGEST_BODY)))?;
+        verify(&header, &body).map_err(rejection_response)?;
+
+        Ok(Self { credential, body })
+    }
+}
+
+/// Verifies the body against every supported digest in the header
+fn verify(header: &str, body: &[u8]) -> Result<(), Rejection> {
+    let mut checked = false;
+    for member in header.split(',') {
+        // Get the algorithm and value, e.g. `sha-256=:X48E...=:`
+        let Some((algorithm, value)) = member.trim().split_once('=') else {
+            continue;
+        };
+        let Some(expected) = value
+            .strip_prefix(':')
+            .and_then(|value| value.strip_suffix(':'))
+            .and_then(|value| general_purpose::STANDARD.decode(value).ok())
+        else {
+            continue;
+        };
+
+        // Calculate the digest for known algorithms
+        let actual = match algorithm.to_ascii_lowercase().as_str() {
+            "sha-256" => crypto::sha256(body),
+            "sha-512" => crypto::sha512(body),
+            _ => continue,
+        };
+        if actual != expected {
+            return Err((ERR_DEFAULT, ERR_DIGEST_MISMATCH));
+        }
+        checked = true;
+    }
+
+    if checked {
+        Ok(())
+    } else {
+        Err((ERR_DEFAULT, ERR_DIGEST_ALGORITHM))
+    }
+}
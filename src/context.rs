@@ -0,0 +1,147 @@
+//! Implementation of the audit context extractor
+//!
+//! See [AuthContext] for the most commonly-used data structure
+
+#[cfg(feature = "connect-info")]
+use axum::extract::ConnectInfo;
+use axum_core::extract::FromRequestParts;
+use http::{
+    header::{FORWARDED, USER_AGENT},
+    request::Parts,
+    HeaderMap,
+};
+use std::net::{IpAddr, SocketAddr};
+
+/// Header used to find the request identifier, as set by most proxies and `tower-http`
+const REQUEST_ID: &str = "x-request-id";
+
+/// Header used by most proxies to list the client and any proxies in between
+const X_FORWARDED_FOR: &str = "x-forwarded-for";
+
+/// Header used by some proxies (e.g. nginx) to give the client address directly
+const X_REAL_IP: &str = "x-real-ip";
+
+/// Audit context extractor bundling an auth extractor with information about the client connection
+///
+/// This wraps any other extractor from this crate (or your own custom ones) and adds the resolved client IP, user agent, and request ID alongside it, so audit logging only needs one argument
+///
+/// # Example
+///
+/// ```no_run
+/// use axum_auth::{AuthBearer, AuthContext};
+///
+/// /// Logs who accessed the route before responding
+/// async fn handler(ctx: AuthContext<AuthBearer>) -> String {
+///     println!(
+///         "token {} from {:?} using {:?} (request {:?})",
+///         ctx.credential.0, ctx.client_ip, ctx.user_agent, ctx.request_id
+///     );
+///     format!("Hello, {}", ctx.credential.0)
+/// }
+/// ```
+///
+/// # Client IP
+///
+/// By default, the client IP is taken from the connection itself, which requires the router to be served using [`into_make_service_with_connect_info`](https://docs.rs/axum/latest/axum/struct.Router.html#method.into_make_service_with_connect_info) and the `connect-info` feature. If your server sits behind a reverse proxy, set `TRUST_FORWARDED` to `true` like `AuthContext<AuthBearer, true>` to use these headers first, in order:
+///
+/// 1. `Forwarded` – The `for=` parameter of the last (right-most) element of the standardised header
+/// 2. `X-Forwarded-For` – The last (right-most) address of the list
+/// 3. `X-Real-Ip` – The address given directly
+///
+/// Proxies like nginx, AWS ALB, and Envoy append the address they saw to whatever the client already sent, so only the right-most entry was written by your proxy and everything to the left of it can be made up by the client. With several proxies in a row, this is the address seen by the one closest to your server. Only trust forwarded headers if every request passes through your proxy, as clients can otherwise set them to whatever they like.
+///
+/// # Errors
+///
+/// This extractor only fails if the wrapped extractor fails, returning its rejection as-is
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct AuthContext<A, const TRUST_FORWARDED: bool = false> {
+    /// Validated credential from the wrapped extractor
+    pub credential: A,
+    /// Resolved client address, if it could be found
+    pub client_ip: Option<IpAddr>,
+    /// Contents of the `User-Agent` header, if present
+    pub user_agent: Option<String>,
+    /// Contents of the `X-Request-Id` header, if present
+    pub request_id: Option<String>,
+}
+
+impl<A, B, const TRUST_FORWARDED: bool> FromRequestParts<B> for AuthContext<A, TRUST_FORWARDED>
+where
+    A: FromRequestParts<B> + Send,
+    B: Send + Sync,
+{
+    type Rejection = A::Rejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &B) -> Result<Self, Self::Rejection> {
+        // Get the credential first so nothing else is done if it fails
+        let credential = A::from_request_parts(parts, state).await?;
+
+        // Resolve the client address, preferring forwarded headers if trusted
+        let client_ip = if TRUST_FORWARDED {
+            forwarded_ip(&parts.headers)
+        } else {
+            None
+        };
+        #[cfg(feature = "connect-info")]
+        let client_ip = client_ip.or_else(|| {
+            parts
+                .extensions
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|info| info.0.ip())
+        });
+
+        Ok(Self {
+            credential,
+            client_ip,
+            user_agent: header_string(&parts.headers, USER_AGENT.as_str()),
+            request_id: header_string(&parts.headers, REQUEST_ID),
+        })
+    }
+}
+
+/// Gets a header as an owned string if it's present and made up of visible characters
+fn header_string(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string())
+}
+
+/// Finds the client address from the forwarding headers set by proxies
+fn forwarded_ip(headers: &HeaderMap) -> Option<IpAddr> {
+    // Standardised header, e.g. `for=192.0.2.60;proto=http, for="[2001:db8::1]:4711"`, where only the last element was added by our proxy
+    let standard = header_string(headers, FORWARDED.as_str()).and_then(|value| {
+        let last = value.split(',').next_back()?;
+        last.split(';').find_map(|pair| {
+            let (key, value) = pair.trim().split_once('=')?;
+            if key.eq_ignore_ascii_case("for") {
+                parse_ip(value)
+            } else {
+                None
+            }
+        })
+    });
+
+    // De-facto headers which most proxies still set, appending to whatever the client sent
+    standard
+        .or_else(|| {
+            header_string(headers, X_FORWARDED_FOR)
+                .and_then(|value| parse_ip(value.split(',').next_back()?))
+        })
+        .or_else(|| header_string(headers, X_REAL_IP).and_then(|value| parse_ip(&value)))
+}
+
+/// Parses an address which might be quoted or contain a port, e.g. `"[2001:db8::1]:4711"`
+fn parse_ip(input: &str) -> Option<IpAddr> {
+    let input = input.trim().trim_matches('"');
+    input
+        .parse::<IpAddr>()
+        .ok()
+        .or_else(|| input.parse::<SocketAddr>().ok().map(|addr| addr.ip()))
+        .or_else(|| {
+            input
+                .strip_prefix('[')
+                .and_then(|rest| rest.split_once(']'))
+                .and_then(|(ip, _)| ip.parse().ok())
+        })
+}
@@ -0,0 +1,49 @@
+//! Implementation of reading credentials from a header other than `Authorization`
+//!
+//! See [CredentialHeader] for the most commonly-used data structure
+
+use http::{header::AUTHORIZATION, request::Parts, HeaderName};
+
+/// Header the extractors read credentials from instead of `Authorization`, set by inserting it into the request extensions
+///
+/// Some gateways move the credentials the client sent into a header like `X-Original-Authorization` or `X-Forwarded-Authorization`, putting their own into `Authorization`. Once this is in the request extensions, the extractors of this crate read the credentials from the given header instead, parsing them exactly as before. This applies to every extractor in the request, including those used by layers like [RequireAuthLayer](crate::RequireAuthLayer), so add it outside of them.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{routing::get, Extension, Router};
+/// use axum_auth::{AuthBearer, CredentialHeader};
+///
+/// let app: Router = Router::new()
+///     .route("/", get(|AuthBearer(token): AuthBearer| async move { format!("Found a bearer token: {}", token) }))
+///     .layer(Extension(CredentialHeader::new("x-forwarded-authorization")));
+/// ```
+///
+/// Rejections still mention the `Authorization` header, as clients sent their credentials in it before the gateway moved them.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct CredentialHeader(pub HeaderName);
+
+impl CredentialHeader {
+    /// Reads credentials from the header with the given name
+    ///
+    /// # Panics
+    ///
+    /// This panics if the header isn't a valid header name.
+    pub fn new(header: &str) -> Self {
+        Self(HeaderName::try_from(header).expect("credential header isn't a valid name"))
+    }
+}
+
+impl Default for CredentialHeader {
+    fn default() -> Self {
+        Self(AUTHORIZATION)
+    }
+}
+
+/// Gets the name of the header credentials are read from for the request, which is `Authorization` unless a [CredentialHeader] says otherwise
+pub(crate) fn credential_header(parts: &Parts) -> HeaderName {
+    parts
+        .extensions
+        .get::<CredentialHeader>()
+        .map_or(AUTHORIZATION, |header| header.0.clone())
+}
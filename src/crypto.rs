@@ -0,0 +1,196 @@
+//! Cryptographic primitives shared by every feature which needs them, backed by either `ring` or RustCrypto
+//!
+//! Choose the backend with the `crypto-ring` or `crypto-rustcrypto` feature; if both are enabled, `ring` is used.
+
+// Every feature only uses some of these, so unused ones are expected
+#![allow(dead_code)]
+
+#[cfg(not(any(feature = "crypto-ring", feature = "crypto-rustcrypto")))]
+compile_error!(
+    r#"A crypto backend must be enabled for this feature, add either "crypto-ring" or "crypto-rustcrypto""#
+);
+
+#[cfg(feature = "crypto-ring")]
+pub(crate) use self::ring_backend::*;
+#[cfg(all(feature = "crypto-rustcrypto", not(feature = "crypto-ring")))]
+pub(crate) use self::rustcrypto_backend::*;
+
+/// Hash function used by the algorithms which support several, such as the ones in JWTs
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub(crate) enum Hash {
+    /// SHA-256, as used by `HS256` and `RS256`
+    Sha256,
+    /// SHA-384, as used by `HS384` and `RS384`
+    Sha384,
+    /// SHA-512, as used by `HS512` and `RS512`
+    Sha512,
+}
+
+/// Implementations using [ring](https://github.com/briansmith/ring)
+#[cfg(feature = "crypto-ring")]
+mod ring_backend {
+    use super::Hash;
+    use ring::{
+        digest, hmac,
+        rand::{SecureRandom, SystemRandom},
+        signature,
+    };
+
+    /// Calculates the SHA-256 digest of the data
+    pub(crate) fn sha256(data: &[u8]) -> Vec<u8> {
+        digest::digest(&digest::SHA256, data).as_ref().to_vec()
+    }
+
+    /// Calculates the SHA-512 digest of the data
+    pub(crate) fn sha512(data: &[u8]) -> Vec<u8> {
+        digest::digest(&digest::SHA512, data).as_ref().to_vec()
+    }
+
+    /// Calculates the HMAC-SHA-256 of the data using the key
+    pub(crate) fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+        let key = hmac::Key::new(hmac::HMAC_SHA256, key);
+        hmac::sign(&key, data).as_ref().to_vec()
+    }
+
+    /// Generates random bytes from the secure generator of the system
+    pub(crate) fn random(len: usize) -> Vec<u8> {
+        let mut bytes = vec![0; len];
+        SystemRandom::new()
+            .fill(&mut bytes)
+            .expect("System random generator failed");
+        bytes
+    }
+
+    /// Verifies an RSASSA-PKCS1-v1_5 signature using SHA-256, with the public key in PKCS#1 DER form
+    pub(crate) fn verify_rsa_sha256(public_key: &[u8], message: &[u8], signature: &[u8]) -> bool {
+        signature::UnparsedPublicKey::new(&signature::RSA_PKCS1_2048_8192_SHA256, public_key)
+            .verify(message, signature)
+            .is_ok()
+    }
+
+    /// Verifies an ECDSA P-256 signature using SHA-256, with the public key as an uncompressed point and the signature as `r || s`
+    pub(crate) fn verify_es256(public_key: &[u8], message: &[u8], signature: &[u8]) -> bool {
+        signature::UnparsedPublicKey::new(&signature::ECDSA_P256_SHA256_FIXED, public_key)
+            .verify(message, signature)
+            .is_ok()
+    }
+
+    /// Verifies an HMAC tag in constant time
+    pub(crate) fn verify_hmac(hash: Hash, key: &[u8], data: &[u8], tag: &[u8]) -> bool {
+        let algorithm = match hash {
+            Hash::Sha256 => hmac::HMAC_SHA256,
+            Hash::Sha384 => hmac::HMAC_SHA384,
+            Hash::Sha512 => hmac::HMAC_SHA512,
+        };
+        hmac::verify(&hmac::Key::new(algorithm, key), data, tag).is_ok()
+    }
+
+    /// Verifies an RSASSA-PKCS1-v1_5 signature, with the public key as its big-endian modulus and exponent
+    pub(crate) fn verify_rsa(hash: Hash, n: &[u8], e: &[u8], message: &[u8], sig: &[u8]) -> bool {
+        let params = match hash {
+            Hash::Sha256 => &signature::RSA_PKCS1_2048_8192_SHA256,
+            Hash::Sha384 => &signature::RSA_PKCS1_2048_8192_SHA384,
+            Hash::Sha512 => &signature::RSA_PKCS1_2048_8192_SHA512,
+        };
+        signature::RsaPublicKeyComponents { n, e }
+            .verify(params, message, sig)
+            .is_ok()
+    }
+}
+
+/// Implementations using [RustCrypto](https://github.com/RustCrypto)
+#[cfg(all(feature = "crypto-rustcrypto", not(feature = "crypto-ring")))]
+mod rustcrypto_backend {
+    use super::Hash;
+    use hmac::Mac;
+    use rsa::{pkcs1::DecodeRsaPublicKey, signature::Verifier};
+    use sha2::Digest;
+
+    /// Calculates the SHA-256 digest of the data
+    pub(crate) fn sha256(data: &[u8]) -> Vec<u8> {
+        sha2::Sha256::digest(data).to_vec()
+    }
+
+    /// Calculates the SHA-512 digest of the data
+    pub(crate) fn sha512(data: &[u8]) -> Vec<u8> {
+        sha2::Sha512::digest(data).to_vec()
+    }
+
+    /// Calculates the HMAC-SHA-256 of the data using the key
+    pub(crate) fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut mac =
+            hmac::Hmac::<sha2::Sha256>::new_from_slice(key).expect("HMAC takes any key size");
+        mac.update(data);
+        mac.finalize().into_bytes().to_vec()
+    }
+
+    /// Generates random bytes from the secure generator of the system
+    pub(crate) fn random(len: usize) -> Vec<u8> {
+        let mut bytes = vec![0; len];
+        getrandom::fill(&mut bytes).expect("System random generator failed");
+        bytes
+    }
+
+    /// Verifies an RSASSA-PKCS1-v1_5 signature using SHA-256, with the public key in PKCS#1 DER form
+    pub(crate) fn verify_rsa_sha256(public_key: &[u8], message: &[u8], signature: &[u8]) -> bool {
+        let Ok(public_key) = rsa::RsaPublicKey::from_pkcs1_der(public_key) else {
+            return false;
+        };
+        let Ok(signature) = rsa::pkcs1v15::Signature::try_from(signature) else {
+            return false;
+        };
+        rsa::pkcs1v15::VerifyingKey::<sha2::Sha256>::new(public_key)
+            .verify(message, &signature)
+            .is_ok()
+    }
+
+    /// Verifies an ECDSA P-256 signature using SHA-256, with the public key as an uncompressed point and the signature as `r || s`
+    pub(crate) fn verify_es256(public_key: &[u8], message: &[u8], signature: &[u8]) -> bool {
+        let Ok(public_key) = p256::ecdsa::VerifyingKey::from_sec1_bytes(public_key) else {
+            return false;
+        };
+        let Ok(signature) = p256::ecdsa::Signature::from_slice(signature) else {
+            return false;
+        };
+        public_key.verify(message, &signature).is_ok()
+    }
+
+    /// Verifies an HMAC tag in constant time
+    pub(crate) fn verify_hmac(hash: Hash, key: &[u8], data: &[u8], tag: &[u8]) -> bool {
+        fn verify<M: Mac + hmac::digest::KeyInit>(key: &[u8], data: &[u8], tag: &[u8]) -> bool {
+            let mut mac =
+                <M as hmac::digest::KeyInit>::new_from_slice(key).expect("HMAC takes any key size");
+            mac.update(data);
+            mac.verify_slice(tag).is_ok()
+        }
+        match hash {
+            Hash::Sha256 => verify::<hmac::Hmac<sha2::Sha256>>(key, data, tag),
+            Hash::Sha384 => verify::<hmac::Hmac<sha2::Sha384>>(key, data, tag),
+            Hash::Sha512 => verify::<hmac::Hmac<sha2::Sha512>>(key, data, tag),
+        }
+    }
+
+    /// Verifies an RSASSA-PKCS1-v1_5 signature, with the public key as its big-endian modulus and exponent
+    pub(crate) fn verify_rsa(hash: Hash, n: &[u8], e: &[u8], message: &[u8], sig: &[u8]) -> bool {
+        let Ok(public_key) = rsa::RsaPublicKey::new(
+            rsa::BigUint::from_bytes_be(n),
+            rsa::BigUint::from_bytes_be(e),
+        ) else {
+            return false;
+        };
+        let Ok(signature) = rsa::pkcs1v15::Signature::try_from(sig) else {
+            return false;
+        };
+        match hash {
+            Hash::Sha256 => rsa::pkcs1v15::VerifyingKey::<sha2::Sha256>::new(public_key)
+                .verify(message, &signature)
+                .is_ok(),
+            Hash::Sha384 => rsa::pkcs1v15::VerifyingKey::<sha2::Sha384>::new(public_key)
+                .verify(message, &signature)
+                .is_ok(),
+            Hash::Sha512 => rsa::pkcs1v15::VerifyingKey::<sha2::Sha512>::new(public_key)
+                .verify(message, &signature)
+                .is_ok(),
+        }
+    }
+}
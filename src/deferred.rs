@@ -0,0 +1,118 @@
+//! Implementation of credentials which are only validated once a handler asks for it
+//!
+//! See [Deferred] for the most commonly-used data structure
+
+use crate::{AuthUser, AuthValidator, Rejection, ERR_NO_DEFERRED_VALIDATOR};
+use axum_core::extract::FromRequestParts;
+use http::{request::Parts, StatusCode};
+use std::{
+    fmt,
+    sync::{Arc, OnceLock},
+};
+
+#[cfg(feature = "auth-basic")]
+use crate::AuthBasic;
+#[cfg(feature = "auth-bearer")]
+use crate::AuthBearer;
+
+/// Extractor taking the credentials without validating them, so handlers only call out to the [AuthValidator] on the paths which need it
+///
+/// The credentials are extracted using `A` (e.g. `AuthBearer`) like usual, but a missing or malformed header isn't rejected straight away. Instead, the first call to [verify](Self::verify) gives back the rejection or runs the validator, whose result is kept for any later calls. This avoids calling an identity provider for requests which never needed it, like ones served from a cache.
+///
+/// The validator is taken from the request extensions as an [`Arc<V>`](Arc), so it can be shared with any layers using it too.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{routing::get, Extension, Router};
+/// use axum_auth::{BasicUser, BasicUsers, DeferredBasic, Rejection};
+/// use std::sync::Arc;
+///
+/// /// Serves public posts to anyone, only checking credentials for drafts
+/// async fn handler(auth: DeferredBasic<BasicUsers>) -> Result<String, Rejection> {
+///     let draft = true; // <-- e.g. from a database or cache
+///     if !draft {
+///         return Ok(String::from("Public post"));
+///     }
+///     let user = auth.verify().await?;
+///     Ok(format!("Draft for {}", user.id))
+/// }
+///
+/// let users = BasicUsers::new().with_user("alice", BasicUser::new("hunter2"));
+///
+/// let app: Router = Router::new()
+///     .route("/", get(handler))
+///     .layer(Extension(Arc::new(users)));
+/// ```
+///
+/// # Errors
+///
+/// If no validator was inserted into the request extensions, this extractor responds with `500 INTERNAL SERVER ERROR` and the message "No validator was found for the deferred credentials". Otherwise, [verify](Self::verify) gives the rejection of the extractor or validator.
+pub struct Deferred<A, V> {
+    credentials: Result<A, Rejection>,
+    validator: Arc<V>,
+    verified: OnceLock<Result<AuthUser, Rejection>>,
+}
+
+/// Bearer token which is only validated once a handler asks for it, see [Deferred] for more information
+///
+/// This is enabled via the `auth-bearer` feature
+#[cfg(feature = "auth-bearer")]
+pub type DeferredBearer<V> = Deferred<AuthBearer, V>;
+
+/// Basic auth which is only validated once a handler asks for it, see [Deferred] for more information
+///
+/// This is enabled via the `auth-basic` feature
+#[cfg(feature = "auth-basic")]
+pub type DeferredBasic<V> = Deferred<AuthBasic, V>;
+
+impl<A, V: AuthValidator<A>> Deferred<A, V> {
+    /// Validates the credentials the first time this is called, giving back the same result every time after
+    pub async fn verify(&self) -> Result<AuthUser, Rejection> {
+        if let Some(verified) = self.verified.get() {
+            return verified.clone();
+        }
+
+        let verified = match &self.credentials {
+            Ok(credentials) => self.validator.validate(credentials).await,
+            Err(rejection) => Err(*rejection),
+        };
+        self.verified.get_or_init(|| verified).clone()
+    }
+
+    /// Checks if the credentials have been validated yet, whether they were accepted or not
+    pub fn is_verified(&self) -> bool {
+        self.verified.get().is_some()
+    }
+}
+
+impl<A: fmt::Debug, V> fmt::Debug for Deferred<A, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Deferred")
+            .field("credentials", &self.credentials)
+            .field("verified", &self.verified.get())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<A, V, B> FromRequestParts<B> for Deferred<A, V>
+where
+    A: FromRequestParts<B, Rejection = Rejection>,
+    V: Send + Sync + 'static,
+    B: Send + Sync,
+{
+    type Rejection = Rejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &B) -> Result<Self, Self::Rejection> {
+        let validator = parts
+            .extensions
+            .get::<Arc<V>>()
+            .cloned()
+            .ok_or((StatusCode::INTERNAL_SERVER_ERROR, ERR_NO_DEFERRED_VALIDATOR))?;
+        Ok(Self {
+            credentials: A::from_request_parts(parts, state).await,
+            validator,
+            verified: OnceLock::new(),
+        })
+    }
+}
@@ -0,0 +1,66 @@
+//! Implementation of bypassing authentication during local development
+//!
+//! See [DevBypass] for the most commonly-used data structure
+
+use axum_core::extract::FromRequestParts;
+use http::request::Parts;
+#[cfg(debug_assertions)]
+use {crate::credential_header::credential_header, http::HeaderValue};
+
+/// Environment variable holding the `Authorization` header which [DevBypass] injects
+pub const DEV_BYPASS_VAR: &str = "AXUM_AUTH_DEV_BYPASS";
+
+/// Development-only extractor wrapper which injects fixed credentials when a request doesn't have any
+///
+/// This is enabled via the `dev-bypass` feature
+///
+/// If the [DEV_BYPASS_VAR] (`AXUM_AUTH_DEV_BYPASS`) environment variable is set, requests without an `Authorization` header are given its value as one before the wrapped extractor runs. This means a local frontend can talk to your API without going through the login flow, whilst still going through the same extractor (and so the same validation) as everything else. Requests which do have a header are left alone.
+///
+/// This only works in debug builds; the code injecting credentials isn't even compiled into release builds, so setting the variable on a production server does nothing.
+///
+/// # Example
+///
+/// ```no_run
+/// use axum_auth::{AuthBearer, DevBypass};
+///
+/// /// Run locally using `AXUM_AUTH_DEV_BYPASS="Bearer dev-token" cargo run` to skip logging in
+/// async fn handler(DevBypass(AuthBearer(token)): DevBypass<AuthBearer>) -> String {
+///     format!("Found a bearer token: {}", token)
+/// }
+/// ```
+///
+/// # Errors
+///
+/// This extractor only fails if the wrapped extractor fails, returning its rejection as-is
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct DevBypass<A>(pub A);
+
+impl<A, B> FromRequestParts<B> for DevBypass<A>
+where
+    A: FromRequestParts<B> + Send,
+    B: Send + Sync,
+{
+    type Rejection = A::Rejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &B) -> Result<Self, Self::Rejection> {
+        #[cfg(debug_assertions)]
+        inject(parts);
+        A::from_request_parts(parts, state).await.map(Self)
+    }
+}
+
+/// Adds the credentials from the environment to the request if it has none
+#[cfg(debug_assertions)]
+fn inject(parts: &mut Parts) {
+    let header = credential_header(parts);
+    if parts.headers.contains_key(&header) {
+        return;
+    }
+    let credentials = std::env::var(DEV_BYPASS_VAR)
+        .ok()
+        .and_then(|value| HeaderValue::from_str(&value).ok());
+    if let Some(mut credentials) = credentials {
+        credentials.set_sensitive(true);
+        parts.headers.insert(header, credentials);
+    }
+}
@@ -0,0 +1,326 @@
+//! Implementation of the kinds of errors rejections are made for
+//!
+//! See [AuthErrorKind] for the most commonly-used data structure
+
+use crate::*;
+use axum_core::{
+    extract::OptionalFromRequestParts,
+    response::{IntoResponse, Response},
+};
+#[cfg(any(feature = "auth-basic", feature = "auth-bearer"))]
+use credential_header::credential_header;
+use http::request::Parts;
+use std::{any::Any, convert::Infallible};
+
+/// Kind of error a rejection was made for, so middleware and tests can match on it instead of comparing messages
+///
+/// Use [AuthErrorKind::of] to get the kind of a [Rejection]. Responses made by the layers of this crate, as well as by [Unauthorized] and [ProxyAuthenticationRequired], also carry their kind in the response extensions.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{extract::FromRequestParts, http::Request};
+/// use axum_auth::{AuthBasic, AuthErrorKind};
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let (mut parts, _) = Request::new(()).into_parts();
+/// let rejection = AuthBasic::from_request_parts(&mut parts, &()).await.unwrap_err();
+///
+/// assert_eq!(AuthErrorKind::of(&rejection), AuthErrorKind::MissingHeader);
+/// # }
+/// ```
+///
+/// Optional extractors like `Option<AuthBearer>` also store the kind in the request extensions when there aren't any credentials, so handlers and error pages can explain why, like telling an expired login apart from a missing one. Take an `Option<AuthErrorKind>` after the optional extractor to read it:
+///
+/// ```no_run
+/// use axum_auth::{AuthBearer, AuthErrorKind};
+///
+/// async fn handler(auth: Option<AuthBearer>, why: Option<AuthErrorKind>) -> String {
+///     match (auth, why) {
+///         (Some(AuthBearer(token)), _) => format!("Found a bearer token: {}", token),
+///         (None, Some(AuthErrorKind::MissingHeader)) => "Welcome, guest!".to_string(),
+///         (None, kind) => format!("Couldn't log you in: {:?}", kind),
+///     }
+/// }
+/// ```
+///
+/// This is also how to tell what went wrong inside of middleware:
+///
+/// ```rust
+/// use axum::{middleware::map_response, response::Response, Router};
+/// use axum_auth::AuthErrorKind;
+///
+/// async fn log_auth_errors(resp: Response) -> Response {
+///     if let Some(kind) = resp.extensions().get::<AuthErrorKind>() {
+///         println!("authentication failed with {:?}", kind);
+///     }
+///     resp
+/// }
+///
+/// let app: Router = Router::new().layer(map_response(log_auth_errors));
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+#[non_exhaustive]
+pub enum AuthErrorKind {
+    /// The header with the credentials was missing
+    MissingHeader,
+    /// The header contained characters which aren't allowed in it
+    InvalidChars,
+    /// The credentials couldn't be decoded
+    DecodeFailed,
+    /// The credentials didn't have a password when one was required
+    MissingPassword,
+    /// The basic auth credentials were decoded but had no colon between the username and password
+    MissingColon,
+    /// The credentials were for another scheme
+    WrongScheme,
+    /// The credentials were decoded but didn't have the expected format, like a token with the wrong prefix
+    Malformed,
+    /// The credentials were well-formed but weren't accepted, like an unknown password or bad signature
+    ValidationFailed,
+    /// The credentials or the request they signed are too old or not valid yet
+    Expired,
+    /// The signed request was sent before
+    Replayed,
+    /// The credentials were logged out of, like by visiting a logout path or revoking them
+    LoggedOut,
+    /// No credentials were checked before something which needs them
+    NotAuthenticated,
+    /// The credentials were valid but aren't allowed to access the request
+    Forbidden,
+    /// The credentials were valid but have made more requests than their quota allows
+    QuotaExceeded,
+    /// Something the credentials are checked with couldn't be reached
+    Unavailable,
+    /// The rejection was made outside of this crate, like by a custom [AuthValidator] or using [AuthBasicCustom::ERROR_OVERWRITE]
+    Other,
+}
+
+impl AuthErrorKind {
+    /// Gets the kind of error the rejection was made for
+    pub fn of(rejection: &Rejection) -> Self {
+        Self::from_message(rejection.1)
+    }
+
+    /// Gets the kind of error the message of a rejection was made for, which is [Other](Self::Other) if this crate didn't make it
+    pub fn from_message(message: &str) -> Self {
+        KINDS
+            .iter()
+            .find(|(known, _)| *known == message)
+            .map_or(Self::Other, |(_, kind)| *kind)
+    }
+}
+
+/// Gets the kind of error an optional extractor stored in the request extensions, which is [None] if no credentials were missing or rejected
+impl<B> OptionalFromRequestParts<B> for AuthErrorKind
+where
+    B: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _: &B) -> Result<Option<Self>, Self::Rejection> {
+        Ok(parts.extensions.get::<AuthErrorKind>().copied())
+    }
+}
+
+/// Decodes credentials which are allowed to be missing, giving back [None] if there's no `Authorization` header (or [CredentialHeader]) at all
+///
+/// Whenever there aren't any credentials, the kind of error is stored in the request extensions so handlers can tell why.
+#[cfg(any(feature = "auth-basic", feature = "auth-bearer"))]
+pub(crate) fn decode_optional<T>(
+    parts: &mut Parts,
+    decode: impl FnOnce(&mut Parts) -> Result<T, Rejection>,
+) -> Result<Option<T>, Rejection> {
+    if !parts.headers.contains_key(credential_header(parts)) {
+        parts.extensions.insert(AuthErrorKind::MissingHeader);
+        return Ok(None);
+    }
+    decode(parts).map(Some).inspect_err(|rejection| {
+        parts.extensions.insert(AuthErrorKind::of(rejection));
+    })
+}
+
+/// Turns the rejection into a response, carrying its [AuthErrorKind] in the extensions if it's a [Rejection]
+pub(crate) fn rejection_response<R: IntoResponse + 'static>(rejection: R) -> Response {
+    let kind = (&rejection as &dyn Any)
+        .downcast_ref::<Rejection>()
+        .map(AuthErrorKind::of);
+    let mut resp = rejection.into_response();
+    if let Some(kind) = kind {
+        resp.extensions_mut().insert(kind);
+    }
+    resp
+}
+
+/// Replaces the message of the rejection if there's one to overwrite it with, logging the hidden reason if the `tracing` feature is enabled
+#[cfg(any(feature = "auth-basic", feature = "auth-bearer"))]
+pub(crate) fn overwrite(rejection: Rejection, overwrite: Option<&'static str>) -> Rejection {
+    match overwrite {
+        Some(message) => {
+            #[cfg(feature = "tracing")]
+            tracing::debug!(
+                status = rejection.0.as_u16(),
+                reason = rejection.1,
+                "hid the reason credentials were rejected"
+            );
+            (rejection.0, message)
+        }
+        None => rejection,
+    }
+}
+
+/// Every message this crate makes alongside its kind
+const KINDS: &[(&str, AuthErrorKind)] = &[
+    (ERR_MISSING, AuthErrorKind::MissingHeader),
+    (ERR_CHARS, AuthErrorKind::InvalidChars),
+    (ERR_DECODE, AuthErrorKind::DecodeFailed),
+    (ERR_NO_PASSWORD, AuthErrorKind::MissingPassword),
+    (ERR_NO_COLON, AuthErrorKind::MissingColon),
+    (ERR_WRONG_BASIC, AuthErrorKind::WrongScheme),
+    (ERR_WRONG_BEARER, AuthErrorKind::WrongScheme),
+    (ERR_UNSUPPORTED_SCHEME, AuthErrorKind::WrongScheme),
+    (ERR_WINDOWS_AUTH, AuthErrorKind::WrongScheme),
+    #[cfg(feature = "auth-negotiate")]
+    (ERR_WRONG_NEGOTIATE, AuthErrorKind::WrongScheme),
+    #[cfg(feature = "auth-ntlm")]
+    (ERR_WRONG_NTLM, AuthErrorKind::WrongScheme),
+    #[cfg(feature = "scram")]
+    (ERR_WRONG_SCRAM, AuthErrorKind::WrongScheme),
+    #[cfg(feature = "auth-digest")]
+    (ERR_WRONG_DIGEST, AuthErrorKind::WrongScheme),
+    #[cfg(feature = "hoba")]
+    (ERR_WRONG_HOBA, AuthErrorKind::WrongScheme),
+    #[cfg(feature = "vapid")]
+    (ERR_WRONG_VAPID, AuthErrorKind::WrongScheme),
+    #[cfg(feature = "session")]
+    (ERR_NO_SESSION, AuthErrorKind::MissingHeader),
+    (ERR_NO_TENANT, AuthErrorKind::MissingHeader),
+    #[cfg(feature = "auth-bearer")]
+    (ERR_NO_BEARER_OR_KEY, AuthErrorKind::MissingHeader),
+    #[cfg(feature = "auth-bearer")]
+    (ERR_API_KEY_MISSING, AuthErrorKind::MissingHeader),
+    #[cfg(feature = "jwt")]
+    (ERR_NO_CLIENT_CERT, AuthErrorKind::MissingHeader),
+    (ERR_TENANT_CHARS, AuthErrorKind::InvalidChars),
+    #[cfg(feature = "auth-bearer")]
+    (ERR_API_KEY_CHARS, AuthErrorKind::InvalidChars),
+    (ERR_NOT_EMAIL, AuthErrorKind::Malformed),
+    #[cfg(feature = "uuid")]
+    (ERR_NOT_UUID, AuthErrorKind::Malformed),
+    #[cfg(feature = "auth-bearer")]
+    (ERR_MALFORMED_TOKEN, AuthErrorKind::Malformed),
+    #[cfg(feature = "auth-bearer")]
+    (ERR_TOKEN_PREFIX, AuthErrorKind::Malformed),
+    #[cfg(all(feature = "auth-bearer", feature = "checksum-token"))]
+    (ERR_TOKEN_CHECKSUM, AuthErrorKind::Malformed),
+    #[cfg(feature = "auth-bearer")]
+    (ERR_WEAK_TOKEN, AuthErrorKind::Malformed),
+    #[cfg(feature = "auth-ntlm")]
+    (ERR_NTLM_MESSAGE, AuthErrorKind::Malformed),
+    #[cfg(feature = "scram")]
+    (ERR_SCRAM_MESSAGE, AuthErrorKind::Malformed),
+    #[cfg(feature = "auth-digest")]
+    (ERR_DIGEST_MALFORMED, AuthErrorKind::Malformed),
+    #[cfg(feature = "hoba")]
+    (ERR_HOBA_RESULT, AuthErrorKind::Malformed),
+    #[cfg(feature = "jwt")]
+    (ERR_JWT_MALFORMED, AuthErrorKind::Malformed),
+    (ERR_INVALID_CREDENTIALS, AuthErrorKind::ValidationFailed),
+    #[cfg(feature = "auth-digest")]
+    (ERR_DIGEST_URI, AuthErrorKind::ValidationFailed),
+    (ERR_UNKNOWN_TENANT, AuthErrorKind::ValidationFailed),
+    #[cfg(feature = "signed-url")]
+    (ERR_URL_SIGNATURE, AuthErrorKind::ValidationFailed),
+    #[cfg(feature = "gateway-signature")]
+    (ERR_GATEWAY_SIGNATURE, AuthErrorKind::ValidationFailed),
+    #[cfg(feature = "session")]
+    (ERR_SESSION_INVALID, AuthErrorKind::ValidationFailed),
+    #[cfg(feature = "session")]
+    (ERR_REMEMBER_ME, AuthErrorKind::ValidationFailed),
+    #[cfg(feature = "vapid")]
+    (ERR_VAPID_INVALID, AuthErrorKind::ValidationFailed),
+    #[cfg(feature = "vapid")]
+    (ERR_VAPID_AUDIENCE, AuthErrorKind::ValidationFailed),
+    #[cfg(feature = "jwt")]
+    (ERR_JWT_SIGNATURE, AuthErrorKind::ValidationFailed),
+    #[cfg(feature = "jwt")]
+    (ERR_JWT_ISSUER, AuthErrorKind::ValidationFailed),
+    #[cfg(feature = "jwt")]
+    (ERR_JWT_AUDIENCE, AuthErrorKind::ValidationFailed),
+    #[cfg(feature = "jwt")]
+    (ERR_JWT_CLAIM, AuthErrorKind::ValidationFailed),
+    #[cfg(feature = "jwt")]
+    (ERR_JWT_SUBJECT, AuthErrorKind::ValidationFailed),
+    #[cfg(feature = "jwt")]
+    (ERR_BINDING_MISMATCH, AuthErrorKind::ValidationFailed),
+    #[cfg(feature = "jwt")]
+    (ERR_CERT_MISMATCH, AuthErrorKind::ValidationFailed),
+    #[cfg(feature = "content-digest")]
+    (ERR_DIGEST_MISSING, AuthErrorKind::MissingHeader),
+    #[cfg(feature = "gateway-signature")]
+    (ERR_GATEWAY_SIGNATURE_MISSING, AuthErrorKind::MissingHeader),
+    #[cfg(feature = "content-digest")]
+    (ERR_DIGEST_ALGORITHM, AuthErrorKind::ValidationFailed),
+    #[cfg(feature = "content-digest")]
+    (ERR_DIGEST_MISMATCH, AuthErrorKind::ValidationFailed),
+    (ERR_STALE, AuthErrorKind::Expired),
+    #[cfg(feature = "session")]
+    (ERR_SESSION_EXPIRED, AuthErrorKind::Expired),
+    #[cfg(feature = "scram")]
+    (ERR_SCRAM_SESSION, AuthErrorKind::Expired),
+    #[cfg(feature = "auth-digest")]
+    (ERR_DIGEST_NONCE, AuthErrorKind::Expired),
+    #[cfg(feature = "hoba")]
+    (ERR_HOBA_CHALLENGE, AuthErrorKind::Expired),
+    #[cfg(feature = "vapid")]
+    (ERR_VAPID_EXPIRED, AuthErrorKind::Expired),
+    #[cfg(feature = "jwt")]
+    (ERR_JWT_EXPIRED, AuthErrorKind::Expired),
+    #[cfg(feature = "api-key-store")]
+    (ERR_API_KEY_EXPIRED, AuthErrorKind::Expired),
+    #[cfg(feature = "signed-url")]
+    (ERR_URL_EXPIRED, AuthErrorKind::Expired),
+    (ERR_REPLAYED, AuthErrorKind::Replayed),
+    #[cfg(feature = "session")]
+    (ERR_REVOKED, AuthErrorKind::LoggedOut),
+    #[cfg(feature = "api-key-store")]
+    (ERR_API_KEY_REVOKED, AuthErrorKind::LoggedOut),
+    (ERR_EXCHANGED_TOKEN, AuthErrorKind::Unavailable),
+    #[cfg(feature = "token-exchange")]
+    (ERR_TOKEN_EXCHANGE_REFUSED, AuthErrorKind::Forbidden),
+    #[cfg(feature = "token-exchange")]
+    (ERR_TOKEN_EXCHANGE_UNAVAILABLE, AuthErrorKind::Unavailable),
+    #[cfg(feature = "client-credentials")]
+    (ERR_CLIENT_CREDENTIALS_REFUSED, AuthErrorKind::Unavailable),
+    #[cfg(feature = "client-credentials")]
+    (
+        ERR_CLIENT_CREDENTIALS_UNAVAILABLE,
+        AuthErrorKind::Unavailable,
+    ),
+    #[cfg(feature = "auth-basic")]
+    (ERR_LOGGED_OUT, AuthErrorKind::LoggedOut),
+    (ERR_NO_USER, AuthErrorKind::NotAuthenticated),
+    (ERR_NO_DEFERRED_VALIDATOR, AuthErrorKind::NotAuthenticated),
+    #[cfg(feature = "signed-url")]
+    (ERR_NO_URL_SIGNER, AuthErrorKind::NotAuthenticated),
+    #[cfg(feature = "gateway-signature")]
+    (ERR_NO_GATEWAY_SIGNATURE, AuthErrorKind::NotAuthenticated),
+    #[cfg(feature = "jwt")]
+    (ERR_NO_JWT, AuthErrorKind::NotAuthenticated),
+    (ERR_FORBIDDEN, AuthErrorKind::Forbidden),
+    (ERR_MISSING_ROLE, AuthErrorKind::Forbidden),
+    (ERR_MISSING_PERMISSION, AuthErrorKind::Forbidden),
+    (ERR_MISSING_SCOPE, AuthErrorKind::Forbidden),
+    (ERR_QUOTA_EXCEEDED, AuthErrorKind::QuotaExceeded),
+    #[cfg(feature = "jwt")]
+    (ERR_JWKS_UNAVAILABLE, AuthErrorKind::Unavailable),
+    #[cfg(feature = "validator-timeout")]
+    (ERR_VALIDATOR_TIMEOUT, AuthErrorKind::Unavailable),
+    #[cfg(feature = "circuit-breaker")]
+    (ERR_CIRCUIT_OPEN, AuthErrorKind::Unavailable),
+    #[cfg(feature = "content-digest")]
+    (ERR_DIGEST_BODY, AuthErrorKind::DecodeFailed),
+    #[cfg(feature = "api-key-store")]
+    (ERR_API_KEY_REQUEST, AuthErrorKind::DecodeFailed),
+];
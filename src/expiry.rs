@@ -0,0 +1,52 @@
+//! Implementation of telling handlers how long the credentials of a request stay valid
+//!
+//! See [CredentialExpiry] for the most commonly-used data structure
+
+use http::HeaderValue;
+use std::time::{Duration, SystemTime};
+
+/// When the credential a request was authenticated with stops being valid, which validators can put into the request extensions
+///
+/// Validators which know when credentials expire insert this, like `JwtValidator` with the `jwt` feature using the `exp` claim of the token and `ApiKeys` with the `api-key-store` feature for keys minted with an expiry. Handlers can use it to make sure anything cached on behalf of the credential, like a response or a session, never outlives it, and `CachedValidator` with the `validator-cache` feature won't remember a validation for longer than it.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{http::header::CACHE_CONTROL, response::IntoResponse, Extension};
+/// use axum_auth::CredentialExpiry;
+/// use std::time::Duration;
+///
+/// /// Lets browsers cache the profile for up to an hour, but not once the token has expired
+/// async fn handler(expiry: Option<Extension<CredentialExpiry>>) -> impl IntoResponse {
+///     let max_age = Duration::from_secs(60 * 60);
+///     let cache_control = match expiry {
+///         Some(Extension(expiry)) => expiry.cache_control(max_age),
+///         None => CredentialExpiry::cache_control_for(max_age),
+///     };
+///     ([(CACHE_CONTROL, cache_control)], "Profile")
+/// }
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct CredentialExpiry(pub SystemTime);
+
+impl CredentialExpiry {
+    /// Gets how long the credential stays valid for, which is zero once it has expired
+    pub fn remaining(&self) -> Duration {
+        self.0.duration_since(SystemTime::now()).unwrap_or_default()
+    }
+
+    /// Shortens the duration so it doesn't go past the expiry of the credential
+    pub fn cap(&self, duration: Duration) -> Duration {
+        duration.min(self.remaining())
+    }
+
+    /// Makes a private `Cache-Control` header value lasting up to the given age, but never past the expiry of the credential
+    pub fn cache_control(&self, max_age: Duration) -> HeaderValue {
+        Self::cache_control_for(self.cap(max_age))
+    }
+
+    /// Makes a private `Cache-Control` header value lasting for the given age, for requests without a known expiry
+    pub fn cache_control_for(max_age: Duration) -> HeaderValue {
+        HeaderValue::from_str(&format!("private, max-age={}", max_age.as_secs())).unwrap()
+    }
+}
@@ -0,0 +1,57 @@
+//! Implementation of ready-made middleware running extractors before every handler
+//!
+//! See [AuthGuard] for the most commonly-used data structure
+
+use axum::middleware::{from_extractor, from_extractor_with_state, FromExtractorLayer};
+use std::marker::PhantomData;
+
+/// Guard running an extractor of this crate as middleware, rejecting requests before they reach any handler it's layered on
+///
+/// This is enabled via the `from-extractor` feature
+///
+/// Protecting handlers one by one means adding the extractor to each of them, which is easy to forget on a new route. Layering a guard over the router checks every route at once using [`from_extractor`](https://docs.rs/axum/latest/axum/middleware/fn.from_extractor.html), responding with the rejection of the extractor as-is. Any extractor works, like `AuthBearer` to only require a token, or [RequireRole] after a layer which authenticates the user.
+///
+/// The guard only checks the request and throws the extracted value away, so use [RequireAuthLayer](crate::RequireAuthLayer) instead if handlers need the [AuthUser](crate::AuthUser).
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{routing::get, Router};
+/// use axum_auth::{AuthBearer, AuthGuard, BasicUser, BasicUsers, RequireBasicAuthLayer, RequireRole, Role};
+///
+/// struct Admin;
+///
+/// impl Role for Admin {
+///     const NAME: &'static str = "admin";
+/// }
+///
+/// // Every route needs a bearer token, checked by the handlers themselves later
+/// let api: Router = Router::new()
+///     .route("/", get(|| async { "Hello, API!" }))
+///     .layer(AuthGuard::<AuthBearer>::layer());
+///
+/// // Only admins get through, once they've logged in
+/// let users = BasicUsers::new().with_user("alice", BasicUser::new("hunter2").with_roles(["admin"]));
+/// let admin: Router = Router::new()
+///     .route("/", get(|| async { "Hello, admin!" }))
+///     .layer(AuthGuard::<RequireRole<Admin>>::layer())
+///     .layer(RequireBasicAuthLayer::new(users));
+/// ```
+///
+/// [RequireRole]: crate::RequireRole
+#[derive(Debug)]
+pub struct AuthGuard<E> {
+    extractor: PhantomData<fn() -> E>,
+}
+
+impl<E> AuthGuard<E> {
+    /// Makes a layer running the extractor before every request, for extractors which don't need any state
+    pub fn layer() -> FromExtractorLayer<E, ()> {
+        from_extractor()
+    }
+
+    /// Makes a layer running the extractor before every request, giving it the state
+    pub fn layer_with_state<S>(state: S) -> FromExtractorLayer<E, S> {
+        from_extractor_with_state(state)
+    }
+}
@@ -0,0 +1,192 @@
+//! Implementation of basic auth users stored in a file
+//!
+//! See [FileUsers] for the most commonly-used data structure
+
+use crate::{AuthBasic, AuthUser, AuthValidator, BasicUser, BasicUsers, Rejection, StoreHandle};
+use std::{
+    error::Error,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use toml::{Table, Value};
+
+/// Store of users for basic auth loaded from a TOML file, which can be reloaded without restarting the server
+///
+/// This is enabled via the `file-store` feature
+///
+/// Each user is a table under `users`, with a password and optional lists of roles and scopes:
+///
+/// ```toml
+/// [users.alice]
+/// password = "hunter2"
+/// roles = ["admin"]
+///
+/// [users.bob]
+/// password = "letmein"
+/// scopes = ["posts:read"]
+/// ```
+///
+/// Reloads are atomic, so the whole file is read and checked before any requests see the new users, and a file which can't be read or parsed leaves the current users in place. The `users` table is required even if it's empty, so a file which was emptied to be rewritten isn't mistaken for one without any users. Using [watch](Self::watch) with the `file-watch` feature reloads it every time the file changes, including when editors replace it instead of writing to it.
+///
+/// # Example
+///
+/// ```no_run
+/// use axum::{routing::get, Router};
+/// use axum_auth::{AuthUser, FileUsers, RequireBasicAuthLayer};
+///
+/// let users = FileUsers::load("users.toml").unwrap();
+///
+/// let app: Router = Router::new()
+///     .route("/", get(|user: AuthUser| async move { format!("Hello, {}!", user.id) }))
+///     .layer(RequireBasicAuthLayer::new(users));
+/// ```
+///
+/// # Errors
+///
+/// Loading fails with the [io::Error] from reading the file, or one of kind [InvalidData](io::ErrorKind::InvalidData) if it isn't valid TOML, has no `users` table, or a user is missing their password. Requests are validated in the same way as [BasicUsers].
+#[derive(Debug)]
+pub struct FileUsers {
+    path: PathBuf,
+    handle: StoreHandle,
+    #[cfg(feature = "file-watch")]
+    watcher: Option<notify::RecommendedWatcher>,
+}
+
+impl FileUsers {
+    /// Loads the users from the file at the given path
+    pub fn load(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        let loading = path.clone();
+        let handle = StoreHandle::new(read(&path)?).with_loader(move || read(&loading));
+        Ok(Self {
+            path,
+            handle,
+            #[cfg(feature = "file-watch")]
+            watcher: None,
+        })
+    }
+
+    /// Loads the users from the file at the given path, reloading them every time the file changes
+    ///
+    /// This is enabled via the `file-watch` feature
+    ///
+    /// The directory of the file is watched until the store is dropped. Changes which leave the file unreadable or invalid are ignored, and logged at the warn level with the `tracing` feature, so a half-written file never locks everyone out.
+    #[cfg(feature = "file-watch")]
+    pub fn watch(path: impl Into<PathBuf>) -> io::Result<Self> {
+        use notify::{Event, RecursiveMode, Watcher};
+
+        let mut store = Self::load(path)?;
+        let (path, handle) = (store.path.clone(), store.handle.clone());
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+            // Only reload for changes to this file, as the whole directory is watched
+            let changed = match event {
+                Ok(event) => {
+                    !event.kind.is_access()
+                        && event
+                            .paths
+                            .iter()
+                            .any(|changed| changed.file_name() == path.file_name())
+                }
+                Err(_) => false,
+            };
+            if changed {
+                if let Err(error) = handle.reload() {
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!(
+                        path = %path.display(),
+                        %error,
+                        "kept the current users as their file couldn't be reloaded"
+                    );
+                    #[cfg(not(feature = "tracing"))]
+                    let _ = error;
+                }
+            }
+        })
+        .map_err(io::Error::other)?;
+
+        // Watch the directory so files replaced by renaming them are picked up too
+        let dir = match store.path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        watcher
+            .watch(dir, RecursiveMode::NonRecursive)
+            .map_err(io::Error::other)?;
+        store.watcher = Some(watcher);
+        Ok(store)
+    }
+
+    /// Reads the file again, swapping in its users if it's valid
+    pub fn reload(&self) -> io::Result<()> {
+        self.handle.reload()
+    }
+
+    /// Gets the path of the file the users are loaded from
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Gets the users which are currently loaded
+    pub fn users(&self) -> Arc<BasicUsers> {
+        self.handle.users()
+    }
+
+    /// Gets a handle to the users, which can also add and remove users until the file is next reloaded
+    pub fn handle(&self) -> StoreHandle {
+        self.handle.clone()
+    }
+}
+
+impl AuthValidator<AuthBasic> for FileUsers {
+    async fn validate(&self, credentials: &AuthBasic) -> Result<AuthUser, Rejection> {
+        self.users().validate(credentials).await
+    }
+}
+
+/// Reads and parses the users from the file
+fn read(path: &Path) -> io::Result<BasicUsers> {
+    parse(&fs::read_to_string(path)?)
+}
+
+/// Parses the users from the contents of a TOML file
+fn parse(contents: &str) -> io::Result<BasicUsers> {
+    let table: Table = contents.parse().map_err(invalid)?;
+    let entries = match table.get("users") {
+        Some(Value::Table(entries)) => entries,
+        // Files being written to are briefly empty, which mustn't remove every user
+        _ => return Err(invalid("`users` must be a table")),
+    };
+
+    let mut users = BasicUsers::new();
+    for (id, entry) in entries {
+        let password = entry
+            .get("password")
+            .and_then(Value::as_str)
+            .ok_or_else(|| invalid(format!("user `{}` must have a password", id)))?;
+        let strings = |key: &str| match entry.get(key) {
+            Some(Value::Array(values)) => values
+                .iter()
+                .map(|value| value.as_str().map(String::from))
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(|| invalid(format!("`{}` of user `{}` must be strings", key, id))),
+            Some(_) => Err(invalid(format!(
+                "`{}` of user `{}` must be a list",
+                key, id
+            ))),
+            None => Ok(vec![]),
+        };
+        users.insert(
+            id.clone(),
+            BasicUser::new(password)
+                .with_roles(strings("roles")?)
+                .with_scopes(strings("scopes")?),
+        );
+    }
+    Ok(users)
+}
+
+/// Makes an error for a file which isn't a valid store
+fn invalid(error: impl Into<Box<dyn Error + Send + Sync>>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
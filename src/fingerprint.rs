@@ -0,0 +1,51 @@
+//! Implementation of fingerprinting secrets for logs
+//!
+//! See [Fingerprint] for the most commonly-used data structure
+
+use crate::crypto;
+use std::fmt;
+
+/// Short SHA-256 fingerprint of a secret like a token or password, shown as `sha256:ab12cd34…`
+///
+/// This is enabled via the `fingerprint` feature alongside a crypto backend
+///
+/// Logging the fingerprint instead of the secret keeps logs correlatable across services, as every service sees the same fingerprint for the same token, without exposing the secret value. Only the first four bytes of the digest are shown, which is plenty to tell tokens apart but useless for guessing them.
+///
+/// With this feature enabled, the [Debug](fmt::Debug) output of [AuthBearer](crate::AuthBearer) shows its fingerprint instead of just the length of the token.
+///
+/// # Example
+///
+/// ```rust
+/// use axum_auth::{AuthBearer, Fingerprint};
+///
+/// let bearer = AuthBearer("secret".to_string());
+/// assert_eq!(bearer.fingerprint(), Fingerprint::of("secret"));
+/// assert_eq!(bearer.fingerprint().to_string(), "sha256:2bb80d53…");
+/// assert_eq!(format!("{:?}", bearer), "AuthBearer(sha256:2bb80d53…)");
+/// ```
+#[derive(PartialEq, Eq, Clone, Copy, Hash)]
+pub struct Fingerprint([u8; 4]);
+
+impl Fingerprint {
+    /// Fingerprints any secret, using the first bytes of its SHA-256 digest
+    pub fn of(secret: impl AsRef<[u8]>) -> Self {
+        let digest = crypto::sha256(secret.as_ref());
+        Self([digest[0], digest[1], digest[2], digest[3]])
+    }
+}
+
+impl fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("sha256:")?;
+        for byte in self.0 {
+            write!(f, "{:02x}", byte)?;
+        }
+        f.write_str("…")
+    }
+}
+
+impl fmt::Debug for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
@@ -0,0 +1,528 @@
+//! Implementation of checking the signatures API gateways add to the requests they forward
+//!
+//! See [GatewaySignature] for the most commonly-used data structure
+
+use crate::{
+    challenge::parse_params,
+    crypto::{self, Hash},
+    Rejection, ERR_GATEWAY_SIGNATURE, ERR_GATEWAY_SIGNATURE_MISSING, ERR_NO_GATEWAY_SIGNATURE,
+    ERR_STALE,
+};
+use axum_core::extract::FromRequestParts;
+use base64::{engine::general_purpose, Engine};
+use http::{
+    header::{AUTHORIZATION, DATE},
+    request::Parts,
+    HeaderName, StatusCode,
+};
+use std::{
+    fmt,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Part of the request which is signed by the gateway, making up one line of the string to sign
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum SignedPart {
+    /// Method of the request in uppercase, like `GET`
+    Method,
+    /// Path of the request without the query, like `/orders`
+    Path,
+    /// Path of the request along with the query, like `/orders?page=2`
+    PathAndQuery,
+    /// Request line as sent over HTTP/1.1, like `GET /orders?page=2 HTTP/1.1`
+    RequestLine,
+    /// Pseudo-header from HTTP signatures, like `(request-target): get /orders?page=2`
+    RequestTarget,
+    /// Value of the header, which is empty if it's missing
+    Header(HeaderName),
+    /// Header with its lowercase name in front, like `date: Tue, 07 Jun 2014 20:51:35 GMT`
+    NamedHeader(HeaderName),
+}
+
+impl SignedPart {
+    /// Creates a part signing the value of the header
+    ///
+    /// # Panics
+    ///
+    /// Panics if the name isn't a valid header name, as this is meant to be used with names known at compile time.
+    pub fn header(name: &str) -> Self {
+        Self::Header(header_name(name))
+    }
+
+    /// Creates a part signing the header along with its lowercase name
+    ///
+    /// # Panics
+    ///
+    /// Panics if the name isn't a valid header name, as this is meant to be used with names known at compile time.
+    pub fn named_header(name: &str) -> Self {
+        Self::NamedHeader(header_name(name))
+    }
+
+    /// Writes this part of the request as it's signed
+    fn write(&self, parts: &Parts, out: &mut String) {
+        let path_and_query = parts
+            .uri
+            .path_and_query()
+            .map_or("/", |path_and_query| path_and_query.as_str());
+        match self {
+            Self::Method => out.push_str(parts.method.as_str()),
+            Self::Path => out.push_str(parts.uri.path()),
+            Self::PathAndQuery => out.push_str(path_and_query),
+            Self::RequestLine => {
+                out.push_str(&format!("{} {} HTTP/1.1", parts.method, path_and_query))
+            }
+            Self::RequestTarget => out.push_str(&format!(
+                "(request-target): {} {}",
+                parts.method.as_str().to_lowercase(),
+                path_and_query
+            )),
+            Self::Header(name) => out.push_str(header_value(parts, name)),
+            Self::NamedHeader(name) => {
+                out.push_str(&format!("{}: {}", name, header_value(parts, name)))
+            }
+        }
+    }
+}
+
+/// Algorithm the gateway signs requests with
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum GatewayAlgorithm {
+    /// HMAC using SHA-256
+    HmacSha256,
+    /// HMAC using SHA-384
+    HmacSha384,
+    /// HMAC using SHA-512
+    HmacSha512,
+}
+
+impl GatewayAlgorithm {
+    /// Gets the hash function the HMAC uses
+    fn hash(self) -> Hash {
+        match self {
+            Self::HmacSha256 => Hash::Sha256,
+            Self::HmacSha384 => Hash::Sha384,
+            Self::HmacSha512 => Hash::Sha512,
+        }
+    }
+
+    /// Gets the name of the algorithm in the `algorithm` auth-param, like `hmac-sha256`
+    fn name(self) -> &'static str {
+        match self {
+            Self::HmacSha256 => "hmac-sha256",
+            Self::HmacSha384 => "hmac-sha384",
+            Self::HmacSha512 => "hmac-sha512",
+        }
+    }
+}
+
+/// Encoding of the signature in its header
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SignatureEncoding {
+    /// Lowercase or uppercase hex
+    Hex,
+    /// Standard base64, with optional `=` padding at the end
+    Base64,
+}
+
+impl SignatureEncoding {
+    /// Decodes the signature, giving `None` if it isn't encoded properly
+    fn decode(self, signature: &str) -> Option<Vec<u8>> {
+        match self {
+            Self::Hex => signature
+                .as_bytes()
+                .chunks(2)
+                .map(|pair| match pair {
+                    [high, low] => {
+                        let digit = |byte: &u8| char::from(*byte).to_digit(16);
+                        Some((digit(high)? * 16 + digit(low)?) as u8)
+                    }
+                    _ => None,
+                })
+                .collect(),
+            Self::Base64 => general_purpose::STANDARD
+                .decode(signature)
+                .or_else(|_| general_purpose::STANDARD_NO_PAD.decode(signature))
+                .ok(),
+        }
+    }
+}
+
+/// Profile describing how an API gateway signs the requests it forwards, so services behind it can check they came through the gateway
+///
+/// This is enabled via the `gateway-signature` feature alongside a crypto backend
+///
+/// Gateways like Kong and Tyk can sign each request they forward with a secret shared with the services behind them. The profile says which header the signature is in, how the string to sign is laid out from the [SignedPart]s of the request, and which [GatewayAlgorithm] and [SignatureEncoding] are used. By default the signature is a hex HMAC-SHA256 in the `X-Request-Signature` header, over the method and path with the query joined by newlines.
+///
+/// Presets are available for common layouts, such as [kong](Self::kong) and [tyk](Self::tyk), and any part of them can be changed afterwards. Gateways which send the signature as auth-params of the `Authorization` header, listing the headers they signed, are supported using [with_auth_params](Self::with_auth_params). If the gateway also sends a Unix timestamp which it signs, use [with_timestamp](Self::with_timestamp) so old requests can't be sent again later, or [with_date_window](Self::with_date_window) for the `Date` header. The body isn't signed by itself, so sign a `Content-Digest` header and use `DigestVerified` with the `content-digest` feature if it matters.
+///
+/// Insert this into the request extensions so [GatewayVerified] can check requests with it. The secret is never shown in the [Debug](fmt::Debug) output.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{routing::get, Extension, Router};
+/// use axum_auth::{GatewaySignature, GatewayVerified, SignedPart};
+///
+/// let profile = GatewaySignature::new(b"secret shared with the gateway")
+///     .with_header("x-gateway-signature")
+///     .with_parts([SignedPart::Method, SignedPart::PathAndQuery, SignedPart::header("x-request-id")]);
+///
+/// let app: Router = Router::new()
+///     .route("/", get(|_: GatewayVerified| async { "Came through the gateway" }))
+///     .layer(Extension(profile));
+/// ```
+///
+/// # Errors
+///
+/// See [GatewayVerified] for the rejections made when checking requests.
+#[derive(Clone)]
+pub struct GatewaySignature {
+    key: Arc<[u8]>,
+    header: HeaderName,
+    prefix: Option<String>,
+    parts: Vec<SignedPart>,
+    separator: String,
+    algorithm: GatewayAlgorithm,
+    encoding: SignatureEncoding,
+    scheme: Option<String>,
+    timestamp: Option<(HeaderName, Duration)>,
+    date_window: Option<Duration>,
+}
+
+impl GatewaySignature {
+    /// Default header the signature is taken from, which is `X-Request-Signature`
+    pub const DEFAULT_HEADER: &'static str = "x-request-signature";
+
+    /// Creates a new profile using the secret key shared with the gateway and the defaults
+    pub fn new(key: impl AsRef<[u8]>) -> Self {
+        Self {
+            key: key.as_ref().into(),
+            header: HeaderName::from_static(Self::DEFAULT_HEADER),
+            prefix: None,
+            parts: vec![SignedPart::Method, SignedPart::PathAndQuery],
+            separator: "\n".to_string(),
+            algorithm: GatewayAlgorithm::HmacSha256,
+            encoding: SignatureEncoding::Hex,
+            scheme: None,
+            timestamp: None,
+            date_window: None,
+        }
+    }
+
+    /// Default window the `Date` header of the presets can be away from now, which is 5 minutes like Kong's default clock skew
+    pub const DEFAULT_DATE_WINDOW: Duration = Duration::from_secs(5 * 60);
+
+    /// Creates a new profile laid out like the `hmac-auth` plugin of Kong, as a base64 HMAC-SHA256 in the `Authorization` header
+    ///
+    /// The header looks like `Authorization: hmac username="…", algorithm="hmac-sha256", headers="date request-line", signature="…"`, where only the `Date` header is signed if `headers` is left out. The `Date` header has to be signed and within [DEFAULT_DATE_WINDOW](Self::DEFAULT_DATE_WINDOW) of now.
+    pub fn kong(key: impl AsRef<[u8]>) -> Self {
+        Self::new(key)
+            .with_auth_params("hmac")
+            .with_parts([SignedPart::named_header("date")])
+            .with_encoding(SignatureEncoding::Base64)
+            .with_date_window(Self::DEFAULT_DATE_WINDOW)
+    }
+
+    /// Creates a new profile laid out like the request signing of Tyk, as a base64 HMAC-SHA256 in the `Authorization` header
+    ///
+    /// The header looks like `Authorization: Signature keyId="…",algorithm="hmac-sha256",headers="(request-target) date",signature="…"`, where only the `Date` header is signed if `headers` is left out. The `Date` header has to be signed and within [DEFAULT_DATE_WINDOW](Self::DEFAULT_DATE_WINDOW) of now.
+    pub fn tyk(key: impl AsRef<[u8]>) -> Self {
+        Self::new(key)
+            .with_auth_params("Signature")
+            .with_parts([SignedPart::named_header("date")])
+            .with_encoding(SignatureEncoding::Base64)
+            .with_date_window(Self::DEFAULT_DATE_WINDOW)
+    }
+
+    /// Takes the signature from the header instead, returning it back
+    ///
+    /// # Panics
+    ///
+    /// Panics if the name isn't a valid header name, as this is meant to be used with names known at compile time.
+    pub fn with_header(mut self, header: &str) -> Self {
+        self.header = header_name(header);
+        self
+    }
+
+    /// Takes the signature from the `signature` auth-param of the `Authorization` header using the scheme, like `Authorization: hmac signature="…"`, returning it back
+    ///
+    /// If the auth-params list what was signed in `headers`, like `headers="date request-line"`, that's signed in the order given instead of the parts of the profile. Each name is a [SignedPart::NamedHeader], apart from `request-line` and `(request-target)` which are the [SignedPart::RequestLine] and [SignedPart::RequestTarget]. An `algorithm` auth-param has to match the algorithm of the profile, and the `username` or `keyId` auth-param is given back in [GatewayVerified::key_id]. Use [with_header](Self::with_header) afterwards if the gateway puts them in another header.
+    pub fn with_auth_params(mut self, scheme: impl Into<String>) -> Self {
+        self.header = AUTHORIZATION;
+        self.scheme = Some(scheme.into());
+        self
+    }
+
+    /// Expects the signature to start with the prefix, like `sha256=`, returning it back
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = Some(prefix.into());
+        self
+    }
+
+    /// Signs the parts of the request in the order given, replacing the current ones and returning it back
+    pub fn with_parts(mut self, parts: impl IntoIterator<Item = SignedPart>) -> Self {
+        self.parts = parts.into_iter().collect();
+        self
+    }
+
+    /// Joins the parts of the string to sign with the separator instead of a newline, returning it back
+    pub fn with_separator(mut self, separator: impl Into<String>) -> Self {
+        self.separator = separator.into();
+        self
+    }
+
+    /// Sets the algorithm the gateway signs with, returning it back
+    pub fn with_algorithm(mut self, algorithm: GatewayAlgorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    /// Sets how the signature is encoded in its header, returning it back
+    pub fn with_encoding(mut self, encoding: SignatureEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Only accepts requests with a Unix timestamp in the header which is at most the window away from now either way, returning it back
+    ///
+    /// The header is also added to the end of the signed parts if it isn't signed already, as an unsigned timestamp can be changed by anyone.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the name isn't a valid header name, as this is meant to be used with names known at compile time.
+    pub fn with_timestamp(mut self, header: &str, window: Duration) -> Self {
+        let header = header_name(header);
+        if !signs_header(&self.parts, &header) {
+            self.parts.push(SignedPart::Header(header.clone()));
+        }
+        self.timestamp = Some((header, window));
+        self
+    }
+
+    /// Only accepts requests with an HTTP date in the `Date` header which is at most the window away from now either way, returning it back
+    ///
+    /// The `Date` header is also added to the end of the signed parts if it isn't signed already, and requests listing signed headers in their auth-params without it are rejected, as an unsigned date can be changed by anyone.
+    pub fn with_date_window(mut self, window: Duration) -> Self {
+        if !signs_header(&self.parts, &DATE) {
+            self.parts.push(SignedPart::NamedHeader(DATE));
+        }
+        self.date_window = Some(window);
+        self
+    }
+
+    /// Makes the string to sign for the request from the parts of the profile, which is what the gateway signs unless it lists other headers in its auth-params
+    pub fn string_to_sign(&self, parts: &Parts) -> String {
+        self.join(&self.parts, parts)
+    }
+
+    /// Joins the signed parts of the request using the separator
+    fn join(&self, signed_parts: &[SignedPart], parts: &Parts) -> String {
+        let mut out = String::new();
+        for (i, part) in signed_parts.iter().enumerate() {
+            if i != 0 {
+                out.push_str(&self.separator);
+            }
+            part.write(parts, &mut out);
+        }
+        out
+    }
+
+    /// Checks the signature of the request, along with its timestamp and `Date` header if they're checked
+    ///
+    /// # Errors
+    ///
+    /// See [GatewayVerified] for the rejections this can give.
+    pub fn verify(&self, parts: &Parts) -> Result<GatewayVerified, Rejection> {
+        let header = parts
+            .headers
+            .get(&self.header)
+            .ok_or((StatusCode::UNAUTHORIZED, ERR_GATEWAY_SIGNATURE_MISSING))?;
+        let invalid = (StatusCode::UNAUTHORIZED, ERR_GATEWAY_SIGNATURE);
+        let header = header.to_str().map_err(|_| invalid)?.trim();
+
+        // Get the signature, along with what was signed if the auth-params say
+        let mut key_id = None;
+        let mut signed_parts = None;
+        let signature = match &self.scheme {
+            Some(scheme) => {
+                let params = match header.split_once(' ') {
+                    Some((got, params)) if got.eq_ignore_ascii_case(scheme) => parse_params(params),
+                    _ => return Err(invalid),
+                };
+                let param = |name: &str| {
+                    params
+                        .iter()
+                        .find(|(param, _)| param == name)
+                        .map(|(_, value)| value.as_str())
+                };
+                if param("algorithm")
+                    .is_some_and(|algorithm| !algorithm.eq_ignore_ascii_case(self.algorithm.name()))
+                {
+                    return Err(invalid);
+                }
+                if let Some(headers) = param("headers") {
+                    signed_parts = Some(signed_headers(headers).ok_or(invalid)?);
+                }
+                key_id = param("username").or(param("keyid")).map(str::to_string);
+                param("signature").ok_or(invalid)?.to_string()
+            }
+            None => header.to_string(),
+        };
+        let signed_parts = signed_parts.as_deref().unwrap_or(&self.parts);
+
+        // Decode the signature, treating anything unexpected as invalid
+        let signature = signature.as_str();
+        let signature = match &self.prefix {
+            Some(prefix) => signature.strip_prefix(prefix.as_str()).ok_or(invalid)?,
+            None => signature,
+        };
+        let signature = self.encoding.decode(signature).ok_or(invalid)?;
+
+        // Check the signature in constant time
+        let signed = self.join(signed_parts, parts);
+        if !crypto::verify_hmac(
+            self.algorithm.hash(),
+            &self.key,
+            signed.as_bytes(),
+            &signature,
+        ) {
+            return Err(invalid);
+        }
+
+        // Only trust the timestamp and date once they're known to be signed
+        if let Some((header, window)) = &self.timestamp {
+            if !signs_header(signed_parts, header) {
+                return Err(invalid);
+            }
+            let timestamp = header_value(parts, header)
+                .parse()
+                .ok()
+                .and_then(|secs| UNIX_EPOCH.checked_add(Duration::from_secs(secs)))
+                .ok_or(invalid)?;
+            check_window(timestamp, *window)?;
+        }
+        if let Some(window) = self.date_window {
+            if !signs_header(signed_parts, &DATE) {
+                return Err(invalid);
+            }
+            let date =
+                httpdate::parse_http_date(header_value(parts, &DATE)).map_err(|_| invalid)?;
+            check_window(date, window)?;
+        }
+
+        Ok(GatewayVerified { signed, key_id })
+    }
+}
+
+impl fmt::Debug for GatewaySignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GatewaySignature")
+            .field("header", &self.header)
+            .field("prefix", &self.prefix)
+            .field("parts", &self.parts)
+            .field("separator", &self.separator)
+            .field("algorithm", &self.algorithm)
+            .field("encoding", &self.encoding)
+            .field("scheme", &self.scheme)
+            .field("timestamp", &self.timestamp)
+            .field("date_window", &self.date_window)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Request which was checked to be signed by the gateway using a [GatewaySignature]
+///
+/// This is enabled via the `gateway-signature` feature alongside a crypto backend
+///
+/// The [GatewaySignature] is taken from the request extensions, and the request is checked as seen by the handler, so the path within any nested router is what's signed.
+///
+/// # Example
+///
+/// ```no_run
+/// use axum_auth::GatewayVerified;
+///
+/// /// Shows what the gateway signed, which is handy when setting a gateway up
+/// async fn handler(verified: GatewayVerified) -> String {
+///     format!("The gateway signed {:?}", verified.signed)
+/// }
+/// ```
+///
+/// # Errors
+///
+/// Requests which aren't signed properly get `401 UNAUTHORIZED` with one of these messages:
+///
+/// - Gateway signature header is missing – The request didn't come through the gateway, or the header name is wrong
+/// - Gateway signature is invalid – The request was changed, was signed with another key, didn't sign its timestamp or `Date` header, or the profile doesn't match the gateway
+/// - Request timestamp is outside of the allowed window – The signed timestamp or `Date` header is too old or too far in the future
+///
+/// If no [GatewaySignature] was inserted into the request extensions, this responds with `500 INTERNAL SERVER ERROR` and the message "No gateway signature profile was found for this request".
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct GatewayVerified {
+    /// String which the gateway signed
+    pub signed: String,
+    /// Username or key ID the gateway sent in its auth-params, if there was one
+    pub key_id: Option<String>,
+}
+
+impl<B> FromRequestParts<B> for GatewayVerified
+where
+    B: Send + Sync,
+{
+    type Rejection = Rejection;
+
+    async fn from_request_parts(parts: &mut Parts, _: &B) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<GatewaySignature>()
+            .ok_or((StatusCode::INTERNAL_SERVER_ERROR, ERR_NO_GATEWAY_SIGNATURE))?
+            .verify(parts)
+    }
+}
+
+/// Parses a header name known at compile time
+fn header_name(name: &str) -> HeaderName {
+    HeaderName::try_from(name).expect("gateway signature header isn't a valid name")
+}
+
+/// Gets the value of the header as a string, which is empty if it's missing or not visible ASCII
+fn header_value<'a>(parts: &'a Parts, name: &HeaderName) -> &'a str {
+    parts
+        .headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+}
+
+/// Checks if the header is one of the signed parts
+fn signs_header(signed_parts: &[SignedPart], header: &HeaderName) -> bool {
+    signed_parts.iter().any(|part| {
+        matches!(part, SignedPart::Header(name) | SignedPart::NamedHeader(name) if name == header)
+    })
+}
+
+/// Parses the space-separated names in the `headers` auth-param into the parts they sign, giving `None` if any are invalid
+fn signed_headers(headers: &str) -> Option<Vec<SignedPart>> {
+    let signed_parts = headers
+        .split_ascii_whitespace()
+        .map(|name| match name {
+            "request-line" => Some(SignedPart::RequestLine),
+            "(request-target)" => Some(SignedPart::RequestTarget),
+            name => HeaderName::try_from(name).ok().map(SignedPart::NamedHeader),
+        })
+        .collect::<Option<Vec<_>>>()?;
+    (!signed_parts.is_empty()).then_some(signed_parts)
+}
+
+/// Rejects the time as stale if it's more than the window away from now either way
+fn check_window(time: SystemTime, window: Duration) -> Result<(), Rejection> {
+    let now = SystemTime::now();
+    let skew = now
+        .duration_since(time)
+        .or_else(|_| time.duration_since(now))
+        .unwrap_or_default();
+    if skew > window {
+        return Err((StatusCode::UNAUTHORIZED, ERR_STALE));
+    }
+    Ok(())
+}
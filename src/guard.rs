@@ -0,0 +1,241 @@
+//! Implementation of guard extractors which check the authenticated identity
+//!
+//! See [RequireRole] and [Requires] for the most commonly-used data structures
+
+use crate::{AuthUser, Rejection, ERR_MISSING_PERMISSION, ERR_MISSING_ROLE};
+use axum_core::extract::FromRequestParts;
+use http::{request::Parts, StatusCode};
+use std::{collections::BTreeSet, fmt, marker::PhantomData, ops::Deref};
+
+/// Role which can be required by the [RequireRole] guard, implement this on a marker type
+///
+/// # Example
+///
+/// ```rust
+/// use axum_auth::Role;
+///
+/// /// Administrators of the site
+/// struct Admin;
+///
+/// impl Role for Admin {
+///     const NAME: &'static str = "admin";
+/// }
+/// ```
+pub trait Role {
+    /// Name of the role as found in [AuthUser::roles]
+    const NAME: &'static str;
+}
+
+/// Guard extractor only allowing users with a certain role through, making role checks visible in the handler signature
+///
+/// Stable Rust doesn't allow strings as const generics (like `RequireRole<"admin">`), so roles are given as marker types implementing [Role] instead.
+///
+/// # Example
+///
+/// Once a layer or middleware has inserted an [AuthUser], you can use it like this:
+///
+/// ```no_run
+/// use axum_auth::{RequireRole, Role};
+///
+/// struct Admin;
+///
+/// impl Role for Admin {
+///     const NAME: &'static str = "admin";
+/// }
+///
+/// /// Only lets admins through, showing their identifier
+/// async fn handler(admin: RequireRole<Admin>) -> String {
+///     format!("Hello, admin {}!", admin.id)
+/// }
+/// ```
+///
+/// # Errors
+///
+/// There are a few errors which this extractor can make:
+///
+/// - `401 UNAUTHORIZED` with "No authenticated user was found for this request" – No [AuthUser] was inserted beforehand
+/// - `403 FORBIDDEN` with "You don't have the role required to access this resource" – The user doesn't have the role
+pub struct RequireRole<R> {
+    /// User which was found to have the role
+    pub user: AuthUser,
+    role: PhantomData<fn() -> R>,
+}
+
+impl<R> Deref for RequireRole<R> {
+    type Target = AuthUser;
+
+    fn deref(&self) -> &Self::Target {
+        &self.user
+    }
+}
+
+impl<R> Clone for RequireRole<R> {
+    fn clone(&self) -> Self {
+        Self {
+            user: self.user.clone(),
+            role: PhantomData,
+        }
+    }
+}
+
+impl<R: Role> fmt::Debug for RequireRole<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RequireRole")
+            .field("role", &R::NAME)
+            .field("user", &self.user)
+            .finish()
+    }
+}
+
+impl<R, B> FromRequestParts<B> for RequireRole<R>
+where
+    R: Role,
+    B: Send + Sync,
+{
+    type Rejection = Rejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &B) -> Result<Self, Self::Rejection> {
+        let user = AuthUser::from_request_parts(parts, state).await?;
+        if user.has_role(R::NAME) {
+            Ok(Self {
+                user,
+                role: PhantomData,
+            })
+        } else {
+            Err((StatusCode::FORBIDDEN, ERR_MISSING_ROLE))
+        }
+    }
+}
+
+/// Permission which can be granted in [Permissions] and required by the [Requires] guard, implement this on a marker type
+///
+/// # Example
+///
+/// ```rust
+/// use axum_auth::Permission;
+///
+/// /// Allows writing new posts
+/// struct WritePosts;
+///
+/// impl Permission for WritePosts {
+///     const NAME: &'static str = "posts:write";
+/// }
+/// ```
+pub trait Permission {
+    /// Unique name of the permission, used when listing or logging them
+    const NAME: &'static str;
+}
+
+/// Typed collection of permissions granted to a request, stored inside of the request extensions by validators or your own middleware
+///
+/// Because permissions are marker types instead of strings, a misspelled permission is a compile error instead of a silent denial.
+///
+/// # Example
+///
+/// ```rust
+/// use axum_auth::{Permission, Permissions};
+///
+/// struct ReadPosts;
+///
+/// impl Permission for ReadPosts {
+///     const NAME: &'static str = "posts:read";
+/// }
+///
+/// struct WritePosts;
+///
+/// impl Permission for WritePosts {
+///     const NAME: &'static str = "posts:write";
+/// }
+///
+/// let permissions = Permissions::new().with::<ReadPosts>();
+/// assert!(permissions.has::<ReadPosts>());
+/// assert!(!permissions.has::<WritePosts>());
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Permissions(BTreeSet<&'static str>);
+
+impl Permissions {
+    /// Creates a new empty collection of permissions
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Grants a permission, returning the collection back
+    pub fn with<P: Permission>(mut self) -> Self {
+        self.grant::<P>();
+        self
+    }
+
+    /// Grants a permission in-place
+    pub fn grant<P: Permission>(&mut self) {
+        self.0.insert(P::NAME);
+    }
+
+    /// Takes away a permission if it was granted
+    pub fn revoke<P: Permission>(&mut self) {
+        self.0.remove(P::NAME);
+    }
+
+    /// Checks if a permission has been granted
+    pub fn has<P: Permission>(&self) -> bool {
+        self.0.contains(P::NAME)
+    }
+
+    /// Iterates over the names of every granted permission
+    pub fn iter(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.0.iter().copied()
+    }
+}
+
+/// Guard extractor only allowing requests which have been granted a certain [Permission] through
+///
+/// # Example
+///
+/// Once a layer or middleware has inserted [Permissions], you can use it like this:
+///
+/// ```no_run
+/// use axum_auth::{Permission, Requires};
+///
+/// struct WritePosts;
+///
+/// impl Permission for WritePosts {
+///     const NAME: &'static str = "posts:write";
+/// }
+///
+/// /// Only lets clients allowed to write posts through
+/// async fn handler(_: Requires<WritePosts>) -> &'static str {
+///     "Post created!"
+/// }
+/// ```
+///
+/// # Errors
+///
+/// If the permission wasn't granted, or no [Permissions] were inserted at all, this extractor responds with `403 FORBIDDEN` and the message "You don't have the permission required to access this resource".
+pub struct Requires<P>(PhantomData<fn() -> P>);
+
+impl<P> Clone for Requires<P> {
+    fn clone(&self) -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<P: Permission> fmt::Debug for Requires<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Requires").field(&P::NAME).finish()
+    }
+}
+
+impl<P, B> FromRequestParts<B> for Requires<P>
+where
+    P: Permission,
+    B: Send + Sync,
+{
+    type Rejection = Rejection;
+
+    async fn from_request_parts(parts: &mut Parts, _: &B) -> Result<Self, Self::Rejection> {
+        match parts.extensions.get::<Permissions>() {
+            Some(permissions) if permissions.has::<P>() => Ok(Self(PhantomData)),
+            _ => Err((StatusCode::FORBIDDEN, ERR_MISSING_PERMISSION)),
+        }
+    }
+}
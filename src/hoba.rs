@@ -0,0 +1,327 @@
+//! Implementation of [RFC 7486](https://www.rfc-editor.org/rfc/rfc7486) HTTP Origin-Bound Authentication (HOBA)
+//!
+//! See [HobaAuthLayer] for the most commonly-used data structure
+
+use crate::{
+    challenge::parse_params,
+    crypto, get_header,
+    replay::{signed_nonce, signed_nonce_issued},
+    AuthUser, Challenge, MemoryNonceStore, NonceStore, Unauthorized, ERR_HOBA_CHALLENGE,
+    ERR_HOBA_RESULT, ERR_INVALID_CREDENTIALS, ERR_WRONG_HOBA,
+};
+use axum_core::response::{IntoResponse, Response};
+use base64::{engine::general_purpose, Engine};
+use http::{request::Parts, Request, StatusCode};
+use std::{
+    collections::HashMap,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::{Duration, SystemTime},
+};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// Name of the scheme, which is also what goes into the headers
+const SCHEME: &str = "HOBA";
+
+/// Signature algorithm identifier for RSA-SHA256, which is the only one supported
+const ALG_RSA_SHA256: &str = "0";
+
+/// Public key registered by a client, alongside the identity it logs in as
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct HobaKey {
+    public_key: Vec<u8>,
+    user: AuthUser,
+}
+
+impl HobaKey {
+    /// Creates a new key from an RSA public key in PKCS#1 DER form, logging in as the given user
+    pub fn new(public_key: impl Into<Vec<u8>>, user: AuthUser) -> Self {
+        Self {
+            public_key: public_key.into(),
+            user,
+        }
+    }
+}
+
+/// Store of the keys clients have registered, looked up by their key identifier
+///
+/// The in-memory [HobaKeys] works for keys known upfront, implement this yourself to look keys up in a database. How clients register their keys is up to your application, as the RFC leaves it open.
+pub trait HobaKeyStore: Send + Sync + 'static {
+    /// Looks up the key, giving [None] if it isn't registered; you need to implement this
+    fn key(&self, kid: &str) -> impl Future<Output = Option<HobaKey>> + Send;
+}
+
+/// In-memory [HobaKeyStore] of keys known upfront
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct HobaKeys {
+    keys: HashMap<String, HobaKey>,
+}
+
+impl HobaKeys {
+    /// Creates a new empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a key to the store, returning it back
+    pub fn with_key(mut self, kid: impl Into<String>, key: HobaKey) -> Self {
+        self.insert(kid, key);
+        self
+    }
+
+    /// Adds a key to the store in-place, replacing any existing key with the same identifier
+    pub fn insert(&mut self, kid: impl Into<String>, key: HobaKey) {
+        self.keys.insert(kid.into(), key);
+    }
+
+    /// Removes a key from the store, giving it back if it existed
+    pub fn remove(&mut self, kid: &str) -> Option<HobaKey> {
+        self.keys.remove(kid)
+    }
+}
+
+impl HobaKeyStore for HobaKeys {
+    async fn key(&self, kid: &str) -> Option<HobaKey> {
+        self.keys.get(kid).cloned()
+    }
+}
+
+/// Layer authenticating every request using HOBA, where clients sign a challenge with a key bound to the origin instead of using a password
+///
+/// This is enabled via the `hoba` feature alongside a crypto backend
+///
+/// Requests without a valid signature get a `401 UNAUTHORIZED` with a fresh challenge, which the client signs using the key it registered for this origin. Challenges are stateless (signed by the server using a secret) and can only be used once, which is tracked using a [NonceStore]. Once authenticated, the [AuthUser] of the key is inserted into the request extensions.
+///
+/// Only RSA-SHA256 signatures are supported, with the public keys stored in PKCS#1 DER form.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{routing::get, Router};
+/// use axum_auth::{AuthUser, HobaAuthLayer, HobaKey, HobaKeys};
+///
+/// # let public_key = vec![];
+/// let keys = HobaKeys::new().with_key("my-kid", HobaKey::new(public_key, AuthUser::new("alice")));
+///
+/// let app: Router = Router::new()
+///     .route("/", get(|user: AuthUser| async move { format!("Hello, {}!", user.id) }))
+///     .layer(HobaAuthLayer::new("https://example.com:443", keys));
+/// ```
+///
+/// # Errors
+///
+/// All errors are `401 UNAUTHORIZED` with a fresh challenge, alongside one of these messages:
+///
+/// - \`Authorization\` header must be for HOBA authentication – Some other scheme was used instead
+/// - \`Authorization\` header isn't a valid HOBA result – The `result` parameter was missing or malformed
+/// - HOBA challenge has expired or was already used – The challenge was too old, reused, or not made by this server
+/// - The provided credentials are invalid – The key isn't registered or the signature was wrong
+/// - \`Authorization\` header is missing – The header was required but it wasn't found
+/// - \`Authorization\` header contains invalid characters – The header couldn't be processed because of invalid characters
+pub struct HobaAuthLayer<K, N = MemoryNonceStore> {
+    keys: Arc<K>,
+    nonces: Arc<N>,
+    secret: Arc<[u8]>,
+    origin: Arc<str>,
+    realm: Arc<str>,
+    max_age: Duration,
+}
+
+impl<K> HobaAuthLayer<K> {
+    /// Creates a new layer for the origin (e.g. `https://example.com:443`) using the given keys, with an in-memory nonce store and a random secret
+    ///
+    /// The random secret means challenges only work on the server which made them, use [HobaAuthLayer::with_secret] if you have many servers.
+    pub fn new(origin: impl AsRef<str>, keys: K) -> Self {
+        Self {
+            keys: Arc::new(keys),
+            nonces: Arc::new(MemoryNonceStore::new()),
+            secret: Arc::from(crypto::random(32)),
+            origin: Arc::from(origin.as_ref()),
+            realm: Arc::from(""),
+            max_age: Self::DEFAULT_MAX_AGE,
+        }
+    }
+}
+
+impl<K, N> HobaAuthLayer<K, N> {
+    /// Default amount of time clients have to answer a challenge, which is 1 minute
+    pub const DEFAULT_MAX_AGE: Duration = Duration::from_secs(60);
+
+    /// Sets the realm advertised in the challenge and covered by signatures, returning it back
+    pub fn with_realm(mut self, realm: impl AsRef<str>) -> Self {
+        self.realm = Arc::from(realm.as_ref());
+        self
+    }
+
+    /// Sets the secret challenges are signed with, which has to be the same on every server, returning it back
+    pub fn with_secret(mut self, secret: impl AsRef<[u8]>) -> Self {
+        self.secret = Arc::from(secret.as_ref());
+        self
+    }
+
+    /// Sets how long clients have to answer a challenge, returning it back
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    /// Sets the store remembering which challenges have been used, returning it back
+    pub fn with_nonce_store<N2>(self, nonces: N2) -> HobaAuthLayer<K, N2> {
+        HobaAuthLayer {
+            keys: self.keys,
+            nonces: Arc::new(nonces),
+            secret: self.secret,
+            origin: self.origin,
+            realm: self.realm,
+            max_age: self.max_age,
+        }
+    }
+}
+
+impl<K, N> Clone for HobaAuthLayer<K, N> {
+    fn clone(&self) -> Self {
+        Self {
+            keys: self.keys.clone(),
+            nonces: self.nonces.clone(),
+            secret: self.secret.clone(),
+            origin: self.origin.clone(),
+            realm: self.realm.clone(),
+            max_age: self.max_age,
+        }
+    }
+}
+
+impl<S, K, N> Layer<S> for HobaAuthLayer<K, N> {
+    type Service = HobaAuth<S, K, N>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        HobaAuth {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+/// Service created by [HobaAuthLayer], see it for more information
+pub struct HobaAuth<S, K, N> {
+    inner: S,
+    layer: HobaAuthLayer<K, N>,
+}
+
+impl<S: Clone, K, N> Clone for HobaAuth<S, K, N> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            layer: self.layer.clone(),
+        }
+    }
+}
+
+impl<S, K, N, B> Service<Request<B>> for HobaAuth<S, K, N>
+where
+    S: Service<Request<B>, Response = Response> + Clone + Send + 'static,
+    S::Future: Send,
+    K: HobaKeyStore,
+    N: NonceStore,
+    B: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<B>) -> Self::Future {
+        // Take the service which was driven to readiness, leaving a fresh clone behind
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let layer = self.layer.clone();
+
+        Box::pin(async move {
+            let (mut parts, body) = req.into_parts();
+
+            // Verify the signature and keep the identity for later
+            match verify(&layer, &mut parts).await {
+                Ok(user) => {
+                    parts.extensions.insert(user);
+                    inner.call(Request::from_parts(parts, body)).await
+                }
+                Err(message) => Ok(Unauthorized::new()
+                    .with_challenge(challenge(&layer))
+                    .with_message(message)
+                    .into_response()),
+            }
+        })
+    }
+}
+
+/// Makes a fresh challenge, signed so it doesn't have to be remembered
+fn challenge<K, N>(layer: &HobaAuthLayer<K, N>) -> Challenge {
+    let mut challenge = Challenge::new(SCHEME)
+        .with_param("challenge", signed_nonce(&layer.secret))
+        .with_param("max-age", layer.max_age.as_secs().to_string());
+    if !layer.realm.is_empty() {
+        challenge = challenge.with_param("realm", &*layer.realm);
+    }
+    challenge
+}
+
+/// Checks the signed result in the header, giving back the identity of the key
+async fn verify<K: HobaKeyStore, N: NonceStore>(
+    layer: &HobaAuthLayer<K, N>,
+    parts: &mut Parts,
+) -> Result<AuthUser, &'static str> {
+    // Get the result from the header, e.g. `HOBA result="kid.challenge.nonce.sig"`
+    let authorization = get_header(parts, StatusCode::UNAUTHORIZED).map_err(|(_, msg)| msg)?;
+    let params = match authorization.split_once(' ') {
+        Some((SCHEME, params)) => parse_params(params),
+        _ => return Err(ERR_WRONG_HOBA),
+    };
+    let result = params
+        .iter()
+        .find(|(name, _)| name == "result")
+        .map(|(_, value)| value.as_str())
+        .ok_or(ERR_HOBA_RESULT)?;
+    let [kid, challenge, nonce, signature] = result.split('.').collect::<Vec<_>>()[..] else {
+        return Err(ERR_HOBA_RESULT);
+    };
+    let signature = general_purpose::URL_SAFE_NO_PAD
+        .decode(signature)
+        .map_err(|_| ERR_HOBA_RESULT)?;
+
+    // Check the challenge was made by us and is recent
+    let expires = signed_nonce_issued(&layer.secret, challenge)
+        .and_then(|issued| issued.checked_add(layer.max_age))
+        .ok_or(ERR_HOBA_CHALLENGE)?;
+    if expires < SystemTime::now() {
+        return Err(ERR_HOBA_CHALLENGE);
+    }
+
+    // Check the signature over everything the client was bound to
+    let key = layer.keys.key(kid).await.ok_or(ERR_INVALID_CREDENTIALS)?;
+    let tbs = [
+        nonce,
+        ALG_RSA_SHA256,
+        &layer.origin,
+        &layer.realm,
+        kid,
+        challenge,
+    ]
+    .iter()
+    .map(|part| format!("{}:{}", part.len(), part))
+    .collect::<String>();
+    if !crypto::verify_rsa_sha256(&key.public_key, tbs.as_bytes(), &signature) {
+        return Err(ERR_INVALID_CREDENTIALS);
+    }
+
+    // Only use up the challenge once it's been signed properly
+    if !layer.nonces.insert(challenge, expires).await {
+        return Err(ERR_HOBA_CHALLENGE);
+    }
+    Ok(key.user)
+}
@@ -0,0 +1,44 @@
+//! Implementation of compact JSON Web Signature decoding shared by the token-based schemes
+//!
+//! Signatures are checked by the callers, since each scheme has its own rules for which keys and algorithms to trust.
+
+use base64::{engine::general_purpose, Engine};
+use serde_json::{Map, Value};
+
+/// Decoded but not yet verified compact JWS, such as a JWT
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) struct Jws<'a> {
+    /// Protected header, containing the algorithm and key identifier
+    pub(crate) header: Map<String, Value>,
+    /// Payload, which is the claims for a JWT
+    pub(crate) claims: Map<String, Value>,
+    /// Encoded header and payload joined by a dot, which is what gets signed
+    pub(crate) signing_input: &'a str,
+    /// Raw signature bytes
+    pub(crate) signature: Vec<u8>,
+}
+
+impl<'a> Jws<'a> {
+    /// Decodes the three parts of a compact JWS, giving [None] if it's malformed
+    pub(crate) fn decode(token: &'a str) -> Option<Self> {
+        let (signing_input, signature) = token.rsplit_once('.')?;
+        let (header, claims) = signing_input.split_once('.')?;
+        Some(Self {
+            header: decode_json(header)?,
+            claims: decode_json(claims)?,
+            signing_input,
+            signature: general_purpose::URL_SAFE_NO_PAD.decode(signature).ok()?,
+        })
+    }
+
+    /// Gets the algorithm from the header
+    pub(crate) fn alg(&self) -> Option<&str> {
+        self.header.get("alg")?.as_str()
+    }
+}
+
+/// Decodes a base64url section into a JSON object
+fn decode_json(input: &str) -> Option<Map<String, Value>> {
+    let bytes = general_purpose::URL_SAFE_NO_PAD.decode(input).ok()?;
+    serde_json::from_slice(&bytes).ok()
+}
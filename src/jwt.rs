@@ -0,0 +1,1096 @@
+//! Implementation of [RFC 7519](https://www.rfc-editor.org/rfc/rfc7519) JSON Web Token validation for bearer tokens
+//!
+//! See [JwtValidator] for the most commonly-used data structure
+
+use crate::{
+    crypto::{self, Hash},
+    jws::Jws,
+    AuthBearer, AuthUser, AuthValidator, CredentialExpiry, Rejection, RetryPolicy, Scopes,
+    ERR_JWKS_UNAVAILABLE, ERR_JWT_AUDIENCE, ERR_JWT_CLAIM, ERR_JWT_EXPIRED, ERR_JWT_ISSUER,
+    ERR_JWT_MALFORMED, ERR_JWT_SIGNATURE, ERR_JWT_SUBJECT, ERR_NO_JWT,
+};
+use axum_core::extract::FromRequestParts;
+use base64::{engine::general_purpose, Engine};
+use http::{header::CACHE_CONTROL, request::Parts, Extensions, StatusCode};
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+use std::{
+    collections::BTreeMap,
+    fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, OnceLock, RwLock,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+/// Key which JWTs can be signed with, either given directly or fetched from a JWKS
+///
+/// This is enabled via the `jwt` feature alongside a crypto backend
+///
+/// Each key only accepts the algorithms of its own type, so a token can't pick `HS256` to get an RSA public key used as an HMAC secret.
+#[derive(PartialEq, Eq, Clone)]
+pub struct JwtKey {
+    kid: Option<String>,
+    alg: Option<String>,
+    material: KeyMaterial,
+}
+
+/// Actual key of a [JwtKey], deciding which algorithms it accepts
+#[derive(PartialEq, Eq, Clone)]
+enum KeyMaterial {
+    /// Shared secret for `HS256`, `HS384`, and `HS512`
+    Hmac(Vec<u8>),
+    /// Big-endian modulus and exponent for `RS256`, `RS384`, and `RS512`
+    Rsa { n: Vec<u8>, e: Vec<u8> },
+    /// Uncompressed P-256 point for `ES256`
+    Ec(Vec<u8>),
+}
+
+impl JwtKey {
+    /// Creates a shared secret key for `HS256`, `HS384`, and `HS512` tokens
+    pub fn hmac(secret: impl Into<Vec<u8>>) -> Self {
+        Self::from_material(KeyMaterial::Hmac(secret.into()))
+    }
+
+    /// Creates an RSA public key for `RS256`, `RS384`, and `RS512` tokens from its big-endian modulus and exponent
+    pub fn rsa(n: impl Into<Vec<u8>>, e: impl Into<Vec<u8>>) -> Self {
+        Self::from_material(KeyMaterial::Rsa {
+            n: n.into(),
+            e: e.into(),
+        })
+    }
+
+    /// Creates a P-256 public key for `ES256` tokens from its uncompressed point
+    pub fn es256(public_key: impl Into<Vec<u8>>) -> Self {
+        Self::from_material(KeyMaterial::Ec(public_key.into()))
+    }
+
+    /// Parses a single key of a JSON Web Key Set, giving [None] if it's malformed, unsupported, or only meant for encryption
+    pub fn from_jwk(jwk: &Value) -> Option<Self> {
+        let field = |name| {
+            jwk.get(name)
+                .and_then(Value::as_str)
+                .and_then(|value| general_purpose::URL_SAFE_NO_PAD.decode(value).ok())
+        };
+        if jwk.get("use").and_then(Value::as_str) == Some("enc") {
+            return None;
+        }
+
+        let material = match jwk.get("kty")?.as_str()? {
+            "oct" => KeyMaterial::Hmac(field("k")?),
+            "RSA" => KeyMaterial::Rsa {
+                n: field("n")?,
+                e: field("e")?,
+            },
+            "EC" if jwk.get("crv")?.as_str()? == "P-256" => {
+                let mut point = vec![0x04];
+                point.extend(field("x")?);
+                point.extend(field("y")?);
+                KeyMaterial::Ec(point)
+            }
+            _ => return None,
+        };
+        Some(Self {
+            kid: jwk.get("kid").and_then(Value::as_str).map(Into::into),
+            alg: jwk.get("alg").and_then(Value::as_str).map(Into::into),
+            material,
+        })
+    }
+
+    /// Sets the key identifier which tokens refer to in their `kid` header, returning it back
+    pub fn with_kid(mut self, kid: impl Into<String>) -> Self {
+        self.kid = Some(kid.into());
+        self
+    }
+
+    /// Gets the type of the key as used by the `kty` member of a JWK, which is `oct`, `RSA`, or `EC`
+    pub fn kty(&self) -> &'static str {
+        match self.material {
+            KeyMaterial::Hmac(_) => "oct",
+            KeyMaterial::Rsa { .. } => "RSA",
+            KeyMaterial::Ec(_) => "EC",
+        }
+    }
+
+    /// Gets the [RFC 7638](https://www.rfc-editor.org/rfc/rfc7638) SHA-256 thumbprint of the key, encoded as base64url like identity providers show it
+    pub fn thumbprint(&self) -> String {
+        let encode = |bytes: &[u8]| general_purpose::URL_SAFE_NO_PAD.encode(bytes);
+        // Members are in lexicographic order without whitespace, as the RFC requires
+        let canonical = match &self.material {
+            KeyMaterial::Hmac(k) => format!(r#"{{"k":"{}","kty":"oct"}}"#, encode(k)),
+            KeyMaterial::Rsa { n, e } => {
+                format!(r#"{{"e":"{}","kty":"RSA","n":"{}"}}"#, encode(e), encode(n))
+            }
+            KeyMaterial::Ec(point) => {
+                let coordinates = point.get(1..).unwrap_or_default();
+                let (x, y) = coordinates.split_at(coordinates.len() / 2);
+                format!(
+                    r#"{{"crv":"P-256","kty":"EC","x":"{}","y":"{}"}}"#,
+                    encode(x),
+                    encode(y)
+                )
+            }
+        };
+        encode(&crypto::sha256(canonical.as_bytes()))
+    }
+
+    /// Only accepts tokens signed using the algorithm, like `RS256`, returning it back
+    pub fn with_alg(mut self, alg: impl Into<String>) -> Self {
+        self.alg = Some(alg.into());
+        self
+    }
+
+    /// Creates a key without an identifier or fixed algorithm
+    fn from_material(material: KeyMaterial) -> Self {
+        Self {
+            kid: None,
+            alg: None,
+            material,
+        }
+    }
+
+    /// Checks if this key could have signed a token with the given `kid` header, choosing keys for tokens without one using the selection
+    fn matches(&self, kid: Option<&str>, alg: &str, selection: &KeySelection) -> bool {
+        match (&self.kid, kid) {
+            (Some(own), Some(kid)) => own == kid,
+            (_, Some(_)) => true,
+            (_, None) => selection.selects(self, alg),
+        }
+    }
+
+    /// Verifies the signature using the algorithm, as long as this key supports it
+    fn verify(&self, alg: &str, message: &[u8], signature: &[u8]) -> bool {
+        if self.alg.as_deref().is_some_and(|own| own != alg) {
+            return false;
+        }
+        match (&self.material, alg) {
+            (KeyMaterial::Hmac(secret), "HS256") => {
+                crypto::verify_hmac(Hash::Sha256, secret, message, signature)
+            }
+            (KeyMaterial::Hmac(secret), "HS384") => {
+                crypto::verify_hmac(Hash::Sha384, secret, message, signature)
+            }
+            (KeyMaterial::Hmac(secret), "HS512") => {
+                crypto::verify_hmac(Hash::Sha512, secret, message, signature)
+            }
+            (KeyMaterial::Rsa { n, e }, "RS256") => {
+                crypto::verify_rsa(Hash::Sha256, n, e, message, signature)
+            }
+            (KeyMaterial::Rsa { n, e }, "RS384") => {
+                crypto::verify_rsa(Hash::Sha384, n, e, message, signature)
+            }
+            (KeyMaterial::Rsa { n, e }, "RS512") => {
+                crypto::verify_rsa(Hash::Sha512, n, e, message, signature)
+            }
+            (KeyMaterial::Ec(point), "ES256") => crypto::verify_es256(point, message, signature),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Debug for JwtKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JwtKey")
+            .field("kid", &self.kid)
+            .field("alg", &self.alg)
+            .field("kty", &self.kty())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Validator checking JWTs sent as bearer tokens, resolving them into an [AuthUser] and an [AuthJwt]
+///
+/// This is enabled via the `jwt` feature alongside a crypto backend
+///
+/// Tokens must be signed by one of the keys, which are given using [with_key](Self::with_key) or fetched from the JSON Web Key Set of an identity provider using [with_jwks_url](Self::with_jwks_url). The `exp` claim is required, and `nbf` and `iat` are checked when they're there. The issuer and audience are only checked once they've been configured, so always set them when tokens come from a shared identity provider.
+///
+/// Every key in the key set is trusted by default, so anyone who can change it can sign tokens. To stop that, keys from it can be limited by identifier using [with_allowed_kid](Self::with_allowed_kid), by type using [with_allowed_key_type](Self::with_allowed_key_type), or pinned by thumbprint using [with_pinned_thumbprint](Self::with_pinned_thumbprint).
+///
+/// For the identity, the `sub` claim becomes the [AuthUser] identifier, scopes are read from the `scope` or `scp` claims, and roles are read from the claims given using [with_roles_claim](Self::with_roles_claim). Ready-made setups for common identity providers are in [JwtPreset](crate::JwtPreset).
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{routing::get, Router};
+/// use axum_auth::{AuthBearer, AuthJwt, JwtValidator, RequireAuthLayer};
+///
+/// let validator = JwtValidator::new()
+///     .with_jwks_url("https://auth.example.com/.well-known/jwks.json")
+///     .with_issuer("https://auth.example.com/")
+///     .with_audience("https://api.example.com")
+///     .with_roles_claim("roles");
+///
+/// let app: Router = Router::new()
+///     .route("/", get(|jwt: AuthJwt| async move { format!("Claims: {:?}", jwt.claims) }))
+///     .layer(RequireAuthLayer::<AuthBearer, _>::new(validator));
+/// ```
+///
+/// # Errors
+///
+/// Invalid tokens are rejected with `401 UNAUTHORIZED` and one of these messages:
+///
+/// - Bearer token isn't a valid JWT – The token couldn't be decoded as a signed JWT
+/// - JWT signature is invalid or was made by an unknown key – None of the keys verified the signature
+/// - JWT has expired or isn't valid yet – The time claims were missing or out of range, or the token was older than [with_max_age](Self::with_max_age) allows
+/// - JWT was issued by an untrusted issuer – The `iss` claim wasn't one of the configured issuers
+/// - JWT is meant for another audience – The `aud` claim didn't contain any of the configured audiences, or all of them with [AudiencePolicy::All]
+/// - JWT claims aren't accepted here – A claim given using [with_required_claim](Self::with_required_claim) didn't have an allowed value
+/// - JWT has no subject – The `sub` claim was missing or empty
+///
+/// If the key set couldn't be fetched and there aren't any keys from before which are recent enough to keep using, the response is `503 SERVICE UNAVAILABLE` with the message "Signing keys couldn't be fetched from the identity provider".
+#[derive(Debug, Clone)]
+pub struct JwtValidator {
+    keys: Vec<JwtKey>,
+    jwks: Option<Arc<Jwks>>,
+    jwks_refresh: Duration,
+    jwks_max_stale: Duration,
+    jwks_retry: RetryPolicy,
+    issuers: Vec<String>,
+    audiences: Vec<Audience>,
+    audience_claims: Vec<String>,
+    audience_policy: AudiencePolicy,
+    key_selection: KeySelection,
+    allowlist: KeyAllowlist,
+    required: BTreeMap<String, Vec<String>>,
+    leeway: Duration,
+    max_age: Option<Duration>,
+    roles_claims: Vec<String>,
+}
+
+impl JwtValidator {
+    /// Default leeway given for clock differences, which is 60 seconds
+    pub const DEFAULT_LEEWAY: Duration = Duration::from_secs(60);
+
+    /// Default time a fetched key set is kept before fetching it again, which is 1 hour
+    pub const DEFAULT_JWKS_REFRESH: Duration = Duration::from_secs(60 * 60);
+
+    /// Default time a key set keeps being used past its refresh if it can't be fetched again, which is 1 day
+    pub const DEFAULT_JWKS_MAX_STALE: Duration = Duration::from_secs(24 * 60 * 60);
+
+    /// Name of the counter of key selections for tokens without a `kid` header
+    ///
+    /// This is enabled via the `metrics` feature
+    #[cfg(feature = "metrics")]
+    pub const KEY_SELECTIONS: &'static str = "axum_auth_jwt_key_selections_total";
+
+    /// Creates a new validator without any keys, issuers, or audiences
+    pub fn new() -> Self {
+        Self {
+            keys: vec![],
+            jwks: None,
+            jwks_refresh: Self::DEFAULT_JWKS_REFRESH,
+            jwks_max_stale: Self::DEFAULT_JWKS_MAX_STALE,
+            jwks_retry: RetryPolicy::NONE,
+            issuers: vec![],
+            audiences: vec![],
+            audience_claims: vec!["aud".into()],
+            audience_policy: AudiencePolicy::Any,
+            key_selection: KeySelection::TryAll,
+            allowlist: KeyAllowlist::default(),
+            required: BTreeMap::new(),
+            leeway: Self::DEFAULT_LEEWAY,
+            max_age: None,
+            roles_claims: vec![],
+        }
+    }
+
+    /// Adds a key which tokens can be signed with, returning it back
+    pub fn with_key(mut self, key: JwtKey) -> Self {
+        self.keys.push(key);
+        self
+    }
+
+    /// Fetches keys from the JSON Web Key Set at the URL, replacing any set before and returning it back
+    ///
+    /// The key set is fetched on first use and again once it's older than [DEFAULT_JWKS_REFRESH](Self::DEFAULT_JWKS_REFRESH) or the `max-age` the identity provider gave, or sooner when a token refers to an unknown `kid` so rotated keys are picked up.
+    pub fn with_jwks_url(mut self, url: impl Into<String>) -> Self {
+        self.jwks = Some(Arc::new(Jwks::new(JwksSource::Url(url.into()))));
+        self
+    }
+
+    /// Trusts the OpenID Connect issuer, finding its key set using the discovery document at `/.well-known/openid-configuration`, replacing any key set before and returning it back
+    ///
+    /// The discovery document is fetched alongside the key set the first time it's needed, and kept from then on so only the key set is ever fetched again.
+    pub fn with_discovery(mut self, issuer: impl Into<String>) -> Self {
+        let issuer = issuer.into();
+        let base = issuer.trim_end_matches('/').to_string();
+        self.jwks = Some(Arc::new(Jwks::new(JwksSource::Discovery(base))));
+        self.with_issuer(issuer)
+    }
+
+    /// Sets how long a fetched key set is kept before fetching it again, returning it back
+    pub fn with_jwks_refresh(mut self, refresh: Duration) -> Self {
+        self.jwks_refresh = refresh;
+        self
+    }
+
+    /// Sets how long a key set keeps being used past its refresh while it's fetched again in the background, returning it back
+    ///
+    /// This defaults to [DEFAULT_JWKS_MAX_STALE](Self::DEFAULT_JWKS_MAX_STALE), so tokens can still be checked while the identity provider is briefly unreachable. Use [Duration::ZERO] to always wait for the key set to be fetched again, rejecting tokens if it can't be.
+    pub fn with_jwks_max_stale(mut self, max_stale: Duration) -> Self {
+        self.jwks_max_stale = max_stale;
+        self
+    }
+
+    /// Sets how fetching the key set or discovery document is retried when the identity provider has a transient error, returning it back
+    ///
+    /// This defaults to [RetryPolicy::NONE], relying on keys from before while they're recent enough. Retrying rides out brief errors before there are any keys to fall back on, like just after starting up, instead of rejecting tokens.
+    pub fn with_jwks_retry(mut self, retry: RetryPolicy) -> Self {
+        self.jwks_retry = retry;
+        self
+    }
+
+    /// Only trusts keys fetched from the key set with the identifier, returning it back
+    ///
+    /// Calling this again allows another identifier, like the next key before it's rotated in. Keys given using [with_key](Self::with_key) are always trusted, as they don't come from the identity provider.
+    pub fn with_allowed_kid(mut self, kid: impl Into<String>) -> Self {
+        self.allowlist.kids.push(kid.into());
+        self
+    }
+
+    /// Only trusts keys fetched from the key set with the type, like `RSA` or `EC`, returning it back
+    ///
+    /// Calling this again allows another type. This stops a key set from introducing an `oct` key, whose secret would then be public.
+    pub fn with_allowed_key_type(mut self, kty: impl Into<String>) -> Self {
+        self.allowlist.key_types.push(kty.into());
+        self
+    }
+
+    /// Only trusts keys fetched from the key set with the [thumbprint](JwtKey::thumbprint), returning it back
+    ///
+    /// Calling this again pins another key. Pinning keys means a compromised key set can't introduce keys of its own, but every key rotation needs the new thumbprint to be pinned before the identity provider starts using it.
+    pub fn with_pinned_thumbprint(mut self, thumbprint: impl Into<String>) -> Self {
+        self.allowlist.thumbprints.push(thumbprint.into());
+        self
+    }
+
+    /// Sets how keys are chosen for tokens without a `kid` header, returning it back
+    ///
+    /// With the `metrics` feature, the `KEY_SELECTIONS` counter is recorded for every such token, labelled with the `strategy` and whether it `matched` a key or was `unmatched`, showing if an identity provider still sends them.
+    pub fn with_key_selection(mut self, selection: KeySelection) -> Self {
+        self.key_selection = selection;
+        self
+    }
+
+    /// Adds an issuer which the `iss` claim is allowed to be, returning it back
+    pub fn with_issuer(mut self, issuer: impl Into<String>) -> Self {
+        self.issuers.push(issuer.into());
+        self
+    }
+
+    /// Adds an audience which the `aud` claim is allowed to contain, returning it back
+    ///
+    /// The `aud` claim can be a single audience or an array of them, and the token is accepted if it's meant for any of the audiences added, unless that's changed using [with_audience_policy](Self::with_audience_policy).
+    pub fn with_audience(mut self, audience: impl Into<String>) -> Self {
+        self.audiences.push(Audience::Exact(audience.into()));
+        self
+    }
+
+    /// Adds a resource indicator from [RFC 8707](https://www.rfc-editor.org/rfc/rfc8707) which the `aud` claim is allowed to contain, along with any resource beneath it, returning it back
+    ///
+    /// Resources are beneath the indicator when they continue its path, so `https://api.example.com/orders` allows `https://api.example.com/orders` and `https://api.example.com/orders/42`, but not `https://api.example.com/orders-admin` or `https://api.example.com.evil.com`.
+    pub fn with_audience_prefix(mut self, resource: impl Into<String>) -> Self {
+        self.audiences.push(Audience::Prefix(resource.into()));
+        self
+    }
+
+    /// Sets whether tokens must be meant for any or all of the audiences, returning it back
+    pub fn with_audience_policy(mut self, policy: AudiencePolicy) -> Self {
+        self.audience_policy = policy;
+        self
+    }
+
+    /// Also reads audiences from the claim, for identity providers which don't always use `aud`, returning it back
+    pub fn with_audience_claim(mut self, claim: impl Into<String>) -> Self {
+        self.audience_claims.push(claim.into());
+        self
+    }
+
+    /// Requires the claim to be one of the allowed values, or to contain one if it's a list, returning it back
+    ///
+    /// Calling this again for the same claim adds another allowed value, like a second client identifier.
+    pub fn with_required_claim(
+        mut self,
+        claim: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Self {
+        self.required
+            .entry(claim.into())
+            .or_default()
+            .push(value.into());
+        self
+    }
+
+    /// Requires the `iat` claim and rejects tokens issued longer ago than the maximum age, even if they haven't expired yet, returning it back
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Sets the leeway given for clock differences when checking time claims, returning it back
+    pub fn with_leeway(mut self, leeway: Duration) -> Self {
+        self.leeway = leeway;
+        self
+    }
+
+    /// Adds a claim which roles are read from, returning it back
+    ///
+    /// Dots are used for nested claims like `realm_access.roles`, and `*` matches every nested claim while prefixing the roles found with its name. For example, `resource_access.*.roles` gives roles like `my-api:editor`.
+    pub fn with_roles_claim(mut self, claim: impl Into<String>) -> Self {
+        self.roles_claims.push(claim.into());
+        self
+    }
+
+    /// Verifies the token's signature and claims, giving back its claims
+    ///
+    /// # Errors
+    ///
+    /// See the errors of [JwtValidator] for the rejections this can give.
+    pub async fn verify(&self, token: &str) -> Result<AuthJwt, Rejection> {
+        let jws = Jws::decode(token).ok_or((StatusCode::UNAUTHORIZED, ERR_JWT_MALFORMED))?;
+        let alg = jws
+            .alg()
+            .ok_or((StatusCode::UNAUTHORIZED, ERR_JWT_MALFORMED))?;
+        let kid = jws.header.get("kid").and_then(Value::as_str);
+
+        // Check the signature with the given keys, then the fetched ones
+        let verify = |key: &JwtKey| {
+            key.matches(kid, alg, &self.key_selection)
+                && key.verify(alg, jws.signing_input.as_bytes(), &jws.signature)
+        };
+        let lookup = kid.or(self.key_selection.pinned());
+        let verified = self.keys.iter().any(verify)
+            || match &self.jwks {
+                Some(jwks) => jwks
+                    .keys(
+                        lookup,
+                        self.jwks_refresh,
+                        self.jwks_max_stale,
+                        self.jwks_retry,
+                    )
+                    .await?
+                    .iter()
+                    .any(|key| self.allowlist.allows(key) && verify(key)),
+                None => false,
+            };
+        #[cfg(feature = "metrics")]
+        if kid.is_none() {
+            let outcome = if verified { "matched" } else { "unmatched" };
+            metrics::counter!(
+                Self::KEY_SELECTIONS,
+                "strategy" => self.key_selection.label(),
+                "outcome" => outcome
+            )
+            .increment(1);
+        }
+        if !verified {
+            return Err((StatusCode::UNAUTHORIZED, ERR_JWT_SIGNATURE));
+        }
+
+        // Check the claims
+        let claims = jws.claims;
+        self.check_time(&claims)?;
+        if !self.issuers.is_empty()
+            && !claims
+                .get("iss")
+                .and_then(Value::as_str)
+                .is_some_and(|iss| self.issuers.iter().any(|issuer| issuer == iss))
+        {
+            return Err((StatusCode::UNAUTHORIZED, ERR_JWT_ISSUER));
+        }
+        if !self.audiences.is_empty() {
+            let auds: Vec<_> = self
+                .audience_claims
+                .iter()
+                .flat_map(|claim| strings(claims.get(claim)))
+                .collect();
+            let meant_for = |audience: &Audience| auds.iter().any(|aud| audience.matches(aud));
+            let accepted = match self.audience_policy {
+                AudiencePolicy::Any => self.audiences.iter().any(meant_for),
+                AudiencePolicy::All => self.audiences.iter().all(meant_for),
+            };
+            if !accepted {
+                return Err((StatusCode::UNAUTHORIZED, ERR_JWT_AUDIENCE));
+            }
+        }
+        let allowed = |(claim, values): (&String, &Vec<String>)| {
+            claims.get(claim).is_some_and(|claim| match claim {
+                Value::Array(claim) => claim.iter().any(|claim| matches(claim, values)),
+                claim => matches(claim, values),
+            })
+        };
+        if !self.required.iter().all(allowed) {
+            return Err((StatusCode::UNAUTHORIZED, ERR_JWT_CLAIM));
+        }
+        if claims
+            .get("sub")
+            .and_then(Value::as_str)
+            .is_none_or(str::is_empty)
+        {
+            return Err((StatusCode::UNAUTHORIZED, ERR_JWT_SUBJECT));
+        }
+
+        Ok(AuthJwt { claims })
+    }
+
+    /// Checks the `exp`, `nbf`, and `iat` claims against the current time
+    fn check_time(&self, claims: &Map<String, Value>) -> Result<(), Rejection> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs_f64();
+        let leeway = self.leeway.as_secs_f64();
+        let time = |name| claims.get(name).and_then(Value::as_f64);
+
+        let expired = time("exp").is_none_or(|exp| exp + leeway <= now);
+        let early = [time("nbf"), time("iat")]
+            .into_iter()
+            .flatten()
+            .any(|time| time - leeway > now);
+        let old = self.max_age.is_some_and(|max_age| {
+            time("iat").is_none_or(|iat| iat + max_age.as_secs_f64() + leeway <= now)
+        });
+        if expired || early || old {
+            Err((StatusCode::UNAUTHORIZED, ERR_JWT_EXPIRED))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Gets the identity from the token's claims
+    fn user(&self, jwt: &AuthJwt) -> AuthUser {
+        let id = jwt.sub().unwrap_or_default();
+        let roles = self.roles_claims.iter().flat_map(|claim| {
+            let path: Vec<_> = claim.split('.').collect();
+            nested(&jwt.claims, &path, None)
+        });
+        AuthUser::new(id)
+            .with_roles(roles)
+            .with_scopes(jwt.scopes())
+    }
+}
+
+/// How a [JwtValidator] chooses the keys to check tokens without a `kid` header against, given to [JwtValidator::with_key_selection]
+///
+/// This is enabled via the `jwt` feature alongside a crypto backend
+///
+/// Some identity providers leave out the `kid` header, which is fine with a single key but means every key has to be tried once there are several. Tokens with a `kid` only ever use the key it names, or keys without one.
+///
+/// # Example
+///
+/// Only using the key the identity provider is known to sign with when tokens don't say which:
+///
+/// ```rust
+/// use axum_auth::{JwtValidator, KeySelection};
+///
+/// let validator = JwtValidator::new()
+///     .with_jwks_url("https://auth.example.com/.well-known/jwks.json")
+///     .with_key_selection(KeySelection::Pinned(String::from("2024-signing")));
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub enum KeySelection {
+    /// Tries every key which supports the algorithm of the token, which is the default
+    #[default]
+    TryAll,
+    /// Only tries keys which declared the algorithm of the token in their `alg`
+    ByAlgorithm,
+    /// Only tries the key with this identifier, fetching the key set again if it isn't there yet
+    Pinned(String),
+}
+
+impl KeySelection {
+    /// Checks if the key is chosen for a token signed using the algorithm
+    fn selects(&self, key: &JwtKey, alg: &str) -> bool {
+        match self {
+            Self::TryAll => true,
+            Self::ByAlgorithm => key.alg.as_deref() == Some(alg),
+            Self::Pinned(kid) => key.kid.as_deref() == Some(kid),
+        }
+    }
+
+    /// Gets the identifier of the pinned key, if there is one
+    fn pinned(&self) -> Option<&str> {
+        match self {
+            Self::Pinned(kid) => Some(kid),
+            _ => None,
+        }
+    }
+
+    /// Gets the `strategy` label of the selection
+    #[cfg(feature = "metrics")]
+    fn label(&self) -> &'static str {
+        match self {
+            Self::TryAll => "try_all",
+            Self::ByAlgorithm => "by_algorithm",
+            Self::Pinned(_) => "pinned",
+        }
+    }
+}
+
+/// Keys which a [JwtValidator] trusts from a key set, where empty lists allow anything
+#[derive(Debug, Clone, Default)]
+struct KeyAllowlist {
+    kids: Vec<String>,
+    key_types: Vec<String>,
+    thumbprints: Vec<String>,
+}
+
+impl KeyAllowlist {
+    /// Checks if the key fetched from a key set is allowed
+    fn allows(&self, key: &JwtKey) -> bool {
+        let allowed =
+            |list: &[String], value: &str| list.is_empty() || list.iter().any(|own| own == value);
+        allowed(&self.kids, key.kid.as_deref().unwrap_or_default())
+            && allowed(&self.key_types, key.kty())
+            && (self.thumbprints.is_empty() || allowed(&self.thumbprints, &key.thumbprint()))
+    }
+}
+
+/// How the audiences of a [JwtValidator] are checked against the `aud` claim of tokens, given to [JwtValidator::with_audience_policy]
+///
+/// This is enabled via the `jwt` feature alongside a crypto backend
+///
+/// # Example
+///
+/// Accepting only tokens meant for both the gateway and any resource of the orders service behind it:
+///
+/// ```rust
+/// use axum_auth::{AudiencePolicy, JwtValidator};
+///
+/// let validator = JwtValidator::new()
+///     .with_jwks_url("https://auth.example.com/.well-known/jwks.json")
+///     .with_audience("https://gateway.example.com")
+///     .with_audience_prefix("https://orders.example.com/")
+///     .with_audience_policy(AudiencePolicy::All);
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum AudiencePolicy {
+    /// Tokens must be meant for at least one of the audiences, which is the default
+    #[default]
+    Any,
+    /// Tokens must be meant for every one of the audiences
+    All,
+}
+
+/// Audience which tokens can be meant for
+#[derive(Debug, Clone)]
+enum Audience {
+    /// Audience which must match exactly
+    Exact(String),
+    /// Resource indicator which also matches any resource beneath it
+    Prefix(String),
+}
+
+impl Audience {
+    /// Checks if an audience of a token matches
+    fn matches(&self, aud: &str) -> bool {
+        match self {
+            Self::Exact(audience) => audience == aud,
+            Self::Prefix(resource) => aud.strip_prefix(resource.as_str()).is_some_and(|rest| {
+                rest.is_empty() || resource.ends_with('/') || rest.starts_with('/')
+            }),
+        }
+    }
+}
+
+impl Default for JwtValidator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AuthValidator<AuthBearer> for JwtValidator {
+    async fn validate(&self, credentials: &AuthBearer) -> Result<AuthUser, Rejection> {
+        let jwt = self.verify(&credentials.0).await?;
+        Ok(self.user(&jwt))
+    }
+
+    async fn validate_with_extensions(
+        &self,
+        credentials: &AuthBearer,
+        extensions: &mut Extensions,
+    ) -> Result<AuthUser, Rejection> {
+        let jwt = self.verify(&credentials.0).await?;
+        let user = self.user(&jwt);
+        if let Some(exp) = jwt.exp() {
+            extensions.insert(CredentialExpiry(exp));
+        }
+        extensions.insert(jwt);
+        Ok(user)
+    }
+}
+
+/// Remote JSON Web Key Set, with the keys which were last fetched
+#[derive(Debug)]
+struct Jwks {
+    source: JwksSource,
+    discovered: OnceLock<String>,
+    client: reqwest::Client,
+    cached: RwLock<Option<CachedKeys>>,
+    fetching: tokio::sync::Mutex<()>,
+    revalidating: AtomicBool,
+}
+
+impl Jwks {
+    /// Shortest time between fetches caused by unknown `kid` headers or failed background fetches, so the identity provider isn't flooded
+    const MIN_REFETCH: Duration = Duration::from_secs(30);
+
+    /// Creates a key set which hasn't been fetched yet
+    fn new(source: JwksSource) -> Self {
+        Self {
+            source,
+            discovered: OnceLock::new(),
+            client: reqwest::Client::new(),
+            cached: RwLock::new(None),
+            fetching: tokio::sync::Mutex::new(()),
+            revalidating: AtomicBool::new(false),
+        }
+    }
+
+    /// Gets the keys, fetching them first if they're old or don't contain the `kid`
+    ///
+    /// Keys which are old but still within the staleness allowed are used straight away while they're fetched again in the background, and are kept using if a fetch fails.
+    async fn keys(
+        self: &Arc<Self>,
+        kid: Option<&str>,
+        refresh: Duration,
+        max_stale: Duration,
+        retry: RetryPolicy,
+    ) -> Result<Arc<Vec<JwtKey>>, Rejection> {
+        if let Some(keys) = self.fresh(kid, refresh) {
+            return Ok(keys);
+        }
+
+        // Keep using stale keys which know the `kid` instead of waiting on the identity provider
+        let stale = self.stale(refresh, max_stale);
+        if let Some(keys) = &stale {
+            if knows(keys, kid) {
+                self.revalidate(refresh, max_stale, retry);
+                return Ok(keys.clone());
+            }
+        }
+
+        // Only fetch once at a time, as another request might have just done it
+        let _fetching = self.fetching.lock().await;
+        if let Some(keys) = self.fresh(kid, refresh) {
+            return Ok(keys);
+        }
+        match self.fetch(retry).await {
+            Some((keys, max_age)) => Ok(self.store(keys, max_age)),
+            None => stale.ok_or((StatusCode::SERVICE_UNAVAILABLE, ERR_JWKS_UNAVAILABLE)),
+        }
+    }
+
+    /// Fetches the keys again in the background until it works or they're too stale, unless this is already happening
+    fn revalidate(self: &Arc<Self>, refresh: Duration, max_stale: Duration, retry: RetryPolicy) {
+        if self.revalidating.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        let jwks = self.clone();
+        tokio::spawn(async move {
+            loop {
+                {
+                    let _fetching = jwks.fetching.lock().await;
+                    if jwks.fresh(None, refresh).is_some() {
+                        break;
+                    }
+                    if let Some((keys, max_age)) = jwks.fetch(retry).await {
+                        jwks.store(keys, max_age);
+                        break;
+                    }
+                    if jwks.stale(refresh, max_stale).is_none() {
+                        break;
+                    }
+                }
+                tokio::time::sleep(Self::MIN_REFETCH).await;
+            }
+            jwks.revalidating.store(false, Ordering::Release);
+        });
+    }
+
+    /// Gets the cached keys if they don't need fetching again, which is sooner if the identity provider asked for it
+    fn fresh(&self, kid: Option<&str>, refresh: Duration) -> Option<Arc<Vec<JwtKey>>> {
+        let cached = self.cached.read().unwrap();
+        let cached = cached.as_ref()?;
+        let age = cached.fetched.elapsed();
+        (age < cached.refresh(refresh) && (knows(&cached.keys, kid) || age < Self::MIN_REFETCH))
+            .then(|| cached.keys.clone())
+    }
+
+    /// Gets the cached keys if they're still within the staleness allowed past their refresh
+    fn stale(&self, refresh: Duration, max_stale: Duration) -> Option<Arc<Vec<JwtKey>>> {
+        let cached = self.cached.read().unwrap();
+        let cached = cached.as_ref()?;
+        (cached.fetched.elapsed() < cached.refresh(refresh).saturating_add(max_stale))
+            .then(|| cached.keys.clone())
+    }
+
+    /// Keeps the newly-fetched keys, giving them back
+    fn store(&self, keys: Vec<JwtKey>, max_age: Option<Duration>) -> Arc<Vec<JwtKey>> {
+        let keys = Arc::new(keys);
+        *self.cached.write().unwrap() = Some(CachedKeys {
+            fetched: Instant::now(),
+            max_age,
+            keys: keys.clone(),
+        });
+        keys
+    }
+
+    /// Fetches and parses the key set, skipping keys which aren't supported, alongside how long it can be cached for
+    async fn fetch(&self, retry: RetryPolicy) -> Option<(Vec<JwtKey>, Option<Duration>)> {
+        let url = match &self.source {
+            JwksSource::Url(url) => url,
+            JwksSource::Discovery(issuer) => match self.discovered.get() {
+                Some(url) => url,
+                None => {
+                    let (config, _) = self
+                        .fetch_json(
+                            &format!("{}/.well-known/openid-configuration", issuer),
+                            retry,
+                        )
+                        .await?;
+                    let url = config.get("jwks_uri")?.as_str()?.to_string();
+                    self.discovered.get_or_init(|| url)
+                }
+            },
+        };
+        let (jwks, max_age) = self.fetch_json(url, retry).await?;
+        let keys = jwks
+            .get("keys")?
+            .as_array()?
+            .iter()
+            .filter_map(JwtKey::from_jwk)
+            .collect();
+        Some((keys, max_age))
+    }
+
+    /// Fetches a JSON document, alongside the `max-age` of its `Cache-Control` header, retrying transient errors
+    async fn fetch_json(&self, url: &str, retry: RetryPolicy) -> Option<(Value, Option<Duration>)> {
+        let mut retries = 0;
+        let resp = loop {
+            let transient = match self.client.get(url).send().await {
+                Ok(resp) if resp.status().is_success() => break resp,
+                Ok(resp) => {
+                    resp.status().is_server_error()
+                        || resp.status() == StatusCode::TOO_MANY_REQUESTS
+                }
+                Err(_) => true,
+            };
+            if !transient || retries >= retry.max_retries {
+                return None;
+            }
+            retry.wait(retries).await;
+            retries += 1;
+        };
+        let max_age = resp
+            .headers()
+            .get(CACHE_CONTROL)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| {
+                value
+                    .split(',')
+                    .find_map(|directive| directive.trim().strip_prefix("max-age="))
+            })
+            .and_then(|max_age| max_age.parse().ok())
+            .map(Duration::from_secs);
+        let json = serde_json::from_slice(&resp.bytes().await.ok()?).ok()?;
+        Some((json, max_age))
+    }
+}
+
+/// Keys of a [Jwks] from when they were last fetched
+#[derive(Debug)]
+struct CachedKeys {
+    fetched: Instant,
+    max_age: Option<Duration>,
+    keys: Arc<Vec<JwtKey>>,
+}
+
+impl CachedKeys {
+    /// Gets how long the keys can be used before fetching them again, which is sooner if the identity provider asked for it
+    fn refresh(&self, refresh: Duration) -> Duration {
+        self.max_age.map_or(refresh, |max_age| max_age.min(refresh))
+    }
+}
+
+/// Checks if the keys contain the `kid`, which they always do if there isn't one
+fn knows(keys: &[JwtKey], kid: Option<&str>) -> bool {
+    kid.is_none_or(|kid| keys.iter().any(|key| key.kid.as_deref() == Some(kid)))
+}
+
+/// Where the key set of a [Jwks] is found
+#[derive(Debug)]
+enum JwksSource {
+    /// Fetched from this URL
+    Url(String),
+    /// Fetched from the `jwks_uri` in the OpenID Connect discovery document of this issuer
+    Discovery(String),
+}
+
+/// Claims of a JWT which has been validated by a [JwtValidator]
+///
+/// This is enabled via the `jwt` feature alongside a crypto backend
+///
+/// Once a layer using the [JwtValidator] has accepted the token, this is stored inside of the request extensions next to the [AuthUser] and a [CredentialExpiry] from its `exp` claim, so handlers can read claims beyond the identity, like custom claims set by the identity provider.
+///
+/// # Example
+///
+/// ```no_run
+/// use axum_auth::AuthJwt;
+///
+/// /// Shows which organisation the token belongs to
+/// async fn handler(jwt: AuthJwt) -> String {
+///     format!("Organisation: {:?}", jwt.claims.get("org_id"))
+/// }
+/// ```
+///
+/// Registered claims can be read using accessors like [sub](Self::sub) and [aud](Self::aud), and single custom claims using [claim](Self::claim), so handlers needing one claim don't need a struct for all of them:
+///
+/// ```no_run
+/// use axum_auth::AuthJwt;
+///
+/// /// Shows which plan the user is on, if their token is allowed to see it
+/// async fn handler(jwt: AuthJwt) -> String {
+///     if !jwt.scopes().contains("billing:read") {
+///         return String::from("Not allowed");
+///     }
+///     let plan: Option<String> = jwt.claim("plan");
+///     format!("{} is on {:?}", jwt.sub().unwrap_or_default(), plan)
+/// }
+/// ```
+///
+/// # Errors
+///
+/// If no token was validated beforehand, this extractor responds with `401 UNAUTHORIZED` and the message "No validated JWT was found for this request".
+#[derive(Debug, PartialEq, Clone)]
+pub struct AuthJwt {
+    /// Every claim of the token
+    pub claims: Map<String, Value>,
+}
+
+impl AuthJwt {
+    /// Gets the subject of the token from its `sub` claim, which is the identifier of the user
+    pub fn sub(&self) -> Option<&str> {
+        self.claims.get("sub").and_then(Value::as_str)
+    }
+
+    /// Gets the issuer of the token from its `iss` claim
+    pub fn iss(&self) -> Option<&str> {
+        self.claims.get("iss").and_then(Value::as_str)
+    }
+
+    /// Gets the audiences of the token from its `aud` claim, which can be a single string or an array of them
+    pub fn aud(&self) -> Vec<&str> {
+        match self.claims.get("aud") {
+            Some(Value::String(aud)) => vec![aud],
+            Some(Value::Array(auds)) => auds.iter().filter_map(Value::as_str).collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Gets when the token expires from its `exp` claim
+    pub fn exp(&self) -> Option<SystemTime> {
+        self.time("exp")
+    }
+
+    /// Gets when the token was issued from its `iat` claim
+    pub fn iat(&self) -> Option<SystemTime> {
+        self.time("iat")
+    }
+
+    /// Gets the scopes of the token from its `scope` claim, or its `scp` claim if there isn't one, which can be a space-separated string or an array
+    pub fn scopes(&self) -> Scopes {
+        strings(self.claims.get("scope").or(self.claims.get("scp")))
+            .into_iter()
+            .collect()
+    }
+
+    /// Gets a claim as any type which can be deserialized, giving back [None] if it's missing or has the wrong shape
+    pub fn claim<T: DeserializeOwned>(&self, name: &str) -> Option<T> {
+        T::deserialize(self.claims.get(name)?).ok()
+    }
+
+    /// Gets a claim holding seconds since the Unix epoch as a time
+    fn time(&self, name: &str) -> Option<SystemTime> {
+        let secs = self.claims.get(name)?.as_f64()?;
+        UNIX_EPOCH.checked_add(Duration::try_from_secs_f64(secs).ok()?)
+    }
+}
+
+impl<B> FromRequestParts<B> for AuthJwt
+where
+    B: Send + Sync,
+{
+    type Rejection = Rejection;
+
+    async fn from_request_parts(parts: &mut Parts, _: &B) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<AuthJwt>()
+            .cloned()
+            .ok_or((StatusCode::UNAUTHORIZED, ERR_NO_JWT))
+    }
+}
+
+/// Reads the strings of a nested claim using a dotted path like `realm_access.roles`, where `*` matches every claim and prefixes what's found with its name
+fn nested(claims: &Map<String, Value>, path: &[&str], prefix: Option<&str>) -> Vec<String> {
+    let Some((first, rest)) = path.split_first() else {
+        return vec![];
+    };
+    let found: Vec<_> = match *first {
+        "*" => {
+            // Sort by name, as claims keep the order of the token if `serde_json/preserve_order` is enabled
+            let mut found: Vec<_> = claims
+                .iter()
+                .map(|(name, claim)| (Some(name.as_str()), claim))
+                .collect();
+            found.sort_by_key(|(name, _)| *name);
+            found
+        }
+        name => claims
+            .get(name)
+            .map(|claim| (prefix, claim))
+            .into_iter()
+            .collect(),
+    };
+
+    found
+        .into_iter()
+        .flat_map(
+            |(prefix, claim)| match (rest.is_empty(), claim.as_object()) {
+                (true, _) => strings(Some(claim))
+                    .into_iter()
+                    .map(|value| match prefix {
+                        Some(prefix) => format!("{}:{}", prefix, value),
+                        None => value,
+                    })
+                    .collect(),
+                (false, Some(claims)) => nested(claims, rest, prefix),
+                (false, None) => vec![],
+            },
+        )
+        .collect()
+}
+
+/// Checks if a single claim value is one of the allowed values
+fn matches(claim: &Value, values: &[String]) -> bool {
+    match claim {
+        Value::String(claim) => values.contains(claim),
+        Value::Bool(_) | Value::Number(_) => values.contains(&claim.to_string()),
+        _ => false,
+    }
+}
+
+/// Reads a claim which is either a list of strings or a space-separated string
+fn strings(claim: Option<&Value>) -> Vec<String> {
+    match claim {
+        Some(Value::String(value)) => value.split_whitespace().map(Into::into).collect(),
+        Some(Value::Array(values)) => values
+            .iter()
+            .filter_map(Value::as_str)
+            .map(Into::into)
+            .collect(),
+        _ => vec![],
+    }
+}
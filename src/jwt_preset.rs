@@ -0,0 +1,198 @@
+//! Implementation of ready-made JWT validation for common identity providers
+//!
+//! See [JwtPreset] for the most commonly-used data structure
+
+use crate::{JwtKey, JwtValidator};
+use std::time::Duration;
+
+/// Key set which Microsoft Entra ID signs tokens of every tenant with
+const ENTRA_JWKS: &str = "https://login.microsoftonline.com/common/discovery/v2.0/keys";
+
+/// Key set which Google signs ID tokens with
+const GOOGLE_JWKS: &str = "https://www.googleapis.com/oauth2/v3/certs";
+
+/// Key set which Apple signs identity tokens with
+const APPLE_JWKS: &str = "https://appleid.apple.com/auth/keys";
+
+/// Key set which Firebase Authentication signs ID tokens with
+const FIREBASE_JWKS: &str =
+    "https://www.googleapis.com/service_accounts/v1/jwk/securetoken@system.gserviceaccount.com";
+
+/// Ready-made [JwtValidator] setups for common identity providers, which can be changed further using its methods
+///
+/// Each preset sets the keys, issuer, and audience the identity provider uses, so only the details of your project are needed.
+///
+/// This is enabled via the `jwt` feature alongside a crypto backend
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{routing::get, Router};
+/// use axum_auth::{AuthBearer, AuthUser, JwtPreset, RequireAuthLayer};
+///
+/// let app: Router = Router::new()
+///     .route("/", get(|user: AuthUser| async move { format!("Hello, {}!", user.id) }))
+///     .layer(RequireAuthLayer::<AuthBearer, _>::new(JwtPreset::firebase("my-project")));
+/// ```
+#[derive(Debug)]
+pub enum JwtPreset {}
+
+impl JwtPreset {
+    /// Validates Firebase Authentication ID tokens for the project, using Google's `securetoken` keys
+    ///
+    /// The Firebase `uid` becomes the [AuthUser](crate::AuthUser) identifier and roles are read from a `roles` custom claim, while every custom claim is available using [AuthJwt](crate::AuthJwt).
+    pub fn firebase(project_id: &str) -> JwtValidator {
+        JwtValidator::new()
+            .with_jwks_url(FIREBASE_JWKS)
+            .with_issuer(format!("https://securetoken.google.com/{}", project_id))
+            .with_audience(project_id)
+            .with_roles_claim("roles")
+    }
+
+    /// Validates Auth0 access tokens for the API audience, using the key set of the tenant's domain like `example.us.auth0.com`
+    ///
+    /// Custom domains work the same way, as long as it's the domain tokens are issued from.
+    pub fn auth0(domain: &str, audience: &str) -> JwtValidator {
+        let domain = domain.trim_start_matches("https://").trim_end_matches('/');
+        JwtValidator::new()
+            .with_jwks_url(format!("https://{}/.well-known/jwks.json", domain))
+            .with_issuer(format!("https://{}/", domain))
+            .with_audience(audience)
+    }
+
+    /// Validates Supabase access tokens for the project at the URL like `https://abcd.supabase.co`, using the project's JWT secret
+    ///
+    /// Only signed-in users are accepted, as their tokens have the `authenticated` audience; the anonymous and service keys aren't. The Postgres `role` claim becomes the only role of the [AuthUser](crate::AuthUser). Projects which moved to asymmetric signing keys can also add their key set using [with_jwks_url](JwtValidator::with_jwks_url), which is at `/auth/v1/.well-known/jwks.json`.
+    pub fn supabase(project_url: &str, jwt_secret: impl Into<Vec<u8>>) -> JwtValidator {
+        JwtValidator::new()
+            .with_key(JwtKey::hmac(jwt_secret))
+            .with_issuer(format!("{}/auth/v1", project_url.trim_end_matches('/')))
+            .with_audience("authenticated")
+            .with_roles_claim("role")
+    }
+
+    /// Validates Keycloak access tokens for the realm, finding its keys using OpenID Connect discovery
+    ///
+    /// The base URL is where Keycloak is hosted, like `https://sso.example.com`, which needs `/auth` at the end for versions before 17. Realm roles become roles of the [AuthUser](crate::AuthUser) as-is, and client roles are prefixed with the client like `my-api:editor`, so both can be checked using [RequireRole](crate::RequireRole).
+    ///
+    /// Every client of the realm can get tokens, so add the audience of your API using [with_audience](JwtValidator::with_audience) unless any of them should be accepted.
+    pub fn keycloak(base_url: &str, realm: &str) -> JwtValidator {
+        JwtValidator::new()
+            .with_discovery(format!(
+                "{}/realms/{}",
+                base_url.trim_end_matches('/'),
+                realm
+            ))
+            .with_roles_claim("realm_access.roles")
+            .with_roles_claim("resource_access.*.roles")
+    }
+
+    /// Validates Okta access tokens from the issuer, which is either the org authorization server like `https://example.okta.com` or a custom one like `https://example.okta.com/oauth2/default`
+    ///
+    /// The `scp` claim becomes the scopes of the [AuthUser](crate::AuthUser). Any app of the org can get tokens for the audience, so only accept the clients you expect by requiring their `cid` claim:
+    ///
+    /// ```rust
+    /// use axum_auth::JwtPreset;
+    ///
+    /// let validator = JwtPreset::okta("https://example.okta.com/oauth2/default", "api://default")
+    ///     .with_required_claim("cid", "0oa1b2c3d4e5f6g7h8i9");
+    /// ```
+    pub fn okta(issuer: &str, audience: &str) -> JwtValidator {
+        // Custom authorization servers keep their keys under the issuer, the org one doesn't
+        let issuer = issuer.trim_end_matches('/');
+        let jwks = if issuer.contains("/oauth2/") {
+            format!("{}/v1/keys", issuer)
+        } else {
+            format!("{}/oauth2/v1/keys", issuer)
+        };
+        JwtValidator::new()
+            .with_jwks_url(jwks)
+            .with_issuer(issuer)
+            .with_audience(audience)
+    }
+
+    /// Validates AWS Cognito ID and access tokens of the user pool for the app client
+    ///
+    /// Both kinds of token are told apart using their `token_use` claim: ID tokens name the app client in `aud` while access tokens use `client_id`, so either is checked. Groups from `cognito:groups` become roles of the [AuthUser](crate::AuthUser), and access token scopes become its scopes. To only accept one kind, require its `token_use`:
+    ///
+    /// ```rust
+    /// use axum_auth::JwtPreset;
+    ///
+    /// let validator = JwtPreset::cognito("eu-west-2", "eu-west-2_AbCdEfGhI", "1example23456789")
+    ///     .with_required_claim("token_use", "access");
+    /// ```
+    pub fn cognito(region: &str, pool_id: &str, client_id: &str) -> JwtValidator {
+        let issuer = format!("https://cognito-idp.{}.amazonaws.com/{}", region, pool_id);
+        JwtValidator::new()
+            .with_jwks_url(format!("{}/.well-known/jwks.json", issuer))
+            .with_issuer(issuer)
+            .with_audience(client_id)
+            .with_audience_claim("client_id")
+            .with_roles_claim("cognito:groups")
+    }
+
+    /// Validates Microsoft Entra ID (Azure AD) access tokens for the API audience, only from the allowed tenants
+    ///
+    /// Give a single tenant identifier for line-of-business apps, or every customer tenant for multi-tenant apps signing in through the `common` or `organizations` endpoints, as those tokens can come from any tenant otherwise. Both the v1 (`https://sts.windows.net/{tid}/`) and v2 (`https://login.microsoftonline.com/{tid}/v2.0`) issuers are accepted, and the `tid` claim must be one of the tenants.
+    ///
+    /// The audience is the application ID URI like `api://my-api` for v1 tokens and the client ID for v2 tokens, so add the other using [with_audience](JwtValidator::with_audience) if both versions are used. App roles from `roles` become roles of the [AuthUser](crate::AuthUser) and delegated scopes from `scp` become its scopes.
+    ///
+    /// ```rust
+    /// use axum_auth::JwtPreset;
+    ///
+    /// let validator = JwtPreset::entra(
+    ///     ["72f988bf-86f1-41af-91ab-2d7cd011db47", "f8cdef31-a31e-4b4a-93e4-5f571e91255a"],
+    ///     "api://my-api",
+    /// );
+    /// ```
+    pub fn entra<I, T>(tenants: I, audience: &str) -> JwtValidator
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<str>,
+    {
+        tenants.into_iter().fold(
+            JwtValidator::new()
+                .with_jwks_url(ENTRA_JWKS)
+                .with_audience(audience)
+                .with_roles_claim("roles"),
+            |validator, tenant| {
+                let tenant = tenant.as_ref();
+                validator
+                    .with_issuer(format!("https://sts.windows.net/{}/", tenant))
+                    .with_issuer(format!("https://login.microsoftonline.com/{}/v2.0", tenant))
+                    .with_required_claim("tid", tenant)
+            },
+        )
+    }
+
+    /// Validates Google ID tokens from Sign in with Google for the OAuth client ID, using Google's certificates which are cached for as long as Google allows
+    ///
+    /// Both issuer forms Google uses are accepted. Tokens requested by other clients of the same project, like an Android app, still name your client ID in `aud` but name the client which asked for them in `azp`, which can be required using [with_required_claim](JwtValidator::with_required_claim) as well. Only allow accounts of your Google Workspace domains by requiring their `hd` claim:
+    ///
+    /// ```rust
+    /// use axum_auth::JwtPreset;
+    ///
+    /// let validator = JwtPreset::google("1234567890-abc.apps.googleusercontent.com")
+    ///     .with_required_claim("hd", "example.com");
+    /// ```
+    ///
+    /// Besides layers, tokens which aren't sent as bearer tokens (such as in cookies or the `credential` form field) can be checked using [verify](JwtValidator::verify).
+    pub fn google(client_id: &str) -> JwtValidator {
+        JwtValidator::new()
+            .with_jwks_url(GOOGLE_JWKS)
+            .with_issuer("https://accounts.google.com")
+            .with_issuer("accounts.google.com")
+            .with_audience(client_id)
+    }
+
+    /// Validates Sign in with Apple identity tokens for the client ID, which is the bundle ID of your app or the services ID of your website
+    ///
+    /// Apple only issues identity tokens for a single sign-in, so ones older than 10 minutes are rejected even if they haven't expired. Add the other client ID using [with_audience](JwtValidator::with_audience) when both an app and a website sign in.
+    pub fn apple(client_id: &str) -> JwtValidator {
+        JwtValidator::new()
+            .with_jwks_url(APPLE_JWKS)
+            .with_issuer("https://appleid.apple.com")
+            .with_audience(client_id)
+            .with_max_age(Duration::from_secs(10 * 60))
+    }
+}
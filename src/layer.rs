@@ -0,0 +1,396 @@
+//! Implementation of the tower layers protecting entire routers
+//!
+//! See [RequireAuthLayer] for the most commonly-used data structure
+
+use crate::{
+    challenge::challenge_header, error::rejection_response, AuthValidator, Challenge, CredentialId,
+    PathFilter, Usage, UsageRecorder,
+};
+use axum_core::{
+    extract::FromRequestParts,
+    response::{IntoResponse, Response},
+};
+use http::{
+    header::{CONTENT_LENGTH, WWW_AUTHENTICATE},
+    HeaderMap, HeaderValue, Request, StatusCode,
+};
+use http_body::Body;
+use std::{
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tower_layer::Layer;
+use tower_service::Service;
+
+#[cfg(feature = "metrics")]
+use crate::ExtractionMetrics;
+#[cfg(feature = "auth-basic")]
+use crate::{AuthBasic, BasicUsers};
+#[cfg(feature = "auth-bearer")]
+use crate::{AuthBearer, StaticTokens};
+
+/// Layer requiring every request to carry valid credentials before it reaches the handler
+///
+/// The credentials are extracted using `A` (e.g. [AuthBasic](crate::AuthBasic)) with exactly the same parsing as the extractor, then checked by the [AuthValidator]. Once valid, the resulting [AuthUser](crate::AuthUser) is inserted into the request extensions for guards and handlers to use.
+///
+/// How much each credential is used can be recorded along the way by giving a [UsageRecorder] to [with_usage](Self::with_usage), and only some of the paths can be protected by giving a [PathFilter] to [with_paths](Self::with_paths). With the `metrics` feature, how often extraction succeeds and why it fails can be counted by giving `ExtractionMetrics` to `with_metrics`.
+///
+/// By default, rejections are the same as the extractor's, so a missing header is a `400 BAD REQUEST` without any challenge. To have browsers show their login prompt and clients know how to authenticate, give a [Challenge] with the realm to [with_challenge](Self::with_challenge), like `RequireBasicAuthLayer::new(users).with_challenge(Challenge::basic("Admin area"))`.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{routing::get, Router};
+/// use axum_auth::{AuthUser, BasicUser, BasicUsers, RequireBasicAuthLayer};
+///
+/// let users = BasicUsers::new()
+///     .with_user("alice", BasicUser::new("hunter2").with_roles(["admin"]))
+///     .with_user("bob", BasicUser::new("correct horse battery staple"));
+///
+/// let app: Router = Router::new()
+///     .route("/", get(|user: AuthUser| async move { format!("Hello, {}!", user.id) }))
+///     .layer(RequireBasicAuthLayer::new(users));
+/// ```
+///
+/// # Errors
+///
+/// If the extractor rejects the request, its rejection is used as-is. Otherwise, the rejection given by the [AuthValidator] is used. With a challenge, any of these which are `400 BAD REQUEST` or `401 UNAUTHORIZED` become `401 UNAUTHORIZED` with the `WWW-Authenticate` challenge, keeping their message.
+pub struct RequireAuthLayer<A, V, U = ()> {
+    validator: Arc<V>,
+    usage: Arc<U>,
+    paths: Option<Arc<PathFilter>>,
+    challenges: Vec<Challenge>,
+    #[cfg(feature = "metrics")]
+    metrics: Option<Arc<ExtractionMetrics>>,
+    extractor: PhantomData<fn() -> A>,
+}
+
+/// Layer requiring valid basic auth for every request, see [RequireAuthLayer] for more information
+///
+/// This is enabled via the `auth-basic` feature
+#[cfg(feature = "auth-basic")]
+pub type RequireBasicAuthLayer<V = BasicUsers> = RequireAuthLayer<AuthBasic, V>;
+
+/// Layer requiring a valid bearer token for every request, see [RequireAuthLayer] for more information
+///
+/// This is enabled via the `auth-bearer` feature
+#[cfg(feature = "auth-bearer")]
+pub type RequireBearerAuthLayer<V = StaticTokens> = RequireAuthLayer<AuthBearer, V>;
+
+/// Old name of [RequireBearerAuthLayer], kept so existing code using `RequireBearerLayer::from_tokens` still builds
+///
+/// This is enabled via the `auth-bearer` feature
+#[cfg(feature = "auth-bearer")]
+#[deprecated(note = "use `RequireBearerAuthLayer` instead, which matches `RequireBasicAuthLayer`")]
+pub type RequireBearerLayer<V = StaticTokens> = RequireBearerAuthLayer<V>;
+
+impl<A, V> RequireAuthLayer<A, V> {
+    /// Creates a new layer checking credentials with the given validator
+    pub fn new(validator: V) -> Self {
+        Self {
+            validator: Arc::new(validator),
+            usage: Arc::new(()),
+            paths: None,
+            challenges: vec![],
+            #[cfg(feature = "metrics")]
+            metrics: None,
+            extractor: PhantomData,
+        }
+    }
+}
+
+impl<A, V, U> RequireAuthLayer<A, V, U> {
+    /// Records the usage of every request which was authenticated with the recorder, returning it back
+    pub fn with_usage<T: UsageRecorder>(self, usage: T) -> RequireAuthLayer<A, V, T> {
+        RequireAuthLayer {
+            validator: self.validator,
+            usage: Arc::new(usage),
+            paths: self.paths,
+            challenges: self.challenges,
+            #[cfg(feature = "metrics")]
+            metrics: self.metrics,
+            extractor: PhantomData,
+        }
+    }
+
+    /// Only protects the paths the filter protects, letting requests to other paths through without credentials, returning it back
+    pub fn with_paths(mut self, paths: PathFilter) -> Self {
+        self.paths = Some(Arc::new(paths));
+        self
+    }
+
+    /// Answers missing or invalid credentials with `401 UNAUTHORIZED` and the challenge, after any challenges before, returning it back
+    ///
+    /// Rejections which would've been `400 BAD REQUEST`, like a missing header, become `401 UNAUTHORIZED` too so browsers show their login prompt, while other statuses like `403 FORBIDDEN` are left alone. The realm is set on the challenge, like `Challenge::basic("Admin area")` or `Challenge::bearer().with_param("realm", "API")`.
+    pub fn with_challenge(mut self, challenge: Challenge) -> Self {
+        self.challenges.push(challenge);
+        self
+    }
+
+    /// Counts every extraction using the metrics settings, returning it back
+    ///
+    /// This is enabled via the `metrics` feature
+    #[cfg(feature = "metrics")]
+    pub fn with_metrics(mut self, metrics: ExtractionMetrics) -> Self {
+        self.metrics = Some(Arc::new(metrics));
+        self
+    }
+}
+
+impl<A, V, U> Clone for RequireAuthLayer<A, V, U> {
+    fn clone(&self) -> Self {
+        Self {
+            validator: self.validator.clone(),
+            usage: self.usage.clone(),
+            paths: self.paths.clone(),
+            challenges: self.challenges.clone(),
+            #[cfg(feature = "metrics")]
+            metrics: self.metrics.clone(),
+            extractor: PhantomData,
+        }
+    }
+}
+
+impl<S, A, V, U> Layer<S> for RequireAuthLayer<A, V, U> {
+    type Service = RequireAuth<S, A, V, U>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequireAuth {
+            inner,
+            validator: self.validator.clone(),
+            usage: self.usage.clone(),
+            paths: self.paths.clone(),
+            challenge: challenge_header(&self.challenges),
+            #[cfg(feature = "metrics")]
+            metrics: self.metrics.clone(),
+            extractor: PhantomData,
+        }
+    }
+}
+
+/// Service created by [RequireAuthLayer], see it for more information
+pub struct RequireAuth<S, A, V, U = ()> {
+    inner: S,
+    validator: Arc<V>,
+    usage: Arc<U>,
+    paths: Option<Arc<PathFilter>>,
+    challenge: Option<HeaderValue>,
+    #[cfg(feature = "metrics")]
+    metrics: Option<Arc<ExtractionMetrics>>,
+    extractor: PhantomData<fn() -> A>,
+}
+
+impl<S: Clone, A, V, U> Clone for RequireAuth<S, A, V, U> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            validator: self.validator.clone(),
+            usage: self.usage.clone(),
+            paths: self.paths.clone(),
+            challenge: self.challenge.clone(),
+            #[cfg(feature = "metrics")]
+            metrics: self.metrics.clone(),
+            extractor: PhantomData,
+        }
+    }
+}
+
+impl<S, A, V, U, B> Service<Request<B>> for RequireAuth<S, A, V, U>
+where
+    S: Service<Request<B>, Response = Response> + Clone + Send + 'static,
+    S::Future: Send,
+    A: FromRequestParts<()> + Send + 'static,
+    A::Rejection: IntoResponse + 'static,
+    V: AuthValidator<A>,
+    U: UsageRecorder,
+    B: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<B>) -> Self::Future {
+        // Take the service which was driven to readiness, leaving a fresh clone behind
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let validator = self.validator.clone();
+        let recorder = self.usage.clone();
+        let challenge = self.challenge.clone();
+        #[cfg(feature = "metrics")]
+        let metrics = self.metrics.clone();
+
+        // Let requests to paths which aren't protected straight through
+        if let Some(paths) = &self.paths {
+            if !paths.protects(req.uri().path()) {
+                return Box::pin(async move { inner.call(req).await });
+            }
+        }
+
+        Box::pin(async move {
+            let (mut parts, body) = req.into_parts();
+
+            // Extract the credentials just like the extractor would
+            let credentials = match A::from_request_parts(&mut parts, &()).await {
+                Ok(credentials) => credentials,
+                Err(rejection) => {
+                    let resp = challenged(rejection_response(rejection), challenge);
+                    #[cfg(feature = "metrics")]
+                    if let Some(metrics) = &metrics {
+                        metrics.record(&parts.extensions, Some(&resp));
+                    }
+                    return Ok(resp);
+                }
+            };
+            #[cfg(feature = "metrics")]
+            if let Some(metrics) = &metrics {
+                metrics.record(&parts.extensions, None);
+            }
+
+            // Validate them and keep the identity for later
+            match validator
+                .validate_with_extensions(&credentials, &mut parts.extensions)
+                .await
+            {
+                Ok(user) => {
+                    let mut usage = Usage {
+                        credential: parts
+                            .extensions
+                            .get::<CredentialId>()
+                            .map_or_else(|| user.id.clone(), |id| id.0.clone()),
+                        user: user.clone(),
+                        method: parts.method.clone(),
+                        route: parts.uri.path().to_string(),
+                        status: StatusCode::OK,
+                        request_bytes: content_length(&parts.headers),
+                        response_bytes: None,
+                    };
+                    parts.extensions.insert(user);
+                    let resp = inner.call(Request::from_parts(parts, body)).await?;
+
+                    // Record the usage now the size of the response is known
+                    usage.status = resp.status();
+                    usage.response_bytes = resp
+                        .body()
+                        .size_hint()
+                        .exact()
+                        .or_else(|| content_length(resp.headers()));
+                    recorder.record(usage).await;
+                    Ok(resp)
+                }
+                Err(rejection) => Ok(challenged(rejection_response(rejection), challenge)),
+            }
+        })
+    }
+}
+
+/// Turns the rejection into a `401 UNAUTHORIZED` with the challenge if there is one, as long as it's for missing or invalid credentials
+fn challenged(mut resp: Response, challenge: Option<HeaderValue>) -> Response {
+    if let Some(challenge) = challenge {
+        if matches!(
+            resp.status(),
+            StatusCode::BAD_REQUEST | StatusCode::UNAUTHORIZED
+        ) {
+            *resp.status_mut() = StatusCode::UNAUTHORIZED;
+            resp.headers_mut().insert(WWW_AUTHENTICATE, challenge);
+        }
+    }
+    resp
+}
+
+/// Gets the size of a body from the `Content-Length` header
+fn content_length(headers: &HeaderMap) -> Option<u64> {
+    headers.get(CONTENT_LENGTH)?.to_str().ok()?.parse().ok()
+}
+
+/// Layer adding a `WWW-Authenticate` challenge to every `401 UNAUTHORIZED` response which doesn't have one
+///
+/// Clients are meant to be told how to authenticate whenever they get a `401 UNAUTHORIZED`, but handlers returning [StatusCode::UNAUTHORIZED] by hand usually forget to. Responses which already have the header, like the ones from [Unauthorized](crate::Unauthorized), are left alone.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{routing::get, Router};
+/// use axum_auth::{Challenge, ChallengeLayer};
+/// use http::StatusCode;
+///
+/// let app: Router = Router::new()
+///     .route("/", get(|| async { StatusCode::UNAUTHORIZED }))
+///     .layer(ChallengeLayer::new(Challenge::bearer()));
+/// ```
+#[derive(Debug, Clone)]
+pub struct ChallengeLayer {
+    challenges: Vec<Challenge>,
+}
+
+impl ChallengeLayer {
+    /// Creates a new layer adding the challenge
+    pub fn new(challenge: Challenge) -> Self {
+        Self {
+            challenges: vec![challenge],
+        }
+    }
+
+    /// Adds another challenge after the ones before, returning it back
+    pub fn with_challenge(mut self, challenge: Challenge) -> Self {
+        self.challenges.push(challenge);
+        self
+    }
+}
+
+impl<S> Layer<S> for ChallengeLayer {
+    type Service = AddChallenge<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AddChallenge {
+            inner,
+            challenge: challenge_header(&self.challenges),
+        }
+    }
+}
+
+/// Service created by [ChallengeLayer], see it for more information
+#[derive(Debug, Clone)]
+pub struct AddChallenge<S> {
+    inner: S,
+    challenge: Option<HeaderValue>,
+}
+
+impl<S, B> Service<Request<B>> for AddChallenge<S>
+where
+    S: Service<Request<B>, Response = Response>,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<B>) -> Self::Future {
+        let future = self.inner.call(req);
+        let challenge = self.challenge.clone();
+
+        Box::pin(async move {
+            let mut resp = future.await?;
+
+            // Only add the challenge if the response forgot it
+            if let Some(challenge) = challenge {
+                if resp.status() == StatusCode::UNAUTHORIZED
+                    && !resp.headers().contains_key(WWW_AUTHENTICATE)
+                {
+                    resp.headers_mut().insert(WWW_AUTHENTICATE, challenge);
+                }
+            }
+            Ok(resp)
+        })
+    }
+}
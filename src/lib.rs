@@ -9,27 +9,337 @@
 //! - **Basic auth: [AuthBasic]**
 //! - **Bearer auth: [AuthBearer]**
 //!
+//! Web Push services can check the application server sending a message using `AuthVapid` with the `vapid` feature.
+//!
+//! Digest auth from [RFC 7616](https://www.rfc-editor.org/rfc/rfc7616), where clients prove they know the password without sending it, is available as `AuthDigest` and `DigestAuthLayer` with the `auth-digest` feature.
+//!
+//! Kerberos single sign-on for intranets is available as `AuthNegotiate` with the `auth-negotiate` feature, and the older NTLM handshake as `AuthNtlm` with the `auth-ntlm` feature. Without these, Windows clients logging in automatically get a clear rejection.
+//!
+//! There are also some variations of these for common patterns:
+//!
+//! - Basic auth with an email as the username: [AuthBasicEmail]
+//! - Basic auth keeping the exact bytes of the password for hashing: [AuthBasicRaw]
+//! - Basic auth which must have a colon between the username and password: [AuthBasicStrict]
+//! - Bearer auth with a UUID as the token: `AuthBearerUuid` with the `uuid` feature
+//! - An API key in its own header, like `X-Api-Key`: [AuthApiKey]
+//! - Bearer auth or an `X-Api-Key` header, for APIs migrating between them: [AuthBearerOrApiKey], with keys issued, checked, and revoked through an admin router by `ApiKeys` with the `api-key-store` feature
+//!
 //! If you need to implement custom errors (i.e., status codes and messages), use these:
 //!
 //! - Custom basic auth: [AuthBasicCustom]
-//! - Custom bearer auth: [AuthBearerCustom]
+//! - Custom bearer auth: [AuthBearerCustom], which can also reject malformed tokens early using a [TokenShape], or a `ChecksumToken` with the `checksum-token` feature
+//! - Custom API key auth from any header: [AuthApiKeyCustom]
+//!
+//! Rejections are made with English messages, but their [AuthErrorKind] can be matched on instead, or handed to the handler without rejecting using [AuthResult]. Custom extractors can also hide these messages from clients using [AuthBasicCustom::ERROR_OVERWRITE], [AuthBearerCustom::ERROR_OVERWRITE], or [AuthApiKeyCustom::ERROR_OVERWRITE], logging the hidden reason with the `tracing` feature.
+//!
+//! When a route accepts several schemes, [AuthEither] tries them in order and advertises every [Challenge] if they all fail. Challenges can also be sent by hand using [Unauthorized], or [ProxyAuthenticationRequired] for proxies, and [ChallengeLayer] adds them to any `401 UNAUTHORIZED` which forgot to. Layers can answer missing or invalid credentials with a `401 UNAUTHORIZED` and a challenge for a configurable realm using [RequireAuthLayer::with_challenge], so browsers show their login prompt. Clients and proxies can read them back using [Challenge::parse_all].
+//!
+//! To protect an entire router instead of single handlers, use a layer which checks credentials with an [AuthValidator]:
+//!
+//! - Any extractor: [RequireAuthLayer], which can protect only some paths using a [PathFilter] of glob patterns with the `glob` feature, regular expressions with the `regex` feature, or your own [PathPattern]
+//! - Basic auth: [RequireBasicAuthLayer], made for a single user in one line using [basic_auth_layer!], with users stored in [BasicUsers], changed while running using a [StoreHandle], or in a TOML file which is reloaded when it changes using `FileUsers` with the `file-store` and `file-watch` features
+//! - Bearer tokens: [RequireBearerAuthLayer], made for a few hardcoded machine tokens in one line using `RequireBearerAuthLayer::from_tokens`, which checks them with [StaticTokens] and tells handlers which one was used as a [TokenName], with roles and scopes for each [StaticToken]
+//! - Basic auth for pages people visit, using the browser's login prompt: [BrowserBasicAuthLayer], with [BasicLogout] for logout links
+//! - SCRAM-SHA-256, which never sends the password: `ScramAuthLayer` with the `scram` feature
+//! - Digest auth, which never sends the password either and works with the browser's login prompt: `DigestAuthLayer` with the `auth-digest` feature
+//! - HOBA, where clients sign challenges instead of using passwords: `HobaAuthLayer` with the `hoba` feature
+//!
+//! API platforms billing or monitoring each credential can give a [UsageRecorder] to [RequireAuthLayer::with_usage], which is told the [Usage] of every authenticated request. To stop them making more requests than they're allowed, a [QuotaLayer] rejects credentials once they've used up their [Quota], which `ApiKeys` can set for each key. Validators which know when credentials expire put a [CredentialExpiry] into the request extensions, so anything cached on their behalf never outlives them.
+//!
+//! Instead of sending a password with every request, clients can log in once using `BasicLogin` and get a signed session cookie which `SessionAuthLayer` checks, both with the `session` feature. Sessions can be revoked before they expire using `logout_route` (or `bearer_logout_route`, which also revokes bearer tokens your validator accepts), and `RememberMe` cookies log users back in once their session has expired.
+//!
+//! Validators which call out to a database or identity provider can be wrapped in `CachedValidator` with the `validator-cache` feature, remembering successful validations for a while in memory, in `MokaValidationCache` with the `moka` feature, or in your own `ValidationCache`. To see how often validations fail and how long they take, wrap them in `MeteredValidator` with the `metrics` feature, which can also count why extraction fails for each scheme using `ExtractionMetrics`, and to stop a slow identity provider from stalling every request, wrap them in `TimeoutValidator` with the `validator-timeout` feature. Once one has failed too often in a row, `CircuitBreaker` with the `circuit-breaker` feature rejects straight away for a while instead of waiting on it again.
+//!
+//! Bearer tokens issued as JWTs can be checked using `JwtValidator` with the `jwt` feature, including `JwtPreset` setups for identity providers like Firebase, Auth0, Supabase, and Keycloak, and a `RetryPolicy` for riding out brief errors while fetching their keys. Handlers can read the claims of accepted tokens using `AuthJwt`, either all at once or one at a time, and tokens from several identity providers can each be checked by their own validator using `MultiIssuerValidator`. Tokens bound to a device using claims, or to a `ClientCertificate` for mutual TLS, can be checked against the request by wrapping the validator in `DeviceBound`.
+//!
+//! Once a client has been authenticated, its identity is kept as an [AuthUser] which can be checked using these:
+//!
+//! - Policy hooks: [Authorizer] with the [AuthorizeLayer]
+//! - Role guards: [RequireRole]
+//! - Scope guards: [RequireScope], matching [Scopes] with wildcards like `posts:*`
+//! - Permission guards: [Requires], checking the typed [Permissions] granted to the request
+//!
+//! These are all extractors, so they can also guard an entire router as middleware using `AuthGuard` with the `from-extractor` feature, like `AuthGuard::<RequireRole<Admin>>::layer()`.
+//!
+//! Ready-made authorizers for policy engines are available via features, such as `CedarAuthorizer` with the `cedar` feature and `CasbinAuthorizer` with the `casbin` feature.
+//!
+//! Schemes signing whole requests can use the [ReplayGuard] to stop captured requests from being sent again. With the `content-digest` feature, `DigestVerified` also checks the body against its `Content-Digest` header.
+//!
+//! Links which work without credentials until they expire, like download links sent by email, can be made using `UrlSigner` and checked using `SignedUrl` with the `signed-url` feature.
+//!
+//! Services behind an API gateway like Kong or Tyk can check the signature it adds to each request it forwards using `GatewayVerified`, with presets for common layouts in `GatewaySignature`, both with the `gateway-signature` feature.
+//!
+//! Features which need cryptography (like `auth-digest`, `checksum-token`, `content-digest`, `fingerprint`, `gateway-signature`, `hoba`, `jwt`, `scram`, `session`, `signed-url`, `validator-cache`, and `vapid`) let you choose the backend, so embedded and wasm users aren't forced into the build requirements of `ring`:
+//!
+//! - `crypto-rustcrypto` – Pure-Rust [RustCrypto](https://github.com/RustCrypto) implementations
+//! - `crypto-ring` – Implementations from [ring](https://github.com/briansmith/ring), which is used if both are enabled
+//!
+//! API gateways can send the credentials of a request on to upstream services using [Forward], either as they were sent, encoded again, or exchanged for another token, like one from an [RFC 8693](https://www.rfc-editor.org/rfc/rfc8693) endpoint using `TokenExchange` with the `token-exchange` feature. For calls a service makes on its own behalf, `ClientCredentials` with the `client-credentials` feature gets and refreshes OAuth2 client credentials tokens, sending them as a `BearerToken`.
+//!
+//! Passwords, bearer tokens, and API keys never show up in the `Debug` output of the basic auth, bearer, and API key extractors, which only show their length. With the `fingerprint` feature, they show a short `Fingerprint` of their SHA-256 digest instead, so logs can still be correlated across services.
+//!
+//! During local development, extractors can be wrapped in `DevBypass` with the `dev-bypass` feature to inject fixed credentials from an environment variable, which only works in debug builds.
+//!
+//! Apps can test their own routes without a real identity provider using the test doubles of the `test-util` feature: `MockValidator`, `AlwaysAllow`, and `AlwaysDeny` in place of validators, or `FakeIdentityLayer` to skip authentication altogether.
+//!
+//! Multi-tenant apps can use [AuthTenant] to resolve the tenant of a request from a header, subdomain, or path prefix together with its credentials, and [RequireTenantAuthLayer] to check them with the identity provider of each tenant.
+//!
+//! Behind gateways which move the credentials of the client into another header, like `X-Forwarded-Authorization`, insert a [CredentialHeader] into the request extensions so the extractors read that header instead.
+//!
+//! Routes which only need credentials on some paths, like cache misses, can take them as [Deferred] and only validate them once the handler calls [Deferred::verify].
+//!
+//! For audit logging, any of these can be wrapped in [AuthContext] to also get the client IP, user agent, and request ID.
 //!
 //! That's all there is to it! Check out the [repository](https://github.com/owez/axum-auth) for contributing or some more documentation.
 
-#[cfg(not(any(feature = "auth-basic", feature = "auth-bearer")))]
+#[cfg(not(any(
+    feature = "auth-basic",
+    feature = "auth-bearer",
+    feature = "auth-digest",
+    feature = "dev-bypass",
+    feature = "gateway-signature"
+)))]
 compile_error!(r#"At least one feature must be enabled!"#);
 
+#[cfg(feature = "auth-bearer")]
+mod api_key;
+#[cfg(feature = "api-key-store")]
+mod api_key_store;
 #[cfg(feature = "auth-basic")]
 mod auth_basic;
 #[cfg(feature = "auth-bearer")]
 mod auth_bearer;
+#[cfg(feature = "auth-digest")]
+mod auth_digest;
+#[cfg(feature = "auth-negotiate")]
+mod auth_negotiate;
+#[cfg(feature = "auth-ntlm")]
+mod auth_ntlm;
+mod auth_result;
+mod authorize;
+#[cfg(feature = "jwt")]
+mod binding;
+#[cfg(feature = "auth-basic")]
+mod browser_basic;
+#[cfg(feature = "validator-cache")]
+mod cached_validator;
+#[cfg(feature = "casbin")]
+mod casbin;
+#[cfg(feature = "cedar")]
+mod cedar;
+mod challenge;
+#[cfg(feature = "checksum-token")]
+mod checksum_token;
+#[cfg(feature = "circuit-breaker")]
+mod circuit_breaker;
+#[cfg(feature = "client-credentials")]
+mod client_credentials;
+#[cfg(feature = "content-digest")]
+mod content_digest;
+mod context;
+mod credential_header;
+#[cfg(feature = "__crypto")]
+mod crypto;
+mod deferred;
+#[cfg(feature = "dev-bypass")]
+mod dev_bypass;
+mod error;
+mod expiry;
+#[cfg(feature = "from-extractor")]
+mod extractor_guard;
+#[cfg(feature = "file-store")]
+mod file_store;
+#[cfg(feature = "fingerprint")]
+mod fingerprint;
+mod forward;
+#[cfg(feature = "gateway-signature")]
+mod gateway_signature;
+mod guard;
+#[cfg(feature = "hoba")]
+mod hoba;
+#[cfg(any(feature = "jwt", feature = "vapid"))]
+mod jws;
+#[cfg(feature = "jwt")]
+mod jwt;
+#[cfg(feature = "jwt")]
+mod jwt_preset;
+mod layer;
+#[cfg(feature = "auth-basic")]
+mod macros;
+#[cfg(feature = "metrics")]
+mod metered;
+#[cfg(feature = "jwt")]
+mod multi_issuer;
+mod path_filter;
+mod quota;
+mod replay;
+#[cfg(feature = "jwt")]
+mod retry;
+mod scope;
+#[cfg(feature = "scram")]
+mod scram;
+#[cfg(all(feature = "auth-basic", feature = "session"))]
+mod session;
+#[cfg(feature = "signed-url")]
+mod signed_url;
+#[cfg(feature = "auth-bearer")]
+mod static_tokens;
+#[cfg(feature = "auth-basic")]
+mod store;
+mod tenant;
+#[cfg(feature = "test-util")]
+mod test_util;
+#[cfg(feature = "validator-timeout")]
+mod timeout;
+#[cfg(feature = "token-exchange")]
+mod token_exchange;
+mod usage;
+mod user;
+mod validator;
+#[cfg(feature = "vapid")]
+mod vapid;
 
+#[cfg(feature = "auth-bearer")]
+pub use api_key::{
+    ApiKeyHeader, AuthApiKey, AuthApiKeyCustom, AuthBearerOrApiKey, TokenMechanism, XApiKey,
+};
+#[cfg(feature = "api-key-store")]
+pub use api_key_store::{ApiKeyRecord, ApiKeyStore, ApiKeys, MemoryApiKeyStore};
+#[cfg(feature = "auth-basic")]
+pub use auth_basic::{
+    AuthBasic, AuthBasicCustom, AuthBasicEmail, AuthBasicRaw, AuthBasicStrict,
+    UsernameNormalization,
+};
+#[cfg(feature = "uuid")]
+pub use auth_bearer::AuthBearerUuid;
+#[cfg(feature = "auth-bearer")]
+pub use auth_bearer::{AuthBearer, AuthBearerCustom, TokenCharset, TokenShape};
+#[cfg(feature = "auth-digest")]
+pub use auth_digest::{
+    AuthDigest, DigestAuth, DigestAuthLayer, DigestNonces, DigestUser, DigestUserStore, DigestUsers,
+};
+#[cfg(feature = "auth-negotiate")]
+pub use auth_negotiate::AuthNegotiate;
+#[cfg(feature = "auth-ntlm")]
+pub use auth_ntlm::{AuthNtlm, NtlmStage};
+pub use auth_result::AuthResult;
+pub use authorize::{Authorize, AuthorizeLayer, Authorizer, Decision};
+#[cfg(feature = "jwt")]
+pub use binding::{AuthBound, ClientCertificate, DeviceBound};
+#[cfg(feature = "auth-basic")]
+pub use browser_basic::{BasicLogout, BrowserBasicAuth, BrowserBasicAuthLayer};
+#[cfg(feature = "moka")]
+pub use cached_validator::MokaValidationCache;
+#[cfg(feature = "validator-cache")]
+pub use cached_validator::{
+    CachedValidation, CachedValidator, MemoryValidationCache, ValidationCache,
+};
+#[cfg(feature = "casbin")]
+pub use casbin::CasbinAuthorizer;
+#[cfg(feature = "cedar")]
+pub use cedar::{CedarAuthorizer, CedarContext};
+pub use challenge::{AuthEither, AuthScheme, Challenge, ProxyAuthenticationRequired, Unauthorized};
+#[cfg(feature = "checksum-token")]
+pub use checksum_token::ChecksumToken;
+#[cfg(feature = "circuit-breaker")]
+pub use circuit_breaker::{CircuitBreaker, CircuitState};
+#[cfg(feature = "client-credentials")]
+pub use client_credentials::{BearerToken, ClientCredentials};
+#[cfg(feature = "content-digest")]
+pub use content_digest::DigestVerified;
+pub use context::AuthContext;
+pub use credential_header::CredentialHeader;
+pub use deferred::Deferred;
+#[cfg(feature = "auth-basic")]
+pub use deferred::DeferredBasic;
+#[cfg(feature = "auth-bearer")]
+pub use deferred::DeferredBearer;
+#[cfg(feature = "dev-bypass")]
+pub use dev_bypass::{DevBypass, DEV_BYPASS_VAR};
+pub use error::AuthErrorKind;
+pub use expiry::CredentialExpiry;
+#[cfg(feature = "from-extractor")]
+pub use extractor_guard::AuthGuard;
+#[cfg(feature = "file-store")]
+pub use file_store::FileUsers;
+#[cfg(feature = "fingerprint")]
+pub use fingerprint::Fingerprint;
+pub use forward::{Forward, ForwardCredentials};
+#[cfg(feature = "gateway-signature")]
+pub use gateway_signature::{
+    GatewayAlgorithm, GatewaySignature, GatewayVerified, SignatureEncoding, SignedPart,
+};
+pub use guard::{Permission, Permissions, RequireRole, Requires, Role};
+#[cfg(feature = "hoba")]
+pub use hoba::{HobaAuth, HobaAuthLayer, HobaKey, HobaKeyStore, HobaKeys};
+#[cfg(feature = "jwt")]
+pub use jwt::{AudiencePolicy, AuthJwt, JwtKey, JwtValidator, KeySelection};
+#[cfg(feature = "jwt")]
+pub use jwt_preset::JwtPreset;
+#[cfg(feature = "auth-basic")]
+pub use layer::RequireBasicAuthLayer;
+pub use layer::{AddChallenge, ChallengeLayer, RequireAuth, RequireAuthLayer};
+#[cfg(feature = "auth-bearer")]
+#[allow(deprecated)]
+pub use layer::{RequireBearerAuthLayer, RequireBearerLayer};
+#[doc(hidden)]
 #[cfg(feature = "auth-basic")]
-pub use auth_basic::{AuthBasic, AuthBasicCustom};
+pub use macros::__valid_username;
+#[cfg(feature = "metrics")]
+pub use metered::{ExtractionMetrics, MeteredValidator};
+#[cfg(feature = "jwt")]
+pub use multi_issuer::MultiIssuerValidator;
+pub use path_filter::{PathFilter, PathPattern};
+pub use quota::{EnforceQuota, MemoryQuotaStore, Quota, QuotaLayer, QuotaStore};
+pub use replay::{MemoryNonceStore, NonceStore, ReplayGuard};
+#[cfg(feature = "jwt")]
+pub use retry::RetryPolicy;
+pub use scope::{RequireScope, Scope, Scopes};
+#[cfg(feature = "scram")]
+pub use scram::{
+    MemoryScramStore, ScramAuth, ScramAuthLayer, ScramCredentials, ScramSession, ScramStore,
+};
+#[cfg(all(feature = "session", feature = "auth-bearer"))]
+pub use session::{bearer_logout_route, Revocable};
+#[cfg(feature = "session")]
+pub use session::{
+    logout_route, BasicLogin, MemoryRememberMeStore, MemoryRevocationStore, RememberMe,
+    RememberMeStore, RememberMeToken, RevocationStore, SessionAuth, SessionAuthLayer,
+    SessionIssuer,
+};
+#[cfg(feature = "signed-url")]
+pub use signed_url::{SignedUrl, UrlSigner};
 #[cfg(feature = "auth-bearer")]
-pub use auth_bearer::{AuthBearer, AuthBearerCustom};
+pub use static_tokens::{StaticToken, StaticTokens, TokenName};
+#[cfg(feature = "auth-basic")]
+pub use store::{BasicUser, BasicUsers, StoreHandle};
+pub use tenant::{
+    AuthTenant, RequireTenantAuthLayer, TenantHeader, TenantId, TenantLocation, TenantPathPrefix,
+    TenantResolver, TenantRouted, TenantSource, TenantSubdomain, TenantValidators,
+};
+#[cfg(feature = "test-util")]
+pub use test_util::{AlwaysAllow, AlwaysDeny, FakeIdentity, FakeIdentityLayer, MockValidator};
+#[cfg(feature = "validator-timeout")]
+pub use timeout::TimeoutValidator;
+#[cfg(feature = "token-exchange")]
+pub use token_exchange::TokenExchange;
+pub use usage::{CredentialId, Usage, UsageRecorder};
+pub use user::AuthUser;
+pub use validator::AuthValidator;
+#[cfg(feature = "vapid")]
+pub use vapid::AuthVapid;
 
-use http::{header::AUTHORIZATION, request::Parts, StatusCode};
+use http::StatusCode;
+#[cfg(any(
+    feature = "auth-basic",
+    feature = "auth-digest",
+    feature = "auth-negotiate",
+    feature = "auth-ntlm",
+    feature = "hoba",
+    feature = "scram",
+    feature = "vapid"
+))]
+use {credential_header::credential_header, http::request::Parts};
 
 /// Rejection error used in the [AuthBasicCustom] and [AuthBearerCustom] extractors
 pub type Rejection = (StatusCode, &'static str);
@@ -46,12 +356,335 @@ pub(crate) const ERR_CHARS: &str = "`Authorization` header contains invalid char
 /// The header couldn't be decoded properly for basic auth, might not have had a colon in the header
 pub(crate) const ERR_DECODE: &str = "`Authorization` header could not be decoded";
 
+/// The header was decoded for basic auth but had no colon when one is required in strict mode
+pub(crate) const ERR_NO_COLON: &str =
+    "`Authorization` header must separate the username and password with a colon";
+
+/// The header was for basic auth without a password when one is required in strict mode
+pub(crate) const ERR_NO_PASSWORD: &str = "`Authorization` header must contain a password";
+
+/// The username of basic auth wasn't a valid email address when one was expected
+pub(crate) const ERR_NOT_EMAIL: &str = "`Authorization` username must be an email address";
+
 /// The header was set as bearer authentication when we're expecting basic
 pub(crate) const ERR_WRONG_BASIC: &str = "`Authorization` header must be for basic authentication";
 
+/// The bearer token wasn't a valid UUID when one was expected
+#[cfg(feature = "uuid")]
+pub(crate) const ERR_NOT_UUID: &str = "`Authorization` bearer token must be a UUID";
+
+/// The bearer token didn't have the shape or pattern it was configured to have
+#[cfg(feature = "auth-bearer")]
+pub(crate) const ERR_MALFORMED_TOKEN: &str = "`Authorization` bearer token is malformed";
+
+/// The bearer token didn't start with the required prefix, usually a key for another environment
+#[cfg(feature = "auth-bearer")]
+pub(crate) const ERR_TOKEN_PREFIX: &str =
+    "`Authorization` bearer token has the wrong prefix, check it's meant for this environment";
+
+/// The bearer token checksum doesn't match
+#[cfg(all(feature = "auth-bearer", feature = "checksum-token"))]
+pub(crate) const ERR_TOKEN_CHECKSUM: &str =
+    "`Authorization` bearer token has an invalid checksum, check it was copied correctly";
+
+/// The bearer token had less entropy than the configured floor, probably a placeholder
+#[cfg(feature = "auth-bearer")]
+pub(crate) const ERR_WEAK_TOKEN: &str = "`Authorization` bearer token is too predictable";
+
+/// The header was for a scheme that none of the negotiated schemes accept
+pub(crate) const ERR_UNSUPPORTED_SCHEME: &str = "`Authorization` header uses an unsupported scheme";
+
+/// The header was set as something other than negotiate authentication
+#[cfg(feature = "auth-negotiate")]
+pub(crate) const ERR_WRONG_NEGOTIATE: &str =
+    "`Authorization` header must be for negotiate authentication";
+
+/// The header was set by a Windows client logging in automatically, which plain basic and bearer auth can't handle
+pub(crate) const ERR_WINDOWS_AUTH: &str =
+    "`Authorization` header uses Windows authentication (NTLM or Negotiate) which isn't supported here";
+
+/// The header was set as something other than ntlm authentication
+#[cfg(feature = "auth-ntlm")]
+pub(crate) const ERR_WRONG_NTLM: &str = "`Authorization` header must be for NTLM authentication";
+
+/// The ntlm message was malformed or wasn't one a client sends
+#[cfg(feature = "auth-ntlm")]
+pub(crate) const ERR_NTLM_MESSAGE: &str =
+    "`Authorization` header isn't a valid NTLM negotiate or authenticate message";
+
+/// The header was set as something other than scram authentication
+#[cfg(feature = "scram")]
+pub(crate) const ERR_WRONG_SCRAM: &str =
+    "`Authorization` header must be for SCRAM-SHA-256 authentication";
+
+/// The scram message was malformed or asked for something unsupported
+#[cfg(feature = "scram")]
+pub(crate) const ERR_SCRAM_MESSAGE: &str = "`Authorization` header isn't a valid SCRAM message";
+
+/// The scram session given by the client wasn't found
+#[cfg(feature = "scram")]
+pub(crate) const ERR_SCRAM_SESSION: &str = "SCRAM session has expired or doesn't exist";
+
+/// The header was set as something other than digest authentication
+#[cfg(feature = "auth-digest")]
+pub(crate) const ERR_WRONG_DIGEST: &str =
+    "`Authorization` header must be for digest authentication";
+
+/// The digest response was missing a parameter or used something unsupported
+#[cfg(feature = "auth-digest")]
+pub(crate) const ERR_DIGEST_MALFORMED: &str =
+    "`Authorization` header isn't a valid digest response";
+
+/// The digest response was made for another request target
+#[cfg(feature = "auth-digest")]
+pub(crate) const ERR_DIGEST_URI: &str = "Digest response was made for another request";
+
+/// The digest nonce was too old, had its count reused, or was forged
+#[cfg(feature = "auth-digest")]
+pub(crate) const ERR_DIGEST_NONCE: &str = "Digest nonce has expired or was already used";
+
+/// The header was set as something other than hoba authentication
+#[cfg(feature = "hoba")]
+pub(crate) const ERR_WRONG_HOBA: &str = "`Authorization` header must be for HOBA authentication";
+
+/// The hoba result was missing or malformed
+#[cfg(feature = "hoba")]
+pub(crate) const ERR_HOBA_RESULT: &str = "`Authorization` header isn't a valid HOBA result";
+
+/// The hoba challenge was too old, reused, or forged
+#[cfg(feature = "hoba")]
+pub(crate) const ERR_HOBA_CHALLENGE: &str = "HOBA challenge has expired or was already used";
+
+/// The header was set as something other than vapid authentication
+#[cfg(feature = "vapid")]
+pub(crate) const ERR_WRONG_VAPID: &str = "`Authorization` header must be for VAPID authentication";
+
+/// The vapid token, key, or signature were malformed or didn't match
+#[cfg(feature = "vapid")]
+pub(crate) const ERR_VAPID_INVALID: &str = "VAPID token or key is invalid";
+
+/// The vapid token was for another push service
+#[cfg(feature = "vapid")]
+pub(crate) const ERR_VAPID_AUDIENCE: &str = "VAPID token is meant for another push service";
+
+/// The vapid token expired or lasted for too long
+#[cfg(feature = "vapid")]
+pub(crate) const ERR_VAPID_EXPIRED: &str = "VAPID token has expired or lasts longer than 24 hours";
+
 /// The header was set as basic authentication when we're expecting bearer
 pub(crate) const ERR_WRONG_BEARER: &str = "`Authorization` header must be a bearer token";
 
+/// Neither a bearer token nor an api key header were sent
+#[cfg(feature = "auth-bearer")]
+pub(crate) const ERR_NO_BEARER_OR_KEY: &str = "Neither a bearer token nor an API key was found";
+
+/// The api key header of an [AuthApiKey] wasn't sent
+#[cfg(feature = "auth-bearer")]
+pub(crate) const ERR_API_KEY_MISSING: &str = "API key header is missing";
+
+/// The api key header was empty or had some invalid characters in it
+#[cfg(feature = "auth-bearer")]
+pub(crate) const ERR_API_KEY_CHARS: &str = "API key header contains invalid characters";
+
+/// The body sent to the api key admin router wasn't valid JSON or had no name
+#[cfg(feature = "api-key-store")]
+pub(crate) const ERR_API_KEY_REQUEST: &str = "API key request must be JSON with a name";
+
+/// The api key was made with a ttl which is over
+#[cfg(feature = "api-key-store")]
+pub(crate) const ERR_API_KEY_EXPIRED: &str = "API key has expired";
+
+/// The api key was revoked
+#[cfg(feature = "api-key-store")]
+pub(crate) const ERR_API_KEY_REVOKED: &str = "API key has been revoked";
+
+/// The credentials made more requests than their quota allows
+pub(crate) const ERR_QUOTA_EXCEEDED: &str = "Quota for these credentials has been used up";
+
+/// No validator was inserted into the request before extracting deferred credentials
+pub(crate) const ERR_NO_DEFERRED_VALIDATOR: &str =
+    "No validator was found for the deferred credentials";
+
+/// No [AuthUser] was inserted into the request before it was needed
+pub(crate) const ERR_NO_USER: &str = "No authenticated user was found for this request";
+
+/// The bearer token couldn't be decoded as a jwt
+#[cfg(feature = "jwt")]
+pub(crate) const ERR_JWT_MALFORMED: &str = "Bearer token isn't a valid JWT";
+
+/// None of the keys verified the jwt signature
+#[cfg(feature = "jwt")]
+pub(crate) const ERR_JWT_SIGNATURE: &str = "JWT signature is invalid or was made by an unknown key";
+
+/// The jwt time claims were missing or out of range
+#[cfg(feature = "jwt")]
+pub(crate) const ERR_JWT_EXPIRED: &str = "JWT has expired or isn't valid yet";
+
+/// The jwt issuer wasn't trusted
+#[cfg(feature = "jwt")]
+pub(crate) const ERR_JWT_ISSUER: &str = "JWT was issued by an untrusted issuer";
+
+/// The jwt audience wasn't us
+#[cfg(feature = "jwt")]
+pub(crate) const ERR_JWT_AUDIENCE: &str = "JWT is meant for another audience";
+
+/// A required jwt claim didn't have an allowed value
+#[cfg(feature = "jwt")]
+pub(crate) const ERR_JWT_CLAIM: &str = "JWT claims aren't accepted here";
+
+/// The jwt had no subject to identify the user by
+#[cfg(feature = "jwt")]
+pub(crate) const ERR_JWT_SUBJECT: &str = "JWT has no subject";
+
+/// The token's binding claims didn't match the request it was sent with
+#[cfg(feature = "jwt")]
+pub(crate) const ERR_BINDING_MISMATCH: &str = "Token is bound to another device";
+
+/// The token's `cnf` claim didn't match the client certificate of the request
+#[cfg(feature = "jwt")]
+pub(crate) const ERR_CERT_MISMATCH: &str = "Token is bound to another client certificate";
+
+/// No client certificate was inserted into the request by the server
+#[cfg(feature = "jwt")]
+pub(crate) const ERR_NO_CLIENT_CERT: &str = "No client certificate was presented";
+
+/// The jwt key set couldn't be fetched
+#[cfg(feature = "jwt")]
+pub(crate) const ERR_JWKS_UNAVAILABLE: &str =
+    "Signing keys couldn't be fetched from the identity provider";
+
+/// The validator took longer than its timeout
+#[cfg(feature = "validator-timeout")]
+pub(crate) const ERR_VALIDATOR_TIMEOUT: &str = "Validating the credentials took too long";
+
+/// The circuit breaker is open after too many failures of the validator
+#[cfg(feature = "circuit-breaker")]
+pub(crate) const ERR_CIRCUIT_OPEN: &str = "Credentials can't be checked right now, try again later";
+
+/// No jwt was validated before extracting its claims
+#[cfg(feature = "jwt")]
+pub(crate) const ERR_NO_JWT: &str = "No validated JWT was found for this request";
+
+/// The signed url had no signature or it didn't match
+#[cfg(feature = "signed-url")]
+pub(crate) const ERR_URL_SIGNATURE: &str = "Signed URL is missing its signature or it's invalid";
+
+/// The signed url was signed properly but has expired
+#[cfg(feature = "signed-url")]
+pub(crate) const ERR_URL_EXPIRED: &str = "Signed URL has expired";
+
+/// No url signer was inserted into the request before checking a signed url
+#[cfg(feature = "signed-url")]
+pub(crate) const ERR_NO_URL_SIGNER: &str = "No URL signer was found for this request";
+
+/// The token given back by a token exchange had characters which can't be in a header
+pub(crate) const ERR_EXCHANGED_TOKEN: &str = "Exchanged token can't be sent in a header";
+
+/// The gateway signature header is completely missing
+#[cfg(feature = "gateway-signature")]
+pub(crate) const ERR_GATEWAY_SIGNATURE_MISSING: &str = "Gateway signature header is missing";
+
+/// The gateway signature couldn't be decoded or it didn't match
+#[cfg(feature = "gateway-signature")]
+pub(crate) const ERR_GATEWAY_SIGNATURE: &str = "Gateway signature is invalid";
+
+/// No gateway signature profile was inserted into the request before checking a gateway signature
+#[cfg(feature = "gateway-signature")]
+pub(crate) const ERR_NO_GATEWAY_SIGNATURE: &str =
+    "No gateway signature profile was found for this request";
+
+/// The token exchange endpoint refused to exchange the token
+#[cfg(feature = "token-exchange")]
+pub(crate) const ERR_TOKEN_EXCHANGE_REFUSED: &str =
+    "Token can't be exchanged for the upstream service";
+
+/// The token exchange endpoint couldn't be reached or gave back something unexpected
+#[cfg(feature = "token-exchange")]
+pub(crate) const ERR_TOKEN_EXCHANGE_UNAVAILABLE: &str =
+    "Token exchange endpoint couldn't be reached";
+
+/// The token endpoint refused the client credentials of this service
+#[cfg(feature = "client-credentials")]
+pub(crate) const ERR_CLIENT_CREDENTIALS_REFUSED: &str =
+    "Client credentials were refused by the token endpoint";
+
+/// The token endpoint couldn't be reached or gave back something unexpected
+#[cfg(feature = "client-credentials")]
+pub(crate) const ERR_CLIENT_CREDENTIALS_UNAVAILABLE: &str = "Token endpoint couldn't be reached";
+
+/// The browser visited the logout path
+#[cfg(feature = "auth-basic")]
+pub(crate) const ERR_LOGGED_OUT: &str = "You have been logged out";
+
+/// The [Authorizer] denied the user access to the request
+pub(crate) const ERR_FORBIDDEN: &str = "You are not allowed to access this resource";
+
+/// The [AuthUser] doesn't have the role a [RequireRole] guard needs
+pub(crate) const ERR_MISSING_ROLE: &str =
+    "You don't have the role required to access this resource";
+
+/// The [Scopes] of the [AuthUser] don't allow the one a [RequireScope] guard needs
+pub(crate) const ERR_MISSING_SCOPE: &str =
+    "You don't have the scope required to access this resource";
+
+/// The [Permissions] of the request don't include the one a [Requires] guard needs
+pub(crate) const ERR_MISSING_PERMISSION: &str =
+    "You don't have the permission required to access this resource";
+
+/// The [AuthValidator] didn't accept the credentials
+pub(crate) const ERR_INVALID_CREDENTIALS: &str = "The provided credentials are invalid";
+
+/// The request didn't have a session cookie
+#[cfg(feature = "session")]
+pub(crate) const ERR_NO_SESSION: &str = "Session cookie is missing";
+
+/// The session wasn't signed by the [SessionIssuer]
+#[cfg(feature = "session")]
+pub(crate) const ERR_SESSION_INVALID: &str = "Session cookie is invalid";
+
+/// The session is older than the TTL of the [SessionIssuer]
+#[cfg(feature = "session")]
+pub(crate) const ERR_SESSION_EXPIRED: &str = "Session has expired";
+
+/// The session or bearer token was revoked in a [RevocationStore]
+#[cfg(feature = "session")]
+pub(crate) const ERR_REVOKED: &str = "Session has been revoked";
+
+/// The remember-me cookie was used before, has expired, or was never issued
+#[cfg(feature = "session")]
+pub(crate) const ERR_REMEMBER_ME: &str = "Remember-me cookie is invalid";
+
+/// The tenant couldn't be found where the [TenantSource] looks for it
+pub(crate) const ERR_NO_TENANT: &str = "Tenant is missing from the request";
+
+/// The tenant has characters which aren't allowed in tenants
+pub(crate) const ERR_TENANT_CHARS: &str = "Tenant contains invalid characters";
+
+/// The [TenantResolver] doesn't know the tenant
+pub(crate) const ERR_UNKNOWN_TENANT: &str = "Tenant is unknown";
+
+/// The timestamp of a signed request is too far from now
+pub(crate) const ERR_STALE: &str = "Request timestamp is outside of the allowed window";
+
+/// The nonce of a signed request has been seen before
+pub(crate) const ERR_REPLAYED: &str = "Request has already been used";
+
+/// The `Content-Digest` header is completely missing
+#[cfg(feature = "content-digest")]
+pub(crate) const ERR_DIGEST_MISSING: &str = "`Content-Digest` header is missing";
+
+/// The `Content-Digest` header didn't contain any algorithm we know of
+#[cfg(feature = "content-digest")]
+pub(crate) const ERR_DIGEST_ALGORITHM: &str = "`Content-Digest` header has no supported algorithm";
+
+/// The body was different to what the `Content-Digest` header said
+#[cfg(feature = "content-digest")]
+pub(crate) const ERR_DIGEST_MISMATCH: &str = "Request body doesn't match its `Content-Digest`";
+
+/// The body couldn't be buffered to check its digest
+#[cfg(feature = "content-digest")]
+pub(crate) const ERR_DIGEST_BODY: &str = "Request body could not be read";
+
 // NOTE: Never used as of axum 0.8.0, remove this block in >=0.9.0
 // /// Helper trait for decoding [Parts] to a final extractor; this is the main interface into the decoding system
 // pub(crate) trait DecodeRequestParts: Sized {
@@ -61,12 +694,34 @@ pub(crate) const ERR_WRONG_BEARER: &str = "`Authorization` header must be a bear
 //     fn decode_request_parts(req: &mut Parts, err_code: StatusCode) -> Result<Self, Rejection>;
 // }
 
-/// Gets the auth header from [Parts] of the request or errors with [ERR_CHARS] or [ERR_MISSING] if wrong
+/// Gets the auth header from [Parts] of the request, which is the one given by a [CredentialHeader] if there is one, or errors with [ERR_CHARS] or [ERR_MISSING] if wrong
+#[cfg(any(
+    feature = "auth-basic",
+    feature = "auth-digest",
+    feature = "auth-negotiate",
+    feature = "auth-ntlm",
+    feature = "hoba",
+    feature = "scram",
+    feature = "vapid"
+))]
 pub(crate) fn get_header(parts: &mut Parts, err_code: StatusCode) -> Result<&str, Rejection> {
     parts
         .headers
-        .get(AUTHORIZATION)
+        .get(credential_header(parts))
         .ok_or((err_code, ERR_MISSING))?
         .to_str()
         .map_err(|_| (err_code, ERR_CHARS))
 }
+
+/// Compares two secrets in constant time so the position of the first difference isn't leaked through timing
+#[cfg(any(
+    feature = "auth-basic",
+    feature = "auth-bearer",
+    feature = "auth-digest",
+    feature = "hoba",
+    feature = "scram",
+    feature = "signed-url"
+))]
+pub(crate) fn constant_eq(left: &[u8], right: &[u8]) -> bool {
+    left.len() == right.len() && left.iter().zip(right).fold(0, |acc, (l, r)| acc | (l ^ r)) == 0
+}
@@ -0,0 +1,56 @@
+//! Implementation of the macros setting up layers in a single line
+//!
+//! See [basic_auth_layer] for the most commonly-used macro
+
+/// Makes a [RequireBasicAuthLayer](crate::RequireBasicAuthLayer) letting in a single user, checking the username and password at compile time
+///
+/// This is enabled via the `auth-basic` feature
+///
+/// Meant for protecting a single dashboard or internal tool with one line of setup, where the password is usually baked in using [env!]. Builds fail if either is empty, or if the username contains a `:` which basic auth can't send, instead of the layer quietly letting nobody in. Use [BasicUsers](crate::BasicUsers) directly for more users or roles.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{routing::get, Router};
+/// use axum_auth::basic_auth_layer;
+///
+/// let app: Router = Router::new()
+///     .route("/", get(|| async { "Dashboard" }))
+///     .layer(basic_auth_layer!("admin", "hunter2")); // <-- e.g. env!("ADMIN_PASS")
+/// ```
+///
+/// Empty values don't compile:
+///
+/// ```compile_fail
+/// let layer = axum_auth::basic_auth_layer!("admin", "");
+/// ```
+#[macro_export]
+macro_rules! basic_auth_layer {
+    ($username:expr, $password:expr $(,)?) => {{
+        const _: () = ::core::assert!(
+            $crate::__valid_username($username),
+            "basic_auth_layer! username must not be empty or contain a colon"
+        );
+        const _: () = ::core::assert!(
+            !::core::primitive::str::is_empty($password),
+            "basic_auth_layer! password must not be empty"
+        );
+        $crate::RequireBasicAuthLayer::new(
+            $crate::BasicUsers::new().with_user($username, $crate::BasicUser::new($password)),
+        )
+    }};
+}
+
+/// Checks if the username can be sent using basic auth, which is used by [basic_auth_layer] at compile time
+#[doc(hidden)]
+pub const fn __valid_username(username: &str) -> bool {
+    let bytes = username.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b':' {
+            return false;
+        }
+        i += 1;
+    }
+    !bytes.is_empty()
+}
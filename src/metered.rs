@@ -0,0 +1,197 @@
+//! Implementation of recording metrics for any validator
+//!
+//! See [MeteredValidator] for the most commonly-used data structure
+
+use crate::{AuthErrorKind, AuthUser, AuthValidator, Rejection};
+use axum::extract::MatchedPath;
+use axum_core::response::Response;
+use http::Extensions;
+use std::{borrow::Cow, time::Instant};
+
+/// Wrapper around an [AuthValidator] recording how many validations succeed or fail and how long they take, using the [metrics](https://docs.rs/metrics) facade
+///
+/// This is enabled via the `metrics` feature
+///
+/// Two metrics are recorded for every validation, both labelled with the `validator` name and the `outcome`, which is either `success` or `rejected`:
+///
+/// - `axum_auth_validations_total` – Counter of validations
+/// - `axum_auth_validation_duration_seconds` – Histogram of the time spent in the validator, showing when introspection calls or database lookups become the bottleneck of a request
+///
+/// Nothing is recorded until an exporter like [metrics-exporter-prometheus](https://docs.rs/metrics-exporter-prometheus) is installed.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{routing::get, Router};
+/// use axum_auth::{AuthUser, BasicUser, BasicUsers, MeteredValidator, RequireBasicAuthLayer};
+///
+/// let users = BasicUsers::new().with_user("alice", BasicUser::new("hunter2"));
+/// let metered = MeteredValidator::new(users).with_name("users");
+///
+/// let app: Router = Router::new()
+///     .route("/", get(|user: AuthUser| async move { format!("Hello, {}!", user.id) }))
+///     .layer(RequireBasicAuthLayer::new(metered));
+/// ```
+#[derive(Debug, Clone)]
+pub struct MeteredValidator<V> {
+    inner: V,
+    name: Cow<'static, str>,
+}
+
+impl<V> MeteredValidator<V> {
+    /// Name of the counter of validations
+    pub const VALIDATIONS: &'static str = "axum_auth_validations_total";
+
+    /// Name of the histogram of the time spent validating, in seconds
+    pub const DURATION: &'static str = "axum_auth_validation_duration_seconds";
+
+    /// Records metrics for the given validator, labelled with the name `default`
+    pub fn new(inner: V) -> Self {
+        Self {
+            inner,
+            name: Cow::Borrowed("default"),
+        }
+    }
+
+    /// Sets the name used for the `validator` label, telling apart several validators in one app, returning it back
+    pub fn with_name(mut self, name: impl Into<Cow<'static, str>>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Gets the validator being metered
+    pub fn inner(&self) -> &V {
+        &self.inner
+    }
+}
+
+impl<C, V> AuthValidator<C> for MeteredValidator<V>
+where
+    C: Sync,
+    V: AuthValidator<C>,
+{
+    async fn validate(&self, credentials: &C) -> Result<AuthUser, Rejection> {
+        self.validate_with_extensions(credentials, &mut Extensions::new())
+            .await
+    }
+
+    async fn validate_with_extensions(
+        &self,
+        credentials: &C,
+        extensions: &mut Extensions,
+    ) -> Result<AuthUser, Rejection> {
+        let start = Instant::now();
+        let result = self
+            .inner
+            .validate_with_extensions(credentials, extensions)
+            .await;
+        let labels = [
+            ("validator", self.name.to_string()),
+            ("outcome", outcome(&result).to_string()),
+        ];
+        metrics::counter!(Self::VALIDATIONS, &labels).increment(1);
+        metrics::histogram!(Self::DURATION, &labels).record(start.elapsed());
+        result
+    }
+}
+
+/// Gets the `outcome` label of a validation
+fn outcome(result: &Result<AuthUser, Rejection>) -> &'static str {
+    match result {
+        Ok(_) => "success",
+        Err(_) => "rejected",
+    }
+}
+
+/// Settings for counting how often a [RequireAuthLayer](crate::RequireAuthLayer) extracts credentials and why it fails to, given to [RequireAuthLayer::with_metrics](crate::RequireAuthLayer::with_metrics)
+///
+/// This is enabled via the `metrics` feature
+///
+/// The `axum_auth_extractions_total` counter is recorded for every request reaching the layer, labelled with:
+///
+/// - `scheme` – The name given here, like `bearer`
+/// - `outcome` – Either `success`, or the [AuthErrorKind] of the rejection in snake case, like `missing_header`
+/// - `route` – The route the request matched, like `/users/{id}`, unless turned off using [with_route_labels](Self::with_route_labels)
+///
+/// Labels only ever come from these fixed sets, and never from the credentials or the raw path of the request, so the number of series stays bounded however many tokens or users there are. Routes are the templates of the router rather than the paths requested, but large routers can still turn them off.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{routing::get, Router};
+/// use axum_auth::{AuthUser, BasicUser, BasicUsers, ExtractionMetrics, RequireBasicAuthLayer};
+///
+/// let users = BasicUsers::new().with_user("alice", BasicUser::new("hunter2"));
+/// let metrics = ExtractionMetrics::new("basic").with_route_labels(false);
+///
+/// let app: Router = Router::new()
+///     .route("/", get(|user: AuthUser| async move { format!("Hello, {}!", user.id) }))
+///     .layer(RequireBasicAuthLayer::new(users).with_metrics(metrics));
+/// ```
+#[derive(Debug, Clone)]
+pub struct ExtractionMetrics {
+    scheme: Cow<'static, str>,
+    routes: bool,
+}
+
+impl ExtractionMetrics {
+    /// Name of the counter of extractions
+    pub const EXTRACTIONS: &'static str = "axum_auth_extractions_total";
+
+    /// Creates new settings labelling extractions with the scheme name and the route
+    pub fn new(scheme: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            scheme: scheme.into(),
+            routes: true,
+        }
+    }
+
+    /// Sets whether to label extractions with the route they matched, returning it back
+    pub fn with_route_labels(mut self, routes: bool) -> Self {
+        self.routes = routes;
+        self
+    }
+
+    /// Counts an extraction of the request, which was rejected if there's a rejection response
+    pub(crate) fn record(&self, extensions: &Extensions, rejection: Option<&Response>) {
+        // Rejections which didn't come from this crate have no kind to tell them apart
+        let outcome = rejection.map_or("success", |resp| {
+            resp.extensions()
+                .get::<AuthErrorKind>()
+                .map_or("other", |kind| kind_label(*kind))
+        });
+        let mut labels = vec![
+            ("scheme", self.scheme.to_string()),
+            ("outcome", outcome.to_string()),
+        ];
+        if self.routes {
+            let route = extensions
+                .get::<MatchedPath>()
+                .map_or("unmatched", MatchedPath::as_str);
+            labels.push(("route", route.to_string()));
+        }
+        metrics::counter!(Self::EXTRACTIONS, &labels).increment(1);
+    }
+}
+
+/// Gets the `outcome` label of an extraction which was rejected
+fn kind_label(kind: AuthErrorKind) -> &'static str {
+    match kind {
+        AuthErrorKind::MissingHeader => "missing_header",
+        AuthErrorKind::InvalidChars => "invalid_chars",
+        AuthErrorKind::DecodeFailed => "decode_failed",
+        AuthErrorKind::MissingPassword => "missing_password",
+        AuthErrorKind::MissingColon => "missing_colon",
+        AuthErrorKind::WrongScheme => "wrong_scheme",
+        AuthErrorKind::Malformed => "malformed",
+        AuthErrorKind::ValidationFailed => "validation_failed",
+        AuthErrorKind::Expired => "expired",
+        AuthErrorKind::Replayed => "replayed",
+        AuthErrorKind::LoggedOut => "logged_out",
+        AuthErrorKind::NotAuthenticated => "not_authenticated",
+        AuthErrorKind::Forbidden => "forbidden",
+        AuthErrorKind::QuotaExceeded => "quota_exceeded",
+        AuthErrorKind::Unavailable => "unavailable",
+        AuthErrorKind::Other => "other",
+    }
+}
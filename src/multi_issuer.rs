@@ -0,0 +1,100 @@
+//! Implementation of routing JWTs to a validator for their issuer
+//!
+//! See [MultiIssuerValidator] for the most commonly-used data structure
+
+use crate::{
+    jws::Jws, AuthBearer, AuthUser, AuthValidator, JwtValidator, Rejection, ERR_JWT_ISSUER,
+    ERR_JWT_MALFORMED,
+};
+use http::{Extensions, StatusCode};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Validator accepting JWTs from several identity providers, each checked by its own validator with its own keys and audience
+///
+/// This is enabled via the `jwt` feature alongside a crypto backend
+///
+/// The `iss` claim is read before the token is verified, only to pick the validator of that issuer, which then checks the token like usual. Since the claims it checks are the same ones which were read, a token can't name one issuer to be checked with the keys of another. Tokens from issuers which weren't added are rejected without being checked, which suits B2B products accepting the identity providers of several customers.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{routing::get, Router};
+/// use axum_auth::{AuthBearer, AuthUser, JwtPreset, JwtValidator, MultiIssuerValidator, RequireAuthLayer};
+///
+/// let validator = MultiIssuerValidator::new()
+///     .with_issuer(
+///         "https://acme.okta.com/oauth2/default",
+///         JwtPreset::okta("https://acme.okta.com/oauth2/default", "api://default"),
+///     )
+///     .with_issuer(
+///         "https://login.globex.com/",
+///         JwtValidator::new()
+///             .with_jwks_url("https://login.globex.com/.well-known/jwks.json")
+///             .with_audience("https://api.example.com"),
+///     );
+///
+/// let app: Router = Router::new()
+///     .route("/", get(|user: AuthUser| async move { format!("Hello, {}!", user.id) }))
+///     .layer(RequireAuthLayer::<AuthBearer, _>::new(validator));
+/// ```
+///
+/// # Errors
+///
+/// Tokens which can't be decoded are rejected with `401 UNAUTHORIZED` and the message "Bearer token isn't a valid JWT", and ones without the `iss` claim of an added issuer with "JWT was issued by an untrusted issuer". Otherwise, the rejection of the issuer's validator is used.
+#[derive(Debug, Clone)]
+pub struct MultiIssuerValidator<V = JwtValidator> {
+    validators: HashMap<String, V>,
+}
+
+impl<V> MultiIssuerValidator<V> {
+    /// Creates a new validator which doesn't accept any issuers yet
+    pub fn new() -> Self {
+        Self {
+            validators: HashMap::new(),
+        }
+    }
+
+    /// Checks tokens with the `iss` claim using the validator, replacing any validator before for it and returning it back
+    pub fn with_issuer(mut self, issuer: impl Into<String>, validator: V) -> Self {
+        self.validators.insert(issuer.into(), validator);
+        self
+    }
+
+    /// Gets the validator of the issuer, if it was added
+    pub fn issuer(&self, issuer: &str) -> Option<&V> {
+        self.validators.get(issuer)
+    }
+
+    /// Picks the validator for the token using its unverified `iss` claim
+    fn route(&self, token: &str) -> Result<&V, Rejection> {
+        let jws = Jws::decode(token).ok_or((StatusCode::UNAUTHORIZED, ERR_JWT_MALFORMED))?;
+        jws.claims
+            .get("iss")
+            .and_then(Value::as_str)
+            .and_then(|iss| self.validators.get(iss))
+            .ok_or((StatusCode::UNAUTHORIZED, ERR_JWT_ISSUER))
+    }
+}
+
+impl<V> Default for MultiIssuerValidator<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V: AuthValidator<AuthBearer>> AuthValidator<AuthBearer> for MultiIssuerValidator<V> {
+    async fn validate(&self, credentials: &AuthBearer) -> Result<AuthUser, Rejection> {
+        self.route(&credentials.0)?.validate(credentials).await
+    }
+
+    async fn validate_with_extensions(
+        &self,
+        credentials: &AuthBearer,
+        extensions: &mut Extensions,
+    ) -> Result<AuthUser, Rejection> {
+        self.route(&credentials.0)?
+            .validate_with_extensions(credentials, extensions)
+            .await
+    }
+}
@@ -0,0 +1,115 @@
+//! Implementation of choosing which paths a layer protects
+//!
+//! See [PathFilter] for the most commonly-used data structure
+
+use std::{fmt, sync::Arc};
+
+/// Pattern matched against the path of a request by a [PathFilter]
+///
+/// This is implemented for glob patterns from [globset](https://docs.rs/globset) with the `glob` feature, and for regular expressions from [regex](https://docs.rs/regex) with the `regex` feature. Regular expressions match anywhere in the path unless they're anchored using `^` and `$`.
+pub trait PathPattern: Send + Sync + 'static {
+    /// Checks if the path matches the pattern; you need to implement this
+    fn matches(&self, path: &str) -> bool;
+}
+
+#[cfg(feature = "glob")]
+impl PathPattern for globset::GlobMatcher {
+    fn matches(&self, path: &str) -> bool {
+        self.is_match(path)
+    }
+}
+
+#[cfg(feature = "glob")]
+impl PathPattern for globset::GlobSet {
+    fn matches(&self, path: &str) -> bool {
+        self.is_match(path)
+    }
+}
+
+#[cfg(feature = "regex")]
+impl PathPattern for regex::Regex {
+    fn matches(&self, path: &str) -> bool {
+        self.is_match(path)
+    }
+}
+
+#[cfg(feature = "regex")]
+impl PathPattern for regex::RegexSet {
+    fn matches(&self, path: &str) -> bool {
+        self.is_match(path)
+    }
+}
+
+/// Set of paths protected by a layer, given to [RequireAuthLayer::with_paths](crate::RequireAuthLayer::with_paths)
+///
+/// Paths are protected if they match any included pattern, or every path if nothing was included, and don't match any excluded pattern. Requests to paths which aren't protected skip authentication altogether, so handlers on them won't find an [AuthUser](crate::AuthUser).
+///
+/// The path is the one seen by the layer, which doesn't have the prefix of the router it's nested in.
+///
+/// # Example
+///
+/// Protecting `/api` except for `/api/public` using a pattern of your own, which could instead be a glob like `/api/public/**` with the `glob` feature:
+///
+/// ```rust
+/// use axum::{routing::get, Router};
+/// use axum_auth::{BasicUser, BasicUsers, PathFilter, PathPattern, RequireBasicAuthLayer};
+///
+/// /// Matches paths starting with the prefix
+/// struct Prefix(&'static str);
+///
+/// impl PathPattern for Prefix {
+///     fn matches(&self, path: &str) -> bool {
+///         path.starts_with(self.0)
+///     }
+/// }
+///
+/// let paths = PathFilter::new()
+///     .with_include(Prefix("/api/"))
+///     .with_exclude(Prefix("/api/public/"));
+/// let users = BasicUsers::new().with_user("alice", BasicUser::new("hunter2"));
+///
+/// let app: Router = Router::new()
+///     .route("/api/private", get(|| async { "Private" }))
+///     .route("/api/public/status", get(|| async { "Public" }))
+///     .layer(RequireBasicAuthLayer::new(users).with_paths(paths));
+/// ```
+#[derive(Clone, Default)]
+pub struct PathFilter {
+    include: Vec<Arc<dyn PathPattern>>,
+    exclude: Vec<Arc<dyn PathPattern>>,
+}
+
+impl PathFilter {
+    /// Creates a new filter protecting every path
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Protects paths matching the pattern, so only paths matching an included pattern are protected, returning it back
+    pub fn with_include(mut self, pattern: impl PathPattern) -> Self {
+        self.include.push(Arc::new(pattern));
+        self
+    }
+
+    /// Stops protecting paths matching the pattern, even if they're included, returning it back
+    pub fn with_exclude(mut self, pattern: impl PathPattern) -> Self {
+        self.exclude.push(Arc::new(pattern));
+        self
+    }
+
+    /// Checks if the path is protected
+    pub fn protects(&self, path: &str) -> bool {
+        let included =
+            self.include.is_empty() || self.include.iter().any(|pattern| pattern.matches(path));
+        included && !self.exclude.iter().any(|pattern| pattern.matches(path))
+    }
+}
+
+impl fmt::Debug for PathFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PathFilter")
+            .field("include", &self.include.len())
+            .field("exclude", &self.exclude.len())
+            .finish()
+    }
+}
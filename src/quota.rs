@@ -0,0 +1,222 @@
+//! Implementation of limiting how many requests each credential can make
+//!
+//! See [QuotaLayer] for the most commonly-used data structure
+
+use crate::{error::rejection_response, AuthUser, CredentialId, ERR_QUOTA_EXCEEDED};
+use axum_core::response::Response;
+use http::{header::RETRY_AFTER, HeaderValue, Request, StatusCode};
+use std::{
+    collections::HashMap,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{Duration, SystemTime},
+};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// Allowance of requests a credential can make in each window of time
+///
+/// Validators can put this into the request extensions to give each credential its own allowance, like `ApiKeys` with the `api-key-store` feature does for keys minted with one. Otherwise, the default of the [QuotaLayer] is used.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct Quota {
+    /// Number of requests allowed in each window
+    pub requests: u64,
+    /// Length of each window
+    pub window: Duration,
+}
+
+impl Quota {
+    /// Creates a new quota allowing the number of requests in each window
+    pub fn new(requests: u64, window: Duration) -> Self {
+        Self { requests, window }
+    }
+
+    /// Creates a new quota allowing the number of requests each minute
+    pub fn per_minute(requests: u64) -> Self {
+        Self::new(requests, Duration::from_secs(60))
+    }
+
+    /// Creates a new quota allowing the number of requests each hour
+    pub fn per_hour(requests: u64) -> Self {
+        Self::new(requests, Duration::from_secs(60 * 60))
+    }
+}
+
+/// Store counting the requests made by each credential for the [QuotaLayer]
+///
+/// Windows are fixed, starting with the first request made once the last one has ended. The in-memory [MemoryQuotaStore] works for a single server, implement this yourself to share counts between many (e.g. using Redis `INCR` and `EXPIRE`).
+pub trait QuotaStore: Send + Sync + 'static {
+    /// Counts a request made by the credential, giving back how many have been made in its current window and when that window ends; you need to implement this
+    fn hit(
+        &self,
+        credential: &str,
+        window: Duration,
+    ) -> impl Future<Output = (u64, SystemTime)> + Send;
+}
+
+/// In-memory [QuotaStore] which forgets windows once they end
+#[derive(Debug, Default)]
+pub struct MemoryQuotaStore {
+    windows: Mutex<HashMap<String, (u64, SystemTime)>>,
+}
+
+impl MemoryQuotaStore {
+    /// Creates a new empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl QuotaStore for MemoryQuotaStore {
+    async fn hit(&self, credential: &str, window: Duration) -> (u64, SystemTime) {
+        let now = SystemTime::now();
+        let mut windows = self.windows.lock().unwrap();
+
+        // Forget ended windows so the store doesn't grow forever
+        windows.retain(|_, (_, ends)| *ends > now);
+
+        // Count the request in the current window, starting one if there isn't any
+        let (count, ends) = windows
+            .entry(credential.to_string())
+            .or_insert((0, now + window));
+        *count += 1;
+        (*count, *ends)
+    }
+}
+
+/// Layer rejecting requests once their credential has made more than its [Quota] allows
+///
+/// This goes inside of a layer which authenticates requests, like a [RequireAuthLayer](crate::RequireAuthLayer), so the [AuthUser] is already in the request extensions. Requests are counted by their [CredentialId] if the validator gave one, or by the identifier of the user otherwise, which are the same as the credentials a [UsageRecorder](crate::UsageRecorder) is told about. The [Quota] in the request extensions is used if the validator gave one, or the default set using [with_default](Self::with_default) otherwise. Requests without either, or without an [AuthUser], aren't limited.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{routing::get, Router};
+/// use axum_auth::{
+///     AuthUser, BasicUser, BasicUsers, MemoryQuotaStore, Quota, QuotaLayer, RequireBasicAuthLayer,
+/// };
+///
+/// let users = BasicUsers::new().with_user("alice", BasicUser::new("hunter2"));
+///
+/// // Layers added last run first, so the quota is checked once alice has logged in
+/// let app: Router = Router::new()
+///     .route("/", get(|user: AuthUser| async move { format!("Hello, {}!", user.id) }))
+///     .layer(QuotaLayer::new(MemoryQuotaStore::new()).with_default(Quota::per_minute(60)))
+///     .layer(RequireBasicAuthLayer::new(users));
+/// ```
+///
+/// # Errors
+///
+/// Credentials which have used up their quota are rejected with `429 TOO MANY REQUESTS` and the message "Quota for these credentials has been used up", along with a `Retry-After` header giving the number of seconds until their window ends.
+#[derive(Debug)]
+pub struct QuotaLayer<S = MemoryQuotaStore> {
+    store: Arc<S>,
+    default: Option<Quota>,
+}
+
+impl<S> QuotaLayer<S> {
+    /// Creates a new layer counting requests in the given store, which only limits credentials given a [Quota] by their validator
+    pub fn new(store: S) -> Self {
+        Self {
+            store: Arc::new(store),
+            default: None,
+        }
+    }
+
+    /// Sets the quota of credentials which weren't given one by their validator, returning it back
+    pub fn with_default(mut self, quota: Quota) -> Self {
+        self.default = Some(quota);
+        self
+    }
+}
+
+impl<S> Clone for QuotaLayer<S> {
+    fn clone(&self) -> Self {
+        Self {
+            store: self.store.clone(),
+            default: self.default,
+        }
+    }
+}
+
+impl<I, S> Layer<I> for QuotaLayer<S> {
+    type Service = EnforceQuota<I, S>;
+
+    fn layer(&self, inner: I) -> Self::Service {
+        EnforceQuota {
+            inner,
+            store: self.store.clone(),
+            default: self.default,
+        }
+    }
+}
+
+/// Service created by [QuotaLayer], see it for more information
+#[derive(Debug)]
+pub struct EnforceQuota<I, S = MemoryQuotaStore> {
+    inner: I,
+    store: Arc<S>,
+    default: Option<Quota>,
+}
+
+impl<I: Clone, S> Clone for EnforceQuota<I, S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            store: self.store.clone(),
+            default: self.default,
+        }
+    }
+}
+
+impl<I, S, B> Service<Request<B>> for EnforceQuota<I, S>
+where
+    I: Service<Request<B>, Response = Response> + Clone + Send + 'static,
+    I::Future: Send,
+    S: QuotaStore,
+    B: Send + 'static,
+{
+    type Response = Response;
+    type Error = I::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, I::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<B>) -> Self::Future {
+        // Take the service which was driven to readiness, leaving a fresh clone behind
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let store = self.store.clone();
+
+        // Find who to count the request against and how much they're allowed
+        let extensions = req.extensions();
+        let quota = extensions.get::<Quota>().copied().or(self.default);
+        let credential = match extensions.get::<CredentialId>() {
+            Some(id) => Some(id.0.clone()),
+            None => extensions.get::<AuthUser>().map(|user| user.id.clone()),
+        };
+
+        Box::pin(async move {
+            let (Some(quota), Some(credential)) = (quota, credential) else {
+                return inner.call(req).await;
+            };
+
+            let (count, ends) = store.hit(&credential, quota.window).await;
+            if count <= quota.requests {
+                return inner.call(req).await;
+            }
+
+            // Round up so clients retrying on time are in the next window
+            let wait = ends.duration_since(SystemTime::now()).unwrap_or_default();
+            let secs = wait.as_secs() + u64::from(wait.subsec_nanos() > 0);
+            let mut resp = rejection_response((StatusCode::TOO_MANY_REQUESTS, ERR_QUOTA_EXCEEDED));
+            resp.headers_mut()
+                .insert(RETRY_AFTER, HeaderValue::from(secs));
+            Ok(resp)
+        })
+    }
+}
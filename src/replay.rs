@@ -0,0 +1,163 @@
+//! Implementation of replay protection for signed-request schemes
+//!
+//! See [ReplayGuard] for the most commonly-used data structure
+
+#[cfg(any(feature = "auth-digest", feature = "hoba"))]
+use crate::{constant_eq, crypto};
+use crate::{Rejection, ERR_REPLAYED, ERR_STALE};
+#[cfg(any(feature = "auth-digest", feature = "hoba"))]
+use base64::{engine::general_purpose, Engine};
+use http::StatusCode;
+#[cfg(any(feature = "auth-digest", feature = "hoba"))]
+use std::time::UNIX_EPOCH;
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::Mutex,
+    time::{Duration, SystemTime},
+};
+
+/// Store remembering which nonces have already been used, shared between every scheme which needs one
+///
+/// The in-memory [MemoryNonceStore] works for a single server, implement this yourself to share nonces between many (e.g. using Redis `SET NX EX`).
+pub trait NonceStore: Send + Sync + 'static {
+    /// Remembers the nonce until it expires, giving `true` if it was unused or `false` if it's been seen before; you need to implement this
+    fn insert(&self, nonce: &str, expires: SystemTime) -> impl Future<Output = bool> + Send;
+}
+
+/// In-memory [NonceStore] which forgets nonces once they expire
+#[derive(Debug, Default)]
+pub struct MemoryNonceStore {
+    nonces: Mutex<HashMap<String, SystemTime>>,
+}
+
+impl MemoryNonceStore {
+    /// Creates a new empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl NonceStore for MemoryNonceStore {
+    async fn insert(&self, nonce: &str, expires: SystemTime) -> bool {
+        let now = SystemTime::now();
+        let mut nonces = self.nonces.lock().unwrap();
+
+        // Forget expired nonces so the store doesn't grow forever
+        nonces.retain(|_, expires| *expires > now);
+
+        // Remember the nonce if it's new
+        if nonces.contains_key(nonce) {
+            false
+        } else {
+            nonces.insert(nonce.to_string(), expires);
+            true
+        }
+    }
+}
+
+/// Replay protection for signed requests, checking a sliding timestamp window and remembering nonces
+///
+/// Signed-request schemes should call [ReplayGuard::check] once the signature has been verified, so captured requests can't be sent again. The window defaults to ±5 minutes, allowing for some clock skew between clients and the server.
+///
+/// # Example
+///
+/// ```rust
+/// use axum_auth::{MemoryNonceStore, ReplayGuard};
+/// use std::time::{Duration, SystemTime};
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let guard = ReplayGuard::new(MemoryNonceStore::new());
+///
+/// // First use is fine but the same nonce can't be used again
+/// assert!(guard.check("nonce-1", SystemTime::now()).await.is_ok());
+/// assert!(guard.check("nonce-1", SystemTime::now()).await.is_err());
+///
+/// // Requests from too long ago are rejected outright
+/// let old = SystemTime::now() - Duration::from_secs(10 * 60);
+/// assert!(guard.check("nonce-2", old).await.is_err());
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// There are a few errors which this guard can make, all of which are `401 UNAUTHORIZED`:
+///
+/// - Request timestamp is outside of the allowed window – The request is too old or too far in the future
+/// - Request has already been used – The nonce was seen before, so this is probably a replay
+#[derive(Debug)]
+pub struct ReplayGuard<N> {
+    store: N,
+    window: Duration,
+}
+
+impl<N: NonceStore> ReplayGuard<N> {
+    /// Default amount of clock skew allowed either way, which is 5 minutes
+    pub const DEFAULT_WINDOW: Duration = Duration::from_secs(5 * 60);
+
+    /// Creates a new guard using the given nonce store and the default window
+    pub fn new(store: N) -> Self {
+        Self {
+            store,
+            window: Self::DEFAULT_WINDOW,
+        }
+    }
+
+    /// Sets how far the timestamp of a request may be from now either way, returning it back
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Checks that the timestamp is within the window and that the nonce hasn't been used yet
+    pub async fn check(&self, nonce: &str, timestamp: SystemTime) -> Result<(), Rejection> {
+        // Check the timestamp either way of now
+        let now = SystemTime::now();
+        let skew = now
+            .duration_since(timestamp)
+            .or_else(|_| timestamp.duration_since(now))
+            .unwrap_or_default();
+        if skew > self.window {
+            return Err((StatusCode::UNAUTHORIZED, ERR_STALE));
+        }
+
+        // Remember the nonce until the timestamp would be rejected anyway
+        if self.store.insert(nonce, timestamp + self.window).await {
+            Ok(())
+        } else {
+            Err((StatusCode::UNAUTHORIZED, ERR_REPLAYED))
+        }
+    }
+}
+
+/// Makes a fresh nonce signed with the secret, carrying when it was made so it doesn't have to be remembered
+///
+/// Nonces are the issue time, 16 random bytes, and the first 16 bytes of an HMAC-SHA256 over both, encoded as URL-safe base64.
+#[cfg(any(feature = "auth-digest", feature = "hoba"))]
+pub(crate) fn signed_nonce(secret: &[u8]) -> String {
+    let issued = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let mut value = issued.to_be_bytes().to_vec();
+    value.extend(crypto::random(16));
+    value.extend(&crypto::hmac_sha256(secret, &value)[..16]);
+    general_purpose::URL_SAFE_NO_PAD.encode(value)
+}
+
+/// Gets when a nonce made by [signed_nonce] was issued, giving back [None] if it wasn't signed with the secret
+#[cfg(any(feature = "auth-digest", feature = "hoba"))]
+pub(crate) fn signed_nonce_issued(secret: &[u8], nonce: &str) -> Option<SystemTime> {
+    let value = general_purpose::URL_SAFE_NO_PAD.decode(nonce).ok()?;
+    if value.len() != 40
+        || !constant_eq(
+            &crypto::hmac_sha256(secret, &value[..24])[..16],
+            &value[24..],
+        )
+    {
+        return None;
+    }
+    let issued = u64::from_be_bytes(value[..8].try_into().unwrap());
+    UNIX_EPOCH.checked_add(Duration::from_secs(issued))
+}
@@ -0,0 +1,82 @@
+//! Implementation of retrying the network calls of remote validators
+//!
+//! See [RetryPolicy] for the most commonly-used data structure
+
+use crate::crypto;
+use std::time::Duration;
+
+/// Bounded retries with exponential backoff for the network calls made by remote validators, like fetching the key set of a [JwtValidator](crate::JwtValidator)
+///
+/// This is enabled via the `jwt` feature alongside a crypto backend
+///
+/// Only transient errors are retried, which are connection failures, `429 TOO MANY REQUESTS`, and `5XX` server errors. The delay before each retry doubles from the base delay up to the maximum delay, and with jitter each delay is randomly cut by up to half, so many instances of an app don't retry in lockstep.
+///
+/// # Example
+///
+/// ```rust
+/// use axum_auth::RetryPolicy;
+/// use std::time::Duration;
+///
+/// let retry = RetryPolicy {
+///     max_retries: 4,
+///     base_delay: Duration::from_millis(50),
+///     ..RetryPolicy::STANDARD
+/// };
+///
+/// assert_eq!(retry.delay(0), Duration::from_millis(50));
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Number of retries after the first attempt fails
+    pub max_retries: u32,
+    /// Delay before the first retry, which doubles for each one after it
+    pub base_delay: Duration,
+    /// Longest delay before any retry
+    pub max_delay: Duration,
+    /// Randomly cuts each delay by up to half
+    pub jitter: bool,
+}
+
+impl RetryPolicy {
+    /// Never retries, giving up after the first attempt
+    pub const NONE: Self = Self {
+        max_retries: 0,
+        base_delay: Duration::ZERO,
+        max_delay: Duration::ZERO,
+        jitter: false,
+    };
+
+    /// Retries 3 times, waiting about 100ms, 200ms, and then 400ms, with jitter
+    pub const STANDARD: Self = Self {
+        max_retries: 3,
+        base_delay: Duration::from_millis(100),
+        max_delay: Duration::from_secs(2),
+        jitter: true,
+    };
+
+    /// Gets the delay before the retry, counting from zero, without any jitter
+    pub fn delay(&self, retry: u32) -> Duration {
+        self.base_delay
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max_delay)
+    }
+
+    /// Waits before the retry, counting from zero, with jitter if it's enabled
+    pub(crate) async fn wait(&self, retry: u32) {
+        let delay = self.delay(retry);
+        let delay = if self.jitter {
+            let random = crypto::random(4);
+            let fraction = u32::from_le_bytes([random[0], random[1], random[2], random[3]]);
+            delay.mul_f64(0.5 + f64::from(fraction) / f64::from(u32::MAX) / 2.0)
+        } else {
+            delay
+        };
+        tokio::time::sleep(delay).await;
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::NONE
+    }
+}
@@ -0,0 +1,253 @@
+//! Implementation of OAuth scope parsing, matching, and the scope guard
+//!
+//! See [Scopes] and [RequireScope] for the most commonly-used data structures
+
+use crate::{AuthUser, Rejection, ERR_MISSING_SCOPE};
+use axum_core::extract::FromRequestParts;
+use http::{request::Parts, StatusCode};
+use std::{
+    collections::BTreeSet, convert::Infallible, fmt, marker::PhantomData, ops::Deref, str::FromStr,
+};
+
+/// Normalized set of OAuth scopes, parsed from the space-delimited strings used by JWT `scope` claims, introspection responses, and headers
+///
+/// Scopes are deduplicated and kept in order, so two sets granting the same scopes are always equal and display the same. Granted scopes can use wildcards for whole hierarchies, split by `:`:
+///
+/// - `*` on its own allows every scope
+/// - `read:*` allows `read:posts` and `read:posts:drafts`, but not `read` itself
+/// - `*:posts` allows `read:posts` and `write:posts`, as a `*` which isn't last only stands for one part
+///
+/// # Example
+///
+/// ```rust
+/// use axum_auth::Scopes;
+///
+/// let granted = Scopes::parse("  read:*  profile profile ");
+/// assert_eq!(granted.to_string(), "profile read:*");
+/// assert!(granted.allows("read:posts"));
+/// assert!(!granted.allows("write:posts"));
+/// assert!(granted.allows_all(&Scopes::parse("profile read:comments")));
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Default, Hash)]
+pub struct Scopes(BTreeSet<String>);
+
+impl Scopes {
+    /// Creates a new empty set of scopes
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses a space-delimited string of scopes, ignoring any extra whitespace
+    pub fn parse(scopes: &str) -> Self {
+        scopes.split_whitespace().collect()
+    }
+
+    /// Adds a scope, returning the set back
+    pub fn with(mut self, scope: impl Into<String>) -> Self {
+        self.extend([scope.into()]);
+        self
+    }
+
+    /// Checks if the exact scope is in the set, without any wildcards
+    pub fn contains(&self, scope: &str) -> bool {
+        self.0.contains(scope)
+    }
+
+    /// Checks if the scope is allowed by any scope in the set, including wildcards
+    pub fn allows(&self, scope: &str) -> bool {
+        self.contains(scope) || self.0.iter().any(|granted| matches(granted, scope))
+    }
+
+    /// Checks if every scope in the other set is allowed by this one
+    pub fn allows_all(&self, scopes: &Scopes) -> bool {
+        scopes.iter().all(|scope| self.allows(scope))
+    }
+
+    /// Iterates over the scopes in order
+    pub fn iter(&self) -> impl Iterator<Item = &str> + '_ {
+        self.0.iter().map(String::as_str)
+    }
+
+    /// Gets the number of scopes in the set
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Checks if the set doesn't have any scopes
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Checks if the granted scope has a wildcard which allows the requested one
+fn matches(granted: &str, requested: &str) -> bool {
+    let mut granted = granted.split(':').peekable();
+    let mut requested = requested.split(':');
+    while let Some(part) = granted.next() {
+        let last = granted.peek().is_none();
+        match (part, requested.next()) {
+            // Trailing wildcards allow any number of parts past them
+            ("*", Some(_)) if last => return true,
+            ("*", Some(_)) => {}
+            (part, Some(other)) if part == other => {}
+            _ => return false,
+        }
+    }
+    requested.next().is_none()
+}
+
+impl FromStr for Scopes {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self::parse(s))
+    }
+}
+
+impl fmt::Display for Scopes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, scope) in self.iter().enumerate() {
+            if i != 0 {
+                f.write_str(" ")?;
+            }
+            f.write_str(scope)?;
+        }
+        Ok(())
+    }
+}
+
+impl<S: AsRef<str>> FromIterator<S> for Scopes {
+    fn from_iter<I: IntoIterator<Item = S>>(iter: I) -> Self {
+        let mut scopes = Self::new();
+        scopes.extend(iter);
+        scopes
+    }
+}
+
+impl<S: AsRef<str>> Extend<S> for Scopes {
+    fn extend<I: IntoIterator<Item = S>>(&mut self, iter: I) {
+        // Entries can hold several scopes themselves, like an unsplit `scope` claim
+        for scopes in iter {
+            self.0
+                .extend(scopes.as_ref().split_whitespace().map(Into::into));
+        }
+    }
+}
+
+impl IntoIterator for Scopes {
+    type Item = String;
+    type IntoIter = std::collections::btree_set::IntoIter<String>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl From<&AuthUser> for Scopes {
+    fn from(user: &AuthUser) -> Self {
+        user.scopes.iter().collect()
+    }
+}
+
+/// Scope which can be required by the [RequireScope] guard, implement this on a marker type
+///
+/// # Example
+///
+/// ```rust
+/// use axum_auth::Scope;
+///
+/// /// Allows reading posts
+/// struct ReadPosts;
+///
+/// impl Scope for ReadPosts {
+///     const NAME: &'static str = "posts:read";
+/// }
+/// ```
+pub trait Scope {
+    /// Name of the scope, which the scopes of the [AuthUser] have to allow
+    const NAME: &'static str;
+}
+
+/// Guard extractor only allowing users whose [Scopes] allow a certain scope through, including using wildcards like `posts:*`
+///
+/// # Example
+///
+/// Once a layer or middleware has inserted an [AuthUser], you can use it like this:
+///
+/// ```no_run
+/// use axum_auth::{RequireScope, Scope};
+///
+/// struct ReadPosts;
+///
+/// impl Scope for ReadPosts {
+///     const NAME: &'static str = "posts:read";
+/// }
+///
+/// /// Only lets clients allowed to read posts through
+/// async fn handler(client: RequireScope<ReadPosts>) -> String {
+///     format!("Posts for {}", client.user.id)
+/// }
+/// ```
+///
+/// # Errors
+///
+/// There are a few errors which this extractor can make:
+///
+/// - `401 UNAUTHORIZED` with "No authenticated user was found for this request" – No [AuthUser] was inserted beforehand
+/// - `403 FORBIDDEN` with "You don't have the scope required to access this resource" – The scopes of the user don't allow it
+pub struct RequireScope<S> {
+    /// User whose scopes were found to allow the scope
+    pub user: AuthUser,
+    /// Every scope granted to the user
+    pub scopes: Scopes,
+    scope: PhantomData<fn() -> S>,
+}
+
+impl<S> Deref for RequireScope<S> {
+    type Target = AuthUser;
+
+    fn deref(&self) -> &Self::Target {
+        &self.user
+    }
+}
+
+impl<S> Clone for RequireScope<S> {
+    fn clone(&self) -> Self {
+        Self {
+            user: self.user.clone(),
+            scopes: self.scopes.clone(),
+            scope: PhantomData,
+        }
+    }
+}
+
+impl<S: Scope> fmt::Debug for RequireScope<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RequireScope")
+            .field("scope", &S::NAME)
+            .field("user", &self.user)
+            .finish()
+    }
+}
+
+impl<S, B> FromRequestParts<B> for RequireScope<S>
+where
+    S: Scope,
+    B: Send + Sync,
+{
+    type Rejection = Rejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &B) -> Result<Self, Self::Rejection> {
+        let user = AuthUser::from_request_parts(parts, state).await?;
+        let scopes = Scopes::from(&user);
+        if scopes.allows(S::NAME) {
+            Ok(Self {
+                user,
+                scopes,
+                scope: PhantomData,
+            })
+        } else {
+            Err((StatusCode::FORBIDDEN, ERR_MISSING_SCOPE))
+        }
+    }
+}
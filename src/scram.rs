@@ -0,0 +1,544 @@
+//! Implementation of [RFC 7804](https://www.rfc-editor.org/rfc/rfc7804) `SCRAM-SHA-256` http authentication
+//!
+//! See [ScramAuthLayer] for the most commonly-used data structure
+
+use crate::{
+    challenge::parse_params, constant_eq, crypto, get_header, AuthUser, Challenge, Unauthorized,
+    ERR_INVALID_CREDENTIALS, ERR_SCRAM_MESSAGE, ERR_SCRAM_SESSION, ERR_WRONG_SCRAM,
+};
+use axum_core::response::{IntoResponse, Response};
+use base64::{engine::general_purpose, Engine};
+use http::{request::Parts, HeaderName, HeaderValue, Request, StatusCode};
+use std::{
+    collections::HashMap,
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{Duration, SystemTime},
+};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// Name of the scheme, which is also what goes into the headers
+const SCHEME: &str = "SCRAM-SHA-256";
+
+/// Header the final server message is sent in once authenticated
+const AUTHENTICATION_INFO: HeaderName = HeaderName::from_static("authentication-info");
+
+/// Salted keys of a user, which is everything the server needs to know about their password
+///
+/// The password itself is never stored, so these can be kept in a database instead of password hashes. Passwords are used as given, so normalize them beforehand if your clients might send them in different Unicode forms.
+///
+/// # Example
+///
+/// ```rust
+/// use axum_auth::ScramCredentials;
+///
+/// // Created once when the user signs up, with a random salt
+/// let credentials = ScramCredentials::generate("pencil");
+/// ```
+#[derive(PartialEq, Eq, Clone)]
+pub struct ScramCredentials {
+    salt: Vec<u8>,
+    iterations: u32,
+    stored_key: Vec<u8>,
+    server_key: Vec<u8>,
+}
+
+impl ScramCredentials {
+    /// Default amount of iterations used to salt passwords, which is 4096 as recommended by the RFC
+    pub const DEFAULT_ITERATIONS: u32 = 4096;
+
+    /// Creates the keys for the password using the given salt and amount of iterations
+    pub fn new(password: &str, salt: &[u8], iterations: u32) -> Self {
+        let salted = salt_password(password.as_bytes(), salt, iterations);
+        let client_key = crypto::hmac_sha256(&salted, b"Client Key");
+        Self {
+            salt: salt.to_vec(),
+            iterations,
+            stored_key: crypto::sha256(&client_key),
+            server_key: crypto::hmac_sha256(&salted, b"Server Key"),
+        }
+    }
+
+    /// Creates the keys for the password with a random salt and the default amount of iterations
+    pub fn generate(password: &str) -> Self {
+        Self::new(password, &crypto::random(16), Self::DEFAULT_ITERATIONS)
+    }
+
+    /// Creates credentials from keys which were stored beforehand
+    pub fn from_keys(
+        salt: Vec<u8>,
+        iterations: u32,
+        stored_key: Vec<u8>,
+        server_key: Vec<u8>,
+    ) -> Self {
+        Self {
+            salt,
+            iterations,
+            stored_key,
+            server_key,
+        }
+    }
+
+    /// Salt used for the password
+    pub fn salt(&self) -> &[u8] {
+        &self.salt
+    }
+
+    /// Amount of iterations used to salt the password
+    pub fn iterations(&self) -> u32 {
+        self.iterations
+    }
+
+    /// Key checking the proofs of the client, which should be stored
+    pub fn stored_key(&self) -> &[u8] {
+        &self.stored_key
+    }
+
+    /// Key proving the server knows the password back to the client, which should be stored
+    pub fn server_key(&self) -> &[u8] {
+        &self.server_key
+    }
+}
+
+impl fmt::Debug for ScramCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScramCredentials")
+            .field("iterations", &self.iterations)
+            .field("keys", &"[redacted]")
+            .finish()
+    }
+}
+
+/// Half-finished handshake remembered between the two requests of a client
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ScramSession {
+    username: String,
+    gs2_header: String,
+    client_first: String,
+    server_first: String,
+    nonce: String,
+    credentials: ScramCredentials,
+}
+
+/// Store of the credentials for every user and the handshakes which are in progress
+///
+/// The in-memory [MemoryScramStore] works for a single server, implement this yourself to look users up in a database or to share handshakes between many servers.
+pub trait ScramStore: Send + Sync + 'static {
+    /// Looks up the credentials of the user, giving [None] if they don't exist; you need to implement this
+    fn credentials(&self, username: &str) -> impl Future<Output = Option<ScramCredentials>> + Send;
+
+    /// Remembers the handshake under its session identifier until it expires; you need to implement this
+    fn insert_session(
+        &self,
+        sid: &str,
+        session: ScramSession,
+        expires: SystemTime,
+    ) -> impl Future<Output = ()> + Send;
+
+    /// Forgets the handshake, giving it back if it existed and hasn't expired; you need to implement this
+    fn take_session(&self, sid: &str) -> impl Future<Output = Option<ScramSession>> + Send;
+}
+
+/// In-memory [ScramStore] which forgets handshakes once they expire
+#[derive(Debug, Default)]
+pub struct MemoryScramStore {
+    users: HashMap<String, ScramCredentials>,
+    sessions: Mutex<HashMap<String, (ScramSession, SystemTime)>>,
+}
+
+impl MemoryScramStore {
+    /// Creates a new empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a user to the store, returning it back
+    pub fn with_user(mut self, username: impl Into<String>, credentials: ScramCredentials) -> Self {
+        self.users.insert(username.into(), credentials);
+        self
+    }
+}
+
+impl ScramStore for MemoryScramStore {
+    async fn credentials(&self, username: &str) -> Option<ScramCredentials> {
+        self.users.get(username).cloned()
+    }
+
+    async fn insert_session(&self, sid: &str, session: ScramSession, expires: SystemTime) {
+        let now = SystemTime::now();
+        let mut sessions = self.sessions.lock().unwrap();
+
+        // Forget expired handshakes so the store doesn't grow forever
+        sessions.retain(|_, (_, expires)| *expires > now);
+        sessions.insert(sid.to_string(), (session, expires));
+    }
+
+    async fn take_session(&self, sid: &str) -> Option<ScramSession> {
+        let (session, expires) = self.sessions.lock().unwrap().remove(sid)?;
+        (expires > SystemTime::now()).then_some(session)
+    }
+}
+
+/// Layer authenticating every request using `SCRAM-SHA-256`, which proves the password without ever sending it
+///
+/// This is enabled via the `scram` feature alongside a crypto backend
+///
+/// The handshake takes two requests from the client, which are tied together using the `sid` parameter:
+///
+/// 1. The client sends its first message, which is answered with a `401 UNAUTHORIZED` carrying the first server message
+/// 2. The client sends its final message with the proof, which is checked before the request reaches the handler
+///
+/// Once authenticated, an [AuthUser] with the username is inserted into the request extensions and the final server message is added to the response in the `Authentication-Info` header, so the client can check the server knew the password too.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{routing::get, Router};
+/// use axum_auth::{AuthUser, MemoryScramStore, ScramAuthLayer, ScramCredentials};
+///
+/// let store = MemoryScramStore::new().with_user("user", ScramCredentials::generate("pencil"));
+///
+/// let app: Router = Router::new()
+///     .route("/", get(|user: AuthUser| async move { format!("Hello, {}!", user.id) }))
+///     .layer(ScramAuthLayer::new(store).with_realm("example"));
+/// ```
+///
+/// # Errors
+///
+/// All errors are `401 UNAUTHORIZED` with a challenge to start the handshake again, alongside one of these messages:
+///
+/// - \`Authorization\` header must be for SCRAM-SHA-256 authentication – Some other scheme was used instead
+/// - \`Authorization\` header isn't a valid SCRAM message – The message was malformed or asked for unsupported features like channel binding
+/// - SCRAM session has expired or doesn't exist – The second request took too long or its `sid` was wrong
+/// - The provided credentials are invalid – The user doesn't exist or the proof was wrong
+/// - \`Authorization\` header is missing – The header was required but it wasn't found
+/// - \`Authorization\` header contains invalid characters – The header couldn't be processed because of invalid characters
+pub struct ScramAuthLayer<St> {
+    store: Arc<St>,
+    secret: Arc<[u8]>,
+    realm: Arc<str>,
+    session_ttl: Duration,
+}
+
+impl<St> ScramAuthLayer<St> {
+    /// Default amount of time clients have to send their final message, which is 1 minute
+    pub const DEFAULT_SESSION_TTL: Duration = Duration::from_secs(60);
+
+    /// Creates a new layer using the given store, with an empty realm, the default session lifetime, and a random secret
+    ///
+    /// The secret makes up the salts given to unknown users, so they stay the same every time like those of real users. Being random means they change between servers, use [with_secret](Self::with_secret) if you have many.
+    pub fn new(store: St) -> Self {
+        Self {
+            store: Arc::new(store),
+            secret: Arc::from(crypto::random(32)),
+            realm: Arc::from(""),
+            session_ttl: Self::DEFAULT_SESSION_TTL,
+        }
+    }
+
+    /// Sets the realm advertised in the challenge, returning it back
+    pub fn with_realm(mut self, realm: impl AsRef<str>) -> Self {
+        self.realm = Arc::from(realm.as_ref());
+        self
+    }
+
+    /// Sets the secret the salts of unknown users are made from, which has to be the same on every server, returning it back
+    pub fn with_secret(mut self, secret: impl AsRef<[u8]>) -> Self {
+        self.secret = Arc::from(secret.as_ref());
+        self
+    }
+
+    /// Sets how long clients have to send their final message, returning it back
+    pub fn with_session_ttl(mut self, session_ttl: Duration) -> Self {
+        self.session_ttl = session_ttl;
+        self
+    }
+}
+
+impl<St> Clone for ScramAuthLayer<St> {
+    fn clone(&self) -> Self {
+        Self {
+            store: self.store.clone(),
+            secret: self.secret.clone(),
+            realm: self.realm.clone(),
+            session_ttl: self.session_ttl,
+        }
+    }
+}
+
+impl<S, St> Layer<S> for ScramAuthLayer<St> {
+    type Service = ScramAuth<S, St>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ScramAuth {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+/// Service created by [ScramAuthLayer], see it for more information
+pub struct ScramAuth<S, St> {
+    inner: S,
+    layer: ScramAuthLayer<St>,
+}
+
+impl<S: Clone, St> Clone for ScramAuth<S, St> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            layer: self.layer.clone(),
+        }
+    }
+}
+
+impl<S, St, B> Service<Request<B>> for ScramAuth<S, St>
+where
+    S: Service<Request<B>, Response = Response> + Clone + Send + 'static,
+    S::Future: Send,
+    St: ScramStore,
+    B: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<B>) -> Self::Future {
+        // Take the service which was driven to readiness, leaving a fresh clone behind
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let layer = self.layer.clone();
+
+        Box::pin(async move {
+            let (mut parts, body) = req.into_parts();
+
+            // Go through the handshake and keep the identity for later
+            let (user, info) = match handshake(&layer, &mut parts).await {
+                Ok(done) => done,
+                Err(resp) => return Ok(resp),
+            };
+            parts.extensions.insert(user);
+
+            // Let the client check the server signature too
+            let mut resp = inner.call(Request::from_parts(parts, body)).await?;
+            resp.headers_mut().insert(AUTHENTICATION_INFO, info);
+            Ok(resp)
+        })
+    }
+}
+
+/// Goes through whichever step of the handshake the request is at, giving back the identity and final server message once done
+async fn handshake<St: ScramStore>(
+    layer: &ScramAuthLayer<St>,
+    parts: &mut Parts,
+) -> Result<(AuthUser, HeaderValue), Response> {
+    let restart = |message| {
+        Unauthorized::new()
+            .with_challenge(Challenge::new(SCHEME).with_param("realm", &*layer.realm))
+            .with_message(message)
+            .into_response()
+    };
+
+    // Get the parameters of the header
+    let authorization =
+        get_header(parts, StatusCode::UNAUTHORIZED).map_err(|(_, msg)| restart(msg))?;
+    let params = match authorization.split_once(' ') {
+        Some((SCHEME, params)) => parse_params(params),
+        _ => return Err(restart(ERR_WRONG_SCRAM)),
+    };
+    let param = |name| {
+        params
+            .iter()
+            .find(|(param, _)| param == name)
+            .map(|(_, value)| value.as_str())
+    };
+
+    // Decode the message, which is always there
+    let data = param("data")
+        .and_then(|data| general_purpose::STANDARD.decode(data).ok())
+        .and_then(|data| String::from_utf8(data).ok())
+        .ok_or_else(|| restart(ERR_SCRAM_MESSAGE))?;
+
+    // Continue the handshake if it was started already
+    let Some(sid) = param("sid") else {
+        return Err(first_step(layer, &data).await.unwrap_or_else(restart));
+    };
+    let session = layer
+        .store
+        .take_session(sid)
+        .await
+        .ok_or_else(|| restart(ERR_SCRAM_SESSION))?;
+    let username = session.username.clone();
+    let server_final = final_step(session, &data).map_err(restart)?;
+
+    // Send the final server message once the handler is done
+    let info = format!(
+        "sid={}, data={}",
+        sid,
+        general_purpose::STANDARD.encode(server_final)
+    );
+    let info = HeaderValue::from_str(&info).map_err(|_| restart(ERR_SCRAM_MESSAGE))?;
+    Ok((AuthUser::new(username), info))
+}
+
+/// Answers the first client message with the first server message, starting a new session
+async fn first_step<St: ScramStore>(
+    layer: &ScramAuthLayer<St>,
+    data: &str,
+) -> Result<Response, &'static str> {
+    // Split the header for channel binding from the rest, e.g. `n,,n=user,r=abc`
+    let mut split = data.splitn(3, ',');
+    let (Some(binding), Some(authzid), Some(client_first)) =
+        (split.next(), split.next(), split.next())
+    else {
+        return Err(ERR_SCRAM_MESSAGE);
+    };
+    if !matches!(binding, "n" | "y") || !(authzid.is_empty() || authzid.starts_with("a=")) {
+        return Err(ERR_SCRAM_MESSAGE);
+    }
+
+    // Get the username and nonce of the client
+    let mut attributes = client_first.split(',');
+    let username = attributes
+        .next()
+        .and_then(|username| username.strip_prefix("n="))
+        .and_then(decode_username)
+        .ok_or(ERR_SCRAM_MESSAGE)?;
+    let client_nonce = attributes
+        .next()
+        .and_then(|nonce| nonce.strip_prefix("r="))
+        .filter(|nonce| !nonce.is_empty())
+        .ok_or(ERR_SCRAM_MESSAGE)?;
+
+    // Use throwaway credentials for unknown users so they can't be told apart from existing ones,
+    // with a salt which is the same every time like real ones (RFC 5802 section 5.1)
+    let credentials = match layer.store.credentials(&username).await {
+        Some(credentials) => credentials,
+        None => ScramCredentials::from_keys(
+            crypto::hmac_sha256(&layer.secret, username.as_bytes())[..16].to_vec(),
+            ScramCredentials::DEFAULT_ITERATIONS,
+            crypto::random(32),
+            crypto::random(32),
+        ),
+    };
+
+    // Make the first server message and remember everything for the final step
+    let nonce = format!(
+        "{}{}",
+        client_nonce,
+        general_purpose::STANDARD.encode(crypto::random(18))
+    );
+    let server_first = format!(
+        "r={},s={},i={}",
+        nonce,
+        general_purpose::STANDARD.encode(&credentials.salt),
+        credentials.iterations
+    );
+    let sid = general_purpose::URL_SAFE_NO_PAD.encode(crypto::random(16));
+    let session = ScramSession {
+        username,
+        gs2_header: format!("{},{},", binding, authzid),
+        client_first: client_first.to_string(),
+        server_first: server_first.clone(),
+        nonce,
+        credentials,
+    };
+    layer
+        .store
+        .insert_session(&sid, session, SystemTime::now() + layer.session_ttl)
+        .await;
+
+    Ok(Unauthorized::new()
+        .with_challenge(
+            Challenge::new(SCHEME)
+                .with_param("sid", sid)
+                .with_param("data", general_purpose::STANDARD.encode(server_first)),
+        )
+        .into_response())
+}
+
+/// Checks the proof in the final client message, giving back the final server message
+fn final_step(session: ScramSession, data: &str) -> Result<String, &'static str> {
+    // Split off the proof, e.g. `c=biws,r=abc,p=xyz`
+    let (without_proof, proof) = data.rsplit_once(",p=").ok_or(ERR_SCRAM_MESSAGE)?;
+    let proof = general_purpose::STANDARD
+        .decode(proof)
+        .map_err(|_| ERR_SCRAM_MESSAGE)?;
+
+    // Check the binding and nonce are the same as before
+    let mut attributes = without_proof.split(',');
+    let binding = attributes
+        .next()
+        .and_then(|binding| binding.strip_prefix("c="))
+        .and_then(|binding| general_purpose::STANDARD.decode(binding).ok())
+        .ok_or(ERR_SCRAM_MESSAGE)?;
+    let nonce = attributes
+        .next()
+        .and_then(|nonce| nonce.strip_prefix("r="))
+        .ok_or(ERR_SCRAM_MESSAGE)?;
+    if binding != session.gs2_header.as_bytes() || nonce != session.nonce {
+        return Err(ERR_SCRAM_MESSAGE);
+    }
+
+    // Recover the client key from the proof and check it against the stored key
+    let auth_message = format!(
+        "{},{},{}",
+        session.client_first, session.server_first, without_proof
+    );
+    let credentials = session.credentials;
+    let signature = crypto::hmac_sha256(&credentials.stored_key, auth_message.as_bytes());
+    if proof.len() != signature.len() {
+        return Err(ERR_INVALID_CREDENTIALS);
+    }
+    let client_key = proof
+        .iter()
+        .zip(&signature)
+        .map(|(proof, signature)| proof ^ signature)
+        .collect::<Vec<_>>();
+    if !constant_eq(&crypto::sha256(&client_key), &credentials.stored_key) {
+        return Err(ERR_INVALID_CREDENTIALS);
+    }
+
+    // Prove the server knows the password too
+    let signature = crypto::hmac_sha256(&credentials.server_key, auth_message.as_bytes());
+    Ok(format!("v={}", general_purpose::STANDARD.encode(signature)))
+}
+
+/// Decodes a username with its commas and equals signs escaped as `=2C` and `=3D`
+fn decode_username(input: &str) -> Option<String> {
+    let mut username = String::new();
+    let mut rest = input;
+    while let Some((before, after)) = rest.split_once('=') {
+        username.push_str(before);
+        username.push(match after.get(..2)? {
+            "2C" => ',',
+            "3D" => '=',
+            _ => return None,
+        });
+        rest = &after[2..];
+    }
+    username.push_str(rest);
+    (!username.is_empty()).then_some(username)
+}
+
+/// Salts the password using PBKDF2 with HMAC-SHA-256, called `Hi` by the RFC
+fn salt_password(password: &[u8], salt: &[u8], iterations: u32) -> Vec<u8> {
+    let mut block = crypto::hmac_sha256(password, &[salt, &1u32.to_be_bytes()].concat());
+    let mut salted = block.clone();
+    for _ in 1..iterations {
+        block = crypto::hmac_sha256(password, &block);
+        salted
+            .iter_mut()
+            .zip(&block)
+            .for_each(|(salted, block)| *salted ^= block);
+    }
+    salted
+}
@@ -0,0 +1,1033 @@
+//! Implementation of signed session cookies issued after logging in once
+//!
+//! See [SessionIssuer] for the most commonly-used data structure
+
+use crate::{
+    constant_eq,
+    crypto::{self, Hash},
+    AuthBasic, AuthBasicCustom, AuthUser, AuthValidator, BasicUsers, Challenge, Rejection,
+    Unauthorized, ERR_NO_SESSION, ERR_REMEMBER_ME, ERR_REVOKED, ERR_SESSION_EXPIRED,
+    ERR_SESSION_INVALID,
+};
+#[cfg(feature = "auth-bearer")]
+use crate::{AuthBearer, AuthBearerCustom};
+use axum_core::response::{IntoResponse, Response};
+use base64::{engine::general_purpose, Engine};
+#[cfg(feature = "auth-bearer")]
+use http::Extensions;
+use http::{
+    header::{COOKIE, SET_COOKIE},
+    HeaderMap, HeaderValue, Request, StatusCode,
+};
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// Issuer of signed session cookies, remembering who logged in so clients don't have to send their password every time
+///
+/// This is enabled via the `session` feature alongside a crypto backend
+///
+/// Sessions are signed using HMAC-SHA-256 with the key given, so they can be checked without storing anything on the server. They contain the [AuthUser] they were issued for along with a random session identifier, and expire after the TTL (one day by default).
+///
+/// The key has to be kept secret, as anyone with it can issue sessions for any user. Use at least 32 random bytes, and keep it the same between restarts (and servers) so sessions stay valid.
+///
+/// Cookies are `HttpOnly`, `Secure`, and `SameSite=Lax` by default, so scripts can't read them and browsers don't send them along with requests from other sites.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{routing::{get, post}, Router};
+/// use axum_auth::{AuthUser, BasicLogin, BasicUser, BasicUsers, SessionAuthLayer, SessionIssuer};
+///
+/// let issuer = SessionIssuer::new(*b"an example key which is 32 bytes");
+/// let users = BasicUsers::new().with_user("alice", BasicUser::new("hunter2"));
+///
+/// let app: Router = Router::new()
+///     .route("/", get(|user: AuthUser| async move { format!("Hello, {}!", user.id) }))
+///     .layer(SessionAuthLayer::new(issuer.clone()))
+///     .route("/login", post(BasicLogin::new("App", users, issuer).handler()));
+/// ```
+#[derive(Clone)]
+pub struct SessionIssuer {
+    key: Arc<[u8]>,
+    cookie_name: Arc<str>,
+    ttl: Duration,
+    secure: bool,
+}
+
+impl SessionIssuer {
+    /// Default amount of time sessions last for, which is one day
+    pub const DEFAULT_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+    /// Default name of the cookie holding the session
+    pub const DEFAULT_COOKIE_NAME: &'static str = "session";
+
+    /// Creates a new issuer signing sessions with the given secret key
+    pub fn new(key: impl Into<Vec<u8>>) -> Self {
+        Self {
+            key: key.into().into(),
+            cookie_name: Self::DEFAULT_COOKIE_NAME.into(),
+            ttl: Self::DEFAULT_TTL,
+            secure: true,
+        }
+    }
+
+    /// Sets the name of the cookie holding the session, returning it back
+    pub fn with_cookie_name(mut self, cookie_name: impl AsRef<str>) -> Self {
+        self.cookie_name = cookie_name.as_ref().into();
+        self
+    }
+
+    /// Sets how long sessions last for, returning it back
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Sets if cookies are only sent over HTTPS (which is the default), returning it back
+    ///
+    /// Browsers treat `localhost` as secure, so this is only needed when testing over plain HTTP on other hosts.
+    pub fn with_secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+        self
+    }
+
+    /// Name of the cookie holding the session
+    pub fn cookie_name(&self) -> &str {
+        &self.cookie_name
+    }
+
+    /// Issues a signed session for the user, which can be sent in any way but is usually a cookie made by [cookie](Self::cookie)
+    pub fn issue(&self, user: &AuthUser) -> String {
+        let expires = now() + self.ttl.as_secs();
+        let payload = json!({
+            "sid": general_purpose::URL_SAFE_NO_PAD.encode(crypto::random(16)),
+            "sub": user.id,
+            "roles": user.roles,
+            "scopes": user.scopes,
+            "exp": expires,
+        });
+        let payload = general_purpose::URL_SAFE_NO_PAD.encode(payload.to_string());
+        let signature = crypto::hmac_sha256(&self.key, payload.as_bytes());
+        format!(
+            "{}.{}",
+            payload,
+            general_purpose::URL_SAFE_NO_PAD.encode(signature)
+        )
+    }
+
+    /// Issues a signed session for the user as the value of a `Set-Cookie` header
+    pub fn cookie(&self, user: &AuthUser) -> HeaderValue {
+        make_cookie(&self.cookie_name, &self.issue(user), self.ttl, self.secure)
+    }
+
+    /// Checks a session made by [issue](Self::issue), giving back the user it was issued for
+    ///
+    /// # Errors
+    ///
+    /// Sessions which weren't signed with the key of this issuer are rejected with `401 UNAUTHORIZED` and the message "Session cookie is invalid", whilst expired ones get the message "Session has expired".
+    pub fn verify(&self, session: &str) -> Result<AuthUser, Rejection> {
+        self.decode(session).map(|(user, _)| user)
+    }
+
+    /// Checks a session, giving back the user it was issued for along with its identifier
+    fn decode(&self, session: &str) -> Result<(AuthUser, Revocation), Rejection> {
+        let invalid = (StatusCode::UNAUTHORIZED, ERR_SESSION_INVALID);
+        let (payload, signature) = session.split_once('.').ok_or(invalid)?;
+        let signature = general_purpose::URL_SAFE_NO_PAD
+            .decode(signature)
+            .map_err(|_| invalid)?;
+        if !crypto::verify_hmac(Hash::Sha256, &self.key, payload.as_bytes(), &signature) {
+            return Err(invalid);
+        }
+
+        // Signed by us, so the contents can be trusted
+        let claims = general_purpose::URL_SAFE_NO_PAD
+            .decode(payload)
+            .ok()
+            .and_then(|payload| serde_json::from_slice::<Value>(&payload).ok())
+            .ok_or(invalid)?;
+        if claims["exp"].as_u64().is_none_or(|exp| exp <= now()) {
+            return Err((StatusCode::UNAUTHORIZED, ERR_SESSION_EXPIRED));
+        }
+        let strings = |name: &str| {
+            claims[name]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        };
+        let id = claims["sub"].as_str().ok_or(invalid)?;
+        let user = AuthUser::new(id)
+            .with_roles(strings("roles"))
+            .with_scopes(strings("scopes"));
+        let revocation = Revocation {
+            id: claims["sid"].as_str().ok_or(invalid)?.to_string(),
+            expires: UNIX_EPOCH + Duration::from_secs(claims["exp"].as_u64().unwrap_or_default()),
+        };
+        Ok((user, revocation))
+    }
+
+    /// Makes a `Set-Cookie` header value which removes the session cookie from the browser
+    fn clear_cookie(&self) -> HeaderValue {
+        make_cookie(&self.cookie_name, "", Duration::ZERO, self.secure)
+    }
+
+    /// Finds the session cookie in the headers of a request
+    fn find<'a>(&self, headers: &'a HeaderMap) -> Option<&'a str> {
+        find_cookie(headers, &self.cookie_name)
+    }
+}
+
+impl fmt::Debug for SessionIssuer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SessionIssuer")
+            .field("key", &"[redacted]")
+            .field("cookie_name", &self.cookie_name)
+            .field("ttl", &self.ttl)
+            .field("secure", &self.secure)
+            .finish()
+    }
+}
+
+/// Finds a cookie by its name in the headers of a request
+fn find_cookie<'a>(headers: &'a HeaderMap, cookie_name: &str) -> Option<&'a str> {
+    headers
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|cookie| cookie.trim().split_once('='))
+        .find(|(name, _)| *name == cookie_name)
+        .map(|(_, value)| value)
+}
+
+/// Makes a `Set-Cookie` header value from its name, contents, and attributes
+fn make_cookie(name: &str, value: &str, max_age: Duration, secure: bool) -> HeaderValue {
+    let cookie = format!(
+        "{}={}; Path=/; Max-Age={}; HttpOnly; SameSite=Lax{}",
+        name,
+        value,
+        max_age.as_secs(),
+        if secure { "; Secure" } else { "" }
+    );
+    let mut cookie = HeaderValue::try_from(cookie).expect("cookies are made of valid characters");
+    cookie.set_sensitive(true);
+    cookie
+}
+
+/// Gets the current unix time in seconds
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Layer requiring every request to carry a session cookie issued by a [SessionIssuer]
+///
+/// This is enabled via the `session` feature alongside a crypto backend
+///
+/// Once the session is checked, the [AuthUser] it was issued for is inserted into the request extensions for guards and handlers to use. See [SessionIssuer] for an example.
+///
+/// # Errors
+///
+/// Requests are rejected with `401 UNAUTHORIZED` and one of these messages:
+///
+/// - Session cookie is missing – The request didn't have a session cookie
+/// - Session cookie is invalid – The session wasn't signed by the issuer, or was changed since
+/// - Session has expired – The session was valid but is older than the TTL of the issuer
+/// - Session has been revoked – The session was logged out of using [logout_route]
+/// - Remember-me cookie is invalid – The remember-me cookie was used before or has expired, see [RememberMe]
+#[derive(Debug)]
+pub struct SessionAuthLayer<R = (), M = ()> {
+    issuer: SessionIssuer,
+    revocations: Arc<R>,
+    remember_me: Option<RememberMe<M>>,
+}
+
+impl SessionAuthLayer {
+    /// Creates a new layer checking sessions made by the issuer
+    pub fn new(issuer: SessionIssuer) -> Self {
+        Self {
+            issuer,
+            revocations: Arc::new(()),
+            remember_me: None,
+        }
+    }
+}
+
+impl<R, M> SessionAuthLayer<R, M> {
+    /// Rejects sessions which were revoked in the store, like by [logout_route], returning it back
+    pub fn with_revocations<T: RevocationStore>(
+        self,
+        revocations: Arc<T>,
+    ) -> SessionAuthLayer<T, M> {
+        SessionAuthLayer {
+            issuer: self.issuer,
+            revocations,
+            remember_me: self.remember_me,
+        }
+    }
+
+    /// Issues a new session whenever a request only has a valid remember-me cookie, returning it back
+    ///
+    /// The new session and the rotated remember-me cookie are both set on the response, so the client carries on without noticing its session expired.
+    pub fn with_remember_me<T: RememberMeStore>(
+        self,
+        remember_me: RememberMe<T>,
+    ) -> SessionAuthLayer<R, T> {
+        SessionAuthLayer {
+            issuer: self.issuer,
+            revocations: self.revocations,
+            remember_me: Some(remember_me),
+        }
+    }
+}
+
+impl<R, M> Clone for SessionAuthLayer<R, M> {
+    fn clone(&self) -> Self {
+        Self {
+            issuer: self.issuer.clone(),
+            revocations: self.revocations.clone(),
+            remember_me: self.remember_me.clone(),
+        }
+    }
+}
+
+impl<S, R, M> Layer<S> for SessionAuthLayer<R, M> {
+    type Service = SessionAuth<S, R, M>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        SessionAuth {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+/// Service created by [SessionAuthLayer], see it for more information
+#[derive(Debug)]
+pub struct SessionAuth<S, R = (), M = ()> {
+    inner: S,
+    layer: SessionAuthLayer<R, M>,
+}
+
+impl<S: Clone, R, M> Clone for SessionAuth<S, R, M> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            layer: self.layer.clone(),
+        }
+    }
+}
+
+impl<S, R, M, B> Service<Request<B>> for SessionAuth<S, R, M>
+where
+    S: Service<Request<B>, Response = Response> + Clone + Send + 'static,
+    S::Future: Send,
+    R: RevocationStore,
+    M: RememberMeStore,
+    B: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<B>) -> Self::Future {
+        // Take the service which was driven to readiness, leaving a fresh clone behind
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let layer = self.layer.clone();
+
+        Box::pin(async move {
+            let (mut parts, body) = req.into_parts();
+
+            // Check the session and keep the identity for later
+            let session = layer
+                .issuer
+                .find(&parts.headers)
+                .ok_or((StatusCode::UNAUTHORIZED, ERR_NO_SESSION))
+                .and_then(|session| layer.issuer.decode(session));
+            let user = match session {
+                Ok((user, revocation)) => {
+                    if layer.revocations.is_revoked(&revocation.id).await {
+                        Err((StatusCode::UNAUTHORIZED, ERR_REVOKED))
+                    } else {
+                        Ok(user)
+                    }
+                }
+                Err(rejection) => Err(rejection),
+            };
+
+            // Swap a remember-me cookie for a new session if there's no usable one
+            let remembered = match (&user, &layer.remember_me) {
+                (Err(_), Some(remember_me)) => remember_me.refresh(&parts.headers).await,
+                _ => None,
+            };
+            let (user, cookies) = match (user, remembered) {
+                (_, Some(Ok((user, remember_cookie)))) => {
+                    let session_cookie = layer.issuer.cookie(&user);
+                    (Ok(user), vec![session_cookie, remember_cookie])
+                }
+                (_, Some(Err((rejection, clear)))) => (Err(rejection), vec![clear]),
+                (user, None) => (user, vec![]),
+            };
+
+            let mut resp = match user {
+                Ok(user) => {
+                    parts.extensions.insert(user);
+                    inner.call(Request::from_parts(parts, body)).await?
+                }
+                Err(rejection) => rejection.into_response(),
+            };
+            for cookie in cookies {
+                resp.headers_mut().append(SET_COOKIE, cookie);
+            }
+            Ok(resp)
+        })
+    }
+}
+
+/// Identifier of a session or token to be revoked, along with when it would have expired anyway
+struct Revocation {
+    id: String,
+    expires: SystemTime,
+}
+
+/// Store remembering which sessions and bearer tokens have been revoked before they expire, used by [logout_route] and [bearer_logout_route]
+///
+/// This is enabled via the `session` feature alongside a crypto backend
+///
+/// The in-memory [MemoryRevocationStore] works for a single server, implement this yourself to share revocations between many (e.g. using Redis `SET EX`). The `()` store never revokes anything, which is what layers use until they're given a real one.
+pub trait RevocationStore: Send + Sync + 'static {
+    /// Remembers the identifier as revoked until it expires; you need to implement this
+    fn revoke(&self, id: &str, expires: SystemTime) -> impl Future<Output = ()> + Send;
+
+    /// Checks if the identifier has been revoked; you need to implement this
+    fn is_revoked(&self, id: &str) -> impl Future<Output = bool> + Send;
+}
+
+impl RevocationStore for () {
+    async fn revoke(&self, _: &str, _: SystemTime) {}
+
+    async fn is_revoked(&self, _: &str) -> bool {
+        false
+    }
+}
+
+/// In-memory [RevocationStore] which forgets revocations once they expire
+///
+/// This is enabled via the `session` feature alongside a crypto backend
+#[derive(Debug, Default)]
+pub struct MemoryRevocationStore {
+    revoked: Mutex<HashMap<String, SystemTime>>,
+}
+
+impl MemoryRevocationStore {
+    /// Creates a new empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl RevocationStore for MemoryRevocationStore {
+    async fn revoke(&self, id: &str, expires: SystemTime) {
+        let now = SystemTime::now();
+        let mut revoked = self.revoked.lock().unwrap();
+
+        // Forget expired revocations before the map would grow, so sweeping stays cheap per insert
+        if revoked.len() >= revoked.capacity() {
+            revoked.retain(|_, expires| *expires > now);
+        }
+        revoked.insert(id.to_string(), expires);
+    }
+
+    async fn is_revoked(&self, id: &str) -> bool {
+        self.revoked
+            .lock()
+            .unwrap()
+            .get(id)
+            .is_some_and(|expires| *expires > SystemTime::now())
+    }
+}
+
+/// Gets the identifier bearer tokens are revoked under, which is a digest so the store never holds the tokens themselves
+#[cfg(feature = "auth-bearer")]
+fn token_id(token: &str) -> String {
+    general_purpose::URL_SAFE_NO_PAD.encode(crypto::sha256(token.as_bytes()))
+}
+
+/// Validator wrapper rejecting bearer tokens which were revoked using [bearer_logout_route] before checking them with the inner validator
+///
+/// This is enabled via the `session` and `auth-bearer` features alongside a crypto backend
+///
+/// Tokens are revoked under a SHA-256 digest of themselves, so the store never holds tokens which could still be used.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{routing::{get, post}, Router};
+/// use axum_auth::{
+///     bearer_logout_route, AuthBearer, AuthUser, MemoryRevocationStore, RequireAuthLayer,
+///     Revocable, SessionIssuer,
+/// };
+/// # use axum_auth::{AuthValidator, Rejection};
+/// # struct MyValidator;
+/// # impl AuthValidator<AuthBearer> for MyValidator {
+/// #     async fn validate(&self, _: &AuthBearer) -> Result<AuthUser, Rejection> {
+/// #         Ok(AuthUser::new("alice"))
+/// #     }
+/// # }
+/// use std::sync::Arc;
+///
+/// let revocations = Arc::new(MemoryRevocationStore::new());
+/// let issuer = SessionIssuer::new(*b"an example key which is 32 bytes");
+///
+/// let app: Router = Router::new()
+///     .route("/", get(|user: AuthUser| async move { format!("Hello, {}!", user.id) }))
+///     .route("/logout", post(bearer_logout_route(revocations.clone(), issuer, MyValidator)))
+///     .layer(RequireAuthLayer::<AuthBearer, _>::new(Revocable::new(MyValidator, revocations)));
+/// ```
+///
+/// # Errors
+///
+/// Revoked tokens are rejected with `401 UNAUTHORIZED` and the message "Session has been revoked", otherwise the rejections of the inner validator are used as-is.
+#[cfg(feature = "auth-bearer")]
+#[derive(Debug)]
+pub struct Revocable<V, R> {
+    inner: V,
+    revocations: Arc<R>,
+}
+
+#[cfg(feature = "auth-bearer")]
+impl<V, R> Revocable<V, R> {
+    /// Wraps the validator, checking for revoked tokens in the store first
+    pub fn new(inner: V, revocations: Arc<R>) -> Self {
+        Self { inner, revocations }
+    }
+}
+
+#[cfg(feature = "auth-bearer")]
+impl<V, R> AuthValidator<AuthBearer> for Revocable<V, R>
+where
+    V: AuthValidator<AuthBearer>,
+    R: RevocationStore,
+{
+    async fn validate(&self, credentials: &AuthBearer) -> Result<AuthUser, Rejection> {
+        self.validate_with_extensions(credentials, &mut Extensions::new())
+            .await
+    }
+
+    async fn validate_with_extensions(
+        &self,
+        credentials: &AuthBearer,
+        extensions: &mut Extensions,
+    ) -> Result<AuthUser, Rejection> {
+        if self.revocations.is_revoked(&token_id(&credentials.0)).await {
+            return Err((StatusCode::UNAUTHORIZED, ERR_REVOKED));
+        }
+        self.inner
+            .validate_with_extensions(credentials, extensions)
+            .await
+    }
+}
+
+/// Makes a logout handler revoking the session cookie the request was sent with, responding with `204 NO CONTENT`
+///
+/// This is enabled via the `session` feature alongside a crypto backend
+///
+/// Sessions made by the issuer are revoked until they would have expired, and the cookie is cleared from the browser. Revocations only take effect when the same store is given to [SessionAuthLayer::with_revocations]; use [bearer_logout_route] to revoke bearer tokens as well.
+///
+/// Requests without a valid session are still given a `204 NO CONTENT`, so logging out twice isn't an error.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{routing::{get, post}, Router};
+/// use axum_auth::{logout_route, AuthUser, MemoryRevocationStore, SessionAuthLayer, SessionIssuer};
+/// use std::sync::Arc;
+///
+/// let revocations = Arc::new(MemoryRevocationStore::new());
+/// let issuer = SessionIssuer::new(*b"an example key which is 32 bytes");
+///
+/// let app: Router = Router::new()
+///     .route("/", get(|user: AuthUser| async move { format!("Hello, {}!", user.id) }))
+///     .layer(SessionAuthLayer::new(issuer.clone()).with_revocations(revocations.clone()))
+///     .route("/logout", post(logout_route(revocations, issuer)));
+/// ```
+pub fn logout_route<R: RevocationStore>(
+    revocations: Arc<R>,
+    issuer: SessionIssuer,
+) -> impl Fn(HeaderMap) -> Pin<Box<dyn Future<Output = Response> + Send>> + Clone + Send + Sync + 'static
+{
+    move |headers| {
+        let revocations = revocations.clone();
+        let issuer = issuer.clone();
+        Box::pin(async move { logout(&*revocations, &issuer, headers).await })
+    }
+}
+
+/// Makes a logout handler like [logout_route], which also revokes the bearer token the request was sent with once the validator accepts it
+///
+/// This is enabled via the `session` and `auth-bearer` features alongside a crypto backend
+///
+/// Bearer tokens are revoked for as long as sessions of the issuer last, so set its TTL to at least the lifetime of your tokens. Tokens the validator rejects are never stored, so forged ones can't fill the store. Revocations only take effect when the same store is given to [Revocable].
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{routing::post, Router};
+/// use axum_auth::{bearer_logout_route, MemoryRevocationStore, SessionIssuer};
+/// # use axum_auth::{AuthBearer, AuthUser, AuthValidator, Rejection};
+/// # struct MyValidator;
+/// # impl AuthValidator<AuthBearer> for MyValidator {
+/// #     async fn validate(&self, _: &AuthBearer) -> Result<AuthUser, Rejection> {
+/// #         Ok(AuthUser::new("alice"))
+/// #     }
+/// # }
+/// use std::sync::Arc;
+///
+/// let revocations = Arc::new(MemoryRevocationStore::new());
+/// let issuer = SessionIssuer::new(*b"an example key which is 32 bytes");
+///
+/// let app: Router = Router::new()
+///     .route("/logout", post(bearer_logout_route(revocations, issuer, MyValidator)));
+/// ```
+#[cfg(feature = "auth-bearer")]
+pub fn bearer_logout_route<R, V>(
+    revocations: Arc<R>,
+    issuer: SessionIssuer,
+    validator: V,
+) -> impl Fn(HeaderMap) -> Pin<Box<dyn Future<Output = Response> + Send>> + Clone + Send + Sync + 'static
+where
+    R: RevocationStore,
+    V: AuthValidator<AuthBearer>,
+{
+    let validator = Arc::new(validator);
+    move |headers| {
+        let revocations = revocations.clone();
+        let issuer = issuer.clone();
+        let validator = validator.clone();
+        Box::pin(async move {
+            // Revoke the bearer token, which has to be valid so forged ones can't fill the store
+            let (mut parts, _) = Request::new(()).into_parts();
+            parts.headers = headers;
+            if let Ok(bearer) = AuthBearer::decode_request_parts(&mut parts) {
+                if validator
+                    .validate_with_extensions(&bearer, &mut parts.extensions)
+                    .await
+                    .is_ok()
+                {
+                    revocations
+                        .revoke(&token_id(&bearer.0), SystemTime::now() + issuer.ttl)
+                        .await;
+                }
+            }
+            logout(&*revocations, &issuer, parts.headers).await
+        })
+    }
+}
+
+/// Revokes the session of a logout request, responding with `204 NO CONTENT`
+async fn logout<R: RevocationStore>(
+    revocations: &R,
+    issuer: &SessionIssuer,
+    headers: HeaderMap,
+) -> Response {
+    // Revoke the session, which has to be valid so forged ones can't fill the store
+    if let Some(Ok((_, revocation))) = issuer.find(&headers).map(|session| issuer.decode(session)) {
+        revocations.revoke(&revocation.id, revocation.expires).await;
+    }
+
+    (
+        StatusCode::NO_CONTENT,
+        [(SET_COOKIE, issuer.clear_cookie())],
+    )
+        .into_response()
+}
+
+/// Remember-me token series stored for a user, checked when the short-lived session is gone
+///
+/// This is enabled via the `session` feature alongside a crypto backend
+///
+/// Only a digest of the verifier is kept, so a leaked store can't be used to log in.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RememberMeToken {
+    /// User the token logs in as
+    pub user: AuthUser,
+    /// SHA-256 digest of the verifier half of the cookie
+    pub verifier_hash: Vec<u8>,
+    /// When the token stops being accepted
+    pub expires: SystemTime,
+}
+
+/// Store remembering the remember-me tokens of users by their selector, used by [RememberMe]
+///
+/// This is enabled via the `session` feature alongside a crypto backend
+///
+/// The in-memory [MemoryRememberMeStore] works for a single server, but remember-me tokens are meant to outlast restarts, so implement this yourself for a database in production. The `()` store never remembers anything, which is what layers use until they're given a real one.
+pub trait RememberMeStore: Send + Sync + 'static {
+    /// Stores the token under its selector; you need to implement this
+    fn insert(&self, selector: &str, token: RememberMeToken) -> impl Future<Output = ()> + Send;
+
+    /// Removes the token with the selector, giving it back if there was one; you need to implement this
+    fn take(&self, selector: &str) -> impl Future<Output = Option<RememberMeToken>> + Send;
+
+    /// Removes every token of the user, which happens when one of them looks stolen; you need to implement this
+    fn remove_user(&self, id: &str) -> impl Future<Output = ()> + Send;
+}
+
+impl RememberMeStore for () {
+    async fn insert(&self, _: &str, _: RememberMeToken) {}
+
+    async fn take(&self, _: &str) -> Option<RememberMeToken> {
+        None
+    }
+
+    async fn remove_user(&self, _: &str) {}
+}
+
+/// In-memory [RememberMeStore] which forgets tokens once they expire
+///
+/// This is enabled via the `session` feature alongside a crypto backend
+#[derive(Debug, Default)]
+pub struct MemoryRememberMeStore {
+    tokens: Mutex<HashMap<String, RememberMeToken>>,
+}
+
+impl MemoryRememberMeStore {
+    /// Creates a new empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl RememberMeStore for MemoryRememberMeStore {
+    async fn insert(&self, selector: &str, token: RememberMeToken) {
+        let now = SystemTime::now();
+        let mut tokens = self.tokens.lock().unwrap();
+
+        // Forget expired tokens so the store doesn't grow forever
+        tokens.retain(|_, token| token.expires > now);
+        tokens.insert(selector.to_string(), token);
+    }
+
+    async fn take(&self, selector: &str) -> Option<RememberMeToken> {
+        self.tokens.lock().unwrap().remove(selector)
+    }
+
+    async fn remove_user(&self, id: &str) {
+        self.tokens
+            .lock()
+            .unwrap()
+            .retain(|_, token| token.user.id != id);
+    }
+}
+
+/// Long-lived "remember me" cookies which log users back in once their short-lived session has expired
+///
+/// This is enabled via the `session` feature alongside a crypto backend
+///
+/// Cookies are made of a random selector, used to find the token in the [RememberMeStore], and a random verifier, whose digest is checked against the stored one. Every time a cookie is used it's swapped for a new one, so each can only be used once. If a used cookie turns up again, it was probably stolen, so every remember-me token of the user is removed to log both the thief and the user out.
+///
+/// Give this to [SessionAuthLayer::with_remember_me] so sessions are refreshed without the client noticing, and to [BasicLogin::with_remember_me] to issue the cookie when logging in. Logging out has to use [RememberMe::logout_route] so the cookie is forgotten too.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{routing::{get, post}, Router};
+/// use axum_auth::{
+///     AuthUser, BasicLogin, BasicUser, BasicUsers, MemoryRememberMeStore, MemoryRevocationStore,
+///     RememberMe, SessionAuthLayer, SessionIssuer,
+/// };
+/// use std::{sync::Arc, time::Duration};
+///
+/// let issuer = SessionIssuer::new(*b"an example key which is 32 bytes").with_ttl(Duration::from_secs(15 * 60));
+/// let remember_me = RememberMe::new(Arc::new(MemoryRememberMeStore::new()));
+/// let revocations = Arc::new(MemoryRevocationStore::new());
+/// let users = BasicUsers::new().with_user("alice", BasicUser::new("hunter2"));
+///
+/// let app: Router = Router::new()
+///     .route("/", get(|user: AuthUser| async move { format!("Hello, {}!", user.id) }))
+///     .layer(
+///         SessionAuthLayer::new(issuer.clone())
+///             .with_revocations(revocations.clone())
+///             .with_remember_me(remember_me.clone()),
+///     )
+///     .route("/login", post(BasicLogin::new("App", users, issuer.clone()).with_remember_me(remember_me.clone()).handler()))
+///     .route("/logout", post(remember_me.logout_route(revocations, issuer)));
+/// ```
+#[derive(Debug)]
+pub struct RememberMe<M> {
+    store: Arc<M>,
+    cookie_name: Arc<str>,
+    ttl: Duration,
+    secure: bool,
+}
+
+impl<M> RememberMe<M> {
+    /// Default amount of time remember-me cookies last for, which is 30 days
+    pub const DEFAULT_TTL: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+    /// Default name of the cookie holding the remember-me token
+    pub const DEFAULT_COOKIE_NAME: &'static str = "remember_me";
+
+    /// Creates new remember-me cookies kept in the store
+    pub fn new(store: Arc<M>) -> Self {
+        Self {
+            store,
+            cookie_name: Self::DEFAULT_COOKIE_NAME.into(),
+            ttl: Self::DEFAULT_TTL,
+            secure: true,
+        }
+    }
+
+    /// Sets the name of the cookie holding the remember-me token, returning it back
+    pub fn with_cookie_name(mut self, cookie_name: impl AsRef<str>) -> Self {
+        self.cookie_name = cookie_name.as_ref().into();
+        self
+    }
+
+    /// Sets how long remember-me cookies last for, returning it back
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Sets if cookies are only sent over HTTPS (which is the default), returning it back
+    pub fn with_secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+        self
+    }
+
+    /// Makes a `Set-Cookie` header value which removes the remember-me cookie from the browser
+    fn clear_cookie(&self) -> HeaderValue {
+        make_cookie(&self.cookie_name, "", Duration::ZERO, self.secure)
+    }
+}
+
+impl<M: RememberMeStore> RememberMe<M> {
+    /// Issues a new remember-me token for the user as the value of a `Set-Cookie` header
+    pub async fn cookie(&self, user: &AuthUser) -> HeaderValue {
+        let selector = general_purpose::URL_SAFE_NO_PAD.encode(crypto::random(12));
+        let verifier = crypto::random(32);
+        let token = RememberMeToken {
+            user: user.clone(),
+            verifier_hash: crypto::sha256(&verifier),
+            expires: SystemTime::now() + self.ttl,
+        };
+        self.store.insert(&selector, token).await;
+
+        let value = format!(
+            "{}.{}",
+            selector,
+            general_purpose::URL_SAFE_NO_PAD.encode(verifier)
+        );
+        make_cookie(&self.cookie_name, &value, self.ttl, self.secure)
+    }
+
+    /// Checks the remember-me cookie of a request, giving back the user along with the rotated cookie, or the rejection along with a cookie clearing it
+    ///
+    /// This gives back [None] if there's no remember-me cookie at all.
+    async fn refresh(
+        &self,
+        headers: &HeaderMap,
+    ) -> Option<Result<(AuthUser, HeaderValue), (Rejection, HeaderValue)>> {
+        let cookie = find_cookie(headers, &self.cookie_name)?;
+        let rejected = || {
+            Err((
+                (StatusCode::UNAUTHORIZED, ERR_REMEMBER_ME),
+                self.clear_cookie(),
+            ))
+        };
+        let Some((selector, verifier)) = cookie.split_once('.') else {
+            return Some(rejected());
+        };
+        let Ok(verifier) = general_purpose::URL_SAFE_NO_PAD.decode(verifier) else {
+            return Some(rejected());
+        };
+
+        // Every cookie can only be used once, so take it out whatever happens
+        let Some(token) = self.store.take(selector).await else {
+            return Some(rejected());
+        };
+        if !constant_eq(&crypto::sha256(&verifier), &token.verifier_hash) {
+            // The selector was right but the verifier wasn't, so the cookie was probably stolen
+            self.store.remove_user(&token.user.id).await;
+            return Some(rejected());
+        }
+        if token.expires <= SystemTime::now() {
+            return Some(rejected());
+        }
+
+        let cookie = self.cookie(&token.user).await;
+        Some(Ok((token.user, cookie)))
+    }
+
+    /// Makes a logout handler like [logout_route], which also forgets the remember-me token and clears its cookie
+    pub fn logout_route<R: RevocationStore>(
+        self,
+        revocations: Arc<R>,
+        issuer: SessionIssuer,
+    ) -> impl Fn(HeaderMap) -> Pin<Box<dyn Future<Output = Response> + Send>>
+           + Clone
+           + Send
+           + Sync
+           + 'static {
+        let remember_me = Arc::new(self);
+        move |headers| {
+            let remember_me = remember_me.clone();
+            let revocations = revocations.clone();
+            let issuer = issuer.clone();
+            Box::pin(async move {
+                if let Some((selector, _)) = find_cookie(&headers, &remember_me.cookie_name)
+                    .and_then(|cookie| cookie.split_once('.'))
+                {
+                    remember_me.store.take(selector).await;
+                }
+                let mut resp = logout(&*revocations, &issuer, headers).await;
+                resp.headers_mut()
+                    .append(SET_COOKIE, remember_me.clear_cookie());
+                resp
+            })
+        }
+    }
+}
+
+impl<M> Clone for RememberMe<M> {
+    fn clone(&self) -> Self {
+        Self {
+            store: self.store.clone(),
+            cookie_name: self.cookie_name.clone(),
+            ttl: self.ttl,
+            secure: self.secure,
+        }
+    }
+}
+
+/// Login handler checking basic auth once and giving back a session cookie, so the password doesn't have to be sent with every request
+///
+/// This is enabled via the `session` feature alongside a crypto backend
+///
+/// Valid credentials get a `204 NO CONTENT` with the session in a `Set-Cookie` header, which a [SessionAuthLayer] with the same issuer accepts from then on. See [SessionIssuer] for an example.
+///
+/// # Errors
+///
+/// Anything but valid credentials gets a `401 UNAUTHORIZED` with a basic challenge for the realm, so browsers show their login prompt. The message is the one given by the extractor or the [AuthValidator].
+pub struct BasicLogin<V = BasicUsers, M = ()> {
+    realm: Arc<str>,
+    validator: Arc<V>,
+    issuer: SessionIssuer,
+    remember_me: Option<RememberMe<M>>,
+}
+
+impl<V> BasicLogin<V> {
+    /// Creates a new login for the realm, checking credentials with the validator and issuing sessions with the issuer
+    pub fn new(realm: impl AsRef<str>, validator: V, issuer: SessionIssuer) -> Self {
+        Self {
+            realm: realm.as_ref().into(),
+            validator: Arc::new(validator),
+            issuer,
+            remember_me: None,
+        }
+    }
+}
+
+impl<V, M> BasicLogin<V, M> {
+    /// Also issues a remember-me cookie when logging in, returning it back
+    pub fn with_remember_me<T: RememberMeStore>(
+        self,
+        remember_me: RememberMe<T>,
+    ) -> BasicLogin<V, T> {
+        BasicLogin {
+            realm: self.realm,
+            validator: self.validator,
+            issuer: self.issuer,
+            remember_me: Some(remember_me),
+        }
+    }
+}
+
+impl<V: AuthValidator<AuthBasic>, M: RememberMeStore> BasicLogin<V, M> {
+    /// Responds to the login request with the headers it was sent with
+    pub async fn respond(&self, headers: HeaderMap) -> Response {
+        // Get the credentials just like the extractor would
+        let (mut parts, _) = Request::new(()).into_parts();
+        parts.headers = headers;
+        let credentials = match AuthBasic::decode_request_parts(&mut parts) {
+            Ok(credentials) => credentials,
+            Err((_, message)) => return self.unauthorized(message),
+        };
+
+        // Validate them and swap them for a session
+        match self
+            .validator
+            .validate_with_extensions(&credentials, &mut parts.extensions)
+            .await
+        {
+            Ok(user) => {
+                let mut resp = (
+                    StatusCode::NO_CONTENT,
+                    [(SET_COOKIE, self.issuer.cookie(&user))],
+                )
+                    .into_response();
+                if let Some(remember_me) = &self.remember_me {
+                    resp.headers_mut()
+                        .append(SET_COOKIE, remember_me.cookie(&user).await);
+                }
+                resp
+            }
+            Err((_, message)) => self.unauthorized(message),
+        }
+    }
+
+    /// Turns this into a handler for the login route
+    pub fn handler(
+        self,
+    ) -> impl Fn(HeaderMap) -> Pin<Box<dyn Future<Output = Response> + Send>>
+           + Clone
+           + Send
+           + Sync
+           + 'static {
+        let login = Arc::new(self);
+        move |headers| {
+            let login = login.clone();
+            Box::pin(async move { login.respond(headers).await })
+        }
+    }
+
+    /// Responds with a challenge so the browser asks for credentials
+    fn unauthorized(&self, message: &'static str) -> Response {
+        Unauthorized::new()
+            .with_challenge(Challenge::basic(&*self.realm))
+            .with_message(message)
+            .into_response()
+    }
+}
+
+impl<V, M> Clone for BasicLogin<V, M> {
+    fn clone(&self) -> Self {
+        Self {
+            realm: self.realm.clone(),
+            validator: self.validator.clone(),
+            issuer: self.issuer.clone(),
+            remember_me: self.remember_me.clone(),
+        }
+    }
+}
@@ -0,0 +1,188 @@
+//! Implementation of links which are signed to work without credentials until they expire
+//!
+//! See [SignedUrl] for the most commonly-used data structure
+
+use crate::{
+    constant_eq, crypto, Rejection, ERR_NO_URL_SIGNER, ERR_URL_EXPIRED, ERR_URL_SIGNATURE,
+};
+use axum_core::extract::FromRequestParts;
+use base64::{engine::general_purpose, Engine};
+use http::{request::Parts, StatusCode};
+use std::{
+    fmt,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Signer of links which work without credentials until they expire, like download links sent by email
+///
+/// This is enabled via the `signed-url` feature alongside a crypto backend
+///
+/// Links get an `expires` parameter with the Unix time they stop working, followed by a `signature` parameter with the HMAC-SHA256 of everything before it. As the expiry is signed along with the path and the rest of the query, clients can't make a link last longer by changing it. Insert this into the request extensions so [SignedUrl] can check links with it.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{routing::get, Extension, Router};
+/// use axum_auth::{SignedUrl, UrlSigner};
+/// use std::time::{Duration, SystemTime};
+///
+/// let signer = UrlSigner::new(b"a long random secret key");
+/// let link = signer.sign("/files/report.pdf", SystemTime::now() + Duration::from_secs(60 * 60));
+///
+/// let app: Router = Router::new()
+///     .route("/files/{name}", get(|url: SignedUrl| async move { format!("Valid for {:?}", url.remaining()) }))
+///     .layer(Extension(signer));
+/// ```
+#[derive(Clone)]
+pub struct UrlSigner {
+    key: Arc<[u8]>,
+}
+
+impl UrlSigner {
+    /// Creates a new signer using the secret key
+    pub fn new(key: impl AsRef<[u8]>) -> Self {
+        Self {
+            key: key.as_ref().into(),
+        }
+    }
+
+    /// Signs the path and query of a link so it works until the given time, giving back the path and query to send out
+    pub fn sign(&self, path_and_query: &str, expires: SystemTime) -> String {
+        let separator = if path_and_query.contains('?') {
+            '&'
+        } else {
+            '?'
+        };
+        let unsigned = format!(
+            "{}{}expires={}",
+            path_and_query,
+            separator,
+            unix_secs(expires)
+        );
+        let signature = self.signature(&unsigned);
+        format!("{}&signature={}", unsigned, signature)
+    }
+
+    /// Checks the path and query of a signed link, giving back when it expires if it's valid
+    ///
+    /// # Errors
+    ///
+    /// Links with a missing or wrong signature, or which have expired, are rejected with `403 FORBIDDEN`, see [SignedUrl] for the messages.
+    pub fn verify(&self, path_and_query: &str) -> Result<SignedUrl, Rejection> {
+        let invalid = (StatusCode::FORBIDDEN, ERR_URL_SIGNATURE);
+
+        // The signature is always last, signing everything before it
+        let (unsigned, signature) = path_and_query.rsplit_once("&signature=").ok_or(invalid)?;
+        let signature = general_purpose::URL_SAFE_NO_PAD
+            .decode(signature)
+            .map_err(|_| invalid)?;
+        if !constant_eq(
+            &crypto::hmac_sha256(&self.key, unsigned.as_bytes()),
+            &signature,
+        ) {
+            return Err(invalid);
+        }
+
+        // Only trust the expiry once it's known to be signed
+        let (path, query) = unsigned.split_once('?').ok_or(invalid)?;
+        let expires = query
+            .split('&')
+            .filter_map(|param| param.strip_prefix("expires="))
+            .next_back()
+            .and_then(|secs| secs.parse().ok())
+            .and_then(|secs| UNIX_EPOCH.checked_add(Duration::from_secs(secs)))
+            .ok_or(invalid)?;
+        if expires <= SystemTime::now() {
+            return Err((StatusCode::FORBIDDEN, ERR_URL_EXPIRED));
+        }
+
+        Ok(SignedUrl {
+            path: path.to_string(),
+            expires,
+        })
+    }
+
+    /// Makes the signature of the unsigned path and query
+    fn signature(&self, unsigned: &str) -> String {
+        general_purpose::URL_SAFE_NO_PAD.encode(crypto::hmac_sha256(&self.key, unsigned.as_bytes()))
+    }
+}
+
+impl fmt::Debug for UrlSigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UrlSigner").finish_non_exhaustive()
+    }
+}
+
+/// Link made by a [UrlSigner] which was checked to be signed and not expired
+///
+/// This is enabled via the `signed-url` feature alongside a crypto backend
+///
+/// The [UrlSigner] is taken from the request extensions, and the link is checked as seen by the handler, so sign links using the path within any nested router. Handlers can use [remaining](Self::remaining) to warn clients when a link is about to stop working.
+///
+/// # Example
+///
+/// ```no_run
+/// use axum_auth::SignedUrl;
+/// use std::time::Duration;
+///
+/// /// Downloads a file, warning if the link is about to expire
+/// async fn handler(url: SignedUrl) -> String {
+///     if url.remaining() < Duration::from_secs(60) {
+///         format!("Downloading {} but hurry, this link is nearly expired", url.path)
+///     } else {
+///         format!("Downloading {}", url.path)
+///     }
+/// }
+/// ```
+///
+/// # Errors
+///
+/// Links which aren't valid get `403 FORBIDDEN` with one of these messages:
+///
+/// - Signed URL is missing its signature or it's invalid – The link wasn't signed, was changed, or was signed with another key
+/// - Signed URL has expired – The link was signed properly but its expiry has passed
+///
+/// If no [UrlSigner] was inserted into the request extensions, this responds with `500 INTERNAL SERVER ERROR` and the message "No URL signer was found for this request".
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SignedUrl {
+    /// Path of the link
+    pub path: String,
+    /// When the link stops working
+    pub expires: SystemTime,
+}
+
+impl SignedUrl {
+    /// Gets how long the link keeps working for
+    pub fn remaining(&self) -> Duration {
+        self.expires
+            .duration_since(SystemTime::now())
+            .unwrap_or_default()
+    }
+}
+
+impl<B> FromRequestParts<B> for SignedUrl
+where
+    B: Send + Sync,
+{
+    type Rejection = Rejection;
+
+    async fn from_request_parts(parts: &mut Parts, _: &B) -> Result<Self, Self::Rejection> {
+        let signer = parts
+            .extensions
+            .get::<UrlSigner>()
+            .ok_or((StatusCode::INTERNAL_SERVER_ERROR, ERR_NO_URL_SIGNER))?;
+        let path_and_query = parts
+            .uri
+            .path_and_query()
+            .map_or("", |path_and_query| path_and_query.as_str());
+        signer.verify(path_and_query)
+    }
+}
+
+/// Gets the time as seconds since the Unix epoch
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
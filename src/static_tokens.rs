@@ -0,0 +1,214 @@
+//! Implementation of a few hardcoded bearer tokens for machines, like CI and monitoring
+//!
+//! See [StaticTokens] for the most commonly-used data structure
+
+use crate::{
+    constant_eq, AuthBearer, AuthUser, AuthValidator, Rejection, RequireAuthLayer,
+    ERR_INVALID_CREDENTIALS,
+};
+use http::{Extensions, StatusCode};
+use std::fmt;
+
+/// Name of the static token a request was authenticated with, which [StaticTokens] puts into the request extensions
+///
+/// This is enabled via the `auth-bearer` feature
+///
+/// # Example
+///
+/// ```no_run
+/// use axum::Extension;
+/// use axum_auth::TokenName;
+///
+/// /// Tells the caller which of the machine tokens they used
+/// async fn handler(Extension(TokenName(name)): Extension<TokenName>) -> String {
+///     format!("Hello, {}!", name)
+/// }
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct TokenName(pub String);
+
+/// Static bearer token alongside the roles and scopes it's granted, used in [StaticTokens]
+///
+/// This is enabled via the `auth-bearer` feature
+///
+/// Plain strings turn into tokens without any roles or scopes, so they can be given anywhere one of these is needed. The token is redacted from the [Debug](fmt::Debug) output so it can be logged safely.
+#[derive(PartialEq, Eq, Clone)]
+pub struct StaticToken {
+    token: String,
+    roles: Vec<String>,
+    scopes: Vec<String>,
+}
+
+impl StaticToken {
+    /// Creates a new token without any roles or scopes
+    pub fn new(token: impl Into<String>) -> Self {
+        Self {
+            token: token.into(),
+            roles: vec![],
+            scopes: vec![],
+        }
+    }
+
+    /// Adds roles to the token, returning it back
+    pub fn with_roles<I, R>(mut self, roles: I) -> Self
+    where
+        I: IntoIterator<Item = R>,
+        R: Into<String>,
+    {
+        self.roles.extend(roles.into_iter().map(Into::into));
+        self
+    }
+
+    /// Adds scopes to the token, returning it back
+    pub fn with_scopes<I, S>(mut self, scopes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.scopes.extend(scopes.into_iter().map(Into::into));
+        self
+    }
+}
+
+impl From<String> for StaticToken {
+    fn from(token: String) -> Self {
+        Self::new(token)
+    }
+}
+
+impl From<&str> for StaticToken {
+    fn from(token: &str) -> Self {
+        Self::new(token)
+    }
+}
+
+impl fmt::Debug for StaticToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StaticToken")
+            .field("token", &"[redacted]")
+            .field("roles", &self.roles)
+            .field("scopes", &self.scopes)
+            .finish()
+    }
+}
+
+/// Fixed set of named bearer tokens, used as the validator of a [RequireBearerAuthLayer](crate::RequireBearerAuthLayer)
+///
+/// This is enabled via the `auth-bearer` feature
+///
+/// Suits the few hardcoded tokens handed out to machines like CI and monitoring, without any token format or database. The name of the token becomes the identifier of the [AuthUser], and is also put into the request extensions as a [TokenName]. Each token can be a [StaticToken] carrying its own roles and scopes, which are put onto the [AuthUser] so guards like [RequireRole](crate::RequireRole) and [RequireScope](crate::RequireScope) work without any other authorization setup.
+///
+/// Every token is compared in constant time, and all of them are always compared, so neither the tokens nor which one was close leak through timing. The tokens are redacted from the [Debug](fmt::Debug) output so the validator can be logged safely.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{routing::get, Extension, Router};
+/// use axum_auth::{RequireBearerAuthLayer, TokenName};
+///
+/// let app: Router = Router::new()
+///     .route("/", get(|Extension(TokenName(name)): Extension<TokenName>| async move { format!("Hello, {}!", name) }))
+///     .layer(RequireBearerAuthLayer::from_tokens([("ci", "tok1"), ("monitor", "tok2")]));
+/// ```
+///
+/// Tokens can also be granted roles and scopes:
+///
+/// ```rust
+/// use axum_auth::{StaticToken, StaticTokens};
+///
+/// let tokens = StaticTokens::new()
+///     .with_token("ci", StaticToken::new("tok1").with_scopes(["deploy"]))
+///     .with_token("monitor", StaticToken::new("tok2").with_roles(["reader"]));
+/// ```
+///
+/// # Errors
+///
+/// If the token isn't one of the static tokens, this responds with `401 UNAUTHORIZED` and the message "The provided credentials are invalid".
+#[derive(PartialEq, Eq, Clone, Default)]
+pub struct StaticTokens {
+    tokens: Vec<(String, StaticToken)>,
+}
+
+impl StaticTokens {
+    /// Creates a new validator without any tokens yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Accepts the token under the name, replacing any token with the same name and returning it back
+    pub fn with_token(mut self, name: impl Into<String>, token: impl Into<StaticToken>) -> Self {
+        self.insert(name, token);
+        self
+    }
+
+    /// Accepts the token under the name in-place, replacing any token with the same name
+    pub fn insert(&mut self, name: impl Into<String>, token: impl Into<StaticToken>) {
+        let name = name.into();
+        self.remove(&name);
+        self.tokens.push((name, token.into()));
+    }
+
+    /// Stops accepting the token with the name, returning if it existed
+    pub fn remove(&mut self, name: &str) -> bool {
+        let len = self.tokens.len();
+        self.tokens.retain(|(existing, _)| existing != name);
+        self.tokens.len() != len
+    }
+
+    /// Finds the name and details of the token, comparing against every token so the time taken doesn't depend on which one matched
+    fn find(&self, token: &str) -> Option<&(String, StaticToken)> {
+        self.tokens.iter().fold(None, |found, entry| {
+            let matches = constant_eq(entry.1.token.as_bytes(), token.as_bytes());
+            found.or(matches.then_some(entry))
+        })
+    }
+}
+
+impl<N: Into<String>, T: Into<StaticToken>> FromIterator<(N, T)> for StaticTokens {
+    fn from_iter<I: IntoIterator<Item = (N, T)>>(iter: I) -> Self {
+        iter.into_iter().fold(Self::new(), |tokens, (name, token)| {
+            tokens.with_token(name, token)
+        })
+    }
+}
+
+impl fmt::Debug for StaticTokens {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.tokens.iter().map(|(name, token)| (name, token)))
+            .finish()
+    }
+}
+
+impl AuthValidator<AuthBearer> for StaticTokens {
+    async fn validate(&self, credentials: &AuthBearer) -> Result<AuthUser, Rejection> {
+        self.validate_with_extensions(credentials, &mut Extensions::new())
+            .await
+    }
+
+    async fn validate_with_extensions(
+        &self,
+        credentials: &AuthBearer,
+        extensions: &mut Extensions,
+    ) -> Result<AuthUser, Rejection> {
+        let (name, token) = self
+            .find(&credentials.0)
+            .ok_or((StatusCode::UNAUTHORIZED, ERR_INVALID_CREDENTIALS))?;
+        extensions.insert(TokenName(name.clone()));
+        Ok(AuthUser::new(name.clone())
+            .with_roles(token.roles.iter().cloned())
+            .with_scopes(token.scopes.iter().cloned()))
+    }
+}
+
+impl RequireAuthLayer<AuthBearer, StaticTokens> {
+    /// Creates a new layer only accepting the named static tokens, see [StaticTokens] for more information
+    pub fn from_tokens<I, N, T>(tokens: I) -> Self
+    where
+        I: IntoIterator<Item = (N, T)>,
+        N: Into<String>,
+        T: Into<StaticToken>,
+    {
+        Self::new(tokens.into_iter().collect())
+    }
+}
@@ -0,0 +1,235 @@
+//! Implementation of the in-memory credential stores used by the layers
+//!
+//! See [BasicUsers] for the most commonly-used data structure
+
+use crate::{constant_eq, AuthBasic, AuthUser, AuthValidator, Rejection, ERR_INVALID_CREDENTIALS};
+use arc_swap::ArcSwap;
+use http::StatusCode;
+use std::{collections::HashMap, fmt, io, sync::Arc};
+
+/// User which can log in using basic auth, alongside the roles and scopes they're granted
+///
+/// The password is redacted from the [Debug](fmt::Debug) output so stores can be logged safely.
+#[derive(PartialEq, Eq, Clone)]
+pub struct BasicUser {
+    password: String,
+    roles: Vec<String>,
+    scopes: Vec<String>,
+}
+
+impl BasicUser {
+    /// Creates a new user with the given password and no roles or scopes
+    pub fn new(password: impl Into<String>) -> Self {
+        Self {
+            password: password.into(),
+            roles: vec![],
+            scopes: vec![],
+        }
+    }
+
+    /// Adds roles to the user, returning it back
+    pub fn with_roles<I, R>(mut self, roles: I) -> Self
+    where
+        I: IntoIterator<Item = R>,
+        R: Into<String>,
+    {
+        self.roles.extend(roles.into_iter().map(Into::into));
+        self
+    }
+
+    /// Adds scopes to the user, returning it back
+    pub fn with_scopes<I, S>(mut self, scopes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.scopes.extend(scopes.into_iter().map(Into::into));
+        self
+    }
+}
+
+impl fmt::Debug for BasicUser {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BasicUser")
+            .field("password", &"[redacted]")
+            .field("roles", &self.roles)
+            .field("scopes", &self.scopes)
+            .finish()
+    }
+}
+
+/// In-memory store of users for basic auth, used as the validator of a [RequireBasicAuthLayer](crate::RequireBasicAuthLayer)
+///
+/// This is enabled via the `auth-basic` feature
+///
+/// Each user carries their own roles and scopes, which are put onto the [AuthUser] once they've logged in so guards like [RequireRole](crate::RequireRole) work for password-based deployments too.
+///
+/// # Example
+///
+/// ```rust
+/// use axum_auth::{BasicUser, BasicUsers};
+///
+/// let users = BasicUsers::new()
+///     .with_user("alice", BasicUser::new("hunter2").with_roles(["admin"]))
+///     .with_user("bob", BasicUser::new("letmein").with_scopes(["posts:read"]));
+/// ```
+///
+/// # Errors
+///
+/// If the user doesn't exist or the password is wrong, this responds with `401 UNAUTHORIZED` and the message "The provided credentials are invalid". Passwords are compared in constant time.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct BasicUsers {
+    users: HashMap<String, BasicUser>,
+}
+
+impl BasicUsers {
+    /// Creates a new empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a user to the store, returning it back
+    pub fn with_user(mut self, id: impl Into<String>, user: BasicUser) -> Self {
+        self.insert(id, user);
+        self
+    }
+
+    /// Adds a user to the store in-place, replacing any existing user with the same identifier
+    pub fn insert(&mut self, id: impl Into<String>, user: BasicUser) {
+        self.users.insert(id.into(), user);
+    }
+
+    /// Removes a user from the store, giving it back if it existed
+    pub fn remove(&mut self, id: &str) -> Option<BasicUser> {
+        self.users.remove(id)
+    }
+}
+
+impl AuthValidator<AuthBasic> for BasicUsers {
+    async fn validate(&self, credentials: &AuthBasic) -> Result<AuthUser, Rejection> {
+        let (id, password) = &credentials.0;
+        let password = password.as_deref().unwrap_or_default();
+        match self.users.get(id) {
+            Some(user) if constant_eq(user.password.as_bytes(), password.as_bytes()) => {
+                Ok(AuthUser::new(id.clone())
+                    .with_roles(user.roles.iter().cloned())
+                    .with_scopes(user.scopes.iter().cloned()))
+            }
+            _ => Err((StatusCode::UNAUTHORIZED, ERR_INVALID_CREDENTIALS)),
+        }
+    }
+}
+
+/// Function reading the users of a [StoreHandle] again from wherever they're kept
+type Loader = dyn Fn() -> io::Result<BasicUsers> + Send + Sync;
+
+/// Shared handle to a store of users for basic auth, letting long-running servers change who can log in without restarting
+///
+/// This is enabled via the `auth-basic` feature
+///
+/// Clones of the handle all share the same users, so one can be used as the validator of a [RequireBasicAuthLayer](crate::RequireBasicAuthLayer) while others are kept by admin endpoints or a `SIGHUP` handler. Every change builds a new set of users and swaps it in all at once, so requests never see a half-made change. Requests read the current users without taking a lock, so they never wait on each other or on a reload.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{routing::get, Router};
+/// use axum_auth::{AuthUser, BasicUser, BasicUsers, RequireBasicAuthLayer, StoreHandle};
+///
+/// let handle = StoreHandle::new(BasicUsers::new().with_user("alice", BasicUser::new("hunter2")));
+///
+/// let app: Router = Router::new()
+///     .route("/", get(|user: AuthUser| async move { format!("Hello, {}!", user.id) }))
+///     .layer(RequireBasicAuthLayer::new(handle.clone()));
+///
+/// // Later on, e.g. from an admin endpoint
+/// handle.insert_user("bob", BasicUser::new("letmein"));
+/// handle.remove_user("alice");
+/// ```
+///
+/// # Errors
+///
+/// Reloading fails with the error given by the loader, or one of kind [Unsupported](io::ErrorKind::Unsupported) if there's nothing to reload from. Requests are validated in the same way as [BasicUsers].
+#[derive(Clone)]
+pub struct StoreHandle {
+    users: Arc<ArcSwap<BasicUsers>>,
+    loader: Option<Arc<Loader>>,
+}
+
+impl StoreHandle {
+    /// Creates a new handle to the given users
+    pub fn new(users: BasicUsers) -> Self {
+        Self {
+            users: Arc::new(ArcSwap::from_pointee(users)),
+            loader: None,
+        }
+    }
+
+    /// Sets the function used to read the users again when reloading, returning it back
+    pub fn with_loader<F>(mut self, loader: F) -> Self
+    where
+        F: Fn() -> io::Result<BasicUsers> + Send + Sync + 'static,
+    {
+        self.loader = Some(Arc::new(loader));
+        self
+    }
+
+    /// Reads the users again using the loader, swapping them in if it succeeds and keeping the current ones if it doesn't
+    pub fn reload(&self) -> io::Result<()> {
+        let loader = self.loader.as_ref().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::Unsupported,
+                "store has no loader to reload from",
+            )
+        })?;
+        self.replace(loader()?);
+        Ok(())
+    }
+
+    /// Adds a user, replacing any existing user with the same identifier
+    pub fn insert_user(&self, id: impl Into<String>, user: BasicUser) {
+        let id = id.into();
+        self.users.rcu(|users| {
+            let mut changed = BasicUsers::clone(users);
+            changed.insert(id.clone(), user.clone());
+            changed
+        });
+    }
+
+    /// Removes a user, giving it back if it existed
+    pub fn remove_user(&self, id: &str) -> Option<BasicUser> {
+        let mut removed = None;
+        self.users.rcu(|users| {
+            let mut changed = BasicUsers::clone(users);
+            removed = changed.remove(id);
+            changed
+        });
+        removed
+    }
+
+    /// Swaps in a whole new set of users
+    pub fn replace(&self, users: BasicUsers) {
+        self.users.store(Arc::new(users));
+    }
+
+    /// Gets the users as they are right now
+    pub fn users(&self) -> Arc<BasicUsers> {
+        self.users.load_full()
+    }
+}
+
+impl fmt::Debug for StoreHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StoreHandle")
+            .field("users", &self.users)
+            .field("loader", &self.loader.is_some())
+            .finish()
+    }
+}
+
+impl AuthValidator<AuthBasic> for StoreHandle {
+    async fn validate(&self, credentials: &AuthBasic) -> Result<AuthUser, Rejection> {
+        // Check against the users without cloning them, which would make every request touch the same counter
+        let users = self.users.load();
+        users.validate(credentials).await
+    }
+}
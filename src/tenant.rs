@@ -0,0 +1,350 @@
+//! Implementation of resolving the tenant of a request alongside its credentials
+//!
+//! See [AuthTenant] for the most commonly-used data structure
+
+use crate::{
+    AuthUser, AuthValidator, Rejection, RequireAuthLayer, ERR_DEFAULT, ERR_NO_TENANT,
+    ERR_TENANT_CHARS, ERR_UNKNOWN_TENANT,
+};
+use axum_core::extract::FromRequestParts;
+use http::{header::HOST, request::Parts, Extensions, StatusCode};
+use std::{
+    collections::HashMap,
+    fmt,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+    net::IpAddr,
+};
+
+/// Identifier of the tenant a request was made for, resolved by [AuthTenant]
+#[derive(Debug, PartialEq, Eq, Clone, Hash, PartialOrd, Ord)]
+pub struct TenantId(pub String);
+
+impl TenantId {
+    /// Gets the identifier as a string
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for TenantId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Part of the request the tenant is taken from, used by [TenantSource]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TenantLocation {
+    /// Header with the given (lowercase) name, like `x-tenant-id`
+    Header(&'static str),
+    /// First label of the host when it has at least three, like `acme` for `acme.example.com`, which is never taken from IP addresses
+    Subdomain,
+    /// First segment of the path, like `acme` for `/acme/orders`
+    PathPrefix,
+}
+
+/// Configuration of where [AuthTenant] finds the tenant of a request
+///
+/// Ready-made sources are [TenantHeader], [TenantSubdomain], and [TenantPathPrefix], but other headers can be used by implementing this yourself:
+///
+/// ```rust
+/// use axum_auth::{AuthBearer, AuthTenant, TenantLocation, TenantSource};
+///
+/// /// Finds tenants using the header our gateway sets
+/// struct OrgHeader;
+///
+/// impl TenantSource for OrgHeader {
+///     const LOCATION: TenantLocation = TenantLocation::Header("x-org");
+/// }
+///
+/// async fn handler(auth: AuthTenant<AuthBearer, OrgHeader>) -> String {
+///     format!("Found a bearer token for {}: {}", auth.tenant, auth.credential.0)
+/// }
+/// ```
+pub trait TenantSource {
+    /// Part of the request the tenant is taken from; you need to implement this
+    const LOCATION: TenantLocation;
+
+    /// Finds the tenant from the parts of a request; this is automatically implemented
+    fn resolve(parts: &Parts) -> Result<TenantId, Rejection> {
+        let tenant = match Self::LOCATION {
+            TenantLocation::Header(name) => parts
+                .headers
+                .get(name)
+                .map(|value| value.to_str().map_err(|_| (ERR_DEFAULT, ERR_TENANT_CHARS)))
+                .transpose()?,
+            TenantLocation::Subdomain => subdomain(parts),
+            TenantLocation::PathPrefix => {
+                parts.uri.path().trim_start_matches('/').split('/').next()
+            }
+        };
+        let tenant = tenant
+            .filter(|tenant| !tenant.is_empty())
+            .ok_or((ERR_DEFAULT, ERR_NO_TENANT))?;
+
+        // Only allow characters which can't be used to escape into another tenant's keys
+        if tenant.len() > 128
+            || !tenant
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c))
+        {
+            return Err((ERR_DEFAULT, ERR_TENANT_CHARS));
+        }
+        Ok(TenantId(tenant.to_string()))
+    }
+}
+
+/// Tenant source using the `X-Tenant-Id` header, which is the default for [AuthTenant]
+#[derive(Debug, Clone, Copy)]
+pub struct TenantHeader;
+
+impl TenantSource for TenantHeader {
+    const LOCATION: TenantLocation = TenantLocation::Header("x-tenant-id");
+}
+
+/// Tenant source using the subdomain of the host, like `acme` for `acme.example.com`
+#[derive(Debug, Clone, Copy)]
+pub struct TenantSubdomain;
+
+impl TenantSource for TenantSubdomain {
+    const LOCATION: TenantLocation = TenantLocation::Subdomain;
+}
+
+/// Tenant source using the first segment of the path, like `acme` for `/acme/orders`
+///
+/// The path is the one seen by the router the handler is in, so nested routers see it without the prefix they were nested at.
+#[derive(Debug, Clone, Copy)]
+pub struct TenantPathPrefix;
+
+impl TenantSource for TenantPathPrefix {
+    const LOCATION: TenantLocation = TenantLocation::PathPrefix;
+}
+
+/// Multi-tenant extractor resolving the tenant of a request together with its credentials
+///
+/// This wraps any other extractor from this crate (or your own custom ones), giving back the [TenantId] and the credentials at once. Getting both from one extractor means a handler can't look up credentials in the wrong tenant by accident, like when mixing a tenant from the path with a token meant for another.
+///
+/// Where the tenant comes from is set using a [TenantSource], which is the `X-Tenant-Id` header by default.
+///
+/// # Example
+///
+/// ```no_run
+/// use axum_auth::{AuthBasic, AuthBearer, AuthTenant, TenantSubdomain};
+///
+/// /// Takes the tenant from the `X-Tenant-Id` header
+/// async fn handler(auth: AuthTenant<AuthBearer>) -> String {
+///     let (tenant, AuthBearer(token)) = auth.into_parts();
+///     format!("Found a bearer token for {}: {}", tenant, token)
+/// }
+///
+/// /// Takes the tenant from the subdomain, like `acme.example.com`
+/// async fn subdomain(auth: AuthTenant<AuthBasic, TenantSubdomain>) -> String {
+///     let AuthBasic((id, _)) = auth.credential;
+///     format!("Welcome to {}, {}", auth.tenant, id)
+/// }
+/// ```
+///
+/// # Errors
+///
+/// The tenant is resolved first, which fails with `400 BAD REQUEST` and one of these messages:
+///
+/// - Tenant is missing from the request – The tenant couldn't be found where the [TenantSource] looks for it
+/// - Tenant contains invalid characters – Tenants can only be up to 128 ASCII letters, digits, `-`, `_`, or `.`
+///
+/// Otherwise this only fails if the wrapped extractor fails, returning its rejection as-is.
+pub struct AuthTenant<A, T = TenantHeader> {
+    /// Tenant the request was made for
+    pub tenant: TenantId,
+    /// Credential from the wrapped extractor
+    pub credential: A,
+    source: PhantomData<fn() -> T>,
+}
+
+impl<A, T> AuthTenant<A, T> {
+    /// Creates the extractor from a tenant and the credentials used for it
+    pub fn new(tenant: TenantId, credential: A) -> Self {
+        Self {
+            tenant,
+            credential,
+            source: PhantomData,
+        }
+    }
+
+    /// Gives back the tenant and credentials as a tuple
+    pub fn into_parts(self) -> (TenantId, A) {
+        (self.tenant, self.credential)
+    }
+}
+
+impl<A: fmt::Debug, T> fmt::Debug for AuthTenant<A, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AuthTenant")
+            .field("tenant", &self.tenant)
+            .field("credential", &self.credential)
+            .finish()
+    }
+}
+
+impl<A: Clone, T> Clone for AuthTenant<A, T> {
+    fn clone(&self) -> Self {
+        Self::new(self.tenant.clone(), self.credential.clone())
+    }
+}
+
+impl<A: PartialEq, T> PartialEq for AuthTenant<A, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.tenant == other.tenant && self.credential == other.credential
+    }
+}
+
+impl<A: Eq, T> Eq for AuthTenant<A, T> {}
+
+impl<A: Hash, T> Hash for AuthTenant<A, T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.tenant.hash(state);
+        self.credential.hash(state);
+    }
+}
+
+impl<A, T, B> FromRequestParts<B> for AuthTenant<A, T>
+where
+    A: FromRequestParts<B> + Send,
+    A::Rejection: From<Rejection>,
+    T: TenantSource,
+    B: Send + Sync,
+{
+    type Rejection = A::Rejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &B) -> Result<Self, Self::Rejection> {
+        // Resolve the tenant first so credentials are never used without one
+        let tenant = T::resolve(parts)?;
+        let credential = A::from_request_parts(parts, state).await?;
+        Ok(Self::new(tenant, credential))
+    }
+}
+
+/// Selector of the [AuthValidator] used for each tenant, so one router can serve customers with their own identity providers
+///
+/// The ready-made [TenantValidators] keeps one validator per tenant in memory, but this can be implemented to find them elsewhere. Credentials are checked with a resolver by wrapping it in [TenantRouted], usually using [RequireTenantAuthLayer].
+pub trait TenantResolver<C>: Send + Sync + 'static {
+    /// Validator checking the credentials of a tenant
+    type Validator: AuthValidator<C>;
+
+    /// Finds the validator for the tenant, giving back [None] if the tenant is unknown; you need to implement this
+    fn resolve(&self, tenant: &TenantId) -> Option<&Self::Validator>;
+}
+
+/// Validators for each tenant, checking credentials with the identity provider of the tenant they were sent for
+///
+/// # Example
+///
+/// Customers with their own JWT issuers can be served by one router like so:
+///
+/// ```rust
+/// # #[cfg(feature = "jwt")]
+/// # {
+/// use axum::{routing::get, Router};
+/// use axum_auth::{AuthBearer, AuthUser, JwtValidator, RequireTenantAuthLayer, TenantValidators};
+///
+/// let tenants = TenantValidators::new()
+///     .with_tenant("acme", JwtValidator::new().with_discovery("https://login.acme.com"))
+///     .with_tenant("globex", JwtValidator::new().with_discovery("https://auth.globex.net"));
+///
+/// let app: Router = Router::new()
+///     .route("/", get(|user: AuthUser| async move { format!("Hello, {}!", user.id) }))
+///     .layer(RequireTenantAuthLayer::<AuthBearer, _>::tenants(tenants));
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct TenantValidators<V> {
+    tenants: HashMap<String, V>,
+}
+
+impl<V> TenantValidators<V> {
+    /// Creates a new set without any tenants
+    pub fn new() -> Self {
+        Self {
+            tenants: HashMap::new(),
+        }
+    }
+
+    /// Adds a tenant with the validator for its credentials, returning it back
+    pub fn with_tenant(mut self, tenant: impl Into<String>, validator: V) -> Self {
+        self.tenants.insert(tenant.into(), validator);
+        self
+    }
+}
+
+impl<V> Default for TenantValidators<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C, V: AuthValidator<C>> TenantResolver<C> for TenantValidators<V> {
+    type Validator = V;
+
+    fn resolve(&self, tenant: &TenantId) -> Option<&V> {
+        self.tenants.get(&tenant.0)
+    }
+}
+
+/// Validator checking the credentials of an [AuthTenant] with the validator its [TenantResolver] picks for the tenant
+///
+/// Once valid, the [TenantId] is inserted into the request extensions alongside the [AuthUser]. Credentials for unknown tenants are rejected with `401 UNAUTHORIZED` and the message "Tenant is unknown".
+#[derive(Debug, Clone)]
+pub struct TenantRouted<R>(pub R);
+
+impl<C, T, R> AuthValidator<AuthTenant<C, T>> for TenantRouted<R>
+where
+    C: Sync,
+    R: TenantResolver<C>,
+{
+    async fn validate(&self, credentials: &AuthTenant<C, T>) -> Result<AuthUser, Rejection> {
+        self.validate_with_extensions(credentials, &mut Extensions::new())
+            .await
+    }
+
+    async fn validate_with_extensions(
+        &self,
+        credentials: &AuthTenant<C, T>,
+        extensions: &mut Extensions,
+    ) -> Result<AuthUser, Rejection> {
+        let validator = self
+            .0
+            .resolve(&credentials.tenant)
+            .ok_or((StatusCode::UNAUTHORIZED, ERR_UNKNOWN_TENANT))?;
+        let user = validator
+            .validate_with_extensions(&credentials.credential, extensions)
+            .await?;
+        extensions.insert(credentials.tenant.clone());
+        Ok(user)
+    }
+}
+
+/// Layer requiring valid credentials for the tenant of every request, checking them with the validator of that tenant, see [RequireAuthLayer] and [TenantRouted] for more information
+pub type RequireTenantAuthLayer<A, R, T = TenantHeader> =
+    RequireAuthLayer<AuthTenant<A, T>, TenantRouted<R>>;
+
+impl<A, R, T> RequireAuthLayer<AuthTenant<A, T>, TenantRouted<R>> {
+    /// Creates a new layer checking credentials with the validator the resolver picks for each tenant
+    pub fn tenants(resolver: R) -> Self {
+        Self::new(TenantRouted(resolver))
+    }
+}
+
+/// Gets the subdomain from the host of the request, preferring the URI over the `Host` header
+fn subdomain(parts: &Parts) -> Option<&str> {
+    let host = match parts.uri.host() {
+        Some(host) => host,
+        None => parts.headers.get(HOST)?.to_str().ok()?,
+    };
+    let host = host.split(':').next()?;
+    if host.parse::<IpAddr>().is_ok() {
+        return None;
+    }
+    let mut labels = host.split('.');
+    let first = labels.next()?;
+    (labels.count() >= 2).then_some(first)
+}
@@ -0,0 +1,197 @@
+//! Implementation of test doubles for apps using this crate
+//!
+//! See [MockValidator] and [FakeIdentityLayer] for the most commonly-used data structures
+
+#[cfg(feature = "auth-bearer")]
+use crate::AuthBearer;
+#[cfg(feature = "uuid")]
+use crate::AuthBearerUuid;
+use crate::{AuthUser, AuthValidator, Rejection, ERR_INVALID_CREDENTIALS};
+use http::{Request, StatusCode};
+use std::{
+    collections::HashMap,
+    task::{Context, Poll},
+};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// Validator accepting a fixed set of bearer tokens, for testing apps without standing up a real identity provider
+///
+/// This is enabled via the `test-util` feature
+///
+/// Tokens added using [accepting](Self::accepting) resolve into a user with the token as its identifier, whilst [with_token](Self::with_token) lets you choose the user. Any other token is rejected with `401 UNAUTHORIZED`, just like a real validator would.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{routing::get, Router};
+/// use axum_auth::{AuthBearer, AuthUser, MockValidator, RequireAuthLayer};
+///
+/// let validator = MockValidator::accepting(["tok1"])
+///     .with_token("admin-token", AuthUser::new("alice").with_roles(["admin"]));
+///
+/// let app: Router = Router::new()
+///     .route("/", get(|user: AuthUser| async move { user.id }))
+///     .layer(RequireAuthLayer::<AuthBearer, _>::new(validator));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MockValidator {
+    tokens: HashMap<String, AuthUser>,
+}
+
+impl MockValidator {
+    /// Creates a new validator accepting the given tokens, each resolving into a user named after the token
+    pub fn accepting<I, T>(tokens: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        let tokens = tokens
+            .into_iter()
+            .map(|token| {
+                let token = token.into();
+                (token.clone(), AuthUser::new(token))
+            })
+            .collect();
+        Self { tokens }
+    }
+
+    /// Accepts another token which resolves into the given user, returning it back
+    pub fn with_token(mut self, token: impl Into<String>, user: AuthUser) -> Self {
+        self.tokens.insert(token.into(), user);
+        self
+    }
+
+    /// Finds the user for a token or rejects it
+    #[cfg(feature = "auth-bearer")]
+    fn user(&self, token: &str) -> Result<AuthUser, Rejection> {
+        self.tokens
+            .get(token)
+            .cloned()
+            .ok_or((StatusCode::UNAUTHORIZED, ERR_INVALID_CREDENTIALS))
+    }
+}
+
+#[cfg(feature = "auth-bearer")]
+impl AuthValidator<AuthBearer> for MockValidator {
+    async fn validate(&self, credentials: &AuthBearer) -> Result<AuthUser, Rejection> {
+        self.user(&credentials.0)
+    }
+}
+
+#[cfg(feature = "uuid")]
+impl AuthValidator<AuthBearerUuid> for MockValidator {
+    async fn validate(&self, credentials: &AuthBearerUuid) -> Result<AuthUser, Rejection> {
+        self.user(&credentials.0.to_string())
+    }
+}
+
+/// Validator accepting any credentials as the same user, for testing the authorized paths of an app
+///
+/// This is enabled via the `test-util` feature
+///
+/// The credentials still have to be extracted, so requests without any are rejected like usual. By default the user is called `test`, use [new](Self::new) to choose another one.
+///
+/// # Example
+///
+/// ```rust
+/// use axum_auth::{AlwaysAllow, AuthBasic, AuthUser, RequireAuthLayer};
+///
+/// let layer = RequireAuthLayer::<AuthBasic, _>::new(AlwaysAllow::new(AuthUser::new("alice")));
+/// ```
+#[derive(Debug, Clone)]
+pub struct AlwaysAllow(AuthUser);
+
+impl AlwaysAllow {
+    /// Creates a new validator resolving everything into the given user
+    pub fn new(user: AuthUser) -> Self {
+        Self(user)
+    }
+}
+
+impl Default for AlwaysAllow {
+    fn default() -> Self {
+        Self::new(AuthUser::new("test"))
+    }
+}
+
+impl<C: Sync> AuthValidator<C> for AlwaysAllow {
+    async fn validate(&self, _: &C) -> Result<AuthUser, Rejection> {
+        Ok(self.0.clone())
+    }
+}
+
+/// Validator rejecting any credentials with `401 UNAUTHORIZED`, for testing the unauthorized paths of an app
+///
+/// This is enabled via the `test-util` feature
+///
+/// # Example
+///
+/// ```rust
+/// use axum_auth::{AlwaysDeny, AuthBearer, RequireAuthLayer};
+///
+/// let layer = RequireAuthLayer::<AuthBearer, _>::new(AlwaysDeny);
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AlwaysDeny;
+
+impl<C: Sync> AuthValidator<C> for AlwaysDeny {
+    async fn validate(&self, _: &C) -> Result<AuthUser, Rejection> {
+        Err((StatusCode::UNAUTHORIZED, ERR_INVALID_CREDENTIALS))
+    }
+}
+
+/// Layer treating every request as if it was made by the given user, without needing any credentials
+///
+/// This is enabled via the `test-util` feature
+///
+/// The [AuthUser] is inserted into the request extensions, so handlers and later layers like [AuthorizeLayer](crate::AuthorizeLayer) and [RequireRole](crate::RequireRole) see it as if a real authentication layer had run. Use it in place of your authentication layer when testing routes which only care about who the user is.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{routing::get, Router};
+/// use axum_auth::{AuthUser, FakeIdentityLayer};
+///
+/// let app: Router = Router::new()
+///     .route("/", get(|user: AuthUser| async move { user.id }))
+///     .layer(FakeIdentityLayer(AuthUser::new("alice").with_roles(["admin"])));
+/// ```
+#[derive(Debug, Clone)]
+pub struct FakeIdentityLayer(pub AuthUser);
+
+impl<S> Layer<S> for FakeIdentityLayer {
+    type Service = FakeIdentity<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        FakeIdentity {
+            inner,
+            user: self.0.clone(),
+        }
+    }
+}
+
+/// Service created by [FakeIdentityLayer], see it for more information
+#[derive(Debug, Clone)]
+pub struct FakeIdentity<S> {
+    inner: S,
+    user: AuthUser,
+}
+
+impl<S, B> Service<Request<B>> for FakeIdentity<S>
+where
+    S: Service<Request<B>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<B>) -> Self::Future {
+        req.extensions_mut().insert(self.user.clone());
+        self.inner.call(req)
+    }
+}
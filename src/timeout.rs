@@ -0,0 +1,99 @@
+//! Implementation of limiting how long any validator can take
+//!
+//! See [TimeoutValidator] for the most commonly-used data structure
+
+use crate::{AuthUser, AuthValidator, Rejection, ERR_VALIDATOR_TIMEOUT};
+use http::{Extensions, StatusCode};
+use std::time::Duration;
+
+/// Wrapper around an [AuthValidator] giving up once it takes too long, so a slow identity provider or database can't stall every request
+///
+/// This is enabled via the `validator-timeout` feature
+///
+/// Validations which time out are rejected instead of waiting any longer, and nothing the inner validator stored in the request extensions is kept. They're `503 SERVICE UNAVAILABLE` by default so clients retry later, but can be changed to `401 UNAUTHORIZED` using [with_status](Self::with_status) for clients which only understand that.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{routing::get, Router};
+/// use axum_auth::{AuthUser, BasicUser, BasicUsers, RequireBasicAuthLayer, TimeoutValidator};
+/// use std::time::Duration;
+///
+/// let users = BasicUsers::new().with_user("alice", BasicUser::new("hunter2"));
+/// let timeout = TimeoutValidator::new(users, Duration::from_secs(2));
+///
+/// let app: Router = Router::new()
+///     .route("/", get(|user: AuthUser| async move { format!("Hello, {}!", user.id) }))
+///     .layer(RequireBasicAuthLayer::new(timeout));
+/// ```
+///
+/// # Errors
+///
+/// Rejections from the inner validator are given back as-is, and timeouts are rejected with "Validating the credentials took too long" using the configured status code.
+#[derive(Debug, Clone)]
+pub struct TimeoutValidator<V> {
+    inner: V,
+    timeout: Duration,
+    status: StatusCode,
+}
+
+impl<V> TimeoutValidator<V> {
+    /// Default status code of timeouts, which is `503 SERVICE UNAVAILABLE`
+    pub const DEFAULT_STATUS: StatusCode = StatusCode::SERVICE_UNAVAILABLE;
+
+    /// Gives up on the given validator once it's taken longer than the timeout
+    pub fn new(inner: V, timeout: Duration) -> Self {
+        Self {
+            inner,
+            timeout,
+            status: Self::DEFAULT_STATUS,
+        }
+    }
+
+    /// Sets the status code timeouts are rejected with, returning it back
+    pub fn with_status(mut self, status: StatusCode) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// Gets the validator being limited
+    pub fn inner(&self) -> &V {
+        &self.inner
+    }
+}
+
+impl<C, V> AuthValidator<C> for TimeoutValidator<V>
+where
+    C: Sync,
+    V: AuthValidator<C>,
+{
+    async fn validate(&self, credentials: &C) -> Result<AuthUser, Rejection> {
+        self.validate_with_extensions(credentials, &mut Extensions::new())
+            .await
+    }
+
+    async fn validate_with_extensions(
+        &self,
+        credentials: &C,
+        extensions: &mut Extensions,
+    ) -> Result<AuthUser, Rejection> {
+        // Only keep the extensions of validations which finished in time
+        let mut fresh = Extensions::new();
+        let validation = self.inner.validate_with_extensions(credentials, &mut fresh);
+        match tokio::time::timeout(self.timeout, validation).await {
+            Ok(result) => {
+                let user = result?;
+                extensions.extend(fresh);
+                Ok(user)
+            }
+            Err(_) => {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(
+                    timeout_ms = self.timeout.as_millis() as u64,
+                    "validator timed out"
+                );
+                Err((self.status, ERR_VALIDATOR_TIMEOUT))
+            }
+        }
+    }
+}
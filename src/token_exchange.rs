@@ -0,0 +1,190 @@
+//! Implementation of [RFC 8693](https://www.rfc-editor.org/rfc/rfc8693) token exchange for gateways forwarding credentials
+//!
+//! See [TokenExchange] for the most commonly-used data structure
+
+use crate::{crypto, Rejection, ERR_TOKEN_EXCHANGE_REFUSED, ERR_TOKEN_EXCHANGE_UNAVAILABLE};
+use http::StatusCode;
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// Grant type asking the identity provider to exchange a token
+const GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:token-exchange";
+
+/// Type of the tokens being exchanged, which are the access tokens sent to the gateway
+const ACCESS_TOKEN_TYPE: &str = "urn:ietf:params:oauth:token-type:access_token";
+
+/// Client of a token exchange endpoint, swapping the token sent to a gateway for one meant for the upstream service it forwards to
+///
+/// This is enabled via the `token-exchange` feature alongside a crypto backend
+///
+/// Exchanged tokens are remembered until shortly before the `expires_in` the endpoint gave, keyed by a SHA-256 digest of the incoming token and the audience, so the token itself is never kept around. Tokens without an `expires_in` are exchanged every time. Clones share the same remembered tokens.
+///
+/// This fits in as the callback of [Forward::exchange](crate::Forward::exchange), giving the headers to send upstream.
+///
+/// # Example
+///
+/// ```no_run
+/// use axum_auth::{AuthBearer, Forward, Rejection, TokenExchange};
+/// use http::HeaderMap;
+///
+/// /// Gets the headers for calling the orders service on behalf of the client
+/// async fn orders_headers(bearer: AuthBearer, exchange: TokenExchange) -> Result<HeaderMap, Rejection> {
+///     Forward::exchange(&bearer, |bearer| exchange.exchange(&bearer.0, "https://orders.example.com")).await
+/// }
+///
+/// let exchange = TokenExchange::new("https://auth.example.com/oauth2/token")
+///     .with_client_credentials("gateway", "gateway-secret");
+/// ```
+///
+/// # Errors
+///
+/// If the endpoint refuses to exchange the token with `400 BAD REQUEST`, like for an `invalid_grant`, this is `403 FORBIDDEN` with the message "Token can't be exchanged for the upstream service". If the endpoint can't be reached or gives back anything else without a token, this is `502 BAD GATEWAY` with the message "Token exchange endpoint couldn't be reached".
+#[derive(Clone)]
+pub struct TokenExchange {
+    endpoint: String,
+    client_credentials: Option<(String, String)>,
+    scope: Option<String>,
+    margin: Duration,
+    client: reqwest::Client,
+    exchanged: Arc<Mutex<Exchanges>>,
+}
+
+/// Tokens given back by the endpoint, keyed by the digest of the incoming token and the audience
+type Exchanges = HashMap<(Vec<u8>, String), Exchanged>;
+
+/// Token given back by the endpoint, remembered until shortly before it expires
+#[derive(Debug)]
+struct Exchanged {
+    token: String,
+    fresh_until: Instant,
+}
+
+impl TokenExchange {
+    /// Default time before expiring that exchanged tokens stop being used, which is 30 seconds
+    pub const DEFAULT_MARGIN: Duration = Duration::from_secs(30);
+
+    /// Creates a new client of the token exchange endpoint at the URL
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            client_credentials: None,
+            scope: None,
+            margin: Self::DEFAULT_MARGIN,
+            client: reqwest::Client::new(),
+            exchanged: Arc::default(),
+        }
+    }
+
+    /// Authenticates this gateway with the endpoint using basic auth, returning it back
+    pub fn with_client_credentials(
+        mut self,
+        client_id: impl Into<String>,
+        client_secret: impl Into<String>,
+    ) -> Self {
+        self.client_credentials = Some((client_id.into(), client_secret.into()));
+        self
+    }
+
+    /// Asks for exchanged tokens to have the space-separated scopes, returning it back
+    pub fn with_scope(mut self, scope: impl Into<String>) -> Self {
+        self.scope = Some(scope.into());
+        self
+    }
+
+    /// Sets how long before expiring that exchanged tokens stop being used, so they don't expire on the way upstream, returning it back
+    pub fn with_margin(mut self, margin: Duration) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    /// Exchanges the incoming token for one meant for the audience, using a remembered one if it's still fresh
+    ///
+    /// # Errors
+    ///
+    /// See the errors of [TokenExchange] for the rejections this can give.
+    pub async fn exchange(&self, subject_token: &str, audience: &str) -> Result<String, Rejection> {
+        let key = (
+            crypto::sha256(subject_token.as_bytes()),
+            audience.to_string(),
+        );
+        if let Some(exchanged) = self.exchanged.lock().unwrap().get(&key) {
+            if exchanged.fresh_until > Instant::now() {
+                return Ok(exchanged.token.clone());
+            }
+        }
+
+        // Ask the endpoint for a new token
+        let unavailable = (StatusCode::BAD_GATEWAY, ERR_TOKEN_EXCHANGE_UNAVAILABLE);
+        let mut form = vec![
+            ("grant_type", GRANT_TYPE),
+            ("subject_token", subject_token),
+            ("subject_token_type", ACCESS_TOKEN_TYPE),
+            ("audience", audience),
+        ];
+        if let Some(scope) = &self.scope {
+            form.push(("scope", scope));
+        }
+        let mut req = self.client.post(&self.endpoint).form(&form);
+        if let Some((client_id, client_secret)) = &self.client_credentials {
+            req = req.basic_auth(client_id, Some(client_secret));
+        }
+        let resp = req.send().await.map_err(|_| unavailable)?;
+        if resp.status() == StatusCode::BAD_REQUEST {
+            return Err((StatusCode::FORBIDDEN, ERR_TOKEN_EXCHANGE_REFUSED));
+        } else if !resp.status().is_success() {
+            return Err(unavailable);
+        }
+        let json: Value = resp
+            .bytes()
+            .await
+            .ok()
+            .and_then(|body| serde_json::from_slice(&body).ok())
+            .ok_or(unavailable)?;
+        let token = json
+            .get("access_token")
+            .and_then(Value::as_str)
+            .ok_or(unavailable)?
+            .to_string();
+
+        // Remember it until shortly before it expires, forgetting the ones which already have,
+        // unless it lasts too long to be a time
+        let now = Instant::now();
+        let fresh_until = json
+            .get("expires_in")
+            .and_then(Value::as_u64)
+            .and_then(|expires_in| {
+                now.checked_add(Duration::from_secs(expires_in).saturating_sub(self.margin))
+            });
+        if let Some(fresh_until) = fresh_until {
+            let mut exchanged = self.exchanged.lock().unwrap();
+            exchanged.retain(|_, exchanged| exchanged.fresh_until > now);
+            exchanged.insert(
+                key,
+                Exchanged {
+                    token: token.clone(),
+                    fresh_until,
+                },
+            );
+        }
+        Ok(token)
+    }
+}
+
+impl fmt::Debug for TokenExchange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TokenExchange")
+            .field("endpoint", &self.endpoint)
+            .field(
+                "client_id",
+                &self.client_credentials.as_ref().map(|(id, _)| id),
+            )
+            .field("scope", &self.scope)
+            .field("margin", &self.margin)
+            .finish_non_exhaustive()
+    }
+}
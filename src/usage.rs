@@ -0,0 +1,62 @@
+//! Implementation of recording how much each credential is used
+//!
+//! See [UsageRecorder] for the most commonly-used data structure
+
+use crate::AuthUser;
+use http::{Method, StatusCode};
+use std::future::Future;
+
+/// Identifier of the credential a request was authenticated with, which validators can put into the request extensions
+///
+/// Validators which can tell credentials of the same user apart, like `ApiKeys` with the `api-key-store` feature, insert this so [Usage] is recorded per credential instead of per user.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct CredentialId(pub String);
+
+/// Usage of a credential by one request, given to a [UsageRecorder]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Usage {
+    /// Identifier of the credential, which is the [CredentialId] if the validator gave one, or the identifier of the user otherwise
+    pub credential: String,
+    /// User the credential authenticated as
+    pub user: AuthUser,
+    /// Method of the request
+    pub method: Method,
+    /// Path of the request, as seen by the layer
+    pub route: String,
+    /// Status of the response
+    pub status: StatusCode,
+    /// Size of the request body from its `Content-Length` header, which isn't known for streamed bodies
+    pub request_bytes: Option<u64>,
+    /// Size of the response body, which isn't known for streamed bodies
+    pub response_bytes: Option<u64>,
+}
+
+/// Hook recording the [Usage] of every request which was authenticated by a [RequireAuthLayer](crate::RequireAuthLayer), for billing or monitoring API keys and users
+///
+/// Give it to the layer using [RequireAuthLayer::with_usage](crate::RequireAuthLayer::with_usage), so the credentials are only parsed once. It's called once the response has been made, and the response is held until it's done, so hand anything slow off to a channel or background task. The `()` recorder never records anything, which is what layers use until they're given a real one.
+///
+/// # Example
+///
+/// ```rust
+/// use axum_auth::{Usage, UsageRecorder};
+/// use std::{collections::HashMap, sync::Mutex};
+///
+/// /// Counts the bytes sent back to each credential
+/// #[derive(Default)]
+/// struct Egress(Mutex<HashMap<String, u64>>);
+///
+/// impl UsageRecorder for Egress {
+///     async fn record(&self, usage: Usage) {
+///         *self.0.lock().unwrap().entry(usage.credential).or_default() +=
+///             usage.response_bytes.unwrap_or_default();
+///     }
+/// }
+/// ```
+pub trait UsageRecorder: Send + Sync + 'static {
+    /// Records the usage of a request; you need to implement this
+    fn record(&self, usage: Usage) -> impl Future<Output = ()> + Send;
+}
+
+impl UsageRecorder for () {
+    async fn record(&self, _: Usage) {}
+}
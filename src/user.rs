@@ -0,0 +1,93 @@
+//! Implementation of the authenticated identity shared between layers and handlers
+//!
+//! See [AuthUser] for the most commonly-used data structure
+
+use crate::{Rejection, ERR_NO_USER};
+use axum_core::extract::FromRequestParts;
+use http::{request::Parts, StatusCode};
+
+/// Identity of an authenticated client, established by a validator or your own middleware
+///
+/// Once authentication has happened, this is stored inside of the request extensions so later layers (like [AuthorizeLayer](crate::AuthorizeLayer)) and handlers can use it without parsing anything again.
+///
+/// # Example
+///
+/// This structure can be used like any other axum extractor once it has been inserted:
+///
+/// ```no_run
+/// use axum_auth::AuthUser;
+///
+/// /// Greets the user which has already been authenticated by a layer
+/// async fn handler(user: AuthUser) -> String {
+///     format!("Hello, {}! You have the roles {:?}", user.id, user.roles)
+/// }
+/// ```
+///
+/// # Errors
+///
+/// If no identity was inserted into the request beforehand, this extractor responds with `401 UNAUTHORIZED` and the message "No authenticated user was found for this request".
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct AuthUser {
+    /// Unique identifier of the user, such as a username or subject
+    pub id: String,
+    /// Roles granted to the user, such as `admin`
+    pub roles: Vec<String>,
+    /// Scopes granted to the user, such as `read:posts`
+    pub scopes: Vec<String>,
+}
+
+impl AuthUser {
+    /// Creates a new user with the given identifier and no roles or scopes
+    pub fn new(id: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            roles: vec![],
+            scopes: vec![],
+        }
+    }
+
+    /// Adds roles to the user, returning it back
+    pub fn with_roles<I, R>(mut self, roles: I) -> Self
+    where
+        I: IntoIterator<Item = R>,
+        R: Into<String>,
+    {
+        self.roles.extend(roles.into_iter().map(Into::into));
+        self
+    }
+
+    /// Adds scopes to the user, returning it back
+    pub fn with_scopes<I, S>(mut self, scopes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.scopes.extend(scopes.into_iter().map(Into::into));
+        self
+    }
+
+    /// Checks if the user has been granted the given role
+    pub fn has_role(&self, role: &str) -> bool {
+        self.roles.iter().any(|r| r == role)
+    }
+
+    /// Checks if the user has been granted the given scope
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|s| s == scope)
+    }
+}
+
+impl<B> FromRequestParts<B> for AuthUser
+where
+    B: Send + Sync,
+{
+    type Rejection = Rejection;
+
+    async fn from_request_parts(parts: &mut Parts, _: &B) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<AuthUser>()
+            .cloned()
+            .ok_or((StatusCode::UNAUTHORIZED, ERR_NO_USER))
+    }
+}
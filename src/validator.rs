@@ -0,0 +1,48 @@
+//! Implementation of the validation step turning extracted credentials into an identity
+//!
+//! See [AuthValidator] for the most commonly-used data structure
+
+use crate::{AuthUser, Rejection};
+use http::Extensions;
+use std::future::Future;
+
+/// Validator checking extracted credentials and resolving them into an [AuthUser], used by layers like [RequireAuthLayer](crate::RequireAuthLayer)
+///
+/// # Example
+///
+/// This is what a validator accepting a single hardcoded bearer token looks like:
+///
+/// ```rust
+/// use axum_auth::{AuthBearer, AuthUser, AuthValidator, Rejection};
+/// use http::StatusCode;
+///
+/// /// Accepts one token for the monitoring system
+/// struct MonitorToken;
+///
+/// impl AuthValidator<AuthBearer> for MonitorToken {
+///     async fn validate(&self, credentials: &AuthBearer) -> Result<AuthUser, Rejection> {
+///         if credentials.0 == "my-secret-token" {
+///             Ok(AuthUser::new("monitor"))
+///         } else {
+///             Err((StatusCode::UNAUTHORIZED, "Unknown token"))
+///         }
+///     }
+/// }
+/// ```
+pub trait AuthValidator<C>: Send + Sync + 'static {
+    /// Checks the credentials, giving back the identity they belong to or a rejection; you need to implement this
+    fn validate(&self, credentials: &C)
+        -> impl Future<Output = Result<AuthUser, Rejection>> + Send;
+
+    /// Checks the credentials like [validate](Self::validate), also getting the chance to store more details about them in the request extensions, which layers call instead
+    ///
+    /// By default this only validates, so implement it if handlers need more than the [AuthUser], such as the claims of a token.
+    fn validate_with_extensions(
+        &self,
+        credentials: &C,
+        extensions: &mut Extensions,
+    ) -> impl Future<Output = Result<AuthUser, Rejection>> + Send {
+        let _ = extensions;
+        self.validate(credentials)
+    }
+}
@@ -0,0 +1,142 @@
+//! Implementation of [RFC 8292](https://www.rfc-editor.org/rfc/rfc8292) VAPID authentication for Web Push services
+//!
+//! See [AuthVapid] for the most commonly-used data structure
+
+use crate::{
+    challenge::parse_params, crypto, error::rejection_response, get_header, jws::Jws, Challenge,
+    Unauthorized, ERR_VAPID_AUDIENCE, ERR_VAPID_EXPIRED, ERR_VAPID_INVALID, ERR_WRONG_VAPID,
+};
+use axum_core::{
+    extract::FromRequestParts,
+    response::{IntoResponse, Response},
+};
+use base64::{engine::general_purpose, Engine};
+use http::{header::HOST, request::Parts, StatusCode};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Longest time a token may stay valid for, which is 24 hours
+const MAX_LIFETIME: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// VAPID extractor for push services, containing the application server's public key once its token has been verified
+///
+/// This is enabled via the `vapid` feature alongside a crypto backend
+///
+/// The `t` token is checked to be an ES256 JWT signed by the `k` public key, meant for this push service, and not expired or lasting more than 24 hours. The audience is compared to `https://` followed by the `Host` of the request, so make sure unknown hosts are rejected before reaching your server (most reverse proxies do this).
+///
+/// The public key still has to be compared to the one the push subscription was restricted to, which only your handler knows.
+///
+/// # Example
+///
+/// ```no_run
+/// use axum_auth::AuthVapid;
+///
+/// /// Accepts a push message for a subscription
+/// async fn handler(vapid: AuthVapid) -> String {
+///     // Check `vapid.public_key` against the subscription here
+///     format!("Message from {:?}", vapid.subject)
+/// }
+/// ```
+///
+/// # Errors
+///
+/// Missing or non-VAPID headers get `401 UNAUTHORIZED` with a `vapid` challenge and one of these messages:
+///
+/// - \`Authorization\` header must be for VAPID authentication – Some other scheme was used instead
+/// - \`Authorization\` header is missing – The header was required but it wasn't found
+/// - \`Authorization\` header contains invalid characters – The header couldn't be processed because of invalid characters
+///
+/// Invalid tokens get `403 FORBIDDEN` with one of these messages:
+///
+/// - VAPID token or key is invalid – The token, key, or signature were malformed or didn't match
+/// - VAPID token is meant for another push service – The audience wasn't this push service
+/// - VAPID token has expired or lasts longer than 24 hours – The expiry was missing, in the past, or too far ahead
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct AuthVapid {
+    /// Public key of the application server as an uncompressed P-256 point
+    pub public_key: Vec<u8>,
+    /// Contact for the application server, such as a `mailto:` or `https:` URI
+    pub subject: Option<String>,
+    /// When the token stops being valid
+    pub expires: SystemTime,
+}
+
+impl<B> FromRequestParts<B> for AuthVapid
+where
+    B: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, _: &B) -> Result<Self, Self::Rejection> {
+        // Get the token and key, e.g. `vapid t=eyJ0eXAi..., k=BA1Hxzyi...`
+        let unauthorized = |message| {
+            Unauthorized::new()
+                .with_challenge(Challenge::new("vapid"))
+                .with_message(message)
+                .into_response()
+        };
+        let authorization =
+            get_header(parts, StatusCode::UNAUTHORIZED).map_err(|(_, msg)| unauthorized(msg))?;
+        let params = match authorization.split_once(' ') {
+            Some(("vapid", params)) => parse_params(params),
+            _ => return Err(unauthorized(ERR_WRONG_VAPID)),
+        };
+        let param = |name| {
+            params
+                .iter()
+                .find(|(param, _)| param == name)
+                .map(|(_, value)| value.clone())
+        };
+
+        // Check the token was signed by the key
+        let forbidden = |message| rejection_response((StatusCode::FORBIDDEN, message));
+        let (token, public_key) = param("t")
+            .zip(param("k"))
+            .ok_or_else(|| forbidden(ERR_VAPID_INVALID))?;
+        let public_key = general_purpose::URL_SAFE_NO_PAD
+            .decode(public_key.trim_end_matches('='))
+            .map_err(|_| forbidden(ERR_VAPID_INVALID))?;
+        let jws = Jws::decode(&token).ok_or_else(|| forbidden(ERR_VAPID_INVALID))?;
+        if jws.alg() != Some("ES256")
+            || !crypto::verify_es256(&public_key, jws.signing_input.as_bytes(), &jws.signature)
+        {
+            return Err(forbidden(ERR_VAPID_INVALID));
+        }
+
+        // Check the token is for us
+        let host = parts
+            .headers
+            .get(HOST)
+            .and_then(|host| host.to_str().ok())
+            .or_else(|| parts.uri.authority().map(|authority| authority.as_str()))
+            .unwrap_or_default();
+        let origin = format!("https://{}", host.trim_end_matches(":443"));
+        if jws.claims.get("aud").and_then(|aud| aud.as_str()) != Some(origin.as_str()) {
+            return Err(forbidden(ERR_VAPID_AUDIENCE));
+        }
+
+        // Check the token is current and doesn't last too long
+        let now = SystemTime::now();
+        let expires = jws
+            .claims
+            .get("exp")
+            .and_then(|exp| exp.as_u64())
+            .and_then(|exp| UNIX_EPOCH.checked_add(Duration::from_secs(exp)))
+            .filter(|expires| {
+                *expires > now
+                    && now
+                        .checked_add(MAX_LIFETIME)
+                        .is_some_and(|latest| *expires <= latest)
+            })
+            .ok_or_else(|| forbidden(ERR_VAPID_EXPIRED))?;
+
+        Ok(Self {
+            public_key,
+            subject: jws
+                .claims
+                .get("sub")
+                .and_then(|sub| sub.as_str())
+                .map(ToString::to_string),
+            expires,
+        })
+    }
+}
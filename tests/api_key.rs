@@ -0,0 +1,281 @@
+use axum::{
+    extract::{FromRequestParts, OptionalFromRequestParts},
+    routing::get,
+    Router,
+};
+use axum_auth::{
+    ApiKeyHeader, AuthApiKey, AuthApiKeyCustom, AuthBearerOrApiKey, AuthErrorKind, Rejection,
+    TokenMechanism,
+};
+use http::{request::Parts, StatusCode};
+
+/// Header some older clients send their keys in
+struct LegacyKey;
+
+impl ApiKeyHeader for LegacyKey {
+    const NAME: &'static str = "x-legacy-key";
+}
+
+/// Custom API key auth which hides why it failed
+struct HiddenKey(String);
+
+impl AuthApiKeyCustom for HiddenKey {
+    type Rejection = Rejection;
+    const HEADER: &'static str = "x-hidden-key";
+    const ERROR_CODE: StatusCode = StatusCode::UNAUTHORIZED;
+    const ERROR_OVERWRITE: Option<&'static str> = Some("Invalid API key");
+
+    fn from_header(contents: &str) -> Self {
+        Self(contents.to_string())
+    }
+}
+
+impl<B> FromRequestParts<B> for HiddenKey
+where
+    B: Send + Sync,
+{
+    type Rejection = Rejection;
+
+    async fn from_request_parts(parts: &mut Parts, _: &B) -> Result<Self, Self::Rejection> {
+        Self::decode_request_parts(parts)
+    }
+}
+
+impl<B> OptionalFromRequestParts<B> for HiddenKey
+where
+    B: Send + Sync,
+{
+    type Rejection = Rejection;
+
+    async fn from_request_parts(parts: &mut Parts, _: &B) -> Result<Option<Self>, Self::Rejection> {
+        Self::decode_optional_request_parts(parts)
+    }
+}
+
+/// Launches spin-off axum instance
+async fn launcher() {
+    // Make routes
+    let app = Router::new()
+        .route("/", get(tester))
+        .route("/legacy", get(tester_legacy))
+        .route("/optional", get(tester_optional))
+        .route("/key", get(tester_key))
+        .route("/key/legacy", get(tester_key_legacy))
+        .route("/key/optional", get(tester_key_optional))
+        .route("/key/hidden", get(tester_key_hidden))
+        .route("/key/hidden/optional", get(tester_key_hidden_optional));
+
+    // Launch
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:3029")
+        .await
+        .unwrap();
+
+    println!("listening on {}", listener.local_addr().unwrap());
+    axum::serve(listener, app.into_make_service())
+        .await
+        .unwrap();
+
+    async fn tester(auth: AuthBearerOrApiKey) -> String {
+        format!("Got {:?} {}", auth.mechanism, auth.token)
+    }
+
+    async fn tester_legacy(auth: AuthBearerOrApiKey<LegacyKey>) -> String {
+        format!("Got {:?} {}", auth.mechanism, auth.token)
+    }
+
+    async fn tester_optional(
+        auth: Option<AuthBearerOrApiKey>,
+        kind: Option<AuthErrorKind>,
+    ) -> String {
+        format!("Got {:?} {:?}", auth.map(|auth| auth.mechanism), kind)
+    }
+
+    async fn tester_key(auth: AuthApiKey) -> String {
+        format!("Got {}", auth.key)
+    }
+
+    async fn tester_key_legacy(auth: AuthApiKey<LegacyKey>) -> String {
+        format!("Got {}", auth.key)
+    }
+
+    async fn tester_key_optional(auth: Option<AuthApiKey>, kind: Option<AuthErrorKind>) -> String {
+        format!("Got {:?} {:?}", auth.map(|auth| auth.key), kind)
+    }
+
+    async fn tester_key_hidden(HiddenKey(key): HiddenKey) -> String {
+        format!("Got {}", key)
+    }
+
+    async fn tester_key_hidden_optional(
+        auth: Result<Option<HiddenKey>, Rejection>,
+        kind: Option<AuthErrorKind>,
+    ) -> String {
+        format!(
+            "Got {:?} {:?}",
+            auth.map(|auth| auth.map(|HiddenKey(key)| key)),
+            kind
+        )
+    }
+}
+
+fn url(end: &str) -> String {
+    format!("http://127.0.0.1:3029{}", end)
+}
+
+#[tokio::test]
+async fn tester() {
+    // Launch axum instance
+    tokio::task::spawn(launcher());
+
+    // Wait for boot
+    tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
+
+    // Both mechanisms are accepted and tagged
+    let client = reqwest::Client::new();
+    let resp = client
+        .get(url("/"))
+        .bearer_auth("new-token")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::OK.as_u16());
+    assert_eq!(resp.text().await.unwrap(), "Got Bearer new-token");
+    let resp = client
+        .get(url("/"))
+        .header("X-Api-Key", "old-key")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::OK.as_u16());
+    assert_eq!(resp.text().await.unwrap(), "Got ApiKey old-key");
+
+    // Bearer tokens win when both are sent
+    let resp = client
+        .get(url("/"))
+        .bearer_auth("new-token")
+        .header("X-Api-Key", "old-key")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.text().await.unwrap(), "Got Bearer new-token");
+
+    // Other headers can be used for the key
+    let resp = client
+        .get(url("/legacy"))
+        .header("X-Legacy-Key", "old-key")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.text().await.unwrap(), "Got ApiKey old-key");
+    let resp = client
+        .get(url("/legacy"))
+        .header("X-Api-Key", "old-key")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::BAD_REQUEST.as_u16());
+    assert_eq!(
+        resp.text().await.unwrap(),
+        "Neither a bearer token nor an API key was found"
+    );
+
+    // Bad credentials are rejected
+    let resp = client
+        .get(url("/"))
+        .basic_auth("user", Some("pass"))
+        .header("X-Api-Key", "old-key")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::BAD_REQUEST.as_u16());
+    assert_eq!(
+        resp.text().await.unwrap(),
+        "`Authorization` header must be a bearer token"
+    );
+    let resp = client
+        .get(url("/"))
+        .header("X-Api-Key", "  ")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::BAD_REQUEST.as_u16());
+    assert_eq!(
+        resp.text().await.unwrap(),
+        "API key header contains invalid characters"
+    );
+
+    // Optional extraction only gives none when neither was sent
+    let resp = client.get(url("/optional")).send().await.unwrap();
+    assert_eq!(resp.text().await.unwrap(), "Got None Some(MissingHeader)");
+    let resp = client
+        .get(url("/optional"))
+        .header("X-Api-Key", "old-key")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.text().await.unwrap(), "Got Some(ApiKey) None");
+
+    // Keys can also be taken on their own, trimmed
+    let send = |path: &'static str, header: &'static str, key: &'static str| {
+        client.get(url(path)).header(header, key).send()
+    };
+    let resp = send("/key", "X-Api-Key", " my-key ").await.unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::OK.as_u16());
+    assert_eq!(resp.text().await.unwrap(), "Got my-key");
+    let resp = send("/key/legacy", "X-Legacy-Key", "old-key")
+        .await
+        .unwrap();
+    assert_eq!(resp.text().await.unwrap(), "Got old-key");
+
+    // Bearer tokens and other headers aren't keys
+    let resp = client
+        .get(url("/key"))
+        .bearer_auth("new-token")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::BAD_REQUEST.as_u16());
+    assert_eq!(resp.text().await.unwrap(), "API key header is missing");
+    let resp = send("/key/legacy", "X-Api-Key", "my-key").await.unwrap();
+    assert_eq!(resp.text().await.unwrap(), "API key header is missing");
+    let resp = send("/key", "X-Api-Key", "").await.unwrap();
+    assert_eq!(
+        resp.text().await.unwrap(),
+        "API key header contains invalid characters"
+    );
+
+    // Optional extraction gives none only when the header is missing
+    let resp = client.get(url("/key/optional")).send().await.unwrap();
+    assert_eq!(resp.text().await.unwrap(), "Got None Some(MissingHeader)");
+    let resp = send("/key/optional", "X-Api-Key", "my-key").await.unwrap();
+    assert_eq!(resp.text().await.unwrap(), "Got Some(\"my-key\") None");
+    let resp = send("/key/optional", "X-Api-Key", " ").await.unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::BAD_REQUEST.as_u16());
+
+    // Custom extractors use their own header, status code, and message
+    let resp = send("/key/hidden", "X-Hidden-Key", "secret").await.unwrap();
+    assert_eq!(resp.text().await.unwrap(), "Got secret");
+    let resp = client.get(url("/key/hidden")).send().await.unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::UNAUTHORIZED.as_u16());
+    assert_eq!(resp.text().await.unwrap(), "Invalid API key");
+    let resp = send("/key/hidden", "X-Hidden-Key", " ").await.unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::UNAUTHORIZED.as_u16());
+    assert_eq!(resp.text().await.unwrap(), "Invalid API key");
+
+    // The kind is still known to the app when the message is hidden
+    let resp = send("/key/hidden/optional", "X-Hidden-Key", " ")
+        .await
+        .unwrap();
+    assert_eq!(
+        resp.text().await.unwrap(),
+        "Got Err((401, \"Invalid API key\")) Some(InvalidChars)"
+    );
+}
+
+#[test]
+fn redacted() {
+    let auth = AuthBearerOrApiKey::<LegacyKey>::new("secret", TokenMechanism::ApiKey);
+    assert!(!format!("{:?}", auth).contains("secret"));
+    let auth = AuthApiKey::<LegacyKey>::new("secret");
+    assert!(!format!("{:?}", auth).contains("secret"));
+}
@@ -0,0 +1,316 @@
+#![cfg(feature = "api-key-store")]
+
+use axum::{routing::get, Router};
+use axum_auth::{
+    ApiKeyStore, ApiKeys, AuthApiKey, AuthBearer, AuthBearerOrApiKey, AuthUser, AuthValidator,
+    BasicUser, BasicUsers, CredentialExpiry, MemoryApiKeyStore, Quota, RequireAuthLayer,
+    RequireBasicAuthLayer, Role,
+};
+use http::{Extensions, StatusCode};
+use serde_json::{json, Value};
+use std::{sync::Arc, time::Duration};
+
+struct Admin;
+
+impl Role for Admin {
+    const NAME: &'static str = "admin";
+}
+
+/// Launches spin-off axum instance
+async fn launcher() {
+    // Make keys and the admins managing them
+    let keys = ApiKeys::new(Arc::new(MemoryApiKeyStore::new()));
+    let admins = BasicUsers::new()
+        .with_user("alice", BasicUser::new("hunter2").with_roles(["admin"]))
+        .with_user("bob", BasicUser::new("letmein"));
+
+    // Make routes
+    let app = Router::new()
+        .route("/", get(tester_key))
+        .layer(RequireAuthLayer::<AuthBearerOrApiKey, _>::new(keys.clone()))
+        .nest(
+            "/admin",
+            keys.admin_router::<Admin>()
+                .layer(RequireBasicAuthLayer::new(admins)),
+        );
+
+    // Launch
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:3034")
+        .await
+        .unwrap();
+
+    println!("listening on {}", listener.local_addr().unwrap());
+    axum::serve(listener, app.into_make_service())
+        .await
+        .unwrap();
+
+    async fn tester_key(user: AuthUser) -> String {
+        format!("Got {} with {:?}", user.id, user.scopes)
+    }
+}
+
+fn url(end: &str) -> String {
+    format!("http://127.0.0.1:3034{}", end)
+}
+
+/// Sends the API key, giving back the status code and body
+async fn send_key(key: &str) -> (u16, String) {
+    let resp = reqwest::Client::new()
+        .get(url("/"))
+        .header("X-Api-Key", key)
+        .send()
+        .await
+        .unwrap();
+    (resp.status().as_u16(), resp.text().await.unwrap())
+}
+
+#[tokio::test]
+async fn tester() {
+    // Launch axum instance
+    tokio::task::spawn(launcher());
+
+    // Wait for boot
+    tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
+
+    // Admins can mint keys, which are only shown once
+    let client = reqwest::Client::new();
+    let resp = client
+        .post(url("/admin/keys"))
+        .basic_auth("alice", Some("hunter2"))
+        .body(json!({"name": "ci", "user": "bot", "scopes": ["deploy"]}).to_string())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::CREATED.as_u16());
+    let minted: Value = serde_json::from_str(&resp.text().await.unwrap()).unwrap();
+    let key = minted["key"].as_str().unwrap().to_string();
+    let id = minted["id"].as_str().unwrap().to_string();
+    assert!(key.starts_with(&format!("{}.", id)));
+    assert_eq!(minted["user"], "bot");
+
+    // Keys work as API keys and bearer tokens
+    assert_eq!(
+        send_key(&key).await,
+        (200, String::from("Got bot with [\"deploy\"]"))
+    );
+    let resp = client.get(url("/")).bearer_auth(&key).send().await.unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::OK.as_u16());
+
+    // Wrong secrets and unknown keys are rejected
+    assert_eq!(send_key(&format!("{}.AAAA", id)).await.0, 401);
+    assert_eq!(send_key("nonsense").await.0, 401);
+
+    // Listing never shows the secret, and the user defaults to the admin
+    let resp = client
+        .post(url("/admin/keys"))
+        .basic_auth("alice", Some("hunter2"))
+        .body(r#"{"name": "laptop"}"#)
+        .send()
+        .await
+        .unwrap();
+    let laptop: Value = serde_json::from_str(&resp.text().await.unwrap()).unwrap();
+    assert_eq!(laptop["user"], "alice");
+    let resp = client
+        .get(url("/admin/keys"))
+        .basic_auth("alice", Some("hunter2"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::OK.as_u16());
+    let listed = resp.text().await.unwrap();
+    assert!(!listed.contains(&key));
+    let listed: Value = serde_json::from_str(&listed).unwrap();
+    assert_eq!(listed.as_array().unwrap().len(), 2);
+    assert!(listed
+        .as_array()
+        .unwrap()
+        .iter()
+        .all(|key| key.get("key").is_none()));
+
+    // Only admins can manage keys
+    let resp = client
+        .get(url("/admin/keys"))
+        .basic_auth("bob", Some("letmein"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::FORBIDDEN.as_u16());
+    let resp = client.get(url("/admin/keys")).send().await.unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::BAD_REQUEST.as_u16());
+
+    // Bad requests are rejected
+    let resp = client
+        .post(url("/admin/keys"))
+        .basic_auth("alice", Some("hunter2"))
+        .body(r#"{"user": "bot"}"#)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::BAD_REQUEST.as_u16());
+    assert_eq!(
+        resp.text().await.unwrap(),
+        "API key request must be JSON with a name"
+    );
+    let resp = client
+        .post(url("/admin/keys"))
+        .basic_auth("alice", Some("hunter2"))
+        .body(r#"{"name": "forever", "expires_in": 18446744073709551615}"#)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::BAD_REQUEST.as_u16());
+
+    // Keys which were used say when they were last used
+    let used = listed
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|listed| listed["id"] == id.as_str())
+        .unwrap();
+    assert!(used["last_used_at"].is_u64());
+    assert!(used["expires_at"].is_null());
+
+    // Revoked keys stop working straight away, but are kept for auditing
+    let resp = client
+        .delete(url(&format!("/admin/keys/{}", id)))
+        .basic_auth("alice", Some("hunter2"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::NO_CONTENT.as_u16());
+    assert_eq!(
+        send_key(&key).await,
+        (401, String::from("API key has been revoked"))
+    );
+    let resp = client
+        .delete(url("/admin/keys/unknown"))
+        .basic_auth("alice", Some("hunter2"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::NOT_FOUND.as_u16());
+    let resp = client
+        .get(url("/admin/keys"))
+        .basic_auth("alice", Some("hunter2"))
+        .send()
+        .await
+        .unwrap();
+    let listed: Value = serde_json::from_str(&resp.text().await.unwrap()).unwrap();
+    assert!(listed[0]["revoked_at"].is_u64());
+
+    // Expired keys are rejected too
+    let resp = client
+        .post(url("/admin/keys"))
+        .basic_auth("alice", Some("hunter2"))
+        .body(r#"{"name": "temp", "expires_in": 0}"#)
+        .send()
+        .await
+        .unwrap();
+    let temp: Value = serde_json::from_str(&resp.text().await.unwrap()).unwrap();
+    assert!(temp["expires_at"].is_u64());
+    assert!(temp["quota"].is_null());
+
+    // Quotas can be given to keys when minting them
+    let resp = client
+        .post(url("/admin/keys"))
+        .basic_auth("alice", Some("hunter2"))
+        .body(r#"{"name": "metered", "quota": {"requests": 100, "window": 60}}"#)
+        .send()
+        .await
+        .unwrap();
+    let metered: Value = serde_json::from_str(&resp.text().await.unwrap()).unwrap();
+    assert_eq!(metered["quota"], json!({"requests": 100, "window": 60}));
+    let resp = client
+        .post(url("/admin/keys"))
+        .basic_auth("alice", Some("hunter2"))
+        .body(r#"{"name": "metered", "quota": {"requests": 100}}"#)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::BAD_REQUEST.as_u16());
+    assert_eq!(
+        send_key(temp["key"].as_str().unwrap()).await,
+        (401, String::from("API key has expired"))
+    );
+}
+
+#[tokio::test]
+async fn ttl() {
+    // Minted keys expire after the TTL
+    let keys =
+        ApiKeys::new(Arc::new(MemoryApiKeyStore::new())).with_ttl(Duration::from_millis(100));
+    let (record, key) = keys.mint("ci", AuthUser::new("bot")).await;
+    assert!(record.expires_at.is_some());
+    let credentials = AuthBearer(key);
+    let mut extensions = Extensions::new();
+    let user = keys
+        .validate_with_extensions(&credentials, &mut extensions)
+        .await
+        .unwrap();
+    assert_eq!(user.id, "bot");
+
+    // Their expiry is given to handlers
+    assert_eq!(
+        extensions.get::<CredentialExpiry>(),
+        record.expires_at.map(CredentialExpiry).as_ref()
+    );
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    assert_eq!(
+        keys.validate(&credentials).await.unwrap_err(),
+        (StatusCode::UNAUTHORIZED, "API key has expired")
+    );
+}
+
+#[tokio::test]
+async fn quota() {
+    // Minted keys carry the quota into the request extensions
+    let keys = ApiKeys::new(Arc::new(MemoryApiKeyStore::new())).with_quota(Quota::per_minute(10));
+    let (record, key) = keys.mint("ci", AuthUser::new("bot")).await;
+    assert_eq!(record.quota, Some(Quota::per_minute(10)));
+    let mut extensions = Extensions::new();
+    keys.validate_with_extensions(&AuthBearer(key), &mut extensions)
+        .await
+        .unwrap();
+    assert_eq!(extensions.get::<Quota>(), Some(&Quota::per_minute(10)));
+}
+
+#[tokio::test]
+async fn api_key_header() {
+    // Keys can be checked when only sent in an API key header, going through the same checks
+    let keys = ApiKeys::new(Arc::new(MemoryApiKeyStore::new())).with_ttl(Duration::from_secs(60));
+    let _: Router = Router::new()
+        .route("/", get(|| async { "Hello!" }))
+        .layer(RequireAuthLayer::<AuthApiKey, _>::new(keys.clone()));
+    let (record, key) = keys.mint("ci", AuthUser::new("bot")).await;
+    let credentials: AuthApiKey = AuthApiKey::new(key);
+    let mut extensions = Extensions::new();
+    let user = keys
+        .validate_with_extensions(&credentials, &mut extensions)
+        .await
+        .unwrap();
+    assert_eq!(user.id, "bot");
+    assert_eq!(
+        extensions.get::<CredentialExpiry>(),
+        record.expires_at.map(CredentialExpiry).as_ref()
+    );
+
+    // The last use is recorded in the background
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    let stored = keys.store().get(&record.id).await.unwrap();
+    assert!(stored.last_used_at.is_some());
+
+    // Revoked and unknown keys are rejected
+    assert!(keys.revoke(&record.id).await);
+    assert_eq!(
+        keys.validate(&credentials).await.unwrap_err(),
+        (StatusCode::UNAUTHORIZED, "API key has been revoked")
+    );
+    let unknown: AuthApiKey = AuthApiKey::new("unknown.key");
+    assert_eq!(
+        keys.validate(&unknown).await.unwrap_err(),
+        (
+            StatusCode::UNAUTHORIZED,
+            "The provided credentials are invalid"
+        )
+    );
+}
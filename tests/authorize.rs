@@ -0,0 +1,121 @@
+use axum::{
+    extract::Request,
+    middleware::{self, Next},
+    response::Response,
+    routing::get,
+    Router,
+};
+use axum_auth::{AuthUser, AuthorizeLayer, Authorizer, Decision};
+use http::{request::Parts, Method, StatusCode};
+
+/// Lets everyone read but only admins write
+struct AdminWrites;
+
+impl Authorizer for AdminWrites {
+    async fn authorize(&self, user: &AuthUser, req: &Parts) -> Decision {
+        (req.method == Method::GET || user.has_role("admin")).into()
+    }
+}
+
+/// Simple identity middleware trusting the `x-user` header, standing in for a real one
+async fn identity(mut req: Request, next: Next) -> Response {
+    let user = req
+        .headers()
+        .get("x-user")
+        .and_then(|value| value.to_str().ok())
+        .map(|id| {
+            if id == "root" {
+                AuthUser::new(id).with_roles(["admin"])
+            } else {
+                AuthUser::new(id)
+            }
+        });
+    if let Some(user) = user {
+        req.extensions_mut().insert(user);
+    }
+    next.run(req).await
+}
+
+/// Launches spin-off axum instance
+async fn launcher() {
+    // Make routes
+    let app = Router::new()
+        .route("/", get(tester).post(tester))
+        .layer(AuthorizeLayer::new(AdminWrites))
+        .layer(middleware::from_fn(identity));
+
+    // Launch
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:3003")
+        .await
+        .unwrap();
+
+    println!("listening on {}", listener.local_addr().unwrap());
+    axum::serve(listener, app.into_make_service())
+        .await
+        .unwrap();
+
+    async fn tester(user: AuthUser) -> String {
+        format!("Got {} with {:?}", user.id, user.roles)
+    }
+}
+
+fn url(end: &str) -> String {
+    format!("http://127.0.0.1:3003{}", end)
+}
+
+#[tokio::test]
+async fn tester() {
+    // Launch axum instance
+    tokio::task::spawn(launcher());
+
+    // Wait for boot
+    tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
+
+    // Try allowed read
+    let client = reqwest::Client::new();
+    let resp = client
+        .get(url("/"))
+        .header("x-user", "alice")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::OK.as_u16());
+    assert_eq!(
+        resp.text().await.unwrap(),
+        String::from("Got alice with []")
+    );
+
+    // Try denied write
+    let resp = client
+        .post(url("/"))
+        .header("x-user", "alice")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::FORBIDDEN.as_u16());
+    assert_eq!(
+        resp.text().await.unwrap(),
+        String::from("You are not allowed to access this resource")
+    );
+
+    // Try allowed write
+    let resp = client
+        .post(url("/"))
+        .header("x-user", "root")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::OK.as_u16());
+    assert_eq!(
+        resp.text().await.unwrap(),
+        String::from("Got root with [\"admin\"]")
+    );
+
+    // Try without any identity
+    let resp = client.get(url("/")).send().await.unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::UNAUTHORIZED.as_u16());
+    assert_eq!(
+        resp.text().await.unwrap(),
+        String::from("No authenticated user was found for this request")
+    );
+}
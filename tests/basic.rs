@@ -1,5 +1,7 @@
 use axum::{routing::get, Router};
-use axum_auth::{AuthBasic, AuthBearer};
+use axum_auth::{
+    AuthBasic, AuthBasicEmail, AuthBasicRaw, AuthBasicStrict, AuthBearer, AuthErrorKind,
+};
 use http::StatusCode;
 
 /// Launches spin-off axum instance
@@ -7,7 +9,13 @@ async fn launcher() {
     // Make routes
     let app = Router::new()
         .route("/basic", get(tester_basic))
-        .route("/bearer", get(auth_bearer));
+        .route("/bearer", get(auth_bearer))
+        .route("/email", get(tester_email))
+        .route("/raw", get(tester_raw))
+        .route("/strict", get(tester_strict))
+        .route("/optional/basic", get(tester_optional_basic))
+        .route("/optional/bearer", get(tester_optional_bearer))
+        .route("/optional/why", get(tester_optional_why));
 
     // Launch
     let listener = tokio::net::TcpListener::bind("127.0.0.1:3000")
@@ -23,9 +31,40 @@ async fn launcher() {
         format!("Got {} and {:?}", id, password)
     }
 
+    async fn tester_email(AuthBasicEmail((email, _)): AuthBasicEmail) -> String {
+        format!("Got {}", email)
+    }
+
+    async fn tester_raw(auth: AuthBasicRaw) -> String {
+        format!(
+            "Got {} and {:?} from {:?}",
+            auth.username, auth.password, auth.password_bytes
+        )
+    }
+
+    async fn tester_strict(AuthBasicStrict((id, password)): AuthBasicStrict) -> String {
+        format!("Got {} and {}", id, password)
+    }
+
     async fn auth_bearer(AuthBearer(token): AuthBearer) -> String {
         format!("Got {}", token)
     }
+
+    async fn tester_optional_basic(auth: Option<AuthBasic>) -> String {
+        format!("Got {:?}", auth.map(|AuthBasic((id, _))| id))
+    }
+
+    async fn tester_optional_bearer(auth: Option<AuthBearer>) -> String {
+        format!("Got {:?}", auth.map(|AuthBearer(token)| token))
+    }
+
+    async fn tester_optional_why(auth: Option<AuthBearer>, why: Option<AuthErrorKind>) -> String {
+        format!(
+            "Got {:?} because {:?}",
+            auth.map(|AuthBearer(token)| token),
+            why
+        )
+    }
 }
 
 fn url(end: &str) -> String {
@@ -44,6 +83,10 @@ async fn tester() {
     good().await;
     switched().await;
     nothing().await;
+    email().await;
+    raw().await;
+    strict().await;
+    optional().await;
 }
 
 /// The requests which should be returned fine
@@ -131,3 +174,246 @@ async fn nothing() {
     assert_eq!(resp.status().as_u16(), StatusCode::OK.as_u16());
     assert_eq!(resp.text().await.unwrap(), String::from("Got "))
 }
+
+/// Emails should be validated and have their domain lowercased
+async fn email() {
+    // Try good email
+    let client = reqwest::Client::new();
+    let resp = client
+        .get(url("/email"))
+        .basic_auth("Jane.Doe@Example.COM", Some("My Password"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::OK.as_u16());
+    assert_eq!(
+        resp.text().await.unwrap(),
+        String::from("Got Jane.Doe@example.com")
+    );
+
+    // Try bad emails
+    for username in [
+        "janedoe",
+        "jane@localhost",
+        "jane doe@example.com",
+        "@example.com",
+        "jane@-example.com",
+    ] {
+        let resp = client
+            .get(url("/email"))
+            .basic_auth(username, Some("My Password"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status().as_u16(), StatusCode::BAD_REQUEST.as_u16());
+        assert_eq!(
+            resp.text().await.unwrap(),
+            String::from("`Authorization` username must be an email address")
+        );
+    }
+
+    // Try windows authentication in both
+    for route in ["/basic", "/bearer"] {
+        let resp = client
+            .get(url(route))
+            .header(
+                "Authorization",
+                "NTLM TlRMTVNTUAABAAAAB4IIogAAAAAAAAAAAAAAAAAAAAAKAGFKAAAADw==",
+            )
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status().as_u16(), StatusCode::BAD_REQUEST.as_u16());
+        assert_eq!(
+            resp.text().await.unwrap(),
+            String::from("`Authorization` header uses Windows authentication (NTLM or Negotiate) which isn't supported here")
+        );
+    }
+}
+
+/// Passwords in other encodings should be kept exactly as they were sent
+async fn raw() {
+    // Try a UTF-8 password
+    let client = reqwest::Client::new();
+    let resp = client
+        .get(url("/raw"))
+        .basic_auth("user", Some("pass"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(
+        resp.text().await.unwrap(),
+        String::from("Got user and Some(\"pass\") from Some([112, 97, 115, 115])")
+    );
+
+    // Try a Latin-1 password, which the plain extractor rejects
+    for (route, expected) in [
+        ("/basic", "`Authorization` header could not be decoded"),
+        (
+            "/raw",
+            "Got user and Some(\"caf\u{fffd}\") from Some([99, 97, 102, 233])",
+        ),
+    ] {
+        let resp = client
+            .get(url(route))
+            .header("Authorization", "Basic dXNlcjpjYWbp")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.text().await.unwrap(), String::from(expected));
+    }
+
+    // Try a Latin-1 username and no password at all
+    let resp = client
+        .get(url("/raw"))
+        .header("Authorization", "Basic dXPpcjpwYXNz")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::BAD_REQUEST.as_u16());
+    let resp = client
+        .get(url("/raw"))
+        .header("Authorization", "Basic dXNlcg==")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(
+        resp.text().await.unwrap(),
+        String::from("Got user and None from None")
+    );
+}
+
+/// Strict extractors should say when the colon is missing
+async fn strict() {
+    // Try with a colon, even if the password is empty
+    let client = reqwest::Client::new();
+    for (password, expected) in [("pass", "Got user and pass"), ("", "Got user and ")] {
+        let resp = client
+            .get(url("/strict"))
+            .basic_auth("user", Some(password))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.text().await.unwrap(), String::from(expected));
+    }
+
+    // Try without a colon, which the plain extractor accepts
+    for (route, status, expected) in [
+        ("/basic", StatusCode::OK, "Got user and None"),
+        (
+            "/strict",
+            StatusCode::BAD_REQUEST,
+            "`Authorization` header must separate the username and password with a colon",
+        ),
+    ] {
+        let resp = client
+            .get(url(route))
+            .header("Authorization", "Basic dXNlcg==")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status().as_u16(), status.as_u16());
+        assert_eq!(resp.text().await.unwrap(), String::from(expected));
+    }
+}
+
+/// Optional extractors should only be [None] if the header is missing
+async fn optional() {
+    let client = reqwest::Client::new();
+
+    // Try missing headers
+    for route in ["/optional/basic", "/optional/bearer"] {
+        let resp = client.get(url(route)).send().await.unwrap();
+        assert_eq!(resp.status().as_u16(), StatusCode::OK.as_u16());
+        assert_eq!(resp.text().await.unwrap(), String::from("Got None"));
+    }
+
+    // Try good headers
+    let resp = client
+        .get(url("/optional/basic"))
+        .basic_auth("My Username", Some("My Password"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(
+        resp.text().await.unwrap(),
+        String::from("Got Some(\"My Username\")")
+    );
+    let resp = client
+        .get(url("/optional/bearer"))
+        .bearer_auth("My Token")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(
+        resp.text().await.unwrap(),
+        String::from("Got Some(\"My Token\")")
+    );
+
+    // Try malformed headers, which still error
+    let resp = client
+        .get(url("/optional/bearer"))
+        .basic_auth("123", Some("Hello"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::BAD_REQUEST.as_u16());
+    assert_eq!(
+        resp.text().await.unwrap(),
+        String::from("`Authorization` header must be a bearer token")
+    );
+    let resp = client
+        .get(url("/optional/basic"))
+        .header("Authorization", "Basic !!!")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::BAD_REQUEST.as_u16());
+
+    // Handlers can find out why there weren't any credentials
+    let resp = client.get(url("/optional/why")).send().await.unwrap();
+    assert_eq!(
+        resp.text().await.unwrap(),
+        String::from("Got None because Some(MissingHeader)")
+    );
+    let resp = client
+        .get(url("/optional/why"))
+        .bearer_auth("My Token")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(
+        resp.text().await.unwrap(),
+        String::from("Got Some(\"My Token\") because None")
+    );
+}
+
+#[test]
+fn redacted() {
+    // Passwords never show up, but the usernames still do
+    let debugs = [
+        format!("{:?}", AuthBasic(("alice".into(), Some("hunter2".into())))),
+        format!("{:?}", AuthBasicStrict(("alice".into(), "hunter2".into()))),
+        format!(
+            "{:?}",
+            AuthBasicEmail(("alice@example.com".into(), Some("hunter2".into())))
+        ),
+        format!(
+            "{:?}",
+            AuthBasicRaw {
+                username: "alice".into(),
+                password: Some("hunter2".into()),
+                password_bytes: Some(b"hunter2".to_vec()),
+            }
+        ),
+    ];
+    for debug in debugs {
+        assert!(debug.contains("alice"));
+        assert!(!debug.contains("hunter2"));
+        assert!(!debug.contains("104, 117"));
+    }
+    assert_eq!(
+        format!("{:?}", AuthBasic(("alice".into(), None))),
+        "AuthBasic((\"alice\", None))"
+    );
+}
@@ -0,0 +1,197 @@
+#![cfg(feature = "jwt")]
+
+use axum::{extract::Request, middleware::map_request, routing::get, Router};
+use axum_auth::{
+    AuthBearer, AuthBound, AuthUser, ClientCertificate, DeviceBound, JwtKey, JwtValidator,
+    RequireAuthLayer,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use hmac::{Hmac, Mac};
+use http::StatusCode;
+use sha2::{Digest, Sha256};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Shared secret for the HS256 tokens
+const SECRET: &[u8] = b"correct horse battery staple";
+
+/// User agent the tokens are bound to
+const USER_AGENT: &str = "ExampleApp/1.0 (iPhone)";
+
+/// Launches spin-off axum instance
+async fn launcher() {
+    let validator = DeviceBound::new(JwtValidator::new().with_key(JwtKey::hmac(SECRET)))
+        .with_binding("device_id", "x-device-id")
+        .with_hashed_binding("ua_hash", "user-agent");
+
+    let mtls = DeviceBound::new(JwtValidator::new().with_key(JwtKey::hmac(SECRET)))
+        .with_certificate_binding();
+
+    // Make routes
+    let app = Router::new()
+        .route("/", get(tester))
+        .layer(RequireAuthLayer::<AuthBound<AuthBearer>, _>::new(validator))
+        .route(
+            "/mtls",
+            get(tester)
+                .layer(RequireAuthLayer::<AuthBound<AuthBearer>, _>::new(mtls))
+                .layer(map_request(forwarded_cert)),
+        );
+
+    // Launch
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:3027")
+        .await
+        .unwrap();
+
+    println!("listening on {}", listener.local_addr().unwrap());
+    axum::serve(listener, app.into_make_service())
+        .await
+        .unwrap();
+
+    async fn tester(user: AuthUser) -> String {
+        format!("Got {}", user.id)
+    }
+}
+
+/// Inserts the certificate forwarded by the pretend TLS proxy, like a real server would after the handshake
+async fn forwarded_cert(mut req: Request) -> Request {
+    let cert = req
+        .headers()
+        .get("x-client-cert")
+        .and_then(|value| ClientCertificate::from_pem(value.to_str().unwrap()));
+    if let Some(cert) = cert {
+        req.extensions_mut().insert(cert);
+    }
+    req
+}
+
+/// Makes a PEM certificate from the DER contents, which is never parsed so doesn't have to be real
+fn pem(der: &[u8]) -> String {
+    format!(
+        "-----BEGIN CERTIFICATE-----{}-----END CERTIFICATE-----",
+        base64::engine::general_purpose::STANDARD.encode(der)
+    )
+}
+
+fn url(end: &str) -> String {
+    format!("http://127.0.0.1:3027{}", end)
+}
+
+/// Signs the claims into an HS256 token
+fn sign(claims: serde_json::Value) -> String {
+    let header = URL_SAFE_NO_PAD.encode(r#"{"alg":"HS256","typ":"JWT"}"#);
+    let payload = URL_SAFE_NO_PAD.encode(claims.to_string());
+    let mut mac = Hmac::<Sha256>::new_from_slice(SECRET).unwrap();
+    mac.update(format!("{}.{}", header, payload).as_bytes());
+    let signature = URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes());
+    format!("{}.{}.{}", header, payload, signature)
+}
+
+/// Makes a token for alice bound to the device
+fn bound_token(device_id: &str) -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    sign(serde_json::json!({
+        "sub": "alice",
+        "exp": now + 60,
+        "device_id": device_id,
+        "ua_hash": URL_SAFE_NO_PAD.encode(Sha256::digest(USER_AGENT)),
+    }))
+}
+
+#[tokio::test]
+async fn tester() {
+    // Launch axum instance
+    tokio::task::spawn(launcher());
+
+    // Wait for boot
+    tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
+
+    // Tests
+    let client = reqwest::Client::new();
+    let send = |token: String, device_id: &'static str, user_agent: &'static str| {
+        client
+            .get(url("/"))
+            .bearer_auth(token)
+            .header("x-device-id", device_id)
+            .header("user-agent", user_agent)
+            .send()
+    };
+
+    // Same device
+    let resp = send(bound_token("device-1"), "device-1", USER_AGENT)
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.text().await.unwrap(), "Got alice");
+
+    // Replayed from another device or browser
+    for resp in [
+        send(bound_token("device-1"), "device-2", USER_AGENT),
+        send(bound_token("device-1"), "device-1", "curl/8.0"),
+    ] {
+        let resp = resp.await.unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            resp.text().await.unwrap(),
+            "Token is bound to another device"
+        );
+    }
+
+    // Tokens without the claims aren't bound to anything
+    let unbound = sign(serde_json::json!({ "sub": "alice", "exp": u32::MAX }));
+    let resp = send(unbound, "device-1", USER_AGENT).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+    // The wrapped validator still rejects bad tokens first
+    let resp = send("garbage".into(), "device-1", USER_AGENT)
+        .await
+        .unwrap();
+    assert_eq!(resp.text().await.unwrap(), "Bearer token isn't a valid JWT");
+
+    certificate().await;
+}
+
+/// Tokens bound to a client certificate only work alongside it
+async fn certificate() {
+    let client = reqwest::Client::new();
+    let token = sign(serde_json::json!({
+        "sub": "alice",
+        "exp": u32::MAX,
+        "cnf": { "x5t#S256": URL_SAFE_NO_PAD.encode(Sha256::digest(b"certificate-1")) },
+    }));
+    let send = |cert: Option<&[u8]>| {
+        let req = client.get(url("/mtls")).bearer_auth(&token);
+        match cert {
+            Some(cert) => req.header("x-client-cert", pem(cert)),
+            None => req,
+        }
+        .send()
+    };
+
+    let resp = send(Some(b"certificate-1")).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.text().await.unwrap(), "Got alice");
+
+    for resp in [send(Some(b"certificate-2")), send(None)] {
+        let resp = resp.await.unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            resp.text().await.unwrap(),
+            "Token is bound to another client certificate"
+        );
+    }
+}
+
+#[test]
+fn thumbprint() {
+    // Thumbprints are the unpadded base64url SHA-256 of the DER contents
+    let cert = ClientCertificate::from_pem(&pem(b"certificate-1")).unwrap();
+    assert_eq!(cert.0, b"certificate-1");
+    assert_eq!(
+        cert.thumbprint(),
+        URL_SAFE_NO_PAD.encode(Sha256::digest(b"certificate-1"))
+    );
+    assert_eq!(ClientCertificate::from_pem("not a certificate"), None);
+}
@@ -0,0 +1,164 @@
+#![cfg(feature = "auth-basic")]
+
+use axum::{routing::get, Router};
+use axum_auth::{AuthUser, BasicLogout, BasicUser, BasicUsers, BrowserBasicAuthLayer};
+use base64::{engine::general_purpose, Engine};
+use http::StatusCode;
+
+/// Launches spin-off axum instance
+async fn launcher() {
+    // Make routes
+    let users = BasicUsers::new()
+        .with_user("alice", BasicUser::new("hunter2"))
+        .with_user("jürgen", BasicUser::new("grüße"));
+    let app = Router::new()
+        .route("/", get(tester_page))
+        .route("/logout", get(tester_page))
+        .layer(BrowserBasicAuthLayer::new("Dashboard", users).with_logout_path("/logout"))
+        .route(
+            "/signout",
+            get(BasicLogout::new("Dashboard")
+                .with_dummy_credentials("logout", "logout")
+                .handler()),
+        )
+        .route(
+            "/signout/realm",
+            get(BasicLogout::new("Dashboard")
+                .with_changed_realm("Dashboard (logged out)")
+                .handler()),
+        );
+
+    // Launch
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:3018")
+        .await
+        .unwrap();
+
+    println!("listening on {}", listener.local_addr().unwrap());
+    axum::serve(listener, app.into_make_service())
+        .await
+        .unwrap();
+
+    async fn tester_page(user: AuthUser) -> String {
+        format!("Hello, {}!", user.id)
+    }
+}
+
+fn url(end: &str) -> String {
+    format!("http://127.0.0.1:3018{}", end)
+}
+
+#[tokio::test]
+async fn tester() {
+    // Launch axum instance
+    tokio::task::spawn(launcher());
+
+    // Wait for boot
+    tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
+
+    // No credentials asks the browser for them
+    let client = reqwest::Client::new();
+    let resp = client.get(url("/")).send().await.unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::UNAUTHORIZED.as_u16());
+    assert_eq!(
+        resp.headers()["www-authenticate"],
+        "Basic realm=\"Dashboard\", charset=\"UTF-8\""
+    );
+    assert_eq!(
+        resp.text().await.unwrap(),
+        String::from("`Authorization` header is missing")
+    );
+
+    // Correct credentials
+    let resp = client
+        .get(url("/"))
+        .basic_auth("alice", Some("hunter2"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::OK.as_u16());
+    assert_eq!(resp.text().await.unwrap(), String::from("Hello, alice!"));
+
+    // Wrong credentials ask again instead of forbidding
+    let resp = client
+        .get(url("/"))
+        .basic_auth("alice", Some("wrong"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::UNAUTHORIZED.as_u16());
+    assert!(resp.headers().contains_key("www-authenticate"));
+
+    // Non-ASCII credentials in UTF-8 and in a browser's Latin-1 fallback
+    let utf8 = general_purpose::STANDARD.encode("jürgen:grüße");
+    let latin1: Vec<u8> = "jürgen:grüße".chars().map(|c| c as u8).collect();
+    let latin1 = general_purpose::STANDARD.encode(latin1);
+    for encoded in [utf8, latin1] {
+        let resp = client
+            .get(url("/"))
+            .header("Authorization", format!("Basic {}", encoded))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status().as_u16(), StatusCode::OK.as_u16());
+        assert_eq!(resp.text().await.unwrap(), String::from("Hello, jürgen!"));
+    }
+
+    // Malformed header asks again instead of a bad request
+    let resp = client
+        .get(url("/"))
+        .header("Authorization", "Basic !!!")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::UNAUTHORIZED.as_u16());
+    assert_eq!(
+        resp.text().await.unwrap(),
+        String::from("`Authorization` header could not be decoded")
+    );
+
+    // Logout path clears credentials even if they're correct
+    let resp = client
+        .get(url("/logout"))
+        .basic_auth("alice", Some("hunter2"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::UNAUTHORIZED.as_u16());
+    assert!(resp.headers().contains_key("www-authenticate"));
+    assert_eq!(
+        resp.text().await.unwrap(),
+        String::from("You have been logged out")
+    );
+
+    // Logout handler asks again for the realm, or accepts the dummy credentials
+    let resp = client
+        .get(url("/signout"))
+        .basic_auth("alice", Some("hunter2"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::UNAUTHORIZED.as_u16());
+    assert_eq!(
+        resp.headers()["www-authenticate"],
+        "Basic realm=\"Dashboard\", charset=\"UTF-8\""
+    );
+    let resp = client
+        .get(url("/signout"))
+        .basic_auth("logout", Some("logout"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::OK.as_u16());
+    assert_eq!(
+        resp.text().await.unwrap(),
+        String::from("You have been logged out")
+    );
+
+    // Logout handler with a changed realm
+    let resp = client.get(url("/signout/realm")).send().await.unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::UNAUTHORIZED.as_u16());
+    assert_eq!(
+        resp.headers()["www-authenticate"],
+        "Basic realm=\"Dashboard (logged out)\", charset=\"UTF-8\""
+    );
+}
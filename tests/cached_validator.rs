@@ -0,0 +1,312 @@
+#![cfg(feature = "validator-cache")]
+
+use axum::{routing::get, Router};
+use axum_auth::{
+    AuthBearer, AuthUser, AuthValidator, CachedValidation, CachedValidator, CredentialExpiry,
+    MemoryValidationCache, Rejection, RequireAuthLayer, ValidationCache,
+};
+use http::{Extensions, StatusCode};
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::{Duration, SystemTime},
+};
+
+/// Number of times the slow validator has been called
+static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+/// Whether the identity provider behind [RemoteTokens] is down
+static DOWN: AtomicBool = AtomicBool::new(false);
+
+/// Validator standing in for a database lookup, counting how often it's used
+struct SlowTokens;
+
+impl AuthValidator<AuthBearer> for SlowTokens {
+    async fn validate(&self, credentials: &AuthBearer) -> Result<AuthUser, Rejection> {
+        CALLS.fetch_add(1, Ordering::SeqCst);
+        match credentials.0.as_str() {
+            "alice-token" => Ok(AuthUser::new("alice")),
+            "bob-token" => Ok(AuthUser::new("bob")),
+            "outage" => Err((StatusCode::SERVICE_UNAVAILABLE, "Database is down")),
+            _ => Err((StatusCode::UNAUTHORIZED, "Unknown token")),
+        }
+    }
+}
+
+/// Validator standing in for an identity provider which can go down
+struct RemoteTokens;
+
+impl AuthValidator<AuthBearer> for RemoteTokens {
+    async fn validate(&self, credentials: &AuthBearer) -> Result<AuthUser, Rejection> {
+        if DOWN.load(Ordering::SeqCst) {
+            return Err((StatusCode::SERVICE_UNAVAILABLE, "Identity provider is down"));
+        }
+        match credentials.0.as_str() {
+            "carol-token" => Ok(AuthUser::new("carol")),
+            _ => Err((StatusCode::UNAUTHORIZED, "Unknown token")),
+        }
+    }
+}
+
+/// Validator counting how often it's used, for checking caches without a server
+#[derive(Default)]
+struct CountedTokens(AtomicUsize);
+
+impl AuthValidator<AuthBearer> for CountedTokens {
+    async fn validate(&self, credentials: &AuthBearer) -> Result<AuthUser, Rejection> {
+        self.0.fetch_add(1, Ordering::SeqCst);
+        match credentials.0.as_str() {
+            "erin-token" => Ok(AuthUser::new("erin")),
+            _ => Err((StatusCode::UNAUTHORIZED, "Unknown token")),
+        }
+    }
+}
+
+/// Validator accepting tokens which are the number of seconds until they expire, even once they have
+struct ExpiringTokens;
+
+impl AuthValidator<AuthBearer> for ExpiringTokens {
+    async fn validate(&self, credentials: &AuthBearer) -> Result<AuthUser, Rejection> {
+        self.validate_with_extensions(credentials, &mut Extensions::new())
+            .await
+    }
+
+    async fn validate_with_extensions(
+        &self,
+        credentials: &AuthBearer,
+        extensions: &mut Extensions,
+    ) -> Result<AuthUser, Rejection> {
+        let secs: i64 = credentials.0.parse().unwrap();
+        let expires = match u64::try_from(secs) {
+            Ok(secs) => SystemTime::now() + Duration::from_secs(secs),
+            Err(_) => SystemTime::now() - Duration::from_secs(secs.unsigned_abs()),
+        };
+        extensions.insert(CredentialExpiry(expires));
+        Ok(AuthUser::new("erin"))
+    }
+}
+
+/// Cache standing in for a distributed one, recording the TTL of everything inserted
+#[derive(Default)]
+struct RecordingCache {
+    memory: MemoryValidationCache,
+    ttls: Mutex<Vec<Duration>>,
+}
+
+impl ValidationCache for RecordingCache {
+    async fn get(&self, key: &[u8]) -> Option<CachedValidation> {
+        self.memory.get(key).await
+    }
+
+    async fn insert_with_ttl(&self, key: &[u8], validation: CachedValidation, ttl: Duration) {
+        self.ttls.lock().unwrap().push(ttl);
+        self.memory.insert_with_ttl(key, validation, ttl).await;
+    }
+
+    async fn invalidate(&self, key: &[u8]) {
+        self.memory.invalidate(key).await;
+    }
+}
+
+/// Launches spin-off axum instance
+async fn launcher() {
+    // Make routes
+    let cached = CachedValidator::new(SlowTokens)
+        .with_ttl(Duration::from_millis(500))
+        .with_max_size(1);
+    let negative = CachedValidator::new(SlowTokens).with_negative_ttl(Duration::from_millis(500));
+    let stale = CachedValidator::new(RemoteTokens)
+        .with_ttl(Duration::from_millis(100))
+        .with_stale_if_unavailable(Duration::from_millis(500));
+    let app = Router::new()
+        .route("/", get(tester_cached))
+        .layer(RequireAuthLayer::<AuthBearer, _>::new(cached))
+        .merge(
+            Router::new()
+                .route("/negative", get(tester_cached))
+                .layer(RequireAuthLayer::<AuthBearer, _>::new(negative)),
+        )
+        .merge(
+            Router::new()
+                .route("/stale", get(tester_cached))
+                .layer(RequireAuthLayer::<AuthBearer, _>::new(stale)),
+        );
+
+    // Launch
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:3019")
+        .await
+        .unwrap();
+
+    println!("listening on {}", listener.local_addr().unwrap());
+    axum::serve(listener, app.into_make_service())
+        .await
+        .unwrap();
+
+    async fn tester_cached(user: AuthUser) -> String {
+        format!("Got {}", user.id)
+    }
+}
+
+fn url(end: &str) -> String {
+    format!("http://127.0.0.1:3019{}", end)
+}
+
+/// Sends the token, giving back the status code and body
+async fn send(token: &str) -> (u16, String) {
+    send_to("/", token).await
+}
+
+/// Sends the token to the path, giving back the status code and body
+async fn send_to(path: &str, token: &str) -> (u16, String) {
+    let resp = reqwest::Client::new()
+        .get(url(path))
+        .bearer_auth(token)
+        .send()
+        .await
+        .unwrap();
+    (resp.status().as_u16(), resp.text().await.unwrap())
+}
+
+#[tokio::test]
+async fn tester() {
+    // Launch axum instance
+    tokio::task::spawn(launcher());
+
+    // Wait for boot
+    tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
+
+    // Repeated requests only validate once
+    for _ in 0..3 {
+        assert_eq!(send("alice-token").await, (200, String::from("Got alice")));
+    }
+    assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+
+    // Failures are never cached
+    for _ in 0..2 {
+        assert_eq!(send("wrong").await.0, StatusCode::UNAUTHORIZED.as_u16());
+    }
+    assert_eq!(CALLS.load(Ordering::SeqCst), 3);
+
+    // Full cache forgets the older entry
+    assert_eq!(send("bob-token").await, (200, String::from("Got bob")));
+    assert_eq!(send("alice-token").await, (200, String::from("Got alice")));
+    assert_eq!(CALLS.load(Ordering::SeqCst), 5);
+
+    // Expired entries are validated again
+    tokio::time::sleep(Duration::from_millis(600)).await;
+    assert_eq!(send("alice-token").await, (200, String::from("Got alice")));
+    assert_eq!(CALLS.load(Ordering::SeqCst), 6);
+
+    // Failures can be cached briefly too, except for server errors
+    for _ in 0..3 {
+        assert_eq!(send_to("/negative", "wrong").await.0, 401);
+    }
+    assert_eq!(CALLS.load(Ordering::SeqCst), 7);
+    for _ in 0..2 {
+        assert_eq!(send_to("/negative", "outage").await.0, 503);
+    }
+    assert_eq!(CALLS.load(Ordering::SeqCst), 9);
+
+    // Cached failures expire sooner or later as well
+    tokio::time::sleep(Duration::from_millis(600)).await;
+    assert_eq!(send_to("/negative", "wrong").await.0, 401);
+    assert_eq!(CALLS.load(Ordering::SeqCst), 10);
+
+    // Expired validations are only used while the identity provider is down
+    let carol = (200, String::from("Got carol"));
+    assert_eq!(send_to("/stale", "carol-token").await, carol);
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    DOWN.store(true, Ordering::SeqCst);
+    assert_eq!(send_to("/stale", "carol-token").await, carol);
+    assert_eq!(send_to("/stale", "dave-token").await.0, 503);
+
+    // Until they're too old to use
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert_eq!(send_to("/stale", "carol-token").await.0, 503);
+    DOWN.store(false, Ordering::SeqCst);
+    assert_eq!(send_to("/stale", "carol-token").await, carol);
+}
+
+/// Checks a cache is used for hits and forgets invalidated credentials
+async fn check_cache<S: ValidationCache>(cached: CachedValidator<CountedTokens, S>) {
+    let erin = AuthBearer(String::from("erin-token"));
+    for _ in 0..3 {
+        assert_eq!(cached.validate(&erin).await.unwrap().id, "erin");
+    }
+    assert_eq!(cached.inner().0.load(Ordering::SeqCst), 1);
+
+    // Invalidated credentials are validated again
+    cached.invalidate(&erin).await;
+    assert_eq!(cached.validate(&erin).await.unwrap().id, "erin");
+    assert_eq!(cached.inner().0.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn custom_cache() {
+    let cached = CachedValidator::new(CountedTokens::default())
+        .with_ttl(Duration::from_secs(10))
+        .with_stale_if_unavailable(Duration::from_secs(5))
+        .with_cache(RecordingCache::default());
+    check_cache(cached).await;
+}
+
+#[tokio::test]
+async fn custom_cache_ttl() {
+    // Successful validations are kept for the grace period too
+    let cached = CachedValidator::new(CountedTokens::default())
+        .with_ttl(Duration::from_secs(10))
+        .with_stale_if_unavailable(Duration::from_secs(5))
+        .with_negative_ttl(Duration::from_secs(1))
+        .with_cache(RecordingCache::default());
+    cached
+        .validate(&AuthBearer(String::from("erin-token")))
+        .await
+        .unwrap();
+    cached
+        .validate(&AuthBearer(String::from("wrong")))
+        .await
+        .unwrap_err();
+    assert_eq!(
+        *cached.cache().ttls.lock().unwrap(),
+        [Duration::from_secs(15), Duration::from_secs(1)]
+    );
+}
+
+#[cfg(feature = "moka")]
+#[tokio::test]
+async fn moka_cache() {
+    let cached = CachedValidator::new(CountedTokens::default())
+        .with_cache(axum_auth::MokaValidationCache::new(100));
+    check_cache(cached).await;
+}
+
+#[tokio::test]
+async fn credential_expiry() {
+    let cached = CachedValidator::new(ExpiringTokens)
+        .with_ttl(Duration::from_secs(10))
+        .with_stale_if_unavailable(Duration::from_secs(5))
+        .with_cache(RecordingCache::default());
+
+    // Validations aren't remembered for longer than the credentials are valid
+    let mut extensions = Extensions::new();
+    cached
+        .validate_with_extensions(&AuthBearer(String::from("2")), &mut extensions)
+        .await
+        .unwrap();
+    let remaining = extensions.get::<CredentialExpiry>().unwrap().remaining();
+    assert!(remaining > Duration::from_secs(1) && remaining <= Duration::from_secs(2));
+    {
+        let ttls = cached.cache().ttls.lock().unwrap();
+        assert_eq!(ttls.len(), 1);
+        assert!(ttls[0] > Duration::from_secs(1) && ttls[0] <= Duration::from_secs(2));
+    }
+
+    // Credentials which have already expired aren't remembered at all
+    cached
+        .validate(&AuthBearer(String::from("-1")))
+        .await
+        .unwrap();
+    assert_eq!(cached.cache().ttls.lock().unwrap().len(), 1);
+}
@@ -0,0 +1,111 @@
+#![cfg(feature = "casbin")]
+
+use axum::{
+    extract::Request,
+    middleware::{self, Next},
+    response::Response,
+    routing::get,
+    Router,
+};
+use axum_auth::{AuthUser, AuthorizeLayer, CasbinAuthorizer};
+use casbin::{CoreApi, DefaultModel, Enforcer, StringAdapter};
+use http::StatusCode;
+
+const MODEL: &str = r#"
+[request_definition]
+r = sub, obj, act
+
+[policy_definition]
+p = sub, obj, act
+
+[role_definition]
+g = _, _
+
+[policy_effect]
+e = some(where (p.eft == allow))
+
+[matchers]
+m = g(r.sub, p.sub) && r.obj == p.obj && r.act == p.act
+"#;
+
+/// Makes an enforcer from the model above and the given policy
+async fn enforcer(policy: &str) -> Enforcer {
+    let model = DefaultModel::from_str(MODEL).await.unwrap();
+    Enforcer::new(model, StringAdapter::new(policy.to_string()))
+        .await
+        .unwrap()
+}
+
+/// Simple identity middleware trusting the `x-user` header, standing in for a real one
+async fn identity(mut req: Request, next: Next) -> Response {
+    let id = req.headers().get("x-user").unwrap().to_str().unwrap();
+    let user = AuthUser::new(id);
+    req.extensions_mut().insert(user);
+    next.run(req).await
+}
+
+/// Launches spin-off axum instance
+async fn launcher(authorizer: CasbinAuthorizer) {
+    // Make routes
+    let app = Router::new()
+        .route("/posts", get(tester).post(tester))
+        .layer(AuthorizeLayer::new(authorizer))
+        .layer(middleware::from_fn(identity));
+
+    // Launch
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:3005")
+        .await
+        .unwrap();
+
+    println!("listening on {}", listener.local_addr().unwrap());
+    axum::serve(listener, app.into_make_service())
+        .await
+        .unwrap();
+
+    async fn tester(user: AuthUser) -> String {
+        format!("Got {}", user.id)
+    }
+}
+
+fn url(end: &str) -> String {
+    format!("http://127.0.0.1:3005{}", end)
+}
+
+/// Sends a request as the given user and returns the status
+async fn status(method: &str, user: &str) -> u16 {
+    reqwest::Client::new()
+        .request(method.parse().unwrap(), url("/posts"))
+        .header("x-user", user)
+        .send()
+        .await
+        .unwrap()
+        .status()
+        .as_u16()
+}
+
+#[tokio::test]
+async fn tester() {
+    // Launch axum instance
+    let authorizer = CasbinAuthorizer::new(
+        enforcer("p, reader, /posts, GET\np, admin, /posts, POST\ng, alice, reader").await,
+    );
+    tokio::task::spawn(launcher(authorizer.clone()));
+
+    // Wait for boot
+    tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
+
+    // Try with the first policy
+    assert_eq!(status("GET", "alice").await, StatusCode::OK.as_u16());
+    assert_eq!(
+        status("POST", "alice").await,
+        StatusCode::FORBIDDEN.as_u16()
+    );
+    assert_eq!(status("GET", "bob").await, StatusCode::FORBIDDEN.as_u16());
+
+    // Try again after swapping in a new policy
+    authorizer
+        .replace(enforcer("p, admin, /posts, POST\ng, alice, admin").await)
+        .await;
+    assert_eq!(status("GET", "alice").await, StatusCode::FORBIDDEN.as_u16());
+    assert_eq!(status("POST", "alice").await, StatusCode::OK.as_u16());
+}
@@ -0,0 +1,92 @@
+#![cfg(feature = "cedar")]
+
+use axum::{
+    extract::Request,
+    middleware::{self, Next},
+    response::Response,
+    routing::get,
+    Router,
+};
+use axum_auth::{AuthUser, AuthorizeLayer, CedarAuthorizer};
+use cedar_policy::PolicySet;
+use http::StatusCode;
+
+/// Simple identity middleware trusting the `x-user` header, standing in for a real one
+async fn identity(mut req: Request, next: Next) -> Response {
+    let user = match req.headers().get("x-user").map(|v| v.to_str().unwrap()) {
+        Some("root") => AuthUser::new("root").with_roles(["admin"]),
+        Some(id) => AuthUser::new(id),
+        None => return next.run(req).await,
+    };
+    req.extensions_mut().insert(user);
+    next.run(req).await
+}
+
+/// Launches spin-off axum instance
+async fn launcher() {
+    // Make policies
+    let policies: PolicySet = r#"
+        permit(principal, action == Action::"GET", resource);
+        permit(principal in Role::"admin", action, resource);
+        forbid(principal, action, resource == Path::"/secret") unless { principal == User::"root" };
+    "#
+    .parse()
+    .unwrap();
+
+    // Make routes
+    let app = Router::new()
+        .route("/", get(tester).post(tester))
+        .route("/secret", get(tester))
+        .layer(AuthorizeLayer::new(CedarAuthorizer::new(policies)))
+        .layer(middleware::from_fn(identity));
+
+    // Launch
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:3004")
+        .await
+        .unwrap();
+
+    println!("listening on {}", listener.local_addr().unwrap());
+    axum::serve(listener, app.into_make_service())
+        .await
+        .unwrap();
+
+    async fn tester(user: AuthUser) -> String {
+        format!("Got {}", user.id)
+    }
+}
+
+fn url(end: &str) -> String {
+    format!("http://127.0.0.1:3004{}", end)
+}
+
+#[tokio::test]
+async fn tester() {
+    // Launch axum instance
+    tokio::task::spawn(launcher());
+
+    // Wait for boot
+    tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
+
+    // Try each method, path, and user combination
+    let client = reqwest::Client::new();
+    let cases = [
+        ("GET", "/", "alice", StatusCode::OK),
+        ("POST", "/", "alice", StatusCode::FORBIDDEN),
+        ("POST", "/", "root", StatusCode::OK),
+        ("GET", "/secret", "alice", StatusCode::FORBIDDEN),
+        ("GET", "/secret", "root", StatusCode::OK),
+    ];
+    for (method, path, user, status) in cases {
+        let resp = client
+            .request(method.parse().unwrap(), url(path))
+            .header("x-user", user)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(
+            resp.status().as_u16(),
+            status.as_u16(),
+            "{method} {path} as {user}"
+        );
+    }
+}
@@ -0,0 +1,70 @@
+use axum_auth::Challenge;
+
+#[test]
+fn round_trip() {
+    // Every kind of challenge the builder makes parses back into itself
+    let challenges = vec![
+        Challenge::basic("Admin \"area\", with commas"),
+        Challenge::bearer(),
+        Challenge::new("Bearer")
+            .with_param("realm", "api")
+            .with_param("error", "invalid_token")
+            .with_param("scope", "read write"),
+        Challenge::new("Negotiate").with_token68("YIIBhgYGKwYBBQUCoIIB="),
+        Challenge::new("Negotiate"),
+        Challenge::new("HOBA")
+            .with_param("challenge", "abc==")
+            .with_param("max-age", "10"),
+    ];
+    let header = challenges
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ");
+    assert_eq!(Challenge::parse_all(&header), challenges);
+    for challenge in &challenges {
+        assert_eq!(
+            Challenge::parse_all(&challenge.to_string()),
+            vec![challenge.clone()]
+        );
+    }
+}
+
+#[test]
+fn parse() {
+    // Example from RFC 9110, with unquoted values and odd spacing
+    let challenges = Challenge::parse_all(
+        r#"Newauth realm="apps", type=1,  title="Login to \"apps\"", Basic realm="simple""#,
+    );
+    assert_eq!(
+        challenges,
+        vec![
+            Challenge::new("Newauth")
+                .with_param("realm", "apps")
+                .with_param("type", "1")
+                .with_param("title", "Login to \"apps\""),
+            Challenge::new("Basic").with_param("realm", "simple"),
+        ]
+    );
+
+    // Parameter names are case-insensitive
+    let challenges = Challenge::parse_all(r#"Digest REALM="x", Nonce = "abc""#);
+    assert_eq!(challenges[0].params()[0].0, "realm");
+    assert_eq!(challenges[0].param("NONCE"), Some("abc"));
+    assert_eq!(challenges[0].param("qop"), None);
+
+    // Token68 challenges next to others
+    let challenges = Challenge::parse_all("Negotiate abc==, NTLM, Bearer");
+    assert_eq!(challenges.len(), 3);
+    assert_eq!(challenges[0].token68(), Some("abc=="));
+    assert_eq!(challenges[1].scheme(), "NTLM");
+    assert_eq!(challenges[2].scheme(), "Bearer");
+
+    // Garbage is skipped over
+    assert_eq!(Challenge::parse_all(""), vec![]);
+    assert_eq!(Challenge::parse_all(" , ,"), vec![]);
+    assert_eq!(
+        Challenge::parse_all(r#""oops", Bearer"#),
+        vec![Challenge::bearer()]
+    );
+}
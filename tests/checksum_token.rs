@@ -0,0 +1,117 @@
+#![cfg(feature = "checksum-token")]
+
+use axum::{extract::FromRequestParts, routing::get, Router};
+use axum_auth::{AuthBearerCustom, ChecksumToken, Rejection};
+use http::{request::Parts, StatusCode};
+
+/// Bearer extractor only accepting GitHub classic personal access tokens
+struct GithubPat(String);
+
+impl AuthBearerCustom for GithubPat {
+    type Rejection = Rejection;
+    const ERROR_CODE: StatusCode = StatusCode::BAD_REQUEST;
+    const ERROR_OVERWRITE: Option<&'static str> = None;
+    const CHECKSUM_TOKEN: Option<ChecksumToken> = Some(ChecksumToken::GITHUB_PAT);
+
+    fn from_header(contents: &str) -> Self {
+        Self(contents.to_string())
+    }
+}
+
+impl<B> FromRequestParts<B> for GithubPat
+where
+    B: Send + Sync,
+{
+    type Rejection = Rejection;
+
+    async fn from_request_parts(parts: &mut Parts, _: &B) -> Result<Self, Self::Rejection> {
+        Self::decode_request_parts(parts)
+    }
+}
+
+/// Launches spin-off axum instance
+async fn launcher() {
+    // Make routes
+    let app = Router::new().route("/github", get(tester_github));
+
+    // Launch
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:3017")
+        .await
+        .unwrap();
+
+    println!("listening on {}", listener.local_addr().unwrap());
+    axum::serve(listener, app.into_make_service())
+        .await
+        .unwrap();
+
+    async fn tester_github(GithubPat(token): GithubPat) -> String {
+        format!("Got {}", token)
+    }
+}
+
+fn url(end: &str) -> String {
+    format!("http://127.0.0.1:3017{}", end)
+}
+
+#[test]
+fn checksums() {
+    // CRC32 check value of `123456789` encoded as base62
+    let format = ChecksumToken::new("t_", 9);
+    assert!(format.verify("t_1234567893Jzrme"));
+    assert!(!format.verify("t_1234567893Jzrmf"));
+    assert!(!format.verify("x_1234567893Jzrme"));
+    assert!(!format.verify("t_1234567893Jzrm"));
+
+    // Generated tokens always verify
+    for _ in 0..32 {
+        let token = ChecksumToken::GITHUB_PAT.generate();
+        assert_eq!(token.len(), 40);
+        assert!(ChecksumToken::GITHUB_PAT.verify(&token));
+        assert!(ChecksumToken::is_github_token(&token));
+    }
+
+    // Fine-grained tokens only have their shape checked
+    let fine_grained = format!("github_pat_{}_{}", "A".repeat(22), "b".repeat(59));
+    assert!(ChecksumToken::is_github_token(&fine_grained));
+    assert!(!ChecksumToken::is_github_token(&fine_grained[..90]));
+}
+
+#[tokio::test]
+async fn tester() {
+    // Launch axum instance
+    tokio::task::spawn(launcher());
+
+    // Wait for boot
+    tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
+
+    // Generated token
+    let client = reqwest::Client::new();
+    let token = ChecksumToken::GITHUB_PAT.generate();
+    let resp = client
+        .get(url("/github"))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::OK.as_u16());
+    assert_eq!(resp.text().await.unwrap(), format!("Got {}", token));
+
+    // Typo in the token
+    let typo = match &token[4..5] {
+        "x" => format!("ghp_y{}", &token[5..]),
+        _ => format!("ghp_x{}", &token[5..]),
+    };
+    let resp = client
+        .get(url("/github"))
+        .bearer_auth(typo)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::UNAUTHORIZED.as_u16());
+    assert_eq!(
+        resp.text().await.unwrap(),
+        String::from(
+            "`Authorization` bearer token has an invalid checksum, check it was copied correctly"
+        )
+    );
+}
@@ -0,0 +1,129 @@
+#![cfg(feature = "circuit-breaker")]
+
+use axum::{routing::get, Router};
+use axum_auth::{
+    AuthBearer, AuthUser, AuthValidator, CircuitBreaker, CircuitState, Rejection, RequireAuthLayer,
+};
+use http::StatusCode;
+use std::{
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    time::Duration,
+};
+
+/// Whether the identity provider is currently down
+static DOWN: AtomicBool = AtomicBool::new(false);
+
+/// Number of times the identity provider has been called
+static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+/// Validator standing in for an identity provider which can go down
+struct RemoteTokens;
+
+impl AuthValidator<AuthBearer> for RemoteTokens {
+    async fn validate(&self, credentials: &AuthBearer) -> Result<AuthUser, Rejection> {
+        CALLS.fetch_add(1, Ordering::SeqCst);
+        if DOWN.load(Ordering::SeqCst) {
+            return Err((StatusCode::BAD_GATEWAY, "Identity provider is down"));
+        }
+        match credentials.0.as_str() {
+            "alice-token" => Ok(AuthUser::new("alice")),
+            _ => Err((StatusCode::UNAUTHORIZED, "Unknown token")),
+        }
+    }
+}
+
+/// Launches spin-off axum instance
+async fn launcher() {
+    // Make routes
+    let breaker = CircuitBreaker::new(RemoteTokens)
+        .with_failure_threshold(2)
+        .with_open_duration(Duration::from_millis(300))
+        .with_fallback((StatusCode::SERVICE_UNAVAILABLE, "Try again soon"));
+    let app = Router::new()
+        .route("/", get(tester_breaker))
+        .layer(RequireAuthLayer::<AuthBearer, _>::new(breaker));
+
+    // Launch
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:3032")
+        .await
+        .unwrap();
+
+    println!("listening on {}", listener.local_addr().unwrap());
+    axum::serve(listener, app.into_make_service())
+        .await
+        .unwrap();
+
+    async fn tester_breaker(user: AuthUser) -> String {
+        format!("Got {}", user.id)
+    }
+}
+
+fn url(end: &str) -> String {
+    format!("http://127.0.0.1:3032{}", end)
+}
+
+/// Sends the token, giving back the status code and body
+async fn get_with(token: &str) -> (u16, String) {
+    let resp = reqwest::Client::new()
+        .get(url("/"))
+        .bearer_auth(token)
+        .send()
+        .await
+        .unwrap();
+    (resp.status().as_u16(), resp.text().await.unwrap())
+}
+
+#[tokio::test]
+async fn tester() {
+    // Launch axum instance
+    tokio::task::spawn(launcher());
+
+    // Wait for boot
+    tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
+
+    // Bad credentials never open the circuit
+    let ok = (StatusCode::OK.as_u16(), String::from("Got alice"));
+    let unknown = (
+        StatusCode::UNAUTHORIZED.as_u16(),
+        String::from("Unknown token"),
+    );
+    for _ in 0..3 {
+        assert_eq!(get_with("mallory-token").await, unknown);
+    }
+    assert_eq!(get_with("alice-token").await, ok);
+
+    // Failures in a row open it, after which the validator isn't called
+    DOWN.store(true, Ordering::SeqCst);
+    let down = (
+        StatusCode::BAD_GATEWAY.as_u16(),
+        String::from("Identity provider is down"),
+    );
+    let fallback = (
+        StatusCode::SERVICE_UNAVAILABLE.as_u16(),
+        String::from("Try again soon"),
+    );
+    assert_eq!(get_with("alice-token").await, down);
+    assert_eq!(get_with("alice-token").await, down);
+    let calls = CALLS.load(Ordering::SeqCst);
+    assert_eq!(get_with("alice-token").await, fallback);
+    assert_eq!(get_with("mallory-token").await, fallback);
+    assert_eq!(CALLS.load(Ordering::SeqCst), calls);
+
+    // A failed probe opens it again
+    tokio::time::sleep(Duration::from_millis(350)).await;
+    assert_eq!(get_with("alice-token").await, down);
+    assert_eq!(get_with("alice-token").await, fallback);
+
+    // A working probe closes it
+    DOWN.store(false, Ordering::SeqCst);
+    tokio::time::sleep(Duration::from_millis(350)).await;
+    assert_eq!(get_with("alice-token").await, ok);
+    assert_eq!(get_with("mallory-token").await, unknown);
+    assert_eq!(get_with("alice-token").await, ok);
+}
+
+#[test]
+fn state() {
+    let breaker = CircuitBreaker::new(RemoteTokens);
+    assert_eq!(breaker.state(), CircuitState::Closed);
+}
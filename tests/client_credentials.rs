@@ -0,0 +1,141 @@
+#![cfg(feature = "client-credentials")]
+
+use axum::{routing::post, Form, Json, Router};
+use axum_auth::{AuthBasic, BearerToken, ClientCredentials};
+use http::StatusCode;
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Number of tokens the endpoint has given out
+static ISSUED: AtomicUsize = AtomicUsize::new(0);
+
+/// Launches spin-off axum instance
+async fn launcher() {
+    // Make routes
+    let app = Router::new().route("/token", post(token));
+
+    // Launch
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:3043")
+        .await
+        .unwrap();
+
+    println!("listening on {}", listener.local_addr().unwrap());
+    axum::serve(listener, app.into_make_service())
+        .await
+        .unwrap();
+
+    /// Token endpoint standing in for an identity provider
+    async fn token(
+        AuthBasic((client_id, client_secret)): AuthBasic,
+        Form(form): Form<HashMap<String, String>>,
+    ) -> Result<Json<Value>, StatusCode> {
+        assert_eq!(form["grant_type"], "client_credentials");
+        match (client_id.as_str(), client_secret.as_deref()) {
+            ("orders", Some("orders-secret")) => (),
+            ("crashing", _) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+            _ => return Err(StatusCode::UNAUTHORIZED),
+        }
+        let n = ISSUED.fetch_add(1, Ordering::SeqCst);
+        let scope = form.get("scope").map(String::as_str).unwrap_or("none");
+        let expires_in = match form.get("audience").map(String::as_str) {
+            Some("short-lived") => 10,
+            Some("forever") => u64::MAX,
+            _ => 3600,
+        };
+        Ok(Json(json!({
+            "access_token": format!("{}-{}-{}", client_id, scope, n),
+            "token_type": "Bearer",
+            "expires_in": expires_in,
+        })))
+    }
+}
+
+fn url(end: &str) -> String {
+    format!("http://127.0.0.1:3043{}", end)
+}
+
+#[tokio::test]
+async fn tester() {
+    // Launch axum instance
+    tokio::task::spawn(launcher());
+
+    // Wait for boot
+    tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
+
+    let credentials =
+        ClientCredentials::new(url("/token"), "orders", "orders-secret").with_scope("billing:read");
+
+    // Tokens are asked for once, then remembered across clones
+    let token = credentials.token().await.unwrap();
+    assert_eq!(token, BearerToken(String::from("orders-billing:read-0")));
+    assert_eq!(credentials.token().await.unwrap(), token);
+    assert_eq!(credentials.clone().token().await.unwrap(), token);
+
+    // Concurrent calls share a single new token
+    let fresh = ClientCredentials::new(url("/token"), "orders", "orders-secret");
+    let (first, second) = tokio::join!(fresh.token(), fresh.token());
+    assert_eq!(first.unwrap(), BearerToken(String::from("orders-none-1")));
+    assert_eq!(second.unwrap(), BearerToken(String::from("orders-none-1")));
+
+    // Tokens expiring within the margin are never reused
+    let short = ClientCredentials::new(url("/token"), "orders", "orders-secret")
+        .with_audience("short-lived");
+    assert_eq!(short.token().await.unwrap().as_str(), "orders-none-2");
+    assert_eq!(short.token().await.unwrap().as_str(), "orders-none-3");
+
+    // Tokens lasting too long to be a time are used but not remembered
+    let forever =
+        ClientCredentials::new(url("/token"), "orders", "orders-secret").with_audience("forever");
+    assert_eq!(forever.token().await.unwrap().as_str(), "orders-none-4");
+    assert_eq!(forever.token().await.unwrap().as_str(), "orders-none-5");
+
+    // Tokens are sent as sensitive bearer headers without showing up in debug output
+    let value = token.to_header_value().unwrap();
+    assert_eq!(value, "Bearer orders-billing:read-0");
+    assert!(value.is_sensitive());
+    assert_eq!(
+        token.to_headers()["authorization"],
+        "Bearer orders-billing:read-0"
+    );
+    assert!(!format!("{:?}", token).contains("orders"));
+    assert!(!format!("{:?}", credentials).contains("orders-secret"));
+    assert_eq!(
+        BearerToken(String::from("bad\ntoken")).to_header_value(),
+        None
+    );
+    assert!(BearerToken(String::from("bad\ntoken"))
+        .to_headers()
+        .is_empty());
+
+    // Refusals and outages are told apart
+    assert_eq!(
+        ClientCredentials::new(url("/token"), "orders", "wrong-secret")
+            .token()
+            .await,
+        Err((
+            StatusCode::BAD_GATEWAY,
+            "Client credentials were refused by the token endpoint"
+        ))
+    );
+    assert_eq!(
+        ClientCredentials::new(url("/token"), "crashing", "secret")
+            .token()
+            .await,
+        Err((
+            StatusCode::BAD_GATEWAY,
+            "Token endpoint couldn't be reached"
+        ))
+    );
+    assert_eq!(
+        ClientCredentials::new("http://127.0.0.1:1/token", "orders", "orders-secret")
+            .token()
+            .await,
+        Err((
+            StatusCode::BAD_GATEWAY,
+            "Token endpoint couldn't be reached"
+        ))
+    );
+}
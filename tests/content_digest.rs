@@ -0,0 +1,117 @@
+#![cfg(feature = "content-digest")]
+
+use axum::{routing::post, Router};
+use axum_auth::{AuthBearer, DigestVerified};
+use http::StatusCode;
+
+/// Examples from RFC 9530, section 2
+const BODY: &str = "{\"hello\": \"world\"}\n";
+const SHA_256: &str = "sha-256=:RK/0qy18MlBSVnWgjwz6lZEWjP/lF5HF9bvEF8FabDg=:";
+const SHA_512: &str = "sha-512=:YMAam51Jz/jOATT6/zvHrLVgOYTGFy1d6GJiOHTohq4yP+pgk4vf2aCsyRZOtw8MjkM7iw7yZ/WkppmM44T3qg==:";
+
+/// Launches spin-off axum instance
+async fn launcher() {
+    // Make routes
+    let app = Router::new()
+        .route("/plain", post(tester_plain))
+        .route("/bearer", post(tester_bearer));
+
+    // Launch
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:3008")
+        .await
+        .unwrap();
+
+    println!("listening on {}", listener.local_addr().unwrap());
+    axum::serve(listener, app.into_make_service())
+        .await
+        .unwrap();
+
+    async fn tester_plain(verified: DigestVerified<()>) -> String {
+        format!("Got {} bytes", verified.body.len())
+    }
+
+    async fn tester_bearer(verified: DigestVerified<AuthBearer>) -> String {
+        format!(
+            "Got {} bytes from {}",
+            verified.body.len(),
+            verified.credential.0
+        )
+    }
+}
+
+fn url(end: &str) -> String {
+    format!("http://127.0.0.1:3008{}", end)
+}
+
+#[tokio::test]
+async fn tester() {
+    // Launch axum instance
+    tokio::task::spawn(launcher());
+
+    // Wait for boot
+    tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
+
+    // Try both algorithms together and alone
+    let client = reqwest::Client::new();
+    for digest in [
+        format!("{}, {}", SHA_256, SHA_512),
+        SHA_256.to_string(),
+        SHA_512.to_string(),
+    ] {
+        let resp = client
+            .post(url("/plain"))
+            .header("content-digest", digest)
+            .body(BODY)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status().as_u16(), StatusCode::OK.as_u16());
+        assert_eq!(resp.text().await.unwrap(), String::from("Got 19 bytes"));
+    }
+
+    // Try with credentials
+    let resp = client
+        .post(url("/bearer"))
+        .bearer_auth("My Token")
+        .header("content-digest", SHA_256)
+        .body(BODY)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(
+        resp.text().await.unwrap(),
+        String::from("Got 19 bytes from My Token")
+    );
+
+    // Try tampered body
+    let resp = client
+        .post(url("/plain"))
+        .header("content-digest", SHA_256)
+        .body("{\"hello\": \"there\"}\n")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::BAD_REQUEST.as_u16());
+    assert_eq!(
+        resp.text().await.unwrap(),
+        String::from("Request body doesn't match its `Content-Digest`")
+    );
+
+    // Try unknown algorithm and missing header
+    let resp = client
+        .post(url("/plain"))
+        .header("content-digest", "md5=:AAAA:")
+        .body(BODY)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(
+        resp.text().await.unwrap(),
+        String::from("`Content-Digest` header has no supported algorithm")
+    );
+    let resp = client.post(url("/plain")).body(BODY).send().await.unwrap();
+    assert_eq!(
+        resp.text().await.unwrap(),
+        String::from("`Content-Digest` header is missing")
+    );
+}
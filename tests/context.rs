@@ -0,0 +1,134 @@
+#![cfg(feature = "connect-info")]
+
+use axum::{routing::get, Router};
+use axum_auth::{AuthBearer, AuthContext};
+use http::StatusCode;
+use std::net::SocketAddr;
+
+/// Launches spin-off axum instance
+async fn launcher() {
+    // Make routes
+    let app = Router::new()
+        .route("/direct", get(tester_direct))
+        .route("/proxied", get(tester_proxied));
+
+    // Launch
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:3002")
+        .await
+        .unwrap();
+
+    println!("listening on {}", listener.local_addr().unwrap());
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .unwrap();
+
+    async fn tester_direct(ctx: AuthContext<AuthBearer>) -> String {
+        format!(
+            "Got {} from {:?} using {:?} as {:?}",
+            ctx.credential.0, ctx.client_ip, ctx.user_agent, ctx.request_id
+        )
+    }
+
+    async fn tester_proxied(ctx: AuthContext<AuthBearer, true>) -> String {
+        format!("Got {} from {:?}", ctx.credential.0, ctx.client_ip)
+    }
+}
+
+fn url(end: &str) -> String {
+    format!("http://127.0.0.1:3002{}", end)
+}
+
+#[tokio::test]
+async fn tester() {
+    // Launch axum instance
+    tokio::task::spawn(launcher());
+
+    // Wait for boot
+    tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
+
+    // Tests
+    direct().await;
+    proxied().await;
+    missing().await;
+}
+
+/// Forwarded headers should be ignored unless trusted
+async fn direct() {
+    let client = reqwest::Client::new();
+    let resp = client
+        .get(url("/direct"))
+        .bearer_auth("My Token")
+        .header("user-agent", "tester/1.0")
+        .header("x-request-id", "abc123")
+        .header("x-forwarded-for", "203.0.113.7")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::OK.as_u16());
+    assert_eq!(
+        resp.text().await.unwrap(),
+        String::from(
+            "Got My Token from Some(127.0.0.1) using Some(\"tester/1.0\") as Some(\"abc123\")"
+        )
+    );
+}
+
+/// Forwarded headers should be used in the documented order once trusted
+async fn proxied() {
+    // Try standardised header over the others
+    let client = reqwest::Client::new();
+    let resp = client
+        .get(url("/proxied"))
+        .bearer_auth("My Token")
+        .header(
+            "forwarded",
+            "for=198.51.100.1, for=\"[2001:db8::1]:4711\";proto=http",
+        )
+        .header("x-forwarded-for", "203.0.113.7")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(
+        resp.text().await.unwrap(),
+        String::from("Got My Token from Some(2001:db8::1)")
+    );
+
+    // Try de-facto header, where the proxy appended the address it saw to a spoofed one
+    let resp = client
+        .get(url("/proxied"))
+        .bearer_auth("My Token")
+        .header("x-forwarded-for", "10.0.0.1, 203.0.113.7")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(
+        resp.text().await.unwrap(),
+        String::from("Got My Token from Some(203.0.113.7)")
+    );
+
+    // Try falling back to the connection
+    let resp = client
+        .get(url("/proxied"))
+        .bearer_auth("My Token")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(
+        resp.text().await.unwrap(),
+        String::from("Got My Token from Some(127.0.0.1)")
+    );
+}
+
+/// The wrapped extractor's rejection should be kept as-is
+async fn missing() {
+    let client = reqwest::Client::new();
+    let resp = client.get(url("/direct")).send().await.unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::BAD_REQUEST.as_u16());
+    assert_eq!(
+        resp.text().await.unwrap(),
+        String::from("`Authorization` header is missing")
+    );
+}
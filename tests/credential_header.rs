@@ -0,0 +1,111 @@
+use axum::{routing::get, Extension, Router};
+use axum_auth::{AuthBasic, AuthBearer, CredentialHeader};
+use http::StatusCode;
+
+/// Launches spin-off axum instance
+async fn launcher() {
+    // Make routes
+    let app = Router::new()
+        .route("/basic", get(tester_basic))
+        .route("/bearer", get(tester_bearer))
+        .route("/optional", get(tester_optional))
+        .layer(Extension(CredentialHeader::new(
+            "x-forwarded-authorization",
+        )));
+
+    // Launch
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:3028")
+        .await
+        .unwrap();
+
+    println!("listening on {}", listener.local_addr().unwrap());
+    axum::serve(listener, app.into_make_service())
+        .await
+        .unwrap();
+
+    async fn tester_basic(AuthBasic((id, password)): AuthBasic) -> String {
+        format!("Got {} and {:?}", id, password)
+    }
+
+    async fn tester_bearer(AuthBearer(token): AuthBearer) -> String {
+        format!("Got {}", token)
+    }
+
+    async fn tester_optional(auth: Option<AuthBearer>) -> String {
+        format!("Got {:?}", auth.map(|AuthBearer(token)| token))
+    }
+}
+
+fn url(end: &str) -> String {
+    format!("http://127.0.0.1:3028{}", end)
+}
+
+#[tokio::test]
+async fn tester() {
+    // Launch axum instance
+    tokio::task::spawn(launcher());
+
+    // Wait for boot
+    tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
+
+    // Credentials are read from the forwarded header, ignoring the gateway's own
+    let client = reqwest::Client::new();
+    let resp = client
+        .get(url("/bearer"))
+        .header("Authorization", "Bearer gateway-token")
+        .header("X-Forwarded-Authorization", "Bearer client-token")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::OK.as_u16());
+    assert_eq!(resp.text().await.unwrap(), "Got client-token");
+    let resp = client
+        .get(url("/basic"))
+        .header(
+            "X-Forwarded-Authorization",
+            "Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ==",
+        )
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(
+        resp.text().await.unwrap(),
+        "Got Aladdin and Some(\"open sesame\")"
+    );
+
+    // Parsing is the same as before
+    let resp = client
+        .get(url("/bearer"))
+        .header(
+            "X-Forwarded-Authorization",
+            "Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ==",
+        )
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::BAD_REQUEST.as_u16());
+    assert_eq!(
+        resp.text().await.unwrap(),
+        "`Authorization` header must be a bearer token"
+    );
+
+    // The usual header is ignored altogether
+    let resp = client
+        .get(url("/bearer"))
+        .bearer_auth("gateway-token")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::BAD_REQUEST.as_u16());
+    assert_eq!(
+        resp.text().await.unwrap(),
+        "`Authorization` header is missing"
+    );
+    let resp = client
+        .get(url("/optional"))
+        .bearer_auth("gateway-token")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.text().await.unwrap(), "Got None");
+}
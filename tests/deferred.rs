@@ -0,0 +1,112 @@
+use axum::{extract::Query, routing::get, Extension, Router};
+use axum_auth::{AuthBearer, AuthUser, AuthValidator, DeferredBearer, Rejection};
+use http::StatusCode;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+/// Validations made so far
+static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+/// Validator counting how often it's called
+struct Counted;
+
+impl AuthValidator<AuthBearer> for Counted {
+    async fn validate(&self, credentials: &AuthBearer) -> Result<AuthUser, Rejection> {
+        CALLS.fetch_add(1, Ordering::SeqCst);
+        match credentials.0.as_str() {
+            "alice-token" => Ok(AuthUser::new("alice")),
+            _ => Err((StatusCode::UNAUTHORIZED, "Unknown token")),
+        }
+    }
+}
+
+/// Launches spin-off axum instance
+async fn launcher() {
+    // Make routes
+    let app = Router::new()
+        .route("/", get(tester_deferred))
+        .layer(Extension(Arc::new(Counted)))
+        .route("/novalidator", get(tester_deferred));
+
+    // Launch
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:3038")
+        .await
+        .unwrap();
+
+    println!("listening on {}", listener.local_addr().unwrap());
+    axum::serve(listener, app.into_make_service())
+        .await
+        .unwrap();
+
+    async fn tester_deferred(
+        auth: DeferredBearer<Counted>,
+        Query(query): Query<HashMap<String, String>>,
+    ) -> Result<String, Rejection> {
+        if query.contains_key("cached") {
+            return Ok(String::from("Cached"));
+        }
+        let user = auth.verify().await?;
+        assert!(auth.is_verified());
+        assert_eq!(auth.verify().await?, user);
+        Ok(format!("Got {}", user.id))
+    }
+}
+
+fn url(end: &str) -> String {
+    format!("http://127.0.0.1:3038{}", end)
+}
+
+/// Sends the token if there is one, giving back the status code and body
+async fn send(end: &str, token: Option<&str>) -> (u16, String) {
+    let mut req = reqwest::Client::new().get(url(end));
+    if let Some(token) = token {
+        req = req.bearer_auth(token);
+    }
+    let resp = req.send().await.unwrap();
+    (resp.status().as_u16(), resp.text().await.unwrap())
+}
+
+#[tokio::test]
+async fn tester() {
+    // Launch axum instance
+    tokio::task::spawn(launcher());
+
+    // Wait for boot
+    tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
+
+    // Paths which don't need credentials never validate them, even when they're missing or wrong
+    assert_eq!(
+        send("/?cached", Some("alice-token")).await,
+        (200, String::from("Cached"))
+    );
+    assert_eq!(send("/?cached", None).await, (200, String::from("Cached")));
+    assert_eq!(send("/?cached", Some("wrong")).await.0, 200);
+    assert_eq!(CALLS.load(Ordering::SeqCst), 0);
+
+    // Otherwise they're validated once, however often the handler asks
+    assert_eq!(
+        send("/", Some("alice-token")).await,
+        (200, String::from("Got alice"))
+    );
+    assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+    assert_eq!(
+        send("/", Some("wrong")).await,
+        (401, String::from("Unknown token"))
+    );
+    assert_eq!(CALLS.load(Ordering::SeqCst), 2);
+
+    // Missing credentials are only rejected once they're needed
+    assert_eq!(
+        send("/", None).await,
+        (400, String::from("`Authorization` header is missing"))
+    );
+    assert_eq!(CALLS.load(Ordering::SeqCst), 2);
+
+    // Forgetting the validator is a server error
+    assert_eq!(send("/novalidator?cached", None).await.0, 500);
+}
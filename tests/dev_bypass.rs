@@ -0,0 +1,66 @@
+#![cfg(feature = "dev-bypass")]
+
+use axum::{routing::get, Router};
+use axum_auth::{AuthBearer, DevBypass, DEV_BYPASS_VAR};
+use http::StatusCode;
+
+/// Launches spin-off axum instance
+async fn launcher() {
+    // Make routes
+    let app = Router::new().route("/", get(tester));
+
+    // Launch
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:3022")
+        .await
+        .unwrap();
+
+    println!("listening on {}", listener.local_addr().unwrap());
+    axum::serve(listener, app).await.unwrap();
+
+    async fn tester(DevBypass(AuthBearer(token)): DevBypass<AuthBearer>) -> String {
+        format!("Got {}", token)
+    }
+}
+
+#[tokio::test]
+async fn tester() {
+    // Launch axum instance
+    tokio::task::spawn(launcher());
+
+    // Wait for boot
+    tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
+
+    // Tests
+    unset().await;
+    std::env::set_var(DEV_BYPASS_VAR, "Bearer dev-token");
+    injected().await;
+    real().await;
+}
+
+/// Without the variable a header is still required
+async fn unset() {
+    let resp = reqwest::get("http://127.0.0.1:3022/").await.unwrap();
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
+
+/// Requests without a header get the credentials from the variable, but only in debug builds
+async fn injected() {
+    let resp = reqwest::get("http://127.0.0.1:3022/").await.unwrap();
+    if cfg!(debug_assertions) {
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.text().await.unwrap(), "Got dev-token");
+    } else {
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+}
+
+/// Requests with a header are left alone
+async fn real() {
+    let resp = reqwest::Client::new()
+        .get("http://127.0.0.1:3022/")
+        .bearer_auth("real-token")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.text().await.unwrap(), "Got real-token");
+}
@@ -0,0 +1,274 @@
+#![cfg(feature = "auth-digest")]
+
+use axum::{routing::get, Router};
+use axum_auth::{
+    AuthDigest, AuthUser, Challenge, DigestAuthLayer, DigestNonces, DigestUser, DigestUsers,
+};
+use http::StatusCode;
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+
+/// Launches spin-off axum instance
+async fn launcher() {
+    // Make users, one of which was hashed beforehand
+    let users = DigestUsers::new()
+        .with_user(
+            "alice",
+            DigestUser::from_password("alice", "testrealm", "hunter2"),
+        )
+        .with_user(
+            "bob",
+            DigestUser::new(
+                AuthDigest::ha1("bob", "testrealm", "letmein"),
+                AuthUser::new("bob").with_roles(["admin"]),
+            ),
+        );
+
+    // Make routes
+    let app = Router::new()
+        .route("/digest", get(tester_digest))
+        .layer(DigestAuthLayer::new("testrealm", users.clone()))
+        .merge(
+            Router::new().route("/stale", get(tester_digest)).layer(
+                DigestAuthLayer::new("testrealm", users)
+                    .with_nonces(DigestNonces::new().with_max_age(Duration::ZERO)),
+            ),
+        );
+
+    // Launch
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:3045")
+        .await
+        .unwrap();
+
+    println!("listening on {}", listener.local_addr().unwrap());
+    axum::serve(listener, app.into_make_service())
+        .await
+        .unwrap();
+
+    async fn tester_digest(user: AuthUser, digest: AuthDigest) -> String {
+        format!(
+            "Got {} with {:?} from {}",
+            user.id, user.roles, digest.realm
+        )
+    }
+}
+
+fn url(end: &str) -> String {
+    format!("http://127.0.0.1:3045{}", end)
+}
+
+/// Hashes the data like a client would
+fn hash(data: &str) -> String {
+    Sha256::digest(data.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Gets the challenge the server gave back
+fn challenge(resp: &reqwest::Response) -> Challenge {
+    let header = resp.headers()["www-authenticate"].to_str().unwrap();
+    Challenge::parse_all(header).remove(0)
+}
+
+/// Answers the challenge like a client would
+fn answer(
+    challenge: &Challenge,
+    uri: &str,
+    username: &str,
+    password: &str,
+    nc: &str,
+    session: bool,
+) -> String {
+    let (realm, nonce) = (
+        challenge.param("realm").unwrap(),
+        challenge.param("nonce").unwrap(),
+    );
+    let cnonce = "0a4f113b";
+    let mut ha1 = hash(&format!("{}:{}:{}", username, realm, password));
+    if session {
+        ha1 = hash(&format!("{}:{}:{}", ha1, nonce, cnonce));
+    }
+    let ha2 = hash(&format!("GET:{}", uri));
+    let response = hash(&format!("{}:{}:{}:{}:auth:{}", ha1, nonce, nc, cnonce, ha2));
+    format!(
+        r#"Digest username="{}", realm="{}", nonce="{}", uri="{}", algorithm={}, qop=auth, nc={}, cnonce="{}", response="{}", opaque="{}""#,
+        username,
+        realm,
+        nonce,
+        uri,
+        if session { "SHA-256-sess" } else { "SHA-256" },
+        nc,
+        cnonce,
+        response,
+        challenge.param("opaque").unwrap()
+    )
+}
+
+#[tokio::test]
+async fn tester() {
+    // Launch axum instance
+    tokio::task::spawn(launcher());
+
+    // Wait for boot
+    tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
+
+    // Requests without credentials get a challenge
+    let client = reqwest::Client::new();
+    let resp = client.get(url("/digest")).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    let first = challenge(&resp);
+    assert_eq!(first.scheme(), "Digest");
+    assert_eq!(first.param("realm"), Some("testrealm"));
+    assert_eq!(first.param("qop"), Some("auth"));
+    assert_eq!(first.param("algorithm"), Some("SHA-256"));
+    assert_eq!(first.param("stale"), None);
+    assert_eq!(
+        resp.text().await.unwrap(),
+        "`Authorization` header is missing"
+    );
+
+    // Answering it logs in, using the roles of the user
+    let send = |path: &'static str, authorization: String| {
+        client
+            .get(url(path))
+            .header("authorization", authorization)
+            .send()
+    };
+    let resp = send(
+        "/digest",
+        answer(&first, "/digest", "alice", "hunter2", "00000001", false),
+    )
+    .await
+    .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(
+        resp.text().await.unwrap(),
+        "Got alice with [] from testrealm"
+    );
+    let resp = client.get(url("/digest")).send().await.unwrap();
+    let second = challenge(&resp);
+    let resp = send(
+        "/digest",
+        answer(&second, "/digest", "bob", "letmein", "00000001", true),
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        resp.text().await.unwrap(),
+        "Got bob with [\"admin\"] from testrealm"
+    );
+
+    // Nonce counts can't be used twice, but the next one works
+    let resp = send(
+        "/digest",
+        answer(&first, "/digest", "alice", "hunter2", "00000001", false),
+    )
+    .await
+    .unwrap();
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(
+        resp.text().await.unwrap(),
+        "Digest nonce has expired or was already used"
+    );
+    let resp = send(
+        "/digest",
+        answer(&first, "/digest", "alice", "hunter2", "00000002", false),
+    )
+    .await
+    .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    // Wrong passwords, targets, and opaque values are rejected with a fresh challenge
+    let resp = send(
+        "/digest",
+        answer(&first, "/digest", "alice", "wrong", "00000003", false),
+    )
+    .await
+    .unwrap();
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    assert_ne!(challenge(&resp).param("nonce"), first.param("nonce"));
+    assert_eq!(
+        resp.text().await.unwrap(),
+        "The provided credentials are invalid"
+    );
+    let resp = send(
+        "/digest",
+        answer(&first, "/other", "alice", "hunter2", "00000003", false),
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        resp.text().await.unwrap(),
+        "Digest response was made for another request"
+    );
+    let forged = Challenge::new("Digest")
+        .with_param("realm", "testrealm")
+        .with_param("nonce", first.param("nonce").unwrap())
+        .with_param("opaque", "forged");
+    let resp = send(
+        "/digest",
+        answer(&forged, "/digest", "alice", "hunter2", "00000003", false),
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        resp.text().await.unwrap(),
+        "Digest nonce has expired or was already used"
+    );
+
+    // Unsupported algorithms are malformed
+    let resp = send(
+        "/digest",
+        answer(&first, "/digest", "alice", "hunter2", "00000003", false).replace("SHA-256", "MD5"),
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        resp.text().await.unwrap(),
+        "`Authorization` header isn't a valid digest response"
+    );
+    let resp = client
+        .get(url("/digest"))
+        .basic_auth("alice", Some("hunter2"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(
+        resp.text().await.unwrap(),
+        "`Authorization` header must be for digest authentication"
+    );
+
+    // Expired nonces are stale, so clients can answer again without asking for the password
+    let resp = client.get(url("/stale")).send().await.unwrap();
+    let expiring = challenge(&resp);
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    let resp = send(
+        "/stale",
+        answer(&expiring, "/stale", "alice", "hunter2", "00000001", false),
+    )
+    .await
+    .unwrap();
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(challenge(&resp).param("stale"), Some("true"));
+
+    // Stale nonces with the wrong password aren't marked as stale
+    let resp = send(
+        "/stale",
+        answer(&expiring, "/stale", "alice", "wrong", "00000002", false),
+    )
+    .await
+    .unwrap();
+    assert_eq!(challenge(&resp).param("stale"), None);
+}
+
+#[test]
+fn redacted() {
+    let debug = format!(
+        "{:?}",
+        DigestUser::from_password("alice", "testrealm", "hunter2")
+    );
+    assert!(debug.contains("alice"));
+    assert!(!debug.contains(&AuthDigest::ha1("alice", "testrealm", "hunter2")));
+}
@@ -0,0 +1,87 @@
+use axum::{routing::get, Router};
+use axum_auth::{AuthBasic, AuthBearer, AuthEither};
+use http::StatusCode;
+
+/// Launches spin-off axum instance
+async fn launcher() {
+    // Make routes
+    let app = Router::new().route("/either", get(tester_either));
+
+    // Launch
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:3010")
+        .await
+        .unwrap();
+
+    println!("listening on {}", listener.local_addr().unwrap());
+    axum::serve(listener, app.into_make_service())
+        .await
+        .unwrap();
+
+    async fn tester_either(auth: AuthEither<AuthBearer, AuthBasic>) -> String {
+        match auth {
+            AuthEither::Left(AuthBearer(token)) => format!("Got token {}", token),
+            AuthEither::Right(AuthBasic((id, _))) => format!("Got user {}", id),
+        }
+    }
+}
+
+fn url(end: &str) -> String {
+    format!("http://127.0.0.1:3010{}", end)
+}
+
+#[tokio::test]
+async fn tester() {
+    // Launch axum instance
+    tokio::task::spawn(launcher());
+
+    // Wait for boot
+    tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
+
+    // Try both schemes
+    let client = reqwest::Client::new();
+    let resp = client
+        .get(url("/either"))
+        .bearer_auth("My Token")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(
+        resp.text().await.unwrap(),
+        String::from("Got token My Token")
+    );
+    let resp = client
+        .get(url("/either"))
+        .basic_auth("My Username", Some("My Password"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(
+        resp.text().await.unwrap(),
+        String::from("Got user My Username")
+    );
+
+    // Try missing, unsupported, and broken credentials
+    for (header, message) in [
+        (None, "`Authorization` header is missing"),
+        (
+            Some("Digest username=\"x\""),
+            "`Authorization` header uses an unsupported scheme",
+        ),
+        (
+            Some("Basic not-base64"),
+            "`Authorization` header could not be decoded",
+        ),
+    ] {
+        let mut req = client.get(url("/either"));
+        if let Some(header) = header {
+            req = req.header("Authorization", header);
+        }
+        let resp = req.send().await.unwrap();
+        assert_eq!(resp.status().as_u16(), StatusCode::UNAUTHORIZED.as_u16());
+        assert_eq!(
+            resp.headers()["www-authenticate"],
+            "Bearer, Basic realm=\"Restricted\", charset=\"UTF-8\""
+        );
+        assert_eq!(resp.text().await.unwrap(), String::from(message));
+    }
+}
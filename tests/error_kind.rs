@@ -0,0 +1,195 @@
+use axum::{
+    extract::{FromRequestParts, OptionalFromRequestParts},
+    middleware::map_response,
+    response::Response,
+    routing::get,
+    Router,
+};
+use axum_auth::{
+    AuthBasic, AuthBasicStrict, AuthBearer, AuthErrorKind, AuthResult, AuthUser, BasicUser,
+    BasicUsers, RequireBasicAuthLayer,
+};
+use http::{HeaderValue, Request, StatusCode};
+
+/// Launches spin-off axum instance
+async fn launcher() {
+    // Make routes
+    let users = BasicUsers::new().with_user("alice", BasicUser::new("hunter2"));
+    let app = Router::new()
+        .route("/", get(tester_basic))
+        .layer(RequireBasicAuthLayer::new(users))
+        .layer(map_response(tag_kind))
+        .route("/soft", get(tester_soft));
+
+    // Launch
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:3020")
+        .await
+        .unwrap();
+
+    println!("listening on {}", listener.local_addr().unwrap());
+    axum::serve(listener, app.into_make_service())
+        .await
+        .unwrap();
+
+    async fn tester_basic(user: AuthUser) -> String {
+        format!("Got {}", user.id)
+    }
+
+    async fn tester_soft(auth: AuthResult<AuthBearer>, why: Option<AuthErrorKind>) -> String {
+        match auth.into_inner() {
+            Ok(AuthBearer(token)) => format!("Private view for {}", token),
+            Err(kind) => format!("Public view because of {:?} and {:?}", kind, why),
+        }
+    }
+
+    /// Copies the kind of error into a header so it can be seen by the client
+    async fn tag_kind(mut resp: Response) -> Response {
+        if let Some(kind) = resp.extensions().get::<AuthErrorKind>() {
+            let kind = HeaderValue::from_str(&format!("{:?}", kind)).unwrap();
+            resp.headers_mut().insert("x-auth-error", kind);
+        }
+        resp
+    }
+}
+
+fn url(end: &str) -> String {
+    format!("http://127.0.0.1:3020{}", end)
+}
+
+/// Extracts using the authorization header, giving back the kind of error
+async fn kind_of<A: FromRequestParts<(), Rejection = (StatusCode, &'static str)>>(
+    authorization: Option<&str>,
+) -> Option<AuthErrorKind> {
+    let mut req = Request::builder();
+    if let Some(authorization) = authorization {
+        req = req.header("Authorization", authorization);
+    }
+    let (mut parts, _) = req.body(()).unwrap().into_parts();
+    A::from_request_parts(&mut parts, &())
+        .await
+        .err()
+        .map(|rejection| AuthErrorKind::of(&rejection))
+}
+
+#[tokio::test]
+async fn kinds() {
+    assert_eq!(
+        kind_of::<AuthBasic>(None).await,
+        Some(AuthErrorKind::MissingHeader)
+    );
+    assert_eq!(
+        kind_of::<AuthBasic>(Some("Basic !!!")).await,
+        Some(AuthErrorKind::DecodeFailed)
+    );
+    assert_eq!(
+        kind_of::<AuthBasicStrict>(Some("Basic dXNlcg==")).await,
+        Some(AuthErrorKind::MissingColon)
+    );
+    assert_eq!(
+        kind_of::<AuthBasicStrict>(Some("Basic !!!")).await,
+        Some(AuthErrorKind::DecodeFailed)
+    );
+    assert_eq!(
+        kind_of::<AuthBasic>(Some("Bearer token")).await,
+        Some(AuthErrorKind::WrongScheme)
+    );
+    assert_eq!(
+        kind_of::<AuthBearer>(Some("Basic dXNlcjpwYXNz")).await,
+        Some(AuthErrorKind::WrongScheme)
+    );
+    assert_eq!(kind_of::<AuthBearer>(Some("Bearer token")).await, None);
+    assert_eq!(
+        AuthErrorKind::of(&(StatusCode::UNAUTHORIZED, "Unknown token")),
+        AuthErrorKind::Other
+    );
+}
+
+/// Extracts optionally using the authorization header, giving back the kind of error stored in the extensions
+async fn stored_kind<A: OptionalFromRequestParts<()>>(
+    authorization: Option<&str>,
+) -> Option<AuthErrorKind> {
+    let mut req = Request::builder();
+    if let Some(authorization) = authorization {
+        req = req.header("Authorization", authorization);
+    }
+    let (mut parts, _) = req.body(()).unwrap().into_parts();
+    let _ = A::from_request_parts(&mut parts, &()).await;
+    parts.extensions.get::<AuthErrorKind>().copied()
+}
+
+#[tokio::test]
+async fn stored_kinds() {
+    // Optional extractors keep the reason there aren't any credentials, even when rejecting
+    assert_eq!(
+        stored_kind::<AuthBearer>(None).await,
+        Some(AuthErrorKind::MissingHeader)
+    );
+    assert_eq!(
+        stored_kind::<AuthBearer>(Some("Basic dXNlcjpwYXNz")).await,
+        Some(AuthErrorKind::WrongScheme)
+    );
+    assert_eq!(
+        stored_kind::<AuthBasic>(Some("Basic !!!")).await,
+        Some(AuthErrorKind::DecodeFailed)
+    );
+    assert_eq!(stored_kind::<AuthBearer>(Some("Bearer token")).await, None);
+}
+
+#[tokio::test]
+async fn tester() {
+    // Launch axum instance
+    tokio::task::spawn(launcher());
+
+    // Wait for boot
+    tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
+
+    // Kinds of the layer's rejections are visible to middleware
+    let client = reqwest::Client::new();
+    let resp = client.get(url("/")).send().await.unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::BAD_REQUEST.as_u16());
+    assert_eq!(resp.headers()["x-auth-error"], "MissingHeader");
+    let resp = client
+        .get(url("/"))
+        .basic_auth("alice", Some("wrong"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::UNAUTHORIZED.as_u16());
+    assert_eq!(resp.headers()["x-auth-error"], "ValidationFailed");
+
+    // Successful requests don't have one
+    let resp = client
+        .get(url("/"))
+        .basic_auth("alice", Some("hunter2"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::OK.as_u16());
+    assert!(!resp.headers().contains_key("x-auth-error"));
+
+    // Soft-fail extractors never reject, giving the kind to the handler instead
+    let resp = client.get(url("/soft")).send().await.unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::OK.as_u16());
+    assert_eq!(
+        resp.text().await.unwrap(),
+        "Public view because of MissingHeader and Some(MissingHeader)"
+    );
+    let resp = client
+        .get(url("/soft"))
+        .basic_auth("alice", Some("hunter2"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::OK.as_u16());
+    assert_eq!(
+        resp.text().await.unwrap(),
+        "Public view because of WrongScheme and Some(WrongScheme)"
+    );
+    let resp = client
+        .get(url("/soft"))
+        .bearer_auth("token")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.text().await.unwrap(), "Private view for token");
+}
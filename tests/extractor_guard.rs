@@ -0,0 +1,88 @@
+#![cfg(feature = "from-extractor")]
+
+use axum::{routing::get, Router};
+use axum_auth::{
+    AuthBearer, AuthGuard, BasicUser, BasicUsers, RequireBasicAuthLayer, RequireRole, Role,
+};
+use http::StatusCode;
+
+struct Admin;
+
+impl Role for Admin {
+    const NAME: &'static str = "admin";
+}
+
+/// Launches spin-off axum instance
+async fn launcher() {
+    // Make routes
+    let users = BasicUsers::new()
+        .with_user("alice", BasicUser::new("hunter2").with_roles(["admin"]))
+        .with_user("bob", BasicUser::new("letmein"));
+    let api = Router::new()
+        .route("/", get(tester_guarded))
+        .layer(AuthGuard::<AuthBearer>::layer());
+    let admin = Router::new()
+        .route("/", get(tester_guarded))
+        .layer(AuthGuard::<RequireRole<Admin>>::layer_with_state(()))
+        .layer(RequireBasicAuthLayer::new(users));
+    let app = Router::new().nest("/api", api).nest("/admin", admin);
+
+    // Launch
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:3040")
+        .await
+        .unwrap();
+
+    println!("listening on {}", listener.local_addr().unwrap());
+    axum::serve(listener, app.into_make_service())
+        .await
+        .unwrap();
+
+    async fn tester_guarded() -> &'static str {
+        "Got through"
+    }
+}
+
+fn url(end: &str) -> String {
+    format!("http://127.0.0.1:3040{}", end)
+}
+
+#[tokio::test]
+async fn tester() {
+    // Launch axum instance
+    tokio::task::spawn(launcher());
+
+    // Wait for boot
+    tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
+
+    // Requests without the extractor are rejected before the handler
+    let client = reqwest::Client::new();
+    let resp = client.get(url("/api")).send().await.unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::BAD_REQUEST.as_u16());
+    assert_eq!(
+        resp.text().await.unwrap(),
+        "`Authorization` header is missing"
+    );
+    let resp = client
+        .get(url("/api"))
+        .bearer_auth("token")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.text().await.unwrap(), "Got through");
+
+    // Guards work after authenticating layers too
+    let resp = client
+        .get(url("/admin"))
+        .basic_auth("alice", Some("hunter2"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::OK.as_u16());
+    let resp = client
+        .get(url("/admin"))
+        .basic_auth("bob", Some("letmein"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::FORBIDDEN.as_u16());
+}
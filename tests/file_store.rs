@@ -0,0 +1,138 @@
+#![cfg(feature = "file-watch")]
+
+use axum::{routing::get, Router};
+use axum_auth::{AuthUser, FileUsers, RequireBasicAuthLayer};
+use http::StatusCode;
+use std::{fs, path::PathBuf, time::Duration};
+
+/// Gets the path of the users file, which is unique to this test run
+fn path() -> PathBuf {
+    std::env::temp_dir()
+        .join(format!("axum-auth-{}", std::process::id()))
+        .join("users.toml")
+}
+
+/// Launches spin-off axum instance
+async fn launcher() {
+    // Make routes
+    let users = FileUsers::watch(path()).unwrap();
+    let app = Router::new()
+        .route("/", get(tester_file))
+        .layer(RequireBasicAuthLayer::new(users));
+
+    // Launch
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:3033")
+        .await
+        .unwrap();
+
+    println!("listening on {}", listener.local_addr().unwrap());
+    axum::serve(listener, app.into_make_service())
+        .await
+        .unwrap();
+
+    async fn tester_file(user: AuthUser) -> String {
+        format!("Got {} {:?}", user.id, user.roles)
+    }
+}
+
+fn url(end: &str) -> String {
+    format!("http://127.0.0.1:3033{}", end)
+}
+
+/// Logs in as the user, giving back the status code
+async fn login(username: &str, password: &str) -> u16 {
+    reqwest::Client::new()
+        .get(url("/"))
+        .basic_auth(username, Some(password))
+        .send()
+        .await
+        .unwrap()
+        .status()
+        .as_u16()
+}
+
+/// Waits until logging in as the user gives the status code, as the watcher reloads in the background
+async fn wait_for(username: &str, password: &str, status: StatusCode) {
+    for _ in 0..50 {
+        if login(username, password).await == status.as_u16() {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("Logging in as {} never gave {}", username, status);
+}
+
+#[tokio::test]
+async fn tester() {
+    // Write the users before launching
+    fs::create_dir_all(path().parent().unwrap()).unwrap();
+    fs::write(
+        path(),
+        "[users.alice]\npassword = \"hunter2\"\nroles = [\"admin\"]\n",
+    )
+    .unwrap();
+
+    // Launch axum instance
+    tokio::task::spawn(launcher());
+
+    // Wait for boot
+    tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
+
+    // Users from the file can log in
+    let resp = reqwest::Client::new()
+        .get(url("/"))
+        .basic_auth("alice", Some("hunter2"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::OK.as_u16());
+    assert_eq!(resp.text().await.unwrap(), "Got alice [\"admin\"]");
+    assert_eq!(login("bob", "letmein").await, 401);
+
+    // Adding and removing users takes effect without restarting
+    fs::write(path(), "[users.bob]\npassword = \"letmein\"\n").unwrap();
+    wait_for("bob", "letmein", StatusCode::OK).await;
+    assert_eq!(login("alice", "hunter2").await, 401);
+
+    // Invalid files keep the current users
+    fs::write(path(), "[users.carol]\nroles = [\"admin\"]\n").unwrap();
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert_eq!(login("bob", "letmein").await, 200);
+
+    // Files replaced by renaming them are picked up too
+    let replacement = path().with_extension("tmp");
+    fs::write(&replacement, "[users.dave]\npassword = \"pa55\"\n").unwrap();
+    fs::rename(&replacement, path()).unwrap();
+    wait_for("dave", "pa55", StatusCode::OK).await;
+    assert_eq!(login("bob", "letmein").await, 401);
+
+    fs::remove_dir_all(path().parent().unwrap()).unwrap();
+}
+
+#[test]
+fn load() {
+    let dir = std::env::temp_dir().join(format!("axum-auth-load-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    // Missing passwords, bad TOML, and empty files are all invalid data
+    let path = dir.join("users.toml");
+    fs::write(&path, "[users.alice]\nroles = [\"admin\"]\n").unwrap();
+    let err = FileUsers::load(&path).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    fs::write(&path, "[users.alice\n").unwrap();
+    let err = FileUsers::load(&path).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    fs::write(&path, "").unwrap();
+    let err = FileUsers::load(&path).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+    // Reloading by hand swaps the users in
+    fs::write(&path, "[users]\n").unwrap();
+    let users = FileUsers::load(&path).unwrap();
+    assert_eq!(*users.users(), axum_auth::BasicUsers::new());
+    fs::write(&path, "[users.alice]\npassword = \"hunter2\"\n").unwrap();
+    users.reload().unwrap();
+    assert_ne!(*users.users(), axum_auth::BasicUsers::new());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,28 @@
+#![cfg(feature = "fingerprint")]
+
+use axum_auth::{AuthBearer, Fingerprint};
+
+#[test]
+fn correlatable() {
+    // The same token always gets the same fingerprint, and the token itself never shows up
+    let bearer = AuthBearer("my-secret-token".to_string());
+    let debug = format!("{:?}", bearer);
+    assert!(!debug.contains("my-secret-token"));
+    assert_eq!(
+        debug,
+        format!("AuthBearer({})", Fingerprint::of("my-secret-token"))
+    );
+    assert_eq!(
+        bearer.fingerprint(),
+        AuthBearer("my-secret-token".to_string()).fingerprint()
+    );
+    assert_ne!(bearer.fingerprint(), Fingerprint::of("another-token"));
+}
+
+#[test]
+fn format() {
+    // Display and Debug both show a short prefixed digest
+    let fingerprint = Fingerprint::of(b"secret");
+    assert_eq!(fingerprint.to_string(), "sha256:2bb80d53…");
+    assert_eq!(format!("{:?}", fingerprint), "sha256:2bb80d53…");
+}
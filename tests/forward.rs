@@ -0,0 +1,133 @@
+use axum::{routing::get, Router};
+use axum_auth::{AuthBasic, AuthBearer, Forward, Rejection};
+use http::{HeaderMap, StatusCode};
+
+/// Launches spin-off axum instance, acting as both the gateway and the upstream service
+async fn launcher() {
+    // Make routes
+    let app = Router::new()
+        .route("/upstream", get(upstream))
+        .route("/as-is", get(tester_as_is))
+        .route("/reencode", get(tester_reencode))
+        .route("/exchange", get(tester_exchange));
+
+    // Launch
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:3021")
+        .await
+        .unwrap();
+
+    println!("listening on {}", listener.local_addr().unwrap());
+    axum::serve(listener, app.into_make_service())
+        .await
+        .unwrap();
+
+    /// Upstream service showing which credentials it got
+    async fn upstream(headers: HeaderMap) -> String {
+        let values = headers
+            .get_all("authorization")
+            .iter()
+            .map(|value| value.to_str().unwrap())
+            .collect::<Vec<_>>();
+        format!("Upstream got {:?}", values)
+    }
+
+    async fn tester_as_is(headers: HeaderMap) -> String {
+        call_upstream(Forward::as_is(&headers)).await
+    }
+
+    async fn tester_reencode(basic: AuthBasic) -> String {
+        call_upstream(Forward::reencode(&basic)).await
+    }
+
+    async fn tester_exchange(bearer: AuthBearer) -> Result<String, Rejection> {
+        let headers = Forward::exchange(&bearer, |AuthBearer(token)| {
+            let token = token.clone();
+            async move {
+                match token.as_str() {
+                    "gateway-token" => Ok(String::from("upstream-token")),
+                    _ => Err((StatusCode::UNAUTHORIZED, "Token can't be exchanged")),
+                }
+            }
+        })
+        .await?;
+        Ok(call_upstream(headers).await)
+    }
+
+    async fn call_upstream(headers: HeaderMap) -> String {
+        reqwest::Client::new()
+            .get(url("/upstream"))
+            .headers(headers)
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap()
+    }
+}
+
+fn url(end: &str) -> String {
+    format!("http://127.0.0.1:3021{}", end)
+}
+
+#[test]
+fn sensitive() {
+    // Forwarded values are kept out of logs
+    let headers = Forward::reencode(&AuthBearer("secret".into()));
+    assert!(headers["authorization"].is_sensitive());
+    assert_eq!(format!("{:?}", headers["authorization"]), "Sensitive");
+}
+
+#[tokio::test]
+async fn tester() {
+    // Launch axum instance
+    tokio::task::spawn(launcher());
+
+    // Wait for boot
+    tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
+
+    // Forward the header as it was sent, leaving out proxy credentials
+    let client = reqwest::Client::new();
+    let resp = client
+        .get(url("/as-is"))
+        .header("Authorization", "Custom abc")
+        .header("Proxy-Authorization", "Basic cHJveHk6cHJveHk=")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(
+        resp.text().await.unwrap(),
+        String::from(r#"Upstream got ["Custom abc"]"#)
+    );
+
+    // Forward basic credentials encoded again
+    let resp = client
+        .get(url("/reencode"))
+        .header("Authorization", "Basic YWxpY2U=")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(
+        resp.text().await.unwrap(),
+        String::from(r#"Upstream got ["Basic YWxpY2U="]"#)
+    );
+
+    // Exchange the token for one meant for upstream
+    let resp = client
+        .get(url("/exchange"))
+        .bearer_auth("gateway-token")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(
+        resp.text().await.unwrap(),
+        String::from(r#"Upstream got ["Bearer upstream-token"]"#)
+    );
+    let resp = client
+        .get(url("/exchange"))
+        .bearer_auth("other-token")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::UNAUTHORIZED.as_u16());
+}
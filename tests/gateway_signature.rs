@@ -0,0 +1,366 @@
+#![cfg(feature = "gateway-signature")]
+
+use axum::{routing::get, Extension, Router};
+use axum_auth::{
+    AuthErrorKind, GatewayAlgorithm, GatewaySignature, GatewayVerified, SignatureEncoding,
+    SignedPart,
+};
+use base64::{engine::general_purpose, Engine};
+use hmac::{Hmac, Mac};
+use http::StatusCode;
+use sha2::{Sha256, Sha512};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const KEY: &[u8] = b"secret shared with the gateway";
+
+/// Launches spin-off axum instance
+async fn launcher() {
+    // Make routes, each behind a differently configured gateway
+    let app = Router::new()
+        .route("/default", get(tester_gateway))
+        .layer(Extension(GatewaySignature::new(KEY)))
+        .merge(
+            Router::new()
+                .route("/kong", get(tester_gateway))
+                .layer(Extension(GatewaySignature::kong(KEY))),
+        )
+        .merge(
+            Router::new()
+                .route("/tyk", get(tester_gateway))
+                .layer(Extension(GatewaySignature::tyk(KEY))),
+        )
+        .merge(
+            Router::new()
+                .route("/custom", get(tester_gateway))
+                .layer(Extension(
+                    GatewaySignature::new(KEY)
+                        .with_header("x-gateway-signature")
+                        .with_prefix("sha512=")
+                        .with_parts([SignedPart::Method, SignedPart::header("x-request-id")])
+                        .with_separator("|")
+                        .with_algorithm(GatewayAlgorithm::HmacSha512)
+                        .with_timestamp("x-timestamp", Duration::from_secs(60)),
+                )),
+        )
+        .route("/noprofile", get(tester_gateway));
+
+    // Launch
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:3046")
+        .await
+        .unwrap();
+
+    println!("listening on {}", listener.local_addr().unwrap());
+    axum::serve(listener, app.into_make_service())
+        .await
+        .unwrap();
+
+    async fn tester_gateway(verified: GatewayVerified) -> String {
+        format!("Got {:?} from {:?}", verified.signed, verified.key_id)
+    }
+}
+
+fn url(end: &str) -> String {
+    format!("http://127.0.0.1:3046{}", end)
+}
+
+/// Signs the string using HMAC-SHA256 like a gateway would
+fn sign256(signed: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(KEY).unwrap();
+    mac.update(signed.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Signs the string using HMAC-SHA512 like a gateway would
+fn sign512(signed: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha512>::new_from_slice(KEY).unwrap();
+    mac.update(signed.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn base64(bytes: &[u8]) -> String {
+    general_purpose::STANDARD.encode(bytes)
+}
+
+/// Formats the time as an HTTP date, like `Tue, 07 Jun 2014 20:51:35 GMT`
+fn date(time: SystemTime) -> String {
+    httpdate::fmt_http_date(time)
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+/// Sends the request with the headers, giving back the status code, kind, and body
+async fn visit(path: &str, headers: &[(&str, String)]) -> (u16, AuthErrorKind, String) {
+    let mut req = reqwest::Client::new().get(url(path));
+    for (name, value) in headers {
+        req = req.header(*name, value);
+    }
+    let resp = req.send().await.unwrap();
+    let status = resp.status().as_u16();
+    let body = resp.text().await.unwrap();
+    (status, AuthErrorKind::from_message(&body), body)
+}
+
+#[tokio::test]
+async fn tester() {
+    // Launch axum instance
+    tokio::task::spawn(launcher());
+
+    // Wait for boot
+    tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
+
+    // The default profile signs the method and path with the query as hex
+    let signature = hex(&sign256("GET\n/default?page=2"));
+    let (status, _, body) = visit(
+        "/default?page=2",
+        &[("x-request-signature", signature.clone())],
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK.as_u16());
+    assert_eq!(body, "Got \"GET\\n/default?page=2\" from None");
+    assert_eq!(
+        visit(
+            "/default?page=2",
+            &[("x-request-signature", signature.to_uppercase())]
+        )
+        .await
+        .0,
+        StatusCode::OK.as_u16()
+    );
+
+    // Changed requests, bad encodings, and missing headers are rejected
+    let invalid = (
+        401,
+        AuthErrorKind::ValidationFailed,
+        String::from("Gateway signature is invalid"),
+    );
+    assert_eq!(
+        visit(
+            "/default?page=3",
+            &[("x-request-signature", signature.clone())]
+        )
+        .await,
+        invalid.clone()
+    );
+    assert_eq!(
+        visit("/default?page=2", &[("x-request-signature", "zz".into())]).await,
+        invalid.clone()
+    );
+    assert_eq!(
+        visit("/default?page=2", &[]).await,
+        (
+            401,
+            AuthErrorKind::MissingHeader,
+            String::from("Gateway signature header is missing")
+        )
+    );
+
+    // Kong sends its signature and what it signed as auth-params
+    let date_now = date(SystemTime::now());
+    let kong = |headers: &str, signature: &[u8]| {
+        format!(
+            "hmac username=\"alice\", algorithm=\"hmac-sha256\", headers=\"{}\", signature=\"{}\"",
+            headers,
+            base64(signature)
+        )
+    };
+    let signed = format!("date: {}\nGET /kong HTTP/1.1", date_now);
+    let (status, _, body) = visit(
+        "/kong",
+        &[
+            ("date", date_now.clone()),
+            (
+                "authorization",
+                kong("date request-line", &sign256(&signed)),
+            ),
+        ],
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK.as_u16());
+    assert_eq!(body, format!("Got {:?} from Some(\"alice\")", signed));
+
+    // Without headers only the date is signed
+    let (status, _, _) = visit(
+        "/kong",
+        &[
+            ("date", date_now.clone()),
+            (
+                "authorization",
+                format!(
+                    "hmac username=\"alice\", algorithm=\"hmac-sha256\", signature=\"{}\"",
+                    base64(&sign256(&format!("date: {}", date_now)))
+                ),
+            ),
+        ],
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK.as_u16());
+
+    // Changed dates, other algorithms, unsigned dates, and other schemes are rejected
+    let later = date(SystemTime::now() + Duration::from_secs(1));
+    let resigned = |headers: &str, signed: &str| {
+        vec![
+            ("date", date_now.clone()),
+            ("authorization", kong(headers, &sign256(signed))),
+        ]
+    };
+    assert_eq!(
+        visit(
+            "/kong",
+            &[
+                ("date", later),
+                (
+                    "authorization",
+                    kong("date request-line", &sign256(&signed))
+                ),
+            ],
+        )
+        .await,
+        invalid.clone()
+    );
+    assert_eq!(
+        visit(
+            "/kong",
+            &[
+                ("date", date_now.clone()),
+                (
+                    "authorization",
+                    kong("date request-line", &sign256(&signed))
+                        .replace("hmac-sha256", "hmac-sha512")
+                ),
+            ],
+        )
+        .await,
+        invalid.clone()
+    );
+    assert_eq!(
+        visit("/kong", &resigned("request-line", "GET /kong HTTP/1.1")).await,
+        invalid.clone()
+    );
+    let mut bearer = resigned("date request-line", &signed);
+    bearer[1].1 = bearer[1].1.replacen("hmac", "Bearer", 1);
+    assert_eq!(visit("/kong", &bearer).await, invalid.clone());
+    assert_eq!(
+        visit("/kong", &[("date", date_now.clone())]).await,
+        (
+            401,
+            AuthErrorKind::MissingHeader,
+            String::from("Gateway signature header is missing")
+        )
+    );
+
+    // Dates outside of the window are stale, even when signed
+    let old = date(SystemTime::now() - Duration::from_secs(10 * 60));
+    let stale = (
+        401,
+        AuthErrorKind::Expired,
+        String::from("Request timestamp is outside of the allowed window"),
+    );
+    assert_eq!(
+        visit(
+            "/kong",
+            &[
+                ("date", old.clone()),
+                (
+                    "authorization",
+                    kong("date", &sign256(&format!("date: {}", old)))
+                ),
+            ],
+        )
+        .await,
+        stale.clone()
+    );
+
+    // Tyk sends its signature as HTTP signature auth-params
+    let signed = format!("(request-target): get /tyk\ndate: {}", date_now);
+    let (status, _, body) = visit(
+        "/tyk",
+        &[
+            ("date", date_now.clone()),
+            (
+                "authorization",
+                format!(
+                    "Signature keyId=\"tyk-key\",algorithm=\"hmac-sha256\",headers=\"(request-target) date\",signature=\"{}\"",
+                    base64(&sign256(&signed))
+                ),
+            ),
+        ],
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK.as_u16());
+    assert_eq!(body, format!("Got {:?} from Some(\"tyk-key\")", signed));
+
+    // Custom profiles can change everything, with the timestamp signed as well
+    let custom = |request_id: &str, timestamp: u64, signed: &str| {
+        vec![
+            ("x-request-id", request_id.to_string()),
+            ("x-timestamp", timestamp.to_string()),
+            (
+                "x-gateway-signature",
+                format!("sha512={}", hex(&sign512(signed))),
+            ),
+        ]
+    };
+    let ts = now();
+    let (status, _, body) = visit(
+        "/custom",
+        &custom("req-1", ts, &format!("GET|req-1|{}", ts)),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK.as_u16());
+    assert_eq!(body, format!("Got \"GET|req-1|{}\" from None", ts));
+    assert_eq!(
+        visit(
+            "/custom",
+            &custom("req-1", ts + 1, &format!("GET|req-1|{}", ts))
+        )
+        .await,
+        invalid.clone()
+    );
+    let old = ts - 10 * 60;
+    assert_eq!(
+        visit(
+            "/custom",
+            &custom("req-1", old, &format!("GET|req-1|{}", old))
+        )
+        .await,
+        stale
+    );
+
+    // Timestamps too far away to be a time are invalid rather than overflowing
+    assert_eq!(
+        visit(
+            "/custom",
+            &custom("req-1", u64::MAX, &format!("GET|req-1|{}", u64::MAX))
+        )
+        .await,
+        invalid.clone()
+    );
+    let mut unprefixed = custom("req-1", ts, &format!("GET|req-1|{}", ts));
+    unprefixed[2].1 = unprefixed[2].1.replace("sha512=", "");
+    assert_eq!(visit("/custom", &unprefixed).await, invalid);
+
+    // Forgetting to insert the profile is a server error
+    let (status, _, body) = visit("/noprofile", &[]).await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR.as_u16());
+    assert_eq!(
+        body,
+        "No gateway signature profile was found for this request"
+    );
+}
+
+#[test]
+fn redacted() {
+    let profile = GatewaySignature::new(KEY).with_encoding(SignatureEncoding::Base64);
+    let debug = format!("{:?}", profile);
+    assert!(debug.contains("Base64"));
+    assert!(!debug.contains("secret"));
+}
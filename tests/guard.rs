@@ -0,0 +1,246 @@
+use axum::{
+    extract::Request,
+    middleware::{self, Next},
+    response::Response,
+    routing::get,
+    Router,
+};
+use axum_auth::{
+    AuthUser, Permission, Permissions, RequireRole, RequireScope, Requires, Role, Scope, Scopes,
+};
+use http::StatusCode;
+
+struct Admin;
+
+impl Role for Admin {
+    const NAME: &'static str = "admin";
+}
+
+struct ReadPosts;
+
+impl Permission for ReadPosts {
+    const NAME: &'static str = "posts:read";
+}
+
+struct WritePosts;
+
+impl Permission for WritePosts {
+    const NAME: &'static str = "posts:write";
+}
+
+struct ReadComments;
+
+impl Scope for ReadComments {
+    const NAME: &'static str = "read:comments";
+}
+
+/// Simple identity middleware trusting the `x-user`, `x-roles`, and `x-scopes` headers, standing in for a real one
+async fn identity(mut req: Request, next: Next) -> Response {
+    let headers = req.headers();
+    let user = headers.get("x-user").map(|id| {
+        let roles = headers
+            .get("x-roles")
+            .map(|r| r.to_str().unwrap())
+            .unwrap_or("");
+        let scopes = headers
+            .get("x-scopes")
+            .map(|s| s.to_str().unwrap())
+            .unwrap_or("");
+        AuthUser::new(id.to_str().unwrap())
+            .with_roles(roles.split(',').filter(|r| !r.is_empty()))
+            .with_scopes([scopes])
+    });
+    let writer = headers.contains_key("x-writer");
+    if let Some(user) = user {
+        req.extensions_mut().insert(user);
+    }
+
+    // Grant permissions
+    let permissions = if writer {
+        Permissions::new().with::<ReadPosts>().with::<WritePosts>()
+    } else {
+        Permissions::new().with::<ReadPosts>()
+    };
+    req.extensions_mut().insert(permissions);
+    next.run(req).await
+}
+
+/// Launches spin-off axum instance
+async fn launcher() {
+    // Make routes
+    let app = Router::new()
+        .route("/admin", get(tester_admin))
+        .route("/posts", get(tester_read).post(tester_write))
+        .route("/comments", get(tester_comments))
+        .layer(middleware::from_fn(identity));
+
+    // Launch
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:3006")
+        .await
+        .unwrap();
+
+    println!("listening on {}", listener.local_addr().unwrap());
+    axum::serve(listener, app.into_make_service())
+        .await
+        .unwrap();
+
+    async fn tester_admin(admin: RequireRole<Admin>) -> String {
+        format!("Got admin {}", admin.id)
+    }
+
+    async fn tester_read(_: Requires<ReadPosts>) -> &'static str {
+        "Got posts"
+    }
+
+    async fn tester_comments(client: RequireScope<ReadComments>) -> String {
+        format!("Got comments for {} with {}", client.id, client.scopes)
+    }
+
+    async fn tester_write(_: Requires<ReadPosts>, _: Requires<WritePosts>) -> &'static str {
+        "Wrote post"
+    }
+}
+
+fn url(end: &str) -> String {
+    format!("http://127.0.0.1:3006{}", end)
+}
+
+#[tokio::test]
+async fn tester() {
+    // Launch axum instance
+    tokio::task::spawn(launcher());
+
+    // Wait for boot
+    tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
+
+    // Tests
+    roles().await;
+    permissions().await;
+    scopes().await;
+}
+
+/// Role guards should only let users with the role through
+async fn roles() {
+    // Try with role
+    let client = reqwest::Client::new();
+    let resp = client
+        .get(url("/admin"))
+        .header("x-user", "root")
+        .header("x-roles", "staff,admin")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::OK.as_u16());
+    assert_eq!(resp.text().await.unwrap(), String::from("Got admin root"));
+
+    // Try without role
+    let resp = client
+        .get(url("/admin"))
+        .header("x-user", "alice")
+        .header("x-roles", "staff")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::FORBIDDEN.as_u16());
+    assert_eq!(
+        resp.text().await.unwrap(),
+        String::from("You don't have the role required to access this resource")
+    );
+
+    // Try without user
+    let resp = client.get(url("/admin")).send().await.unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::UNAUTHORIZED.as_u16());
+}
+
+/// Permission guards should only let requests with the permission through
+async fn permissions() {
+    // Try with permission
+    let client = reqwest::Client::new();
+    let resp = client.get(url("/posts")).send().await.unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::OK.as_u16());
+    assert_eq!(resp.text().await.unwrap(), String::from("Got posts"));
+
+    // Try without permission
+    let resp = client.post(url("/posts")).send().await.unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::FORBIDDEN.as_u16());
+    assert_eq!(
+        resp.text().await.unwrap(),
+        String::from("You don't have the permission required to access this resource")
+    );
+
+    // Try with both permissions
+    let resp = client
+        .post(url("/posts"))
+        .header("x-writer", "yes")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::OK.as_u16());
+    assert_eq!(resp.text().await.unwrap(), String::from("Wrote post"));
+}
+
+/// Scope guards should only let users whose scopes allow it through
+async fn scopes() {
+    // Try with scope, or a wildcard allowing it
+    let client = reqwest::Client::new();
+    for scopes in ["profile read:comments", "read:*", "*"] {
+        let resp = client
+            .get(url("/comments"))
+            .header("x-user", "alice")
+            .header("x-scopes", scopes)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status().as_u16(), StatusCode::OK.as_u16());
+        assert_eq!(
+            resp.text().await.unwrap(),
+            format!("Got comments for alice with {}", Scopes::parse(scopes))
+        );
+    }
+
+    // Try without scope
+    let resp = client
+        .get(url("/comments"))
+        .header("x-user", "alice")
+        .header("x-scopes", "read write:comments")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::FORBIDDEN.as_u16());
+    assert_eq!(
+        resp.text().await.unwrap(),
+        String::from("You don't have the scope required to access this resource")
+    );
+
+    // Try without user
+    let resp = client.get(url("/comments")).send().await.unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::UNAUTHORIZED.as_u16());
+}
+
+#[test]
+fn scope_matching() {
+    // Parsing normalizes whitespace, duplicates, and order
+    let scopes = Scopes::parse(" write:posts\tread:posts  read:posts ");
+    assert_eq!(scopes.to_string(), "read:posts write:posts");
+    assert_eq!(scopes, "write:posts read:posts".parse().unwrap());
+    assert_eq!(scopes.len(), 2);
+    assert!(Scopes::parse("   ").is_empty());
+
+    // Trailing wildcards cover the whole hierarchy but not the parent itself
+    let granted = Scopes::parse("read:*");
+    assert!(granted.allows("read:posts"));
+    assert!(granted.allows("read:posts:drafts"));
+    assert!(!granted.allows("read"));
+    assert!(!granted.allows("write:posts"));
+    assert!(!granted.contains("read:posts"));
+
+    // Other wildcards only stand for one part
+    let granted = Scopes::parse("*:posts");
+    assert!(granted.allows("write:posts"));
+    assert!(!granted.allows("write:posts:drafts"));
+    assert!(!granted.allows("posts"));
+
+    assert!(Scopes::parse("*").allows("anything:at:all"));
+    assert!(Scopes::parse("admin read:*").allows_all(&Scopes::parse("read:a read:b admin")));
+    assert!(!Scopes::parse("read:*").allows_all(&Scopes::parse("read:a admin")));
+}
@@ -0,0 +1,369 @@
+use axum::{
+    routing::{delete, get},
+    Router,
+};
+use axum_auth::{
+    AuthUser, BasicUser, BasicUsers, Challenge, ChallengeLayer, RequireBasicAuthLayer,
+    RequireBearerAuthLayer, RequireRole, Role, StaticTokens, StoreHandle, Unauthorized,
+};
+use http::StatusCode;
+use std::{
+    io,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Number of times the handler of the nested router has run
+static NESTED_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+struct Admin;
+
+impl Role for Admin {
+    const NAME: &'static str = "admin";
+}
+
+/// Launches spin-off axum instance
+async fn launcher() {
+    // Make users
+    let users = BasicUsers::new()
+        .with_user("alice", BasicUser::new("hunter2").with_roles(["admin"]))
+        .with_user(
+            "bob",
+            BasicUser::new("letmein").with_scopes(["posts:read", "posts:write"]),
+        );
+
+    // Make a handle which admins can change users through
+    let handle = StoreHandle::new(BasicUsers::new().with_user("carol", BasicUser::new("pa55")));
+    let admin = handle.clone();
+
+    // Make routes
+    let app = Router::new()
+        .route("/basic", get(tester_basic))
+        .route("/basic/admin", get(tester_admin))
+        .layer(RequireBasicAuthLayer::new(users))
+        .merge(
+            Router::new()
+                .route("/manual", get(|| async { StatusCode::UNAUTHORIZED }))
+                .route(
+                    "/manual/challenged",
+                    get(|| async { Unauthorized::new().with_challenge(Challenge::basic("API")) }),
+                )
+                .route("/manual/forbidden", get(|| async { StatusCode::FORBIDDEN }))
+                .layer(
+                    ChallengeLayer::new(Challenge::bearer())
+                        .with_challenge(Challenge::basic("API")),
+                ),
+        )
+        .nest(
+            "/nested",
+            Router::new()
+                .route(
+                    "/bearer",
+                    get(|user: AuthUser| async move {
+                        NESTED_CALLS.fetch_add(1, Ordering::SeqCst);
+                        format!("Got {}", user.id)
+                    }),
+                )
+                .layer(RequireBearerAuthLayer::new(
+                    StaticTokens::new().with_token("ci", "tok1"),
+                )),
+        )
+        .route("/open", get(|| async { "Open" }))
+        .nest(
+            "/challenged",
+            Router::new()
+                .route("/basic", get(tester_basic))
+                .layer(
+                    RequireBasicAuthLayer::new(
+                        BasicUsers::new().with_user("dave", BasicUser::new("s3cret")),
+                    )
+                    .with_challenge(Challenge::basic("Admin area")),
+                )
+                .merge(
+                    Router::new().route("/bearer", get(tester_basic)).layer(
+                        RequireBearerAuthLayer::new(StaticTokens::new().with_token("ci", "tok1"))
+                            .with_challenge(Challenge::bearer().with_param("realm", "API")),
+                    ),
+                ),
+        )
+        .merge(
+            Router::new()
+                .route("/handle", get(tester_basic))
+                .layer(RequireBasicAuthLayer::new(handle)),
+        )
+        .route(
+            "/admin/users/{id}",
+            delete(
+                move |axum::extract::Path(id): axum::extract::Path<String>| async move {
+                    match admin.remove_user(&id) {
+                        Some(_) => StatusCode::NO_CONTENT,
+                        None => StatusCode::NOT_FOUND,
+                    }
+                },
+            ),
+        );
+
+    // Launch
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:3007")
+        .await
+        .unwrap();
+
+    println!("listening on {}", listener.local_addr().unwrap());
+    axum::serve(listener, app.into_make_service())
+        .await
+        .unwrap();
+
+    async fn tester_basic(user: AuthUser) -> String {
+        format!(
+            "Got {} with {:?} and {:?}",
+            user.id, user.roles, user.scopes
+        )
+    }
+
+    async fn tester_admin(admin: RequireRole<Admin>) -> String {
+        format!("Got admin {}", admin.id)
+    }
+}
+
+fn url(end: &str) -> String {
+    format!("http://127.0.0.1:3007{}", end)
+}
+
+#[tokio::test]
+async fn tester() {
+    // Launch axum instance
+    tokio::task::spawn(launcher());
+
+    // Wait for boot
+    tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
+
+    // Tests
+    basic().await;
+    challenge().await;
+    handle().await;
+    nested().await;
+    challenged().await;
+}
+
+/// Basic layer should check users and attach their roles and scopes
+async fn basic() {
+    // Try good user
+    let client = reqwest::Client::new();
+    let resp = client
+        .get(url("/basic"))
+        .basic_auth("bob", Some("letmein"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::OK.as_u16());
+    assert_eq!(
+        resp.text().await.unwrap(),
+        String::from("Got bob with [] and [\"posts:read\", \"posts:write\"]")
+    );
+
+    // Try wrong password
+    let resp = client
+        .get(url("/basic"))
+        .basic_auth("bob", Some("hunter2"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::UNAUTHORIZED.as_u16());
+    assert_eq!(
+        resp.text().await.unwrap(),
+        String::from("The provided credentials are invalid")
+    );
+
+    // Try missing header, which is rejected like the extractor
+    let resp = client.get(url("/basic")).send().await.unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::BAD_REQUEST.as_u16());
+    assert_eq!(
+        resp.text().await.unwrap(),
+        String::from("`Authorization` header is missing")
+    );
+
+    // Try roles from the store with guards
+    let resp = client
+        .get(url("/basic/admin"))
+        .basic_auth("alice", Some("hunter2"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::OK.as_u16());
+    let resp = client
+        .get(url("/basic/admin"))
+        .basic_auth("bob", Some("letmein"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::FORBIDDEN.as_u16());
+}
+
+/// Challenge layer should only fill in challenges which were forgotten
+async fn challenge() {
+    // Plain unauthorized gets the challenges
+    let client = reqwest::Client::new();
+    let resp = client.get(url("/manual")).send().await.unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::UNAUTHORIZED.as_u16());
+    assert_eq!(
+        resp.headers()["www-authenticate"],
+        r#"Bearer, Basic realm="API", charset="UTF-8""#
+    );
+
+    // Existing challenges are kept
+    let resp = client.get(url("/manual/challenged")).send().await.unwrap();
+    assert_eq!(
+        resp.headers()["www-authenticate"],
+        r#"Basic realm="API", charset="UTF-8""#
+    );
+
+    // Other statuses are left alone
+    let resp = client.get(url("/manual/forbidden")).send().await.unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::FORBIDDEN.as_u16());
+    assert!(!resp.headers().contains_key("www-authenticate"));
+}
+
+/// Users can be changed through handles while the server is running
+async fn handle() {
+    let login = |username: &'static str, password: &'static str| async move {
+        reqwest::Client::new()
+            .get(url("/handle"))
+            .basic_auth(username, Some(password))
+            .send()
+            .await
+            .unwrap()
+            .status()
+            .as_u16()
+    };
+    assert_eq!(login("carol", "pa55").await, 200);
+
+    // Removing through an admin endpoint takes effect straight away
+    let client = reqwest::Client::new();
+    let resp = client
+        .delete(url("/admin/users/carol"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::NO_CONTENT.as_u16());
+    let resp = client
+        .delete(url("/admin/users/carol"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::NOT_FOUND.as_u16());
+    assert_eq!(login("carol", "pa55").await, 401);
+}
+
+/// Layers on nested routers only protect them, rejecting before the handler runs
+async fn nested() {
+    let client = reqwest::Client::new();
+    let resp = client
+        .get(url("/nested/bearer"))
+        .bearer_auth("tok1")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::OK.as_u16());
+    assert_eq!(resp.text().await.unwrap(), "Got ci");
+    assert_eq!(NESTED_CALLS.load(Ordering::SeqCst), 1);
+
+    // Rejected requests never reach the handler
+    let resp = client
+        .get(url("/nested/bearer"))
+        .bearer_auth("tok2")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::UNAUTHORIZED.as_u16());
+    let resp = client
+        .get(url("/nested/bearer"))
+        .basic_auth("ci", Some("tok1"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::BAD_REQUEST.as_u16());
+    assert_eq!(
+        resp.text().await.unwrap(),
+        "`Authorization` header must be a bearer token"
+    );
+    assert_eq!(NESTED_CALLS.load(Ordering::SeqCst), 1);
+
+    // Routes outside of the nested router are left open
+    let resp = client.get(url("/open")).send().await.unwrap();
+    assert_eq!(resp.text().await.unwrap(), "Open");
+}
+
+/// Layers with a challenge answer missing or invalid credentials with it and `401 UNAUTHORIZED`
+async fn challenged() {
+    let client = reqwest::Client::new();
+
+    // Missing headers are unauthorized instead of bad requests
+    let resp = client.get(url("/challenged/basic")).send().await.unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::UNAUTHORIZED.as_u16());
+    assert_eq!(
+        resp.headers()["www-authenticate"],
+        r#"Basic realm="Admin area", charset="UTF-8""#
+    );
+    assert_eq!(
+        resp.text().await.unwrap(),
+        "`Authorization` header is missing"
+    );
+
+    // Wrong credentials get the challenge too
+    let resp = client
+        .get(url("/challenged/basic"))
+        .basic_auth("dave", Some("wrong"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::UNAUTHORIZED.as_u16());
+    assert_eq!(
+        resp.headers()["www-authenticate"],
+        r#"Basic realm="Admin area", charset="UTF-8""#
+    );
+    let resp = client
+        .get(url("/challenged/basic"))
+        .basic_auth("dave", Some("s3cret"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::OK.as_u16());
+    assert!(!resp.headers().contains_key("www-authenticate"));
+
+    // Bearer layers use their own realm
+    let resp = client
+        .get(url("/challenged/bearer"))
+        .bearer_auth("tok2")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::UNAUTHORIZED.as_u16());
+    assert_eq!(resp.headers()["www-authenticate"], r#"Bearer realm="API""#);
+}
+
+#[test]
+fn store_handle() {
+    let handle = StoreHandle::new(BasicUsers::new());
+    let shared = handle.clone();
+
+    // Changes are seen through every clone
+    handle.insert_user("alice", BasicUser::new("hunter2"));
+    assert_eq!(
+        *shared.users(),
+        BasicUsers::new().with_user("alice", BasicUser::new("hunter2"))
+    );
+    assert!(shared.remove_user("bob").is_none());
+
+    // Reloading needs a loader, and keeps the users if it fails
+    assert_eq!(
+        handle.reload().unwrap_err().kind(),
+        io::ErrorKind::Unsupported
+    );
+    let failing = handle
+        .clone()
+        .with_loader(|| Err(io::Error::other("database is down")));
+    assert!(failing.reload().is_err());
+    assert_ne!(*shared.users(), BasicUsers::new());
+    let loading = handle.with_loader(|| Ok(BasicUsers::new()));
+    loading.reload().unwrap();
+    assert_eq!(*shared.users(), BasicUsers::new());
+}
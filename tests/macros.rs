@@ -0,0 +1,58 @@
+#![cfg(feature = "auth-basic")]
+
+use axum::{routing::get, Router};
+use axum_auth::{basic_auth_layer, AuthUser};
+use http::StatusCode;
+
+/// Launches spin-off axum instance
+async fn launcher() {
+    // Make routes
+    let app = Router::new()
+        .route("/", get(tester_macro))
+        .layer(basic_auth_layer!("admin", env!("CARGO_PKG_NAME"),));
+
+    // Launch
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:3039")
+        .await
+        .unwrap();
+
+    println!("listening on {}", listener.local_addr().unwrap());
+    axum::serve(listener, app.into_make_service())
+        .await
+        .unwrap();
+
+    async fn tester_macro(user: AuthUser) -> String {
+        format!("Got {}", user.id)
+    }
+}
+
+fn url(end: &str) -> String {
+    format!("http://127.0.0.1:3039{}", end)
+}
+
+#[tokio::test]
+async fn tester() {
+    // Launch axum instance
+    tokio::task::spawn(launcher());
+
+    // Wait for boot
+    tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
+
+    // Only the one user can log in
+    let client = reqwest::Client::new();
+    let resp = client
+        .get(url("/"))
+        .basic_auth("admin", Some("axum-auth"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::OK.as_u16());
+    assert_eq!(resp.text().await.unwrap(), "Got admin");
+    let resp = client
+        .get(url("/"))
+        .basic_auth("admin", Some("wrong"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::UNAUTHORIZED.as_u16());
+}
@@ -0,0 +1,259 @@
+#![cfg(feature = "metrics")]
+
+use axum::{routing::get, Router};
+use axum_auth::{
+    AuthBearer, AuthUser, AuthValidator, ExtractionMetrics, MeteredValidator, Rejection,
+    RequireAuthLayer,
+};
+use http::StatusCode;
+use metrics_util::{
+    debugging::{DebugValue, DebuggingRecorder},
+    MetricKind,
+};
+use std::time::Duration;
+
+/// Validator standing in for an introspection call which takes a while
+struct SlowTokens;
+
+impl AuthValidator<AuthBearer> for SlowTokens {
+    async fn validate(&self, credentials: &AuthBearer) -> Result<AuthUser, Rejection> {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        match credentials.0.as_str() {
+            "alice-token" => Ok(AuthUser::new("alice")),
+            _ => Err((StatusCode::UNAUTHORIZED, "Unknown token")),
+        }
+    }
+}
+
+/// Launches spin-off axum instance
+async fn launcher() {
+    // Make routes
+    let metered = MeteredValidator::new(SlowTokens).with_name("introspection");
+    let unrouted = Router::new()
+        .route("/unrouted/{id}", get(tester_metered))
+        .layer(
+            RequireAuthLayer::<AuthBearer, _>::new(SlowTokens)
+                .with_metrics(ExtractionMetrics::new("unrouted").with_route_labels(false)),
+        );
+    let app = Router::new()
+        .route("/", get(tester_metered))
+        .route("/users/{id}", get(tester_metered))
+        .layer(
+            RequireAuthLayer::<AuthBearer, _>::new(metered)
+                .with_metrics(ExtractionMetrics::new("bearer")),
+        )
+        .merge(unrouted);
+
+    // Launch
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:3030")
+        .await
+        .unwrap();
+
+    println!("listening on {}", listener.local_addr().unwrap());
+    axum::serve(listener, app.into_make_service())
+        .await
+        .unwrap();
+
+    async fn tester_metered(user: AuthUser) -> String {
+        format!("Got {}", user.id)
+    }
+}
+
+fn url(end: &str) -> String {
+    format!("http://127.0.0.1:3030{}", end)
+}
+
+#[tokio::test]
+async fn tester() {
+    // Record everything the validator reports
+    let recorder = DebuggingRecorder::new();
+    let snapshotter = recorder.snapshotter();
+    recorder.install().unwrap();
+
+    // Launch axum instance
+    tokio::task::spawn(launcher());
+
+    // Wait for boot
+    tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
+
+    // Validate twice successfully and once unsuccessfully
+    let client = reqwest::Client::new();
+    for (token, status) in [
+        ("alice-token", StatusCode::OK),
+        ("alice-token", StatusCode::OK),
+        ("mallory-token", StatusCode::UNAUTHORIZED),
+    ] {
+        let resp = client
+            .get(url("/"))
+            .bearer_auth(token)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status().as_u16(), status.as_u16());
+    }
+
+    // Counters and histograms are recorded for each outcome
+    let snapshot = snapshotter.snapshot().into_vec();
+    let find = |kind: MetricKind, outcome: &str| {
+        snapshot
+            .iter()
+            .find(|(key, _, _, _)| {
+                key.kind() == kind
+                    && key
+                        .key()
+                        .labels()
+                        .any(|label| label.key() == "validator" && label.value() == "introspection")
+                    && key
+                        .key()
+                        .labels()
+                        .any(|label| label.key() == "outcome" && label.value() == outcome)
+            })
+            .map(|(_, _, _, value)| value)
+            .unwrap()
+    };
+    assert_eq!(
+        find(MetricKind::Counter, "success"),
+        &DebugValue::Counter(2)
+    );
+    assert_eq!(
+        find(MetricKind::Counter, "rejected"),
+        &DebugValue::Counter(1)
+    );
+    match find(MetricKind::Histogram, "success") {
+        DebugValue::Histogram(durations) => {
+            assert_eq!(durations.len(), 2);
+            assert!(durations.iter().all(|duration| duration.0 >= 0.05));
+        }
+        other => panic!("Expected a histogram, got {:?}", other),
+    }
+
+    // Extractions are counted by scheme, kind of rejection and route template
+    for (path, auth) in [
+        ("/users/1", Some("alice-token")),
+        ("/users/2", None),
+        ("/users/3", None),
+        ("/unrouted/1", None),
+    ] {
+        let mut req = client.get(url(path));
+        if let Some(token) = auth {
+            req = req.bearer_auth(token);
+        }
+        req.send().await.unwrap();
+    }
+    let snapshot = snapshotter.snapshot().into_vec();
+    let extractions = |labels: &[(&str, &str)]| {
+        snapshot
+            .iter()
+            .find(|(key, _, _, _)| {
+                let mut found: Vec<_> = key
+                    .key()
+                    .labels()
+                    .map(|label| (label.key(), label.value()))
+                    .collect();
+                found.sort();
+                let mut labels = labels.to_vec();
+                labels.sort();
+                key.key().name() == ExtractionMetrics::EXTRACTIONS && found == labels
+            })
+            .map(|(_, _, _, value)| value)
+    };
+    assert_eq!(
+        extractions(&[
+            ("scheme", "bearer"),
+            ("outcome", "success"),
+            ("route", "/users/{id}")
+        ]),
+        Some(&DebugValue::Counter(1))
+    );
+    assert_eq!(
+        extractions(&[
+            ("scheme", "bearer"),
+            ("outcome", "missing_header"),
+            ("route", "/users/{id}")
+        ]),
+        Some(&DebugValue::Counter(2))
+    );
+    assert_eq!(
+        extractions(&[("scheme", "unrouted"), ("outcome", "missing_header")]),
+        Some(&DebugValue::Counter(1))
+    );
+
+    // Nothing derived from the token or the raw path ever becomes a label
+    assert!(snapshot.iter().all(|(key, _, _, _)| key
+        .key()
+        .labels()
+        .all(|label| !label.value().contains("alice") && !label.value().contains("/users/1"))));
+}
+
+#[cfg(feature = "jwt")]
+#[test]
+fn key_selection() {
+    use axum_auth::{JwtKey, JwtValidator, KeySelection};
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+    use hmac::{Hmac, Mac};
+    use sha2::Sha256;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    // Sign a token without a `kid` header
+    let exp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+        + 60;
+    let header = URL_SAFE_NO_PAD.encode(r#"{"alg":"HS256"}"#);
+    let claims = URL_SAFE_NO_PAD.encode(format!(r#"{{"sub":"ci","exp":{}}}"#, exp));
+    let input = format!("{}.{}", header, claims);
+    let mut mac = Hmac::<Sha256>::new_from_slice(b"secret").unwrap();
+    mac.update(input.as_bytes());
+    let token = format!(
+        "{}.{}",
+        input,
+        URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes())
+    );
+
+    // Record which strategy chose the key, away from the recorder of the other test
+    let recorder = DebuggingRecorder::new();
+    let snapshotter = recorder.snapshotter();
+    metrics::with_local_recorder(&recorder, || {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let by_algorithm = JwtValidator::new()
+            .with_key(JwtKey::hmac(b"secret"))
+            .with_key_selection(KeySelection::ByAlgorithm);
+        let pinned = JwtValidator::new()
+            .with_key(JwtKey::hmac(b"secret").with_kid("current"))
+            .with_key_selection(KeySelection::Pinned(String::from("current")));
+        runtime.block_on(async {
+            by_algorithm.verify(&token).await.unwrap_err();
+            pinned.verify(&token).await.unwrap();
+        });
+    });
+
+    let snapshot = snapshotter.snapshot().into_vec();
+    let selections = |strategy: &str, outcome: &str| {
+        snapshot
+            .iter()
+            .find(|(key, _, _, _)| {
+                key.key().name() == JwtValidator::KEY_SELECTIONS
+                    && key
+                        .key()
+                        .labels()
+                        .any(|label| label.key() == "strategy" && label.value() == strategy)
+                    && key
+                        .key()
+                        .labels()
+                        .any(|label| label.key() == "outcome" && label.value() == outcome)
+            })
+            .map(|(_, _, _, value)| value)
+    };
+    assert_eq!(
+        selections("by_algorithm", "unmatched"),
+        Some(&DebugValue::Counter(1))
+    );
+    assert_eq!(
+        selections("pinned", "matched"),
+        Some(&DebugValue::Counter(1))
+    );
+    assert_eq!(selections("try_all", "matched"), None);
+}
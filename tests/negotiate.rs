@@ -0,0 +1,96 @@
+#![cfg(feature = "auth-negotiate")]
+
+use axum::{routing::get, Router};
+use axum_auth::{AuthNegotiate, AuthUser, AuthValidator, Rejection, RequireAuthLayer};
+use http::StatusCode;
+
+/// Accepts a single hardcoded ticket
+struct FakeKerberos;
+
+impl AuthValidator<AuthNegotiate> for FakeKerberos {
+    async fn validate(&self, credentials: &AuthNegotiate) -> Result<AuthUser, Rejection> {
+        if credentials.0 == b"ticket" {
+            Ok(AuthUser::new("alice@EXAMPLE.COM"))
+        } else {
+            Err((StatusCode::UNAUTHORIZED, "Unknown ticket"))
+        }
+    }
+}
+
+/// Launches spin-off axum instance
+async fn launcher() {
+    // Make routes
+    let app = Router::new()
+        .route("/sso", get(tester_sso))
+        .layer(RequireAuthLayer::<AuthNegotiate, _>::new(FakeKerberos));
+
+    // Launch
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:3011")
+        .await
+        .unwrap();
+
+    println!("listening on {}", listener.local_addr().unwrap());
+    axum::serve(listener, app.into_make_service())
+        .await
+        .unwrap();
+
+    async fn tester_sso(user: AuthUser) -> String {
+        format!("Got {}", user.id)
+    }
+}
+
+fn url(end: &str) -> String {
+    format!("http://127.0.0.1:3011{}", end)
+}
+
+#[tokio::test]
+async fn tester() {
+    // Launch axum instance
+    tokio::task::spawn(launcher());
+
+    // Wait for boot
+    tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
+
+    // Try without credentials, browsers need the challenge to start
+    let client = reqwest::Client::new();
+    let resp = client.get(url("/sso")).send().await.unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::UNAUTHORIZED.as_u16());
+    assert_eq!(resp.headers()["www-authenticate"], "Negotiate");
+    assert_eq!(
+        resp.text().await.unwrap(),
+        String::from("`Authorization` header is missing")
+    );
+
+    // Try good and bad tickets
+    let resp = client
+        .get(url("/sso"))
+        .header("Authorization", "Negotiate dGlja2V0")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(
+        resp.text().await.unwrap(),
+        String::from("Got alice@EXAMPLE.COM")
+    );
+    let resp = client
+        .get(url("/sso"))
+        .header("Authorization", "Negotiate bm9wZQ==")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::UNAUTHORIZED.as_u16());
+    assert_eq!(resp.text().await.unwrap(), String::from("Unknown ticket"));
+
+    // Try the wrong scheme
+    let resp = client
+        .get(url("/sso"))
+        .bearer_auth("My Token")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::UNAUTHORIZED.as_u16());
+    assert_eq!(
+        resp.text().await.unwrap(),
+        String::from("`Authorization` header must be for negotiate authentication")
+    );
+}
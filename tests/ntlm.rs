@@ -0,0 +1,82 @@
+#![cfg(feature = "auth-ntlm")]
+
+use axum::{
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use axum_auth::{AuthNtlm, NtlmStage};
+use http::StatusCode;
+
+/// Launches spin-off axum instance
+async fn launcher() {
+    // Make routes
+    let app = Router::new().route("/ntlm", get(tester_ntlm));
+
+    // Launch
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:3012")
+        .await
+        .unwrap();
+
+    println!("listening on {}", listener.local_addr().unwrap());
+    axum::serve(listener, app.into_make_service())
+        .await
+        .unwrap();
+
+    async fn tester_ntlm(ntlm: AuthNtlm) -> Response {
+        match ntlm.stage {
+            NtlmStage::Negotiate => AuthNtlm::challenge(b"NTLMSSP\0\x02\0\0\0").into_response(),
+            NtlmStage::Authenticate => format!("Got {} bytes", ntlm.message.len()).into_response(),
+        }
+    }
+}
+
+fn url(end: &str) -> String {
+    format!("http://127.0.0.1:3012{}", end)
+}
+
+#[tokio::test]
+async fn tester() {
+    // Launch axum instance
+    tokio::task::spawn(launcher());
+
+    // Wait for boot
+    tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
+
+    // Try without credentials
+    let client = reqwest::Client::new();
+    let resp = client.get(url("/ntlm")).send().await.unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::UNAUTHORIZED.as_u16());
+    assert_eq!(resp.headers()["www-authenticate"], "NTLM");
+
+    // Try both rounds, with the first wrapped in negotiate like browsers do
+    let resp = client
+        .get(url("/ntlm"))
+        .header("Authorization", "Negotiate TlRMTVNTUAABAAAA")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::UNAUTHORIZED.as_u16());
+    assert_eq!(resp.headers()["www-authenticate"], "NTLM TlRMTVNTUAACAAAA");
+    let resp = client
+        .get(url("/ntlm"))
+        .header("Authorization", "NTLM TlRMTVNTUAADAAAA")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::OK.as_u16());
+    assert_eq!(resp.text().await.unwrap(), String::from("Got 12 bytes"));
+
+    // Try a challenge message, which only servers send
+    let resp = client
+        .get(url("/ntlm"))
+        .header("Authorization", "NTLM TlRMTVNTUAACAAAA")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::UNAUTHORIZED.as_u16());
+    assert_eq!(
+        resp.text().await.unwrap(),
+        String::from("`Authorization` header isn't a valid NTLM negotiate or authenticate message")
+    );
+}
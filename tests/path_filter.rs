@@ -0,0 +1,117 @@
+use axum::{routing::get, Extension, Router};
+use axum_auth::{AuthUser, BasicUser, BasicUsers, PathFilter, PathPattern, RequireBasicAuthLayer};
+use http::StatusCode;
+
+/// Matches paths starting with the prefix
+struct Prefix(&'static str);
+
+impl PathPattern for Prefix {
+    fn matches(&self, path: &str) -> bool {
+        path.starts_with(self.0)
+    }
+}
+
+/// Launches spin-off axum instance
+async fn launcher() {
+    // Make routes
+    let users = BasicUsers::new().with_user("alice", BasicUser::new("hunter2"));
+    let paths = PathFilter::new()
+        .with_include(Prefix("/api/"))
+        .with_exclude(Prefix("/api/public/"));
+    let app = Router::new()
+        .route("/", get(tester_paths))
+        .route("/api/private", get(tester_paths))
+        .route("/api/public/status", get(tester_paths))
+        .layer(RequireBasicAuthLayer::new(users).with_paths(paths));
+
+    // Launch
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:3041")
+        .await
+        .unwrap();
+
+    println!("listening on {}", listener.local_addr().unwrap());
+    axum::serve(listener, app.into_make_service())
+        .await
+        .unwrap();
+
+    async fn tester_paths(user: Option<Extension<AuthUser>>) -> String {
+        format!("Got {:?}", user.map(|Extension(user)| user.id))
+    }
+}
+
+fn url(end: &str) -> String {
+    format!("http://127.0.0.1:3041{}", end)
+}
+
+/// Visits the path without credentials, giving back the status code and body
+async fn visit(end: &str) -> (u16, String) {
+    let resp = reqwest::get(url(end)).await.unwrap();
+    (resp.status().as_u16(), resp.text().await.unwrap())
+}
+
+#[tokio::test]
+async fn tester() {
+    // Launch axum instance
+    tokio::task::spawn(launcher());
+
+    // Wait for boot
+    tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
+
+    // Included paths need credentials
+    assert_eq!(visit("/api/private").await.0, 400);
+    let resp = reqwest::Client::new()
+        .get(url("/api/private"))
+        .basic_auth("alice", Some("hunter2"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::OK.as_u16());
+    assert_eq!(resp.text().await.unwrap(), "Got Some(\"alice\")");
+
+    // Excluded paths and those which weren't included skip authentication
+    assert_eq!(
+        visit("/api/public/status").await,
+        (200, String::from("Got None"))
+    );
+    assert_eq!(visit("/").await, (200, String::from("Got None")));
+}
+
+#[test]
+fn protects() {
+    // Everything is protected by default
+    assert!(PathFilter::new().protects("/anything"));
+
+    // Exclusions win over inclusions
+    let paths = PathFilter::new()
+        .with_include(Prefix("/api/"))
+        .with_exclude(Prefix("/api/public/"));
+    assert!(paths.protects("/api/users"));
+    assert!(!paths.protects("/api/public/status"));
+    assert!(!paths.protects("/health"));
+}
+
+#[cfg(feature = "glob")]
+#[test]
+fn glob() {
+    use globset::Glob;
+
+    let paths = PathFilter::new()
+        .with_include(Glob::new("/api/**").unwrap().compile_matcher())
+        .with_exclude(Glob::new("/api/public/**").unwrap().compile_matcher());
+    assert!(paths.protects("/api/users/42"));
+    assert!(!paths.protects("/api/public/status"));
+    assert!(!paths.protects("/health"));
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn regex() {
+    use regex::Regex;
+
+    let paths = PathFilter::new()
+        .with_include(Regex::new(r"^/api/").unwrap())
+        .with_exclude(Regex::new(r"^/api/v\d+/public/").unwrap());
+    assert!(paths.protects("/api/v1/users"));
+    assert!(!paths.protects("/api/v2/public/status"));
+    assert!(!paths.protects("/health"));
+}
@@ -0,0 +1,116 @@
+use axum::{routing::get, Router};
+use axum_auth::{
+    AuthBearer, AuthUser, AuthValidator, CredentialId, MemoryQuotaStore, Quota, QuotaLayer,
+    QuotaStore, Rejection, RequireAuthLayer,
+};
+use http::{Extensions, StatusCode};
+use std::time::Duration;
+
+/// Validator giving alice's token its own quota, and leaving bob to the default
+struct Tokens;
+
+impl AuthValidator<AuthBearer> for Tokens {
+    async fn validate(&self, credentials: &AuthBearer) -> Result<AuthUser, Rejection> {
+        self.validate_with_extensions(credentials, &mut Extensions::new())
+            .await
+    }
+
+    async fn validate_with_extensions(
+        &self,
+        credentials: &AuthBearer,
+        extensions: &mut Extensions,
+    ) -> Result<AuthUser, Rejection> {
+        match credentials.0.as_str() {
+            "alice-token" => {
+                extensions.insert(CredentialId(credentials.0.clone()));
+                extensions.insert(Quota::per_minute(3));
+                Ok(AuthUser::new("alice"))
+            }
+            "bob-token" => Ok(AuthUser::new("bob")),
+            _ => Err((StatusCode::UNAUTHORIZED, "Unknown token")),
+        }
+    }
+}
+
+/// Launches spin-off axum instance
+async fn launcher() {
+    // Make routes
+    let app = Router::new()
+        .route("/", get(tester_quota))
+        .layer(QuotaLayer::new(MemoryQuotaStore::new()).with_default(Quota::per_hour(1)))
+        .layer(RequireAuthLayer::<AuthBearer, _>::new(Tokens));
+
+    // Launch
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:3036")
+        .await
+        .unwrap();
+
+    println!("listening on {}", listener.local_addr().unwrap());
+    axum::serve(listener, app.into_make_service())
+        .await
+        .unwrap();
+
+    async fn tester_quota(user: AuthUser) -> String {
+        format!("Got {}", user.id)
+    }
+}
+
+fn url(end: &str) -> String {
+    format!("http://127.0.0.1:3036{}", end)
+}
+
+/// Sends the token, giving back the status code and `Retry-After` header
+async fn send(token: &str) -> (u16, Option<u64>) {
+    let resp = reqwest::Client::new()
+        .get(url("/"))
+        .bearer_auth(token)
+        .send()
+        .await
+        .unwrap();
+    let retry_after = resp
+        .headers()
+        .get("Retry-After")
+        .map(|value| value.to_str().unwrap().parse().unwrap());
+    (resp.status().as_u16(), retry_after)
+}
+
+#[tokio::test]
+async fn tester() {
+    // Launch axum instance
+    tokio::task::spawn(launcher());
+
+    // Wait for boot
+    tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
+
+    // Credentials given a quota by their validator use it
+    for _ in 0..3 {
+        assert_eq!(send("alice-token").await, (200, None));
+    }
+    let (status, retry_after) = send("alice-token").await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS.as_u16());
+    assert!((1..=60).contains(&retry_after.unwrap()));
+
+    // Others use the default
+    assert_eq!(send("bob-token").await, (200, None));
+    let (status, retry_after) = send("bob-token").await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS.as_u16());
+    assert!((3540..=3600).contains(&retry_after.unwrap()));
+
+    // Rejected credentials aren't counted
+    assert_eq!(send("mallory-token").await, (401, None));
+}
+
+#[tokio::test]
+async fn memory_store() {
+    // Windows are counted separately for each credential and start again once over
+    let store = MemoryQuotaStore::new();
+    let window = Duration::from_millis(100);
+    let (count, ends) = store.hit("alice", window).await;
+    assert_eq!(count, 1);
+    assert_eq!(store.hit("alice", window).await, (2, ends));
+    assert_eq!(store.hit("bob", window).await.0, 1);
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    let (count, next) = store.hit("alice", window).await;
+    assert_eq!(count, 1);
+    assert!(next > ends);
+}
@@ -0,0 +1,200 @@
+#![cfg(feature = "session")]
+
+use axum::{
+    routing::{get, post},
+    Router,
+};
+use axum_auth::{
+    AuthUser, BasicLogin, BasicUser, BasicUsers, MemoryRememberMeStore, MemoryRevocationStore,
+    RememberMe, SessionAuthLayer, SessionIssuer,
+};
+use http::StatusCode;
+use std::{sync::Arc, time::Duration};
+
+const KEY: &[u8; 32] = b"an example key which is 32 bytes";
+
+/// Launches spin-off axum instance
+async fn launcher() {
+    // Sessions expire straight away so only the remember-me cookie can be used
+    let issuer = SessionIssuer::new(*KEY)
+        .with_secure(false)
+        .with_ttl(Duration::ZERO);
+    let remember_me = RememberMe::new(Arc::new(MemoryRememberMeStore::new())).with_secure(false);
+    let revocations = Arc::new(MemoryRevocationStore::new());
+    let users = BasicUsers::new().with_user("alice", BasicUser::new("hunter2"));
+
+    // Make routes
+    let app = Router::new()
+        .route("/", get(tester))
+        .layer(
+            SessionAuthLayer::new(issuer.clone())
+                .with_revocations(revocations.clone())
+                .with_remember_me(remember_me.clone()),
+        )
+        .route(
+            "/login",
+            post(
+                BasicLogin::new("App", users, issuer.clone())
+                    .with_remember_me(remember_me.clone())
+                    .handler(),
+            ),
+        )
+        .route(
+            "/logout",
+            post(remember_me.logout_route(revocations, issuer)),
+        );
+
+    // Launch
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:3026")
+        .await
+        .unwrap();
+
+    println!("listening on {}", listener.local_addr().unwrap());
+    axum::serve(listener, app.into_make_service())
+        .await
+        .unwrap();
+
+    async fn tester(user: AuthUser) -> String {
+        format!("Got {}", user.id)
+    }
+}
+
+fn url(end: &str) -> String {
+    format!("http://127.0.0.1:3026{}", end)
+}
+
+/// Gets the `name=value` part of every `Set-Cookie` header of a response
+fn cookies(resp: &reqwest::Response) -> Vec<String> {
+    resp.headers()
+        .get_all("set-cookie")
+        .iter()
+        .map(|value| {
+            value
+                .to_str()
+                .unwrap()
+                .split(';')
+                .next()
+                .unwrap()
+                .to_string()
+        })
+        .collect()
+}
+
+/// Logs in as alice, giving back the remember-me cookie
+async fn login(client: &reqwest::Client) -> String {
+    let resp = client
+        .post(url("/login"))
+        .basic_auth("alice", Some("hunter2"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+    let cookies = cookies(&resp);
+    assert_eq!(cookies.len(), 2);
+    assert!(cookies[0].starts_with("session="));
+    assert!(cookies[1].starts_with("remember_me="));
+    cookies[1].clone()
+}
+
+#[tokio::test]
+async fn tester() {
+    // Launch axum instance
+    tokio::task::spawn(launcher());
+
+    // Wait for boot
+    tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
+
+    // Tests
+    refresh().await;
+    stolen().await;
+    logout().await;
+}
+
+/// Remember-me cookies refresh the session and are rotated on every use
+async fn refresh() {
+    let client = reqwest::Client::new();
+    let remember = login(&client).await;
+
+    let resp = client
+        .get(url("/"))
+        .header("Cookie", &remember)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let rotated = cookies(&resp);
+    assert_eq!(rotated.len(), 2);
+    assert!(rotated[0].starts_with("session="));
+    assert!(rotated[1].starts_with("remember_me="));
+    assert_ne!(rotated[1], remember);
+    assert_eq!(resp.text().await.unwrap(), "Got alice");
+
+    // The rotated cookie works in turn
+    let resp = client
+        .get(url("/"))
+        .header("Cookie", &rotated[1])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    // Garbage is rejected and cleared
+    let resp = client
+        .get(url("/"))
+        .header("Cookie", "remember_me=garbage")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(cookies(&resp), ["remember_me="]);
+    assert_eq!(resp.text().await.unwrap(), "Remember-me cookie is invalid");
+}
+
+/// Reusing a rotated cookie looks like theft, so every cookie of the user stops working
+async fn stolen() {
+    let client = reqwest::Client::new();
+    let remember = login(&client).await;
+    let other = login(&client).await;
+    let (selector, _) = remember.split_once('.').unwrap();
+
+    // Right selector with the wrong verifier
+    let resp = client
+        .get(url("/"))
+        .header("Cookie", format!("{}.AAAA", selector))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+    let resp = client
+        .get(url("/"))
+        .header("Cookie", &other)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(resp.text().await.unwrap(), "Remember-me cookie is invalid");
+}
+
+/// Logging out forgets the remember-me cookie
+async fn logout() {
+    let client = reqwest::Client::new();
+    let remember = login(&client).await;
+
+    let resp = client
+        .post(url("/logout"))
+        .header("Cookie", &remember)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+    assert_eq!(cookies(&resp), ["session=", "remember_me="]);
+
+    let resp = client
+        .get(url("/"))
+        .header("Cookie", &remember)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+}
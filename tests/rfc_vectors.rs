@@ -0,0 +1,165 @@
+//! Published test vectors from the RFCs behind each scheme, showing the implementations agree with them
+
+#[cfg(feature = "auth-digest")]
+use axum_auth::AuthDigest;
+#[cfg(feature = "auth-basic")]
+use axum_auth::{AuthBasic, AuthBasicCustom};
+#[cfg(feature = "jwt")]
+use axum_auth::{JwtKey, JwtValidator};
+#[cfg(any(feature = "jwt", feature = "scram"))]
+use base64::{engine::general_purpose, Engine};
+#[cfg(feature = "scram")]
+use {
+    axum_auth::ScramCredentials,
+    hmac::{Hmac, Mac},
+    sha2::{Digest, Sha256},
+};
+
+/// Decodes the basic credentials of a request with the given header
+#[cfg(feature = "auth-basic")]
+fn basic(header: &str) -> AuthBasic {
+    let (mut parts, _) = http::Request::builder()
+        .header("Authorization", header)
+        .body(())
+        .unwrap()
+        .into_parts();
+    AuthBasic::decode_request_parts(&mut parts).unwrap()
+}
+
+/// RFC 7617 section 2, the example of a user-id and password
+#[cfg(feature = "auth-basic")]
+#[test]
+fn rfc7617_aladdin() {
+    assert_eq!(
+        basic("Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ=="),
+        AuthBasic(("Aladdin".to_string(), Some("open sesame".to_string())))
+    );
+}
+
+/// RFC 7617 section 2.1, the example using the UTF-8 charset
+#[cfg(feature = "auth-basic")]
+#[test]
+fn rfc7617_utf8() {
+    assert_eq!(
+        basic("Basic dGVzdDoxMjPCow=="),
+        AuthBasic(("test".to_string(), Some("123£".to_string())))
+    );
+}
+
+/// RFC 7677 section 3, the example SCRAM-SHA-256 exchange for the password `pencil`
+#[cfg(feature = "scram")]
+#[test]
+fn rfc7677_scram_sha256() {
+    let b64 = |value| general_purpose::STANDARD.decode(value).unwrap();
+    let hmac = |key: &[u8], data: &[u8]| {
+        let mut mac = Hmac::<Sha256>::new_from_slice(key).unwrap();
+        mac.update(data);
+        mac.finalize().into_bytes().to_vec()
+    };
+    let credentials = ScramCredentials::new("pencil", &b64("W22ZaJ0SNY7soEsUEjb6gQ=="), 4096);
+    let auth_message = concat!(
+        "n=user,r=rOprNGfwEbeRWgbNEkqO,",
+        "r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0,s=W22ZaJ0SNY7soEsUEjb6gQ==,i=4096,",
+        "c=biws,r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0"
+    );
+
+    // The proof sent by the client has to recover a key matching the stored one
+    let signature = hmac(credentials.stored_key(), auth_message.as_bytes());
+    let client_key: Vec<u8> = b64("dHzbZapWIk4jUhN+Ute9ytag9zjfMHgsqmmiz7AndVQ=")
+        .iter()
+        .zip(signature)
+        .map(|(proof, signature)| proof ^ signature)
+        .collect();
+    assert_eq!(
+        Sha256::digest(client_key).as_slice(),
+        credentials.stored_key()
+    );
+
+    // The server signature has to match the one sent back in the example
+    assert_eq!(
+        hmac(credentials.server_key(), auth_message.as_bytes()),
+        b64("6rriTRBi23WpRR/wtup+mMhUZUn/dB5nLTJRsjl95G4=")
+    );
+}
+
+/// RFC 7515 appendix A.1, the example JWS using HMAC SHA-256
+#[cfg(feature = "jwt")]
+#[tokio::test]
+async fn rfc7515_hs256() {
+    const TOKEN: &str = concat!(
+        "eyJ0eXAiOiJKV1QiLA0KICJhbGciOiJIUzI1NiJ9",
+        ".eyJpc3MiOiJqb2UiLA0KICJleHAiOjEzMDA4MTkzODAsDQogImh0dHA6Ly9leGFtcGxlLmNvbS9pc19yb290Ijp0cnVlfQ",
+        ".dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"
+    );
+    let key = general_purpose::URL_SAFE_NO_PAD
+        .decode("AyM1SysPpbyDfgZld3umj1qzKObwVMkoqQ-EstJQLr_T-1qS0gZH75aKtMN3Yj0iPS4hcgUuTwjAzZr1Z9CAow")
+        .unwrap();
+    let validator = JwtValidator::new()
+        .with_key(JwtKey::hmac(key))
+        .with_issuer("joe");
+
+    // The example expired in 2011, so the signature is checked but the token isn't accepted
+    let (_, message) = validator.verify(TOKEN).await.unwrap_err();
+    assert_eq!(message, "JWT has expired or isn't valid yet");
+
+    // Given enough leeway, only the missing subject is left, so the signature and issuer were accepted
+    let validator = validator.with_leeway(std::time::Duration::from_secs(100 * 365 * 24 * 60 * 60));
+    let (_, message) = validator.verify(TOKEN).await.unwrap_err();
+    assert_eq!(message, "JWT has no subject");
+
+    // Changing the signature at all makes it invalid
+    let tampered = TOKEN.replace(".dBjf", ".eBjf");
+    let (_, message) = validator.verify(&tampered).await.unwrap_err();
+    assert_eq!(
+        message,
+        "JWT signature is invalid or was made by an unknown key"
+    );
+}
+
+/// RFC 7638 section 3.1, the example thumbprint of an RSA key
+#[cfg(feature = "jwt")]
+#[test]
+fn rfc7638_thumbprint() {
+    let jwk = serde_json::json!({
+        "kty": "RSA",
+        "n": concat!(
+            "0vx7agoebGcQSuuPiLJXZptN9nndrQmbXEps2aiAFbWhM78LhWx4cbbfAAtVT86zwu1RK7aPFFxuhDR1L6tSoc_BJECPebWKRXjBZCiFV4n3oknjhMstn",
+            "64tZ_2W-5JsGY4Hc5n9yBXArwl93lqt7_RN5w6Cf0h4QyQ5v-65YGjQR0_FDW2QvzqY368QQMicAtaSqzs8KJZgnYb9c7d0zgdAZHzu6qMQvRL5hajrn1n91CbOpbI",
+            "SD08qNLyrdkt-bFTWhAI4vMQFh6WeZu0fM4lFd2NcRwr3XPksINHaQ-G_xBniIqbw0Ls1jF44-csFCur-kEgU8awapJzKnqDKgw"
+        ),
+        "e": "AQAB",
+        "alg": "RS256",
+        "kid": "2011-04-29"
+    });
+    let key = JwtKey::from_jwk(&jwk).unwrap();
+    assert_eq!(key.kty(), "RSA");
+    assert_eq!(
+        key.thumbprint(),
+        "NzbLsXh8uDCcd-6MNwXF4W_7noWXFZAfHkxZsRGC9Xs"
+    );
+}
+
+/// RFC 7616 section 3.9.1, the example of a response using SHA-256
+#[cfg(feature = "auth-digest")]
+#[test]
+fn rfc7616_sha256() {
+    let (mut parts, _) = http::Request::builder()
+        .uri("/dir/index.html")
+        .header(
+            "Authorization",
+            r#"Digest username="Mufasa", realm="http-auth@example.org", uri="/dir/index.html", algorithm=SHA-256, nonce="7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v", nc=00000001, cnonce="f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ", qop=auth, response="753927fa0e85d155564e2e272a28d1802ca10daf4496794697cf8db5856cb6c1", opaque="FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS""#,
+        )
+        .body(())
+        .unwrap()
+        .into_parts();
+    let digest = AuthDigest::decode_request_parts(&mut parts).unwrap();
+    assert_eq!(digest.username, "Mufasa");
+    assert_eq!(
+        digest.opaque.as_deref(),
+        Some("FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS")
+    );
+
+    let ha1 = AuthDigest::ha1("Mufasa", "http-auth@example.org", "Circle of Life");
+    assert!(digest.verify(&http::Method::GET, &ha1));
+    assert!(!digest.verify(&http::Method::POST, &ha1));
+}
@@ -0,0 +1,238 @@
+#![cfg(feature = "scram")]
+
+use axum::{routing::get, Router};
+use axum_auth::{AuthUser, MemoryScramStore, ScramAuthLayer, ScramCredentials};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use hmac::{Hmac, Mac};
+use http::StatusCode;
+use sha2::{Digest, Sha256};
+
+/// Launches spin-off axum instance
+async fn launcher() {
+    // Make routes
+    let store = MemoryScramStore::new().with_user(
+        "user",
+        ScramCredentials::new(
+            "pencil",
+            &STANDARD.decode("W22ZaJ0SNY7soEsUEjb6gQ==").unwrap(),
+            4096,
+        ),
+    );
+    let app = Router::new()
+        .route("/scram", get(tester_scram))
+        .layer(ScramAuthLayer::new(store).with_realm("testrealm@example.com"));
+
+    // Launch
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:3013")
+        .await
+        .unwrap();
+
+    println!("listening on {}", listener.local_addr().unwrap());
+    axum::serve(listener, app.into_make_service())
+        .await
+        .unwrap();
+
+    async fn tester_scram(user: AuthUser) -> String {
+        format!("Got {}", user.id)
+    }
+}
+
+fn url(end: &str) -> String {
+    format!("http://127.0.0.1:3013{}", end)
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).unwrap();
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Gets a parameter from a header like `SCRAM-SHA-256 sid="abc", data="xyz"`
+fn param(header: &str, name: &str) -> String {
+    header
+        .split_once(' ')
+        .unwrap()
+        .1
+        .split(", ")
+        .find_map(|param| param.strip_prefix(&format!("{}=", name)))
+        .unwrap()
+        .trim_matches('"')
+        .to_string()
+}
+
+#[test]
+fn rfc_7677_keys() {
+    let credentials = ScramCredentials::new(
+        "pencil",
+        &STANDARD.decode("W22ZaJ0SNY7soEsUEjb6gQ==").unwrap(),
+        4096,
+    );
+    assert_eq!(
+        STANDARD.encode(credentials.stored_key()),
+        "WG5d8oPm3OtcPnkdi4Uo7BkeZkBFzpcXkuLmtbsT4qY="
+    );
+    assert_eq!(
+        STANDARD.encode(credentials.server_key()),
+        "wfPLwcE6nTWhTAmQ7tl2KeoiWGPlZqQxSrmfPwDl2dU="
+    );
+}
+
+#[tokio::test]
+async fn tester() {
+    // Launch axum instance
+    tokio::task::spawn(launcher());
+
+    // Wait for boot
+    tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
+
+    // Try without credentials
+    let client = reqwest::Client::new();
+    let resp = client.get(url("/scram")).send().await.unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::UNAUTHORIZED.as_u16());
+    assert_eq!(
+        resp.headers()["www-authenticate"],
+        "SCRAM-SHA-256 realm=\"testrealm@example.com\""
+    );
+
+    for (password, good) in [("pencil", true), ("crayon", false)] {
+        // Send the first client message
+        let client_first = "n=user,r=rOprNGfwEbeRWgbNEkqO";
+        let resp = client
+            .get(url("/scram"))
+            .header(
+                "Authorization",
+                format!(
+                    "SCRAM-SHA-256 data={}",
+                    STANDARD.encode(format!("n,,{}", client_first))
+                ),
+            )
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status().as_u16(), StatusCode::UNAUTHORIZED.as_u16());
+        let challenge = resp.headers()["www-authenticate"].to_str().unwrap();
+        let sid = param(challenge, "sid");
+        let server_first =
+            String::from_utf8(STANDARD.decode(param(challenge, "data")).unwrap()).unwrap();
+        let nonce = server_first
+            .split(',')
+            .next()
+            .unwrap()
+            .strip_prefix("r=")
+            .unwrap();
+        assert!(nonce.starts_with("rOprNGfwEbeRWgbNEkqO"));
+        assert!(server_first.ends_with(",s=W22ZaJ0SNY7soEsUEjb6gQ==,i=4096"));
+
+        // Calculate the proof like a client would
+        let mut block = hmac(
+            password.as_bytes(),
+            &[
+                STANDARD.decode("W22ZaJ0SNY7soEsUEjb6gQ==").unwrap(),
+                vec![0, 0, 0, 1],
+            ]
+            .concat(),
+        );
+        let mut salted = block.clone();
+        for _ in 1..4096 {
+            block = hmac(password.as_bytes(), &block);
+            salted.iter_mut().zip(&block).for_each(|(s, b)| *s ^= b);
+        }
+        let client_key = hmac(&salted, b"Client Key");
+        let stored_key = Sha256::digest(&client_key);
+        let without_proof = format!("c=biws,r={}", nonce);
+        let auth_message = format!("{},{},{}", client_first, server_first, without_proof);
+        let signature = hmac(&stored_key, auth_message.as_bytes());
+        let proof = client_key
+            .iter()
+            .zip(&signature)
+            .map(|(k, s)| k ^ s)
+            .collect::<Vec<_>>();
+
+        // Send the final client message
+        let client_final = format!("{},p={}", without_proof, STANDARD.encode(proof));
+        let resp = client
+            .get(url("/scram"))
+            .header(
+                "Authorization",
+                format!(
+                    "SCRAM-SHA-256 sid={}, data={}",
+                    sid,
+                    STANDARD.encode(client_final)
+                ),
+            )
+            .send()
+            .await
+            .unwrap();
+        if !good {
+            assert_eq!(resp.status().as_u16(), StatusCode::UNAUTHORIZED.as_u16());
+            assert_eq!(
+                resp.text().await.unwrap(),
+                String::from("The provided credentials are invalid")
+            );
+            continue;
+        }
+
+        // Check the server knew the password too
+        assert_eq!(resp.status().as_u16(), StatusCode::OK.as_u16());
+        let info = resp.headers()["authentication-info"]
+            .to_str()
+            .unwrap()
+            .to_string();
+        let server_key = hmac(&salted, b"Server Key");
+        let server_final = format!(
+            "v={}",
+            STANDARD.encode(hmac(&server_key, auth_message.as_bytes()))
+        );
+        assert_eq!(
+            info,
+            format!("sid={}, data={}", sid, STANDARD.encode(server_final))
+        );
+        assert_eq!(resp.text().await.unwrap(), String::from("Got user"));
+
+        // Try reusing the finished session
+        let resp = client
+            .get(url("/scram"))
+            .header(
+                "Authorization",
+                format!(
+                    "SCRAM-SHA-256 sid={}, data={}",
+                    sid,
+                    STANDARD.encode("c=biws")
+                ),
+            )
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(
+            resp.text().await.unwrap(),
+            String::from("SCRAM session has expired or doesn't exist")
+        );
+    }
+
+    // Unknown users get the same salt every time, so they can't be told apart from real ones
+    let salt = |username: &'static str| {
+        let client = client.clone();
+        async move {
+            let resp = client
+                .get(url("/scram"))
+                .header(
+                    "Authorization",
+                    format!(
+                        "SCRAM-SHA-256 data={}",
+                        STANDARD.encode(format!("n,,n={},r=rOprNGfwEbeRWgbNEkqO", username))
+                    ),
+                )
+                .send()
+                .await
+                .unwrap();
+            let challenge = resp.headers()["www-authenticate"].to_str().unwrap();
+            let server_first =
+                String::from_utf8(STANDARD.decode(param(challenge, "data")).unwrap()).unwrap();
+            server_first.split(',').nth(1).unwrap().to_string()
+        }
+    };
+    let unknown = salt("nobody").await;
+    assert!(unknown.starts_with("s="));
+    assert_eq!(salt("nobody").await, unknown);
+    assert_ne!(salt("someone").await, unknown);
+}
@@ -0,0 +1,255 @@
+#![cfg(feature = "session")]
+
+use axum::{
+    routing::{get, post},
+    Router,
+};
+use axum_auth::{
+    bearer_logout_route, AuthBearer, AuthUser, AuthValidator, BasicLogin, BasicUser, BasicUsers,
+    MemoryRevocationStore, Rejection, RequireAuthLayer, Revocable, RevocationStore,
+    SessionAuthLayer, SessionIssuer,
+};
+use http::StatusCode;
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime},
+};
+
+const KEY: &[u8; 32] = b"an example key which is 32 bytes";
+
+/// Accepts any bearer token starting with `token-` as the user named after it
+struct AnyToken;
+
+impl AuthValidator<AuthBearer> for AnyToken {
+    async fn validate(&self, credentials: &AuthBearer) -> Result<AuthUser, Rejection> {
+        if !credentials.0.starts_with("token-") {
+            return Err((StatusCode::UNAUTHORIZED, "Bearer token is invalid"));
+        }
+        Ok(AuthUser::new(&credentials.0))
+    }
+}
+
+/// Counts how many revocations were stored, so forged tokens can be shown to never reach it
+#[derive(Default)]
+struct CountingStore {
+    inner: MemoryRevocationStore,
+    stored: AtomicUsize,
+}
+
+impl RevocationStore for CountingStore {
+    async fn revoke(&self, id: &str, expires: SystemTime) {
+        self.stored.fetch_add(1, Ordering::SeqCst);
+        self.inner.revoke(id, expires).await
+    }
+
+    async fn is_revoked(&self, id: &str) -> bool {
+        self.inner.is_revoked(id).await
+    }
+}
+
+/// Launches spin-off axum instance
+async fn launcher(revocations: Arc<CountingStore>) {
+    let issuer = SessionIssuer::new(*KEY).with_secure(false);
+    let users =
+        BasicUsers::new().with_user("alice", BasicUser::new("hunter2").with_roles(["admin"]));
+
+    // Make routes
+    let app = Router::new()
+        .route("/", get(tester))
+        .layer(SessionAuthLayer::new(issuer.clone()).with_revocations(revocations.clone()))
+        .route(
+            "/bearer",
+            get(tester).layer(RequireAuthLayer::<AuthBearer, _>::new(Revocable::new(
+                AnyToken,
+                revocations.clone(),
+            ))),
+        )
+        .route(
+            "/login",
+            post(BasicLogin::new("App", users, issuer.clone()).handler()),
+        )
+        .route(
+            "/logout",
+            post(bearer_logout_route(revocations, issuer, AnyToken)),
+        );
+
+    // Launch
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:3025")
+        .await
+        .unwrap();
+
+    println!("listening on {}", listener.local_addr().unwrap());
+    axum::serve(listener, app.into_make_service())
+        .await
+        .unwrap();
+
+    async fn tester(user: AuthUser) -> String {
+        format!("Got {} with {:?}", user.id, user.roles)
+    }
+}
+
+fn url(end: &str) -> String {
+    format!("http://127.0.0.1:3025{}", end)
+}
+
+#[tokio::test]
+async fn tester() {
+    // Launch axum instance
+    let revocations = Arc::new(CountingStore::default());
+    tokio::task::spawn(launcher(revocations.clone()));
+
+    // Wait for boot
+    tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
+
+    // Tests
+    login().await;
+    rejected().await;
+    logout(&revocations).await;
+}
+
+/// Logging in once gives a cookie which works without the password
+async fn login() {
+    let client = reqwest::Client::new();
+    let resp = client
+        .post(url("/login"))
+        .basic_auth("alice", Some("hunter2"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+    let cookie = resp.headers()["set-cookie"].to_str().unwrap().to_string();
+    assert!(cookie.starts_with("session="));
+    assert!(cookie.contains("HttpOnly"));
+    assert!(!cookie.contains("Secure"));
+
+    // Send the cookie back like a browser would
+    let session = cookie.split(';').next().unwrap();
+    let resp = client
+        .get(url("/"))
+        .header("Cookie", format!("theme=dark; {}", session))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.text().await.unwrap(), "Got alice with [\"admin\"]");
+}
+
+/// Bad logins and sessions are rejected
+async fn rejected() {
+    let client = reqwest::Client::new();
+    let resp = client
+        .post(url("/login"))
+        .basic_auth("alice", Some("wrong"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    assert!(resp.headers()["www-authenticate"]
+        .to_str()
+        .unwrap()
+        .starts_with("Basic realm=\"App\""));
+    assert!(!resp.headers().contains_key("set-cookie"));
+
+    let resp = client.get(url("/")).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(resp.text().await.unwrap(), "Session cookie is missing");
+
+    // Sessions signed with another key
+    let forged =
+        SessionIssuer::new(*b"another key which is also 32 by.").issue(&AuthUser::new("alice"));
+    let resp = client
+        .get(url("/"))
+        .header("Cookie", format!("session={}", forged))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(resp.text().await.unwrap(), "Session cookie is invalid");
+}
+
+/// Logging out revokes sessions and valid bearer tokens
+async fn logout(revocations: &CountingStore) {
+    let client = reqwest::Client::new();
+    let resp = client
+        .post(url("/login"))
+        .basic_auth("alice", Some("hunter2"))
+        .send()
+        .await
+        .unwrap();
+    let cookie = resp.headers()["set-cookie"].to_str().unwrap();
+    let session = cookie.split(';').next().unwrap().to_string();
+    let get = || client.get(url("/")).header("Cookie", &session).send();
+    assert_eq!(get().await.unwrap().status(), StatusCode::OK);
+
+    // The cookie is cleared and the session can't be used again
+    let resp = client
+        .post(url("/logout"))
+        .header("Cookie", &session)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+    assert!(resp.headers()["set-cookie"]
+        .to_str()
+        .unwrap()
+        .starts_with("session=; Path=/; Max-Age=0"));
+    let resp = get().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(resp.text().await.unwrap(), "Session has been revoked");
+
+    // Bearer tokens are revoked too, without affecting others
+    let bearer = |token: &'static str| client.get(url("/bearer")).bearer_auth(token).send();
+    assert_eq!(bearer("token-1").await.unwrap().status(), StatusCode::OK);
+    let resp = client
+        .post(url("/logout"))
+        .bearer_auth("token-1")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+    assert_eq!(
+        bearer("token-1").await.unwrap().status(),
+        StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(bearer("token-2").await.unwrap().status(), StatusCode::OK);
+
+    // Forged bearer tokens are never stored
+    let stored = revocations.stored.load(Ordering::SeqCst);
+    let resp = client
+        .post(url("/logout"))
+        .bearer_auth("forged")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+    assert_eq!(revocations.stored.load(Ordering::SeqCst), stored);
+
+    // Logging out without anything isn't an error
+    let resp = client.post(url("/logout")).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+}
+
+#[test]
+fn verify() {
+    let issuer = SessionIssuer::new(*KEY);
+    let user = AuthUser::new("alice").with_scopes(["posts:read"]);
+    assert_eq!(issuer.verify(&issuer.issue(&user)), Ok(user.clone()));
+
+    // Changing the contents breaks the signature
+    let session = issuer.issue(&user);
+    let (payload, signature) = session.split_once('.').unwrap();
+    let tampered = format!("{}A.{}", payload, signature);
+    assert_eq!(
+        issuer.verify(&tampered).unwrap_err().1,
+        "Session cookie is invalid"
+    );
+
+    // Sessions don't last forever
+    let expired = issuer.clone().with_ttl(Duration::ZERO).issue(&user);
+    assert_eq!(
+        issuer.verify(&expired).unwrap_err().1,
+        "Session has expired"
+    );
+}
@@ -0,0 +1,97 @@
+#![cfg(feature = "signed-url")]
+
+use axum::{routing::get, Extension, Router};
+use axum_auth::{SignedUrl, UrlSigner};
+use http::StatusCode;
+use std::time::{Duration, SystemTime};
+
+/// Makes the signer shared by the server and the tests
+fn signer() -> UrlSigner {
+    UrlSigner::new(b"a long random secret key")
+}
+
+/// Launches spin-off axum instance
+async fn launcher() {
+    // Make routes
+    let app = Router::new()
+        .route("/files/{name}", get(tester_signed))
+        .route("/unsigned", get(tester_signed))
+        .layer(Extension(signer()))
+        .route("/nosigner", get(tester_signed));
+
+    // Launch
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:3037")
+        .await
+        .unwrap();
+
+    println!("listening on {}", listener.local_addr().unwrap());
+    axum::serve(listener, app.into_make_service())
+        .await
+        .unwrap();
+
+    async fn tester_signed(url: SignedUrl) -> String {
+        format!("Got {} for {}s", url.path, url.remaining().as_secs())
+    }
+}
+
+fn url(end: &str) -> String {
+    format!("http://127.0.0.1:3037{}", end)
+}
+
+/// Visits the path and query, giving back the status code and body
+async fn visit(path_and_query: &str) -> (u16, String) {
+    let resp = reqwest::get(url(path_and_query)).await.unwrap();
+    (resp.status().as_u16(), resp.text().await.unwrap())
+}
+
+#[tokio::test]
+async fn tester() {
+    // Launch axum instance
+    tokio::task::spawn(launcher());
+
+    // Wait for boot
+    tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
+
+    // Signed links work and say how long they have left
+    let expires = SystemTime::now() + Duration::from_secs(120);
+    let link = signer().sign("/files/report.pdf?download=1", expires);
+    let (status, body) = visit(&link).await;
+    assert_eq!(status, StatusCode::OK.as_u16());
+    assert!(body.starts_with("Got /files/report.pdf for 11"));
+
+    // Changing the path, query, or expiry breaks the signature
+    let invalid = (
+        403,
+        String::from("Signed URL is missing its signature or it's invalid"),
+    );
+    assert_eq!(
+        visit(&link.replace("report", "secret")).await,
+        invalid.clone()
+    );
+    assert_eq!(
+        visit(&link.replace("download=1", "download=2")).await,
+        invalid.clone()
+    );
+    let later = link.replace("expires=", &format!("expires={}&expires=", u64::MAX / 2));
+    assert_eq!(visit(&later).await, invalid.clone());
+    assert_eq!(visit("/files/report.pdf").await, invalid.clone());
+    assert_eq!(visit("/unsigned?signature=AAAA").await, invalid);
+
+    // Expired links are rejected even when signed properly
+    let link = signer().sign(
+        "/files/report.pdf",
+        SystemTime::now() - Duration::from_secs(1),
+    );
+    assert_eq!(
+        visit(&link).await,
+        (403, String::from("Signed URL has expired"))
+    );
+
+    // Links signed by another key don't work
+    let link = UrlSigner::new(b"another key").sign("/files/report.pdf", expires);
+    assert_eq!(visit(&link).await.0, 403);
+
+    // Forgetting the signer is a server error
+    let link = signer().sign("/nosigner", expires);
+    assert_eq!(visit(&link).await.0, 500);
+}
@@ -0,0 +1,176 @@
+use axum::{routing::get, Extension, Router};
+use axum_auth::{
+    AuthBearer, AuthUser, AuthValidator, RequireBearerAuthLayer, RequireRole, RequireScope, Role,
+    Scope, StaticToken, StaticTokens, TokenName,
+};
+use http::StatusCode;
+
+struct Deploy;
+
+impl Scope for Deploy {
+    const NAME: &'static str = "deploy";
+}
+
+struct Reader;
+
+impl Role for Reader {
+    const NAME: &'static str = "reader";
+}
+
+/// Launches spin-off axum instance
+async fn launcher() {
+    // Make routes
+    let app = Router::new()
+        .route("/", get(tester_name))
+        .layer(RequireBearerAuthLayer::from_tokens([
+            ("ci", "tok1"),
+            ("monitor", "tok2"),
+        ]))
+        .merge(
+            Router::new()
+                .route(
+                    "/deploy",
+                    get(|client: RequireScope<Deploy>| async move { client.user.id.clone() }),
+                )
+                .route(
+                    "/metrics",
+                    get(|reader: RequireRole<Reader>| async move { reader.id.clone() }),
+                )
+                .layer(RequireBearerAuthLayer::from_tokens([
+                    ("ci", StaticToken::new("tok1").with_scopes(["deploy"])),
+                    ("monitor", StaticToken::new("tok2").with_roles(["reader"])),
+                ])),
+        );
+
+    // Launch
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:3044")
+        .await
+        .unwrap();
+
+    println!("listening on {}", listener.local_addr().unwrap());
+    axum::serve(listener, app.into_make_service())
+        .await
+        .unwrap();
+
+    /// Gives back the user and which token they used
+    async fn tester_name(
+        user: AuthUser,
+        Extension(TokenName(name)): Extension<TokenName>,
+    ) -> String {
+        format!("{}:{}", user.id, name)
+    }
+}
+
+fn url(end: &str) -> String {
+    format!("http://127.0.0.1:3044{}", end)
+}
+
+#[tokio::test]
+async fn tester() {
+    // Launch axum instance
+    tokio::task::spawn(launcher());
+
+    // Wait for boot
+    tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
+
+    let client = reqwest::Client::new();
+
+    // Each token is recorded under its name
+    for (token, expected) in [("tok1", "ci:ci"), ("tok2", "monitor:monitor")] {
+        let resp = client
+            .get(url("/"))
+            .bearer_auth(token)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.text().await.unwrap(), expected);
+    }
+
+    // Unknown tokens, prefixes of tokens, and missing tokens are rejected
+    for token in ["tok3", "tok", "tok12"] {
+        let resp = client
+            .get(url("/"))
+            .bearer_auth(token)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            resp.text().await.unwrap(),
+            "The provided credentials are invalid"
+        );
+    }
+    let resp = client.get(url("/")).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    // Roles and scopes of each token feed the guards
+    for (path, token, status) in [
+        ("/deploy", "tok1", StatusCode::OK),
+        ("/deploy", "tok2", StatusCode::FORBIDDEN),
+        ("/metrics", "tok2", StatusCode::OK),
+        ("/metrics", "tok1", StatusCode::FORBIDDEN),
+    ] {
+        let resp = client
+            .get(url(path))
+            .bearer_auth(token)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), status);
+    }
+}
+
+#[tokio::test]
+async fn tokens() {
+    // Tokens can be replaced and removed by name
+    let mut tokens = StaticTokens::new()
+        .with_token("ci", "old")
+        .with_token("ci", "new");
+    let bearer = |token: &str| AuthBearer(token.to_string());
+    assert!(tokens.validate(&bearer("old")).await.is_err());
+    assert_eq!(
+        tokens.validate(&bearer("new")).await,
+        Ok(AuthUser::new("ci"))
+    );
+    assert!(tokens.remove("ci"));
+    assert!(!tokens.remove("ci"));
+    assert!(tokens.validate(&bearer("new")).await.is_err());
+
+    // Roles and scopes end up on the user
+    let tokens = StaticTokens::new().with_token(
+        "ci",
+        StaticToken::new("tok1")
+            .with_roles(["deployer"])
+            .with_scopes(["deploy", "logs:read"]),
+    );
+    assert_eq!(
+        tokens.validate(&bearer("tok1")).await,
+        Ok(AuthUser::new("ci")
+            .with_roles(["deployer"])
+            .with_scopes(["deploy", "logs:read"]))
+    );
+
+    // Tokens never show up in debug output
+    let tokens: StaticTokens = [
+        ("monitor", StaticToken::from("secret-token")),
+        (
+            "ci",
+            StaticToken::new("other-secret").with_roles(["deployer"]),
+        ),
+    ]
+    .into_iter()
+    .collect();
+    let debug = format!("{:?}", tokens);
+    assert!(debug.contains("monitor"));
+    assert!(debug.contains("deployer"));
+    assert!(!debug.contains("secret-token"));
+    assert!(!debug.contains("other-secret"));
+}
+
+#[test]
+#[allow(deprecated)]
+fn old_name() {
+    // The old name still makes the same layer
+    let _: RequireBearerAuthLayer = axum_auth::RequireBearerLayer::from_tokens([("ci", "tok1")]);
+}
@@ -0,0 +1,205 @@
+use axum::{routing::get, Extension, Router};
+use axum_auth::{
+    AuthBasic, AuthBearer, AuthTenant, AuthUser, BasicUser, BasicUsers, RequireTenantAuthLayer,
+    TenantId, TenantPathPrefix, TenantSubdomain, TenantValidators,
+};
+use http::StatusCode;
+
+/// Launches spin-off axum instance
+async fn launcher() {
+    // Make tenants with their own users
+    let tenants = TenantValidators::new()
+        .with_tenant(
+            "acme",
+            BasicUsers::new().with_user("alice", BasicUser::new("hunter2")),
+        )
+        .with_tenant(
+            "globex",
+            BasicUsers::new().with_user("bob", BasicUser::new("letmein")),
+        );
+    let layer = RequireTenantAuthLayer::<AuthBasic, _>::tenants(tenants);
+
+    // Make routes
+    let app = Router::new()
+        .route("/header", get(tester_header))
+        .route("/subdomain", get(tester_subdomain))
+        .route("/{tenant}/path", get(tester_path))
+        .nest(
+            "/routed",
+            Router::new().route("/", get(tester_routed)).layer(layer),
+        );
+
+    // Launch
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:3024")
+        .await
+        .unwrap();
+
+    println!("listening on {}", listener.local_addr().unwrap());
+    axum::serve(listener, app.into_make_service())
+        .await
+        .unwrap();
+
+    async fn tester_header(
+        AuthTenant {
+            tenant,
+            credential: AuthBearer(token),
+            ..
+        }: AuthTenant<AuthBearer>,
+    ) -> String {
+        format!("Got {} for {}", token, tenant)
+    }
+
+    async fn tester_subdomain(auth: AuthTenant<AuthBearer, TenantSubdomain>) -> String {
+        let (tenant, AuthBearer(token)) = auth.into_parts();
+        format!("Got {} for {}", token, tenant)
+    }
+
+    async fn tester_path(
+        AuthTenant {
+            tenant,
+            credential: AuthBearer(token),
+            ..
+        }: AuthTenant<AuthBearer, TenantPathPrefix>,
+    ) -> String {
+        format!("Got {} for {}", token, tenant)
+    }
+
+    async fn tester_routed(user: AuthUser, Extension(tenant): Extension<TenantId>) -> String {
+        format!("Got {} for {}", user.id, tenant)
+    }
+}
+
+fn url(end: &str) -> String {
+    format!("http://127.0.0.1:3024{}", end)
+}
+
+#[tokio::test]
+async fn tester() {
+    // Launch axum instance
+    tokio::task::spawn(launcher());
+
+    // Wait for boot
+    tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
+
+    // Tests
+    header().await;
+    subdomain().await;
+    path().await;
+    routed().await;
+}
+
+/// Tenants from the default header
+async fn header() {
+    let client = reqwest::Client::new();
+    let resp = client
+        .get(url("/header"))
+        .header("X-Tenant-Id", "acme")
+        .bearer_auth("token")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.text().await.unwrap(), "Got token for acme");
+
+    // Missing tenants are rejected before the credentials are looked at
+    let resp = client.get(url("/header")).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        resp.text().await.unwrap(),
+        "Tenant is missing from the request"
+    );
+
+    // Tenants which could escape into other keys are rejected
+    for tenant in ["acme/other", "acme:other", "acme other"] {
+        let resp = client
+            .get(url("/header"))
+            .header("X-Tenant-Id", tenant)
+            .bearer_auth("token")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            resp.text().await.unwrap(),
+            "Tenant contains invalid characters"
+        );
+    }
+
+    // Credentials are still required
+    let resp = client
+        .get(url("/header"))
+        .header("X-Tenant-Id", "acme")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(
+        resp.text().await.unwrap(),
+        "`Authorization` header is missing"
+    );
+}
+
+/// Tenants from the subdomain of the host
+async fn subdomain() {
+    let client = reqwest::Client::new();
+    let resp = client
+        .get(url("/subdomain"))
+        .header("Host", "acme.example.com:3024")
+        .bearer_auth("token")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.text().await.unwrap(), "Got token for acme");
+
+    // Hosts without subdomains and IP addresses have no tenant
+    for host in ["example.com", "127.0.0.1:3024"] {
+        let resp = client
+            .get(url("/subdomain"))
+            .header("Host", host)
+            .bearer_auth("token")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            resp.text().await.unwrap(),
+            "Tenant is missing from the request"
+        );
+    }
+}
+
+/// Tenants from the first segment of the path
+async fn path() {
+    let client = reqwest::Client::new();
+    let resp = client
+        .get(url("/acme/path"))
+        .bearer_auth("token")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.text().await.unwrap(), "Got token for acme");
+}
+
+/// Credentials are checked with the validator of their tenant
+async fn routed() {
+    let client = reqwest::Client::new();
+    let get = |tenant: &'static str, user: &'static str, password: &'static str| {
+        client
+            .get(url("/routed"))
+            .header("X-Tenant-Id", tenant)
+            .basic_auth(user, Some(password))
+            .send()
+    };
+
+    let resp = get("acme", "alice", "hunter2").await.unwrap();
+    assert_eq!(resp.text().await.unwrap(), "Got alice for acme");
+    let resp = get("globex", "bob", "letmein").await.unwrap();
+    assert_eq!(resp.text().await.unwrap(), "Got bob for globex");
+
+    // Users of one tenant can't get into another
+    let resp = get("globex", "alice", "hunter2").await.unwrap();
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+    // Unknown tenants are rejected
+    let resp = get("initech", "alice", "hunter2").await.unwrap();
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(resp.text().await.unwrap(), "Tenant is unknown");
+}
@@ -0,0 +1,128 @@
+#![cfg(feature = "test-util")]
+
+use axum::{routing::get, Router};
+use axum_auth::{
+    AlwaysAllow, AlwaysDeny, AuthBasic, AuthBearer, AuthUser, FakeIdentityLayer, MockValidator,
+    RequireAuthLayer, RequireRole, Role,
+};
+use http::StatusCode;
+
+struct Admin;
+
+impl Role for Admin {
+    const NAME: &'static str = "admin";
+}
+
+/// Launches spin-off axum instance
+async fn launcher() {
+    // Make validators
+    let mock = MockValidator::accepting(["tok1", "tok2"])
+        .with_token("admin-token", AuthUser::new("alice").with_roles(["admin"]));
+
+    // Make routes
+    let app = Router::new()
+        .nest(
+            "/mock",
+            Router::new()
+                .route("/", get(tester))
+                .layer(RequireAuthLayer::<AuthBearer, _>::new(mock)),
+        )
+        .nest(
+            "/allow",
+            Router::new()
+                .route("/", get(tester))
+                .layer(RequireAuthLayer::<AuthBasic, _>::new(AlwaysAllow::default())),
+        )
+        .nest(
+            "/deny",
+            Router::new()
+                .route("/", get(tester))
+                .layer(RequireAuthLayer::<AuthBearer, _>::new(AlwaysDeny)),
+        )
+        .nest(
+            "/fake",
+            Router::new()
+                .route("/", get(tester))
+                .route("/admin", get(|_: RequireRole<Admin>| async { "Welcome" }))
+                .layer(FakeIdentityLayer(AuthUser::new("bob"))),
+        );
+
+    // Launch
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:3023")
+        .await
+        .unwrap();
+
+    println!("listening on {}", listener.local_addr().unwrap());
+    axum::serve(listener, app.into_make_service())
+        .await
+        .unwrap();
+
+    async fn tester(user: AuthUser) -> String {
+        format!("Got {} with {:?}", user.id, user.roles)
+    }
+}
+
+fn url(end: &str) -> String {
+    format!("http://127.0.0.1:3023{}", end)
+}
+
+#[tokio::test]
+async fn tester() {
+    // Launch axum instance
+    tokio::task::spawn(launcher());
+
+    // Wait for boot
+    tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
+
+    // Tests
+    mock().await;
+    always().await;
+    fake().await;
+}
+
+/// Mock validators only accept their tokens
+async fn mock() {
+    let client = reqwest::Client::new();
+    let get = |token: &'static str| client.get(url("/mock")).bearer_auth(token).send();
+
+    let resp = get("tok1").await.unwrap();
+    assert_eq!(resp.text().await.unwrap(), "Got tok1 with []");
+
+    let resp = get("admin-token").await.unwrap();
+    assert_eq!(resp.text().await.unwrap(), "Got alice with [\"admin\"]");
+
+    let resp = get("tok3").await.unwrap();
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+}
+
+/// Always allowing or denying still needs credentials to be sent
+async fn always() {
+    let client = reqwest::Client::new();
+    let resp = client
+        .get(url("/allow"))
+        .basic_auth("anyone", Some("anything"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.text().await.unwrap(), "Got test with []");
+
+    let resp = client.get(url("/allow")).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let resp = client
+        .get(url("/deny"))
+        .bearer_auth("tok1")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+}
+
+/// Fake identities don't need any credentials but still go through guards
+async fn fake() {
+    let resp = reqwest::get(url("/fake")).await.unwrap();
+    assert_eq!(resp.text().await.unwrap(), "Got bob with []");
+
+    let resp = reqwest::get(url("/fake/admin")).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+}
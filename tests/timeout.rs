@@ -0,0 +1,106 @@
+#![cfg(feature = "validator-timeout")]
+
+use axum::{routing::get, Router};
+use axum_auth::{
+    AuthBearer, AuthUser, AuthValidator, Rejection, RequireAuthLayer, TimeoutValidator,
+};
+use http::StatusCode;
+use std::time::Duration;
+
+/// Validator standing in for an identity provider which is sometimes very slow
+struct SlowTokens;
+
+impl AuthValidator<AuthBearer> for SlowTokens {
+    async fn validate(&self, credentials: &AuthBearer) -> Result<AuthUser, Rejection> {
+        match credentials.0.as_str() {
+            "alice-token" => Ok(AuthUser::new("alice")),
+            "stalled-token" => {
+                tokio::time::sleep(Duration::from_secs(10)).await;
+                Ok(AuthUser::new("stalled"))
+            }
+            _ => Err((StatusCode::UNAUTHORIZED, "Unknown token")),
+        }
+    }
+}
+
+/// Launches spin-off axum instance
+async fn launcher() {
+    // Make routes
+    let timeout = TimeoutValidator::new(SlowTokens, Duration::from_millis(100));
+    let unauthorized = TimeoutValidator::new(SlowTokens, Duration::from_millis(100))
+        .with_status(StatusCode::UNAUTHORIZED);
+    let app = Router::new()
+        .route("/", get(tester_timeout))
+        .layer(RequireAuthLayer::<AuthBearer, _>::new(timeout))
+        .merge(
+            Router::new()
+                .route("/unauthorized", get(tester_timeout))
+                .layer(RequireAuthLayer::<AuthBearer, _>::new(unauthorized)),
+        );
+
+    // Launch
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:3031")
+        .await
+        .unwrap();
+
+    println!("listening on {}", listener.local_addr().unwrap());
+    axum::serve(listener, app.into_make_service())
+        .await
+        .unwrap();
+
+    async fn tester_timeout(user: AuthUser) -> String {
+        format!("Got {}", user.id)
+    }
+}
+
+fn url(end: &str) -> String {
+    format!("http://127.0.0.1:3031{}", end)
+}
+
+#[tokio::test]
+async fn tester() {
+    // Launch axum instance
+    tokio::task::spawn(launcher());
+
+    // Wait for boot
+    tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
+
+    // Fast validations are untouched
+    let client = reqwest::Client::new();
+    let resp = client
+        .get(url("/"))
+        .bearer_auth("alice-token")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::OK.as_u16());
+    assert_eq!(resp.text().await.unwrap(), "Got alice");
+    let resp = client
+        .get(url("/"))
+        .bearer_auth("mallory-token")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::UNAUTHORIZED.as_u16());
+    assert_eq!(resp.text().await.unwrap(), "Unknown token");
+
+    // Stalled validations give up quickly
+    for (route, status) in [
+        ("/", StatusCode::SERVICE_UNAVAILABLE),
+        ("/unauthorized", StatusCode::UNAUTHORIZED),
+    ] {
+        let start = std::time::Instant::now();
+        let resp = client
+            .get(url(route))
+            .bearer_auth("stalled-token")
+            .send()
+            .await
+            .unwrap();
+        assert!(start.elapsed() < Duration::from_secs(2));
+        assert_eq!(resp.status().as_u16(), status.as_u16());
+        assert_eq!(
+            resp.text().await.unwrap(),
+            "Validating the credentials took too long"
+        );
+    }
+}
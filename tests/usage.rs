@@ -0,0 +1,127 @@
+use axum::{
+    routing::{get, post},
+    Router,
+};
+use axum_auth::{
+    AuthBearer, AuthUser, AuthValidator, CredentialId, Rejection, RequireAuthLayer, Usage,
+    UsageRecorder,
+};
+use http::{Extensions, Method, StatusCode};
+use std::sync::Mutex;
+
+/// Usage recorded so far
+static USAGE: Mutex<Vec<Usage>> = Mutex::new(Vec::new());
+
+/// Recorder keeping every usage in [USAGE]
+struct Recorder;
+
+impl UsageRecorder for Recorder {
+    async fn record(&self, usage: Usage) {
+        USAGE.lock().unwrap().push(usage);
+    }
+}
+
+/// Validator giving each of alice's tokens its own credential identifier
+struct Tokens;
+
+impl AuthValidator<AuthBearer> for Tokens {
+    async fn validate(&self, credentials: &AuthBearer) -> Result<AuthUser, Rejection> {
+        self.validate_with_extensions(credentials, &mut Extensions::new())
+            .await
+    }
+
+    async fn validate_with_extensions(
+        &self,
+        credentials: &AuthBearer,
+        extensions: &mut Extensions,
+    ) -> Result<AuthUser, Rejection> {
+        match credentials.0.as_str() {
+            "alice-laptop" | "alice-ci" => {
+                extensions.insert(CredentialId(credentials.0.clone()));
+                Ok(AuthUser::new("alice"))
+            }
+            "bob-token" => Ok(AuthUser::new("bob")),
+            _ => Err((StatusCode::UNAUTHORIZED, "Unknown token")),
+        }
+    }
+}
+
+/// Launches spin-off axum instance
+async fn launcher() {
+    // Make routes
+    let app = Router::new()
+        .route("/", get(tester_usage))
+        .route("/upload", post(tester_upload))
+        .layer(RequireAuthLayer::<AuthBearer, _>::new(Tokens).with_usage(Recorder));
+
+    // Launch
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:3035")
+        .await
+        .unwrap();
+
+    println!("listening on {}", listener.local_addr().unwrap());
+    axum::serve(listener, app.into_make_service())
+        .await
+        .unwrap();
+
+    async fn tester_usage(user: AuthUser) -> String {
+        format!("Got {}", user.id)
+    }
+
+    async fn tester_upload(body: String) -> StatusCode {
+        assert_eq!(body.len(), 11);
+        StatusCode::CREATED
+    }
+}
+
+fn url(end: &str) -> String {
+    format!("http://127.0.0.1:3035{}", end)
+}
+
+#[tokio::test]
+async fn tester() {
+    // Launch axum instance
+    tokio::task::spawn(launcher());
+
+    // Wait for boot
+    tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
+
+    // Make requests with a few credentials
+    let client = reqwest::Client::new();
+    for token in ["alice-laptop", "alice-ci", "bob-token", "mallory-token"] {
+        client
+            .get(url("/"))
+            .bearer_auth(token)
+            .send()
+            .await
+            .unwrap();
+    }
+    let resp = client
+        .post(url("/upload"))
+        .bearer_auth("alice-ci")
+        .body("hello world")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::CREATED.as_u16());
+
+    // Only authenticated requests are recorded, by credential where there is one
+    let usage = USAGE.lock().unwrap().clone();
+    assert_eq!(
+        usage
+            .iter()
+            .map(|usage| usage.credential.as_str())
+            .collect::<Vec<_>>(),
+        ["alice-laptop", "alice-ci", "bob", "alice-ci"]
+    );
+    assert_eq!(usage[0].user, AuthUser::new("alice"));
+    assert_eq!(usage[0].method, Method::GET);
+    assert_eq!(usage[0].route, "/");
+    assert_eq!(usage[0].status, StatusCode::OK);
+    assert_eq!(usage[0].request_bytes, None);
+    assert_eq!(usage[0].response_bytes, Some(9));
+    assert_eq!(usage[3].route, "/upload");
+    assert_eq!(usage[3].status, StatusCode::CREATED);
+    assert_eq!(usage[3].request_bytes, Some(11));
+    assert_eq!(usage[3].response_bytes, Some(0));
+}
@@ -0,0 +1,65 @@
+#![cfg(feature = "uuid")]
+
+use axum::{routing::get, Router};
+use axum_auth::AuthBearerUuid;
+use http::StatusCode;
+
+/// Launches spin-off axum instance
+async fn launcher() {
+    // Make routes
+    let app = Router::new().route("/session", get(tester_session));
+
+    // Launch
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:3009")
+        .await
+        .unwrap();
+
+    println!("listening on {}", listener.local_addr().unwrap());
+    axum::serve(listener, app.into_make_service())
+        .await
+        .unwrap();
+
+    async fn tester_session(AuthBearerUuid(session): AuthBearerUuid) -> String {
+        format!("Got {}", session)
+    }
+}
+
+fn url(end: &str) -> String {
+    format!("http://127.0.0.1:3009{}", end)
+}
+
+#[tokio::test]
+async fn tester() {
+    // Launch axum instance
+    tokio::task::spawn(launcher());
+
+    // Wait for boot
+    tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
+
+    // Try good token
+    let client = reqwest::Client::new();
+    let resp = client
+        .get(url("/session"))
+        .bearer_auth("67E55044-10B1-426F-9247-BB680E5FE0C8")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::OK.as_u16());
+    assert_eq!(
+        resp.text().await.unwrap(),
+        String::from("Got 67e55044-10b1-426f-9247-bb680e5fe0c8")
+    );
+
+    // Try bad token
+    let resp = client
+        .get(url("/session"))
+        .bearer_auth("undefined")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::BAD_REQUEST.as_u16());
+    assert_eq!(
+        resp.text().await.unwrap(),
+        String::from("`Authorization` bearer token must be a UUID")
+    );
+}
@@ -0,0 +1,140 @@
+#![cfg(feature = "vapid")]
+
+use axum::{routing::get, Router};
+use axum_auth::AuthVapid;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use http::StatusCode;
+use p256::ecdsa::{signature::Signer, Signature, SigningKey};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Launches spin-off axum instance
+async fn launcher() {
+    // Make routes
+    let app = Router::new().route("/push", get(tester_push));
+
+    // Launch
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:3015")
+        .await
+        .unwrap();
+
+    println!("listening on {}", listener.local_addr().unwrap());
+    axum::serve(listener, app.into_make_service())
+        .await
+        .unwrap();
+
+    async fn tester_push(vapid: AuthVapid) -> String {
+        format!(
+            "Got {} byte key from {}",
+            vapid.public_key.len(),
+            vapid.subject.unwrap()
+        )
+    }
+}
+
+fn url(end: &str) -> String {
+    format!("http://127.0.0.1:3015{}", end)
+}
+
+/// Signs a token like an application server would, giving back the header
+fn sign(aud: &str, expires_in: i64) -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+    sign_until(aud, (now + expires_in) as u64)
+}
+
+/// Signs a token expiring at the Unix timestamp, giving back the header
+fn sign_until(aud: &str, exp: u64) -> String {
+    let key = SigningKey::from_slice(&[7; 32]).unwrap();
+    let header = URL_SAFE_NO_PAD.encode(r#"{"typ":"JWT","alg":"ES256"}"#);
+    let claims = URL_SAFE_NO_PAD.encode(format!(
+        r#"{{"aud":"{}","exp":{},"sub":"mailto:push@example.com"}}"#,
+        aud, exp
+    ));
+    let signing_input = format!("{}.{}", header, claims);
+    let signature: Signature = key.sign(signing_input.as_bytes());
+    let public_key = key.verifying_key().to_encoded_point(false);
+    format!(
+        "vapid t={}.{}, k={}",
+        signing_input,
+        URL_SAFE_NO_PAD.encode(signature.to_bytes()),
+        URL_SAFE_NO_PAD.encode(public_key.as_bytes())
+    )
+}
+
+/// Requests the push endpoint with the header, giving back the status and body
+async fn push(header: Option<&str>) -> (u16, String) {
+    let mut req = reqwest::Client::new().get(url("/push"));
+    if let Some(header) = header {
+        req = req.header("Authorization", header);
+    }
+    let resp = req.send().await.unwrap();
+    (resp.status().as_u16(), resp.text().await.unwrap())
+}
+
+#[tokio::test]
+async fn tester() {
+    // Launch axum instance
+    tokio::task::spawn(launcher());
+
+    // Wait for boot
+    tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
+
+    // Missing and other schemes should ask for vapid
+    let resp = reqwest::get(url("/push")).await.unwrap();
+    assert_eq!(resp.status().as_u16(), StatusCode::UNAUTHORIZED.as_u16());
+    assert_eq!(resp.headers()["www-authenticate"], "vapid");
+    assert_eq!(
+        push(Some("Bearer abc")).await,
+        (
+            StatusCode::UNAUTHORIZED.as_u16(),
+            String::from("`Authorization` header must be for VAPID authentication")
+        )
+    );
+
+    // Valid token
+    let origin = "https://127.0.0.1:3015";
+    assert_eq!(
+        push(Some(&sign(origin, 3600))).await,
+        (
+            StatusCode::OK.as_u16(),
+            String::from("Got 65 byte key from mailto:push@example.com")
+        )
+    );
+
+    // Tampered signature
+    let mut tampered = sign(origin, 3600);
+    tampered.replace_range(20..21, "A");
+    assert_eq!(
+        push(Some(&tampered)).await,
+        (
+            StatusCode::FORBIDDEN.as_u16(),
+            String::from("VAPID token or key is invalid")
+        )
+    );
+
+    // Another push service
+    assert_eq!(
+        push(Some(&sign("https://push.example.net", 3600))).await,
+        (
+            StatusCode::FORBIDDEN.as_u16(),
+            String::from("VAPID token is meant for another push service")
+        )
+    );
+
+    // Expired, too long lasting, and unrepresentable tokens
+    for header in [
+        sign(origin, -60),
+        sign(origin, 25 * 60 * 60),
+        sign_until(origin, u64::MAX),
+    ] {
+        assert_eq!(
+            push(Some(&header)).await,
+            (
+                StatusCode::FORBIDDEN.as_u16(),
+                String::from("VAPID token has expired or lasts longer than 24 hours")
+            )
+        );
+    }
+}